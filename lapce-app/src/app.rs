@@ -86,7 +86,9 @@
     },
     editor_tab::{EditorTabChild, EditorTabData},
     focus_text::focus_text,
+    hex_editor::hex_editor_view,
     id::{EditorTabId, SplitId},
+    image_viewer::image_viewer_view,
     keymap::keymap_view,
     keypress::keymap::KeyMap,
     listener::Listener,
@@ -94,6 +96,7 @@
         SplitContent, SplitData, SplitDirection, SplitMoveDirection, TabCloseKind,
     },
     markdown::MarkdownContent,
+    markdown_preview::markdown_preview_view,
     palette::{
         PaletteStatus,
         item::{PaletteItem, PaletteItemContent},
@@ -133,6 +136,10 @@ struct Cli {
     #[clap(long, action)]
     plugin_path: Vec<PathBuf>,
 
+    /// The todo list to replace the one of an interactive rebase with
+    #[clap(long, hide = true)]
+    rebase_todo: Option<PathBuf>,
+
     /// Paths to file(s) and/or folder(s) to open.
     /// When path is a file (that exists or not),
     /// it accepts `path:line:column` syntax
@@ -686,6 +693,9 @@ fn editor_tab_header(
     let plugin = window_tab_data.plugin.clone();
     let editors = window_tab_data.main_split.editors;
     let diff_editors = window_tab_data.main_split.diff_editors;
+    let hex_editors = window_tab_data.main_split.hex_editors;
+    let image_viewers = window_tab_data.main_split.image_viewers;
+    let markdown_previews = window_tab_data.main_split.markdown_previews;
     let focus = window_tab_data.common.focus;
     let config = window_tab_data.common.config;
     let internal_command = window_tab_data.common.internal_command;
@@ -728,7 +738,15 @@ fn editor_tab_header(
         let main_split = main_split.clone();
         let plugin = plugin.clone();
         let child_view = {
-            let info = child.view_info(editors, diff_editors, plugin, config);
+            let info = child.view_info(
+                editors,
+                diff_editors,
+                hex_editors,
+                image_viewers,
+                markdown_previews,
+                plugin,
+                config,
+            );
             let hovered = create_rw_signal(false);
 
             use crate::config::ui::TabCloseButton;
@@ -1227,6 +1245,9 @@ fn editor_tab_content(
     let workspace = common.workspace.clone();
     let editors = main_split.editors;
     let diff_editors = main_split.diff_editors;
+    let hex_editors = main_split.hex_editors;
+    let image_viewers = main_split.image_viewers;
+    let markdown_previews = main_split.markdown_previews;
     let config = common.config;
     let focus = common.focus;
     let items = move || {
@@ -1404,6 +1425,31 @@ fn editor_tab_content(
             EditorTabChild::Volt(_, id) => {
                 plugin_info_view(plugin.clone(), id).into_any()
             }
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                let hex_editor = hex_editors.get_untracked(hex_editor_id);
+                if let Some(hex_editor) = hex_editor {
+                    hex_editor_view(hex_editor).into_any()
+                } else {
+                    text("empty hex editor").into_any()
+                }
+            }
+            EditorTabChild::ImageViewer(image_viewer_id) => {
+                let image_viewer = image_viewers.get_untracked(image_viewer_id);
+                if let Some(image_viewer) = image_viewer {
+                    image_viewer_view(image_viewer).into_any()
+                } else {
+                    text("empty image viewer").into_any()
+                }
+            }
+            EditorTabChild::MarkdownPreview(markdown_preview_id) => {
+                let markdown_preview =
+                    markdown_previews.get_untracked(markdown_preview_id);
+                if let Some(markdown_preview) = markdown_preview {
+                    markdown_preview_view(markdown_preview).into_any()
+                } else {
+                    text("empty markdown preview").into_any()
+                }
+            }
         };
         child.style(|s| s.size_full())
     };
@@ -2558,6 +2604,7 @@ fn palette_item(
         | PaletteItemContent::SshHost { .. }
         | PaletteItemContent::Language { .. }
         | PaletteItemContent::LineEnding { .. }
+        | PaletteItemContent::Encoding { .. }
         | PaletteItemContent::ColorTheme { .. }
         | PaletteItemContent::SCMReference { .. }
         | PaletteItemContent::TerminalProfile { .. }
@@ -2996,7 +3043,7 @@ fn hover(window_tab_data: Rc<WindowTabData>) -> impl View {
             move || hover_data.content.get(),
             move |_| id.fetch_add(1, std::sync::atomic::Ordering::Relaxed),
             move |content| match content {
-                MarkdownContent::Text(text_layout) => container(
+                MarkdownContent::Text(text_layout, _) => container(
                     rich_text(move || text_layout.clone())
                         .style(|s| s.max_width(600.0)),
                 )
@@ -3710,6 +3757,9 @@ fn window(window_data: WindowData) -> impl View {
 
 pub fn launch() {
     let cli = Cli::parse();
+    if let Some(todo) = cli.rebase_todo.as_deref() {
+        lapce_proxy::cli::edit_rebase_todo(todo, &cli.paths);
+    }
 
     if !cli.wait {
         logging::panic_hook();
@@ -3851,6 +3901,11 @@ pub fn launch() {
             tracing::error!("{:?}", err);
         }
     }
+    if let Some(path) = LapceConfig::languages_file() {
+        if let Err(err) = watcher.watch(&path, notify::RecursiveMode::Recursive) {
+            tracing::error!("{:?}", err);
+        }
+    }
     if let Some(path) = Directory::plugins_directory() {
         if let Err(err) = watcher.watch(&path, notify::RecursiveMode::Recursive) {
             tracing::error!("{:?}", err);
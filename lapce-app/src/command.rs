@@ -14,6 +14,7 @@
     dap_types::{DapId, RunDebugConfig},
     plugin::{PluginId, VoltID},
     proxy::ProxyStatus,
+    source_control::FileDiff,
     terminal::{TermId, TerminalProfile},
 };
 use lsp_types::{CodeActionOrCommand, Position, WorkspaceEdit};
@@ -394,6 +395,26 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "palette.scm_references")]
     PaletteSCMReferences,
 
+    #[strum(message = "Source Control: Compare Active File With Revision")]
+    #[strum(serialize = "palette.scm_compare_active_file")]
+    PaletteSCMCompareActiveFile,
+
+    #[strum(message = "Source Control: Compare Workspace With Revision")]
+    #[strum(serialize = "palette.scm_compare_workspace")]
+    PaletteSCMCompareWorkspace,
+
+    #[strum(message = "Source Control: Switch Worktree")]
+    #[strum(serialize = "palette.scm_worktrees")]
+    PaletteSCMWorktrees,
+
+    #[strum(message = "Source Control: Create Worktree")]
+    #[strum(serialize = "palette.scm_create_worktree")]
+    PaletteSCMCreateWorktree,
+
+    #[strum(message = "Source Control: Rebase Onto Revision")]
+    #[strum(serialize = "palette.scm_rebase")]
+    PaletteSCMRebase,
+
     #[strum(message = "List Palette Types")]
     #[strum(serialize = "palette.palette_help")]
     PaletteHelp,
@@ -488,6 +509,14 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "toggle_search_visual")]
     ToggleSearchVisual,
 
+    #[strum(message = "Search: Open Results in Editor")]
+    #[strum(serialize = "search.open_editor")]
+    OpenSearchEditor,
+
+    #[strum(message = "Search Editor: Re-run Search")]
+    #[strum(serialize = "search_editor.rerun")]
+    RerunSearchEditor,
+
     #[strum(serialize = "focus_editor")]
     FocusEditor,
 
@@ -505,6 +534,10 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "source_control_copy_active_file_remote_url")]
     SourceControlCopyActiveFileRemoteUrl,
 
+    #[strum(message = "Source Control: Copy Remote File Url of Branch")]
+    #[strum(serialize = "source_control_copy_active_file_remote_branch_url")]
+    SourceControlCopyActiveFileRemoteBranchUrl,
+
     #[strum(message = "Source Control: Discard File Changes")]
     #[strum(serialize = "source_control_discard_active_file_changes")]
     SourceControlDiscardActiveFileChanges,
@@ -516,6 +549,46 @@ pub enum LapceWorkbenchCommand {
     #[strum(serialize = "source_control_discard_workspace_changes")]
     SourceControlDiscardWorkspaceChanges,
 
+    #[strum(message = "Source Control: Show Commit History")]
+    #[strum(serialize = "source_control_show_history")]
+    SourceControlShowHistory,
+
+    #[strum(message = "Source Control: Show File History")]
+    #[strum(serialize = "source_control_show_file_history")]
+    SourceControlShowFileHistory,
+
+    #[strum(message = "Source Control: Toggle Inline Blame")]
+    #[strum(serialize = "source_control_toggle_inline_blame")]
+    SourceControlToggleInlineBlame,
+
+    #[strum(message = "Source Control: Toggle Blame Gutter")]
+    #[strum(serialize = "source_control_toggle_blame_gutter")]
+    SourceControlToggleBlameGutter,
+
+    #[strum(message = "Source Control: Start Rebase")]
+    #[strum(serialize = "source_control_start_rebase")]
+    SourceControlStartRebase,
+
+    #[strum(message = "Source Control: Continue Rebase")]
+    #[strum(serialize = "source_control_continue_rebase")]
+    SourceControlContinueRebase,
+
+    #[strum(message = "Source Control: Skip Commit of Rebase")]
+    #[strum(serialize = "source_control_skip_rebase")]
+    SourceControlSkipRebase,
+
+    #[strum(message = "Source Control: Abort Rebase")]
+    #[strum(serialize = "source_control_abort_rebase")]
+    SourceControlAbortRebase,
+
+    #[strum(message = "Rebase: Next Action")]
+    #[strum(serialize = "rebase_todo.next_action")]
+    RebaseTodoNextAction,
+
+    #[strum(message = "Rebase: Previous Action")]
+    #[strum(serialize = "rebase_todo.previous_action")]
+    RebaseTodoPreviousAction,
+
     #[strum(serialize = "export_current_theme_settings")]
     #[strum(message = "Export current settings to a theme file")]
     ExportCurrentThemeSettings,
@@ -532,6 +605,26 @@ pub enum LapceWorkbenchCommand {
     #[strum(message = "Change current file line ending")]
     ChangeFileLineEnding,
 
+    #[strum(serialize = "reopen_with_encoding")]
+    #[strum(message = "Reopen current file with encoding")]
+    ReopenWithEncoding,
+
+    #[strum(serialize = "save_with_encoding")]
+    #[strum(message = "Save current file with encoding")]
+    SaveWithEncoding,
+
+    #[strum(serialize = "enable_large_file_editing")]
+    #[strum(message = "Enable editing of current large file")]
+    EnableLargeFileEditing,
+
+    #[strum(serialize = "open_hex_editor")]
+    #[strum(message = "Open current file in hex editor")]
+    OpenHexEditor,
+
+    #[strum(serialize = "markdown_preview_to_side")]
+    #[strum(message = "Markdown: Open Preview to the Side")]
+    OpenMarkdownPreviewToSide,
+
     #[strum(serialize = "next_editor_tab")]
     #[strum(message = "Next Editor Tab")]
     NextEditorTab,
@@ -614,10 +707,25 @@ pub enum InternalCommand {
     OpenFileInNewTab {
         path: PathBuf,
     },
+    /// Open a file in a text editor, including the images which are
+    /// otherwise previewed
+    OpenFileAsText {
+        path: PathBuf,
+    },
     MakeConfirmed,
     OpenFileChanges {
         path: PathBuf,
     },
+    /// Open the changes of a file compared to its version at a revision
+    OpenFileChangesAgainst {
+        path: PathBuf,
+        revision: String,
+    },
+    /// Open the changes a commit made to a file, compared to its parent
+    OpenCommitFileChanges {
+        commit: String,
+        diff: FileDiff,
+    },
     ReloadFileExplorer,
     /// Test whether a file/directory can be created at that path
     TestPathCreation {
@@ -779,6 +887,28 @@ pub enum InternalCommand {
         left_path: PathBuf,
         right_path: PathBuf,
     },
+    /// Show the search panel, only searching the files of the folder
+    SearchInFolder {
+        path: PathBuf,
+    },
+    /// Diff the active file against the revision, or against `HEAD` if `None`
+    CompareActiveFileWithRevision {
+        revision: Option<String>,
+    },
+    /// List the files changed since the revision, or stop listing if `None`
+    CompareWorkspaceWithRevision {
+        revision: Option<String>,
+    },
+    /// Add a working tree with the branch checked out and switch to it
+    CreateWorktree {
+        branch: String,
+    },
+    /// Write the todo list of rebasing onto the revision and open it
+    PrepareRebase {
+        onto: String,
+    },
+    /// Rebase following the prepared todo list
+    StartRebase,
     ExecuteProcess {
         program: String,
         arguments: Vec<String>,
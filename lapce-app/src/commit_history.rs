@@ -0,0 +1,307 @@
+use std::{cell::RefCell, path::PathBuf, rc::Rc};
+
+use floem::{
+    ext_event::create_ext_action,
+    reactive::{RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
+};
+use lapce_rpc::{
+    proxy::ProxyResponse,
+    source_control::{FileDiff, GitCommit},
+};
+
+use crate::{command::InternalCommand, window_tab::CommonData};
+
+/// The number of commits requested from the proxy at a time
+const COMMIT_PAGE_SIZE: usize = 100;
+
+/// How a commit is drawn in the commit graph.
+/// Lanes are the columns of the graph, each following a line of history.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CommitGraphRow {
+    /// The lane of the commit itself
+    pub lane: usize,
+    /// Lanes which pass by the commit, from the top to the bottom of the row
+    pub through: Vec<usize>,
+    /// Lanes at the top of the row which lead into the commit
+    pub incoming: Vec<usize>,
+    /// Lanes at the bottom of the row which lead to the commit's parents
+    pub outgoing: Vec<usize>,
+}
+
+impl CommitGraphRow {
+    /// The number of lanes the row needs to be drawn
+    pub fn width(&self) -> usize {
+        self.through
+            .iter()
+            .chain(self.incoming.iter())
+            .chain(self.outgoing.iter())
+            .copied()
+            .max()
+            .unwrap_or(0)
+            .max(self.lane)
+            + 1
+    }
+}
+
+/// Compute the graph rows for the commits, continuing from the lanes of the
+/// previous page. Each lane holds the commit it's waiting for.
+fn commit_graph(
+    commits: &[GitCommit],
+    lanes: &mut Vec<Option<String>>,
+) -> Vec<CommitGraphRow> {
+    fn free_lane(lanes: &mut Vec<Option<String>>) -> usize {
+        match lanes.iter().position(|lane| lane.is_none()) {
+            Some(lane) => lane,
+            None => {
+                lanes.push(None);
+                lanes.len() - 1
+            }
+        }
+    }
+
+    let mut rows = Vec::with_capacity(commits.len());
+    for commit in commits {
+        let incoming: Vec<usize> = lanes
+            .iter()
+            .enumerate()
+            .filter(|(_, id)| id.as_deref() == Some(commit.id.as_str()))
+            .map(|(lane, _)| lane)
+            .collect();
+        for lane in incoming.iter() {
+            lanes[*lane] = None;
+        }
+        let lane = match incoming.first() {
+            Some(lane) => *lane,
+            None => free_lane(lanes),
+        };
+        let through = lanes
+            .iter()
+            .enumerate()
+            .filter(|(_, id)| id.is_some())
+            .map(|(lane, _)| lane)
+            .collect();
+
+        let mut outgoing = Vec::new();
+        for (i, parent) in commit.parents.iter().enumerate() {
+            let target = if let Some(existing) = lanes
+                .iter()
+                .position(|id| id.as_deref() == Some(parent.as_str()))
+            {
+                existing
+            } else if i == 0 {
+                lane
+            } else {
+                free_lane(lanes)
+            };
+            lanes[target] = Some(parent.clone());
+            outgoing.push(target);
+        }
+        while lanes.last().is_some_and(|lane| lane.is_none()) {
+            lanes.pop();
+        }
+
+        rows.push(CommitGraphRow {
+            lane,
+            through,
+            incoming,
+            outgoing,
+        });
+    }
+    rows
+}
+
+#[derive(Clone)]
+pub struct CommitHistoryData {
+    /// The file the history is filtered to, if any
+    pub path: RwSignal<Option<PathBuf>>,
+    pub commits: RwSignal<im::Vector<(GitCommit, CommitGraphRow)>>,
+    pub has_more: RwSignal<bool>,
+    pub loading: RwSignal<bool>,
+    /// The selected commit and the files it changed
+    pub selected: RwSignal<Option<(String, im::Vector<FileDiff>)>>,
+    /// Incremented every time the history is reloaded, so that responses for
+    /// an old history are ignored
+    generation: RwSignal<u64>,
+    lanes: Rc<RefCell<Vec<Option<String>>>>,
+    pub scope: Scope,
+    pub common: Rc<CommonData>,
+}
+
+impl CommitHistoryData {
+    pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
+        Self {
+            path: cx.create_rw_signal(None),
+            commits: cx.create_rw_signal(im::Vector::new()),
+            has_more: cx.create_rw_signal(false),
+            loading: cx.create_rw_signal(false),
+            selected: cx.create_rw_signal(None),
+            generation: cx.create_rw_signal(0),
+            lanes: Rc::new(RefCell::new(Vec::new())),
+            scope: cx,
+            common,
+        }
+    }
+
+    /// Reload the history, filtered to the file if a path is given
+    pub fn show(&self, path: Option<PathBuf>) {
+        self.generation.update(|generation| *generation += 1);
+        self.path.set(path);
+        self.commits.set(im::Vector::new());
+        self.selected.set(None);
+        self.has_more.set(false);
+        self.lanes.borrow_mut().clear();
+        self.loading.set(false);
+        self.load_more();
+    }
+
+    /// Request the next page of commits
+    pub fn load_more(&self) {
+        if self.loading.get_untracked() {
+            return;
+        }
+        self.loading.set(true);
+
+        let generation = self.generation.get_untracked();
+        let data = self.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            if data.generation.get_untracked() != generation {
+                return;
+            }
+            data.loading.set(false);
+            if let Ok(ProxyResponse::GitLogResponse { commits }) = result {
+                data.has_more.set(commits.len() >= COMMIT_PAGE_SIZE);
+                // The parents of a file's commits aren't part of its history, so
+                // there is no graph to draw
+                let rows = if data.path.with_untracked(|path| path.is_none()) {
+                    commit_graph(&commits, &mut data.lanes.borrow_mut())
+                } else {
+                    vec![CommitGraphRow::default(); commits.len()]
+                };
+                data.commits.update(|all| {
+                    all.extend(commits.into_iter().zip(rows));
+                });
+            } else {
+                data.has_more.set(false);
+            }
+        });
+        let skip = self.commits.with_untracked(|commits| commits.len());
+        self.common.proxy.git_log(
+            self.path.get_untracked(),
+            skip,
+            COMMIT_PAGE_SIZE,
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    /// Select the commit and list the files it changed. When the history is
+    /// filtered to a file, the changes of that file are opened directly.
+    pub fn select_commit(&self, commit: &GitCommit) {
+        if self
+            .selected
+            .with_untracked(|s| s.as_ref().map(|(id, _)| id) == Some(&commit.id))
+        {
+            self.selected.set(None);
+            return;
+        }
+
+        let generation = self.generation.get_untracked();
+        let data = self.clone();
+        let id = commit.id.clone();
+        let file_path = commit.path.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            if data.generation.get_untracked() != generation {
+                return;
+            }
+            if let Ok(ProxyResponse::GitCommitFilesResponse { diffs }) = result {
+                if let Some(file_path) = file_path.as_ref() {
+                    if let Some(diff) =
+                        diffs.iter().find(|diff| diff.path() == file_path)
+                    {
+                        data.open_file_changes(&id, diff);
+                    }
+                }
+                data.selected.set(Some((id, diffs.into())));
+            }
+        });
+        self.common
+            .proxy
+            .git_commit_files(commit.id.clone(), move |result| {
+                send(result);
+            });
+    }
+
+    /// Open the changes the commit made to the file in the diff editor
+    pub fn open_file_changes(&self, commit: &str, diff: &FileDiff) {
+        self.common
+            .internal_command
+            .send(InternalCommand::OpenCommitFileChanges {
+                commit: commit.to_string(),
+                diff: diff.clone(),
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_rpc::source_control::GitCommit;
+
+    use super::{CommitGraphRow, commit_graph};
+
+    fn commit(id: &str, parents: &[&str]) -> GitCommit {
+        GitCommit {
+            id: id.to_string(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            author: String::new(),
+            email: String::new(),
+            time: 0,
+            summary: String::new(),
+            message: String::new(),
+            path: None,
+        }
+    }
+
+    #[test]
+    fn test_commit_graph_merge() {
+        // d merges c into b, both of which branch off a
+        let commits = [
+            commit("d", &["b", "c"]),
+            commit("c", &["a"]),
+            commit("b", &["a"]),
+            commit("a", &[]),
+        ];
+        let mut lanes = Vec::new();
+        let rows = commit_graph(&commits, &mut lanes);
+        assert_eq!(
+            rows,
+            vec![
+                CommitGraphRow {
+                    lane: 0,
+                    through: vec![],
+                    incoming: vec![],
+                    outgoing: vec![0, 1],
+                },
+                CommitGraphRow {
+                    lane: 1,
+                    through: vec![0],
+                    incoming: vec![1],
+                    outgoing: vec![1],
+                },
+                CommitGraphRow {
+                    lane: 0,
+                    through: vec![1],
+                    incoming: vec![0],
+                    outgoing: vec![1],
+                },
+                CommitGraphRow {
+                    lane: 1,
+                    through: vec![],
+                    incoming: vec![1],
+                    outgoing: vec![],
+                },
+            ]
+        );
+        assert!(lanes.is_empty());
+    }
+}
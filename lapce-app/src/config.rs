@@ -7,7 +7,10 @@
 use ::core::slice;
 use floem::{peniko::Color, prelude::palette::css};
 use itertools::Itertools;
-use lapce_core::directory::Directory;
+use lapce_core::{
+    directory::Directory,
+    language::{LanguageDefinition, LapceLanguage},
+};
 use lapce_proxy::plugin::wasi::find_all_volts;
 use lapce_rpc::plugin::VoltID;
 use lsp_types::{CompletionItemKind, SymbolKind};
@@ -22,6 +25,7 @@
     color_theme::{ColorThemeConfig, ThemeColor, ThemeColorPreference},
     core::CoreConfig,
     editor::{EditorConfig, SCALE_OR_SIZE_LIMIT, WrapStyle},
+    files::FilesConfig,
     icon::LapceIcons,
     icon_theme::IconThemeConfig,
     svg::SvgStore,
@@ -34,6 +38,7 @@
 pub mod color_theme;
 pub mod core;
 pub mod editor;
+pub mod files;
 pub mod icon;
 pub mod icon_theme;
 pub mod svg;
@@ -100,6 +105,7 @@ pub struct LapceConfig {
     pub ui: UIConfig,
     pub editor: EditorConfig,
     pub terminal: TerminalConfig,
+    pub files: FilesConfig,
     #[serde(default)]
     pub color_theme: ColorThemeConfig,
     #[serde(default)]
@@ -113,6 +119,10 @@ pub struct LapceConfig {
     #[serde(skip)]
     pub available_icon_themes:
         HashMap<String, (String, config::Config, Option<PathBuf>)>,
+    /// The languages registered from `languages.toml` and the volts, which are
+    /// sent to the proxy to be registered there too
+    #[serde(skip)]
+    pub languages: Vec<LanguageDefinition>,
     // #[serde(skip)]
     // tab_layout_info: Arc<RwLock<HashMap<(FontFamily, usize), f64>>>,
     #[serde(skip)]
@@ -147,6 +157,8 @@ pub fn load(
             Self::load_color_themes(disabled_volts, extra_plugin_paths);
         lapce_config.available_icon_themes =
             Self::load_icon_themes(disabled_volts, extra_plugin_paths);
+        lapce_config.languages =
+            Self::load_languages(disabled_volts, extra_plugin_paths);
         lapce_config.resolve_theme(workspace);
 
         lapce_config.color_theme_list = lapce_config
@@ -173,6 +185,7 @@ pub fn load(
         ];
 
         lapce_config.terminal.get_indexed_colors();
+        lapce_config.files.resolve_associations();
 
         lapce_config
     }
@@ -288,8 +301,11 @@ fn resolve_theme(&mut self, workspace: &LapceWorkspace) {
             self.core = new.core;
             self.ui = new.ui;
             self.editor = new.editor;
+            self.editor.resolve_languages();
             self.terminal = new.terminal;
             self.terminal.get_indexed_colors();
+            self.files = new.files;
+            self.files.resolve_associations();
 
             self.color_theme = new.color_theme;
             self.icon_theme = new.icon_theme;
@@ -578,6 +594,81 @@ pub fn settings_file() -> Option<PathBuf> {
         Some(path)
     }
 
+    pub fn languages_file() -> Option<PathBuf> {
+        let path = Directory::config_directory()?.join("languages.toml");
+
+        if !path.exists() {
+            if let Err(err) = std::fs::OpenOptions::new()
+                .create_new(true)
+                .write(true)
+                .open(&path)
+            {
+                tracing::error!("{:?}", err);
+            }
+        }
+
+        Some(path)
+    }
+
+    /// Register the languages of `languages.toml` in the config directory and
+    /// of the volts. Languages which are already registered with the same
+    /// definition are left as they are.
+    fn load_languages(
+        disabled_volts: &[VoltID],
+        extra_plugin_paths: &[PathBuf],
+    ) -> Vec<LanguageDefinition> {
+        let mut files: Vec<PathBuf> = find_all_volts(extra_plugin_paths)
+            .into_iter()
+            .filter(|meta| !disabled_volts.contains(&meta.id()))
+            .filter_map(|meta| meta.languages)
+            .flatten()
+            .map(PathBuf::from)
+            .collect();
+        // The user's languages are registered last to replace those of volts
+        files.extend(Self::languages_file());
+
+        let languages: Vec<LanguageDefinition> = files
+            .iter()
+            .flat_map(|file| Self::load_language_file(file))
+            .collect();
+        for language in &languages {
+            LapceLanguage::register(language.clone());
+        }
+        languages
+    }
+
+    fn load_language_file(path: &Path) -> Vec<LanguageDefinition> {
+        #[derive(Deserialize)]
+        struct LanguagesFile {
+            #[serde(default)]
+            language: Vec<LanguageDefinition>,
+        }
+
+        let Ok(content) = std::fs::read_to_string(path) else {
+            return Vec::new();
+        };
+        let languages = match toml::from_str::<LanguagesFile>(&content) {
+            Ok(file) => file.language,
+            Err(err) => {
+                error!("Failed to load languages from {path:?}: {err}");
+                return Vec::new();
+            }
+        };
+
+        // The paths of the grammars and queries are relative to the file
+        let dir = path.parent();
+        languages
+            .into_iter()
+            .map(|mut language| {
+                if let Some(dir) = dir {
+                    language.grammar = language.grammar.map(|p| dir.join(p));
+                    language.queries = language.queries.map(|p| dir.join(p));
+                }
+                language
+            })
+            .collect()
+    }
+
     pub fn keymaps_file() -> Option<PathBuf> {
         let path = Directory::config_directory()?.join("keymaps.toml");
 
@@ -627,6 +718,15 @@ pub fn files_svg(&self, paths: &[&Path]) -> (String, Option<Color>) {
         }
     }
 
+    /// The language of the file from the user's file associations or else
+    /// from its name and extension
+    pub fn language_for_path(&self, path: &Path) -> Option<LapceLanguage> {
+        self.files
+            .resolved_associations
+            .language(path)
+            .or_else(|| LapceLanguage::from_path_raw(path))
+    }
+
     pub fn file_svg(&self, path: &Path) -> (String, Option<Color>) {
         self.files_svg(slice::from_ref(&path))
     }
@@ -898,18 +998,13 @@ pub fn get_dropdown_info(&self, kind: &str, key: &str) -> Option<DropdownInfo> {
                     .unwrap_or(0),
                 items: self.icon_theme_list.clone(),
             }),
-            ("editor", "wrap-style") => Some(DropdownInfo {
-                // TODO: it would be better to have the text not be the default kebab-case when
-                // displayed in settings, but we would need to map back from the dropdown's value
-                // or index.
-                active_index: self
-                    .wrap_style_list
-                    .iter()
-                    .flat_map(|w| WrapStyle::try_from_str(w))
-                    .position(|w| w == self.editor.wrap_style)
-                    .unwrap_or(0),
-                items: self.wrap_style_list.clone(),
-            }),
+            ("editor", "wrap-style") => Some(self.wrap_style_dropdown(&self.editor)),
+            (kind, "wrap-style") if kind.starts_with("editor.language.") => {
+                let language = LapceLanguage::from_name(
+                    kind.trim_start_matches("editor.language."),
+                )?;
+                Some(self.wrap_style_dropdown(self.editor.for_language(language)))
+            }
             ("ui", "tab-close-button") => Some(DropdownInfo {
                 active_index: self.ui.tab_close_button as usize,
                 items: ui::TabCloseButton::VARIANTS
@@ -946,6 +1041,21 @@ pub fn get_dropdown_info(&self, kind: &str, key: &str) -> Option<DropdownInfo> {
         }
     }
 
+    fn wrap_style_dropdown(&self, editor: &EditorConfig) -> DropdownInfo {
+        DropdownInfo {
+            // TODO: it would be better to have the text not be the default kebab-case when
+            // displayed in settings, but we would need to map back from the dropdown's value
+            // or index.
+            active_index: self
+                .wrap_style_list
+                .iter()
+                .flat_map(|w| WrapStyle::try_from_str(w))
+                .position(|w| w == editor.wrap_style)
+                .unwrap_or(0),
+            items: self.wrap_style_list.clone(),
+        }
+    }
+
     fn get_file_table() -> Option<toml_edit::Document> {
         let path = Self::settings_file()?;
         let content = std::fs::read_to_string(path).ok()?;
@@ -102,6 +102,13 @@ impl LapceColor {
     pub const SOURCE_CONTROL_ADDED: &'static str = "source_control.added";
     pub const SOURCE_CONTROL_REMOVED: &'static str = "source_control.removed";
     pub const SOURCE_CONTROL_MODIFIED: &'static str = "source_control.modified";
+    pub const SOURCE_CONTROL_UNTRACKED: &'static str = "source_control.untracked";
+    pub const SOURCE_CONTROL_IGNORED: &'static str = "source_control.ignored";
+    pub const SOURCE_CONTROL_CONFLICTED: &'static str = "source_control.conflicted";
+    pub const SOURCE_CONTROL_CONFLICT_CURRENT: &'static str =
+        "source_control.conflict.current";
+    pub const SOURCE_CONTROL_CONFLICT_INCOMING: &'static str =
+        "source_control.conflict.incoming";
 
     pub const TERMINAL_CURSOR: &'static str = "terminal.cursor";
     pub const TERMINAL_BACKGROUND: &'static str = "terminal.background";
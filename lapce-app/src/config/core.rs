@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 use structdesc::FieldNames;
 
@@ -22,4 +24,21 @@ pub struct CoreConfig {
         desc = "Enable auto-reload for the plugin when its configuration changes."
     )]
     pub auto_reload_plugin: bool,
+    /// The forges of self-hosted git hosts, used for the remote urls of files,
+    /// e.g. `"git.example.com" = "gitlab"`
+    #[field_names(skip)]
+    pub git_forges: HashMap<String, String>,
+    #[field_names(
+        desc = "The maximum length of the subject line of commit messages, shown as a ruler in the commit box. 0 disables it."
+    )]
+    pub commit_subject_length: usize,
+    #[field_names(
+        desc = "The maximum length of the body lines of commit messages. 0 disables it."
+    )]
+    pub commit_body_line_length: usize,
+    #[field_names(desc = "Add a Signed-off-by trailer to commits by default")]
+    pub commit_sign_off: bool,
+    /// The rules commit messages are checked against, see `CommitLintRule`
+    #[field_names(skip)]
+    pub commit_lint_rules: Vec<String>,
 }
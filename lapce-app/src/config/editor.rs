@@ -1,6 +1,10 @@
+use std::collections::HashMap;
+
 use floem::views::editor::text::RenderWhitespace;
+use lapce_core::language::LapceLanguage;
 use serde::{Deserialize, Serialize};
 use structdesc::FieldNames;
+use tracing::error;
 
 pub const SCALE_OR_SIZE_LIMIT: f64 = 5.0;
 
@@ -140,6 +144,19 @@ pub struct EditorConfig {
     )]
     pub normalize_line_endings: bool,
 
+    #[field_names(desc = "Whether trailing whitespace should be removed on save")]
+    pub trim_trailing_whitespace: bool,
+
+    #[field_names(
+        desc = "Whether a newline should be inserted at the end of the file on save"
+    )]
+    pub insert_final_newline: bool,
+
+    #[field_names(
+        desc = "Whether the newlines after the final newline should be removed on save"
+    )]
+    pub trim_final_newlines: bool,
+
     #[field_names(desc = "If matching brackets are highlighted")]
     pub highlight_matching_brackets: bool,
 
@@ -196,6 +213,12 @@ pub struct EditorConfig {
         desc = "Set the completion lens font size. If 0 it uses the inlay hint font size."
     )]
     pub completion_lens_font_size: usize,
+    #[field_names(
+        desc = "If the editor should display git blame information at the end of the cursor line"
+    )]
+    pub enable_inline_blame: bool,
+    #[field_names(desc = "If the editor should display a git blame gutter")]
+    pub show_blame_gutter: bool,
     #[field_names(
         desc = "Set the cursor blink interval (in milliseconds). Set to 0 to completely disable."
     )]
@@ -240,17 +263,60 @@ pub struct EditorConfig {
     pub bracket_pair_colorization: bool,
     #[field_names(desc = "Bracket colorization Limit")]
     pub bracket_colorization_limit: u64,
+    #[field_names(
+        desc = "The size in megabytes above which a file is opened as a large file, which is read-only by default and has no syntax highlighting, bracket colorization or language server features. 0 to disable it."
+    )]
+    pub large_file_threshold: u64,
     #[field_names(
         desc = "Glob patterns for excluding files and folders (in file explorer)"
     )]
     pub files_exclude: String,
+
+    /// The overrides of the settings for languages, e.g.
+    /// `[editor.language.markdown]`
+    #[serde(default)]
+    #[field_names(skip)]
+    pub language: HashMap<String, serde_json::Map<String, serde_json::Value>>,
+
+    #[serde(skip)]
+    #[field_names(skip)]
+    resolved_languages: HashMap<LapceLanguage, EditorConfig>,
 }
 
 impl EditorConfig {
+    /// The settings which are read with [`EditorConfig::for_language`], and so
+    /// can be overridden per language
+    pub const LANGUAGE_FIELDS: [&'static str; 19] = [
+        "tab_width",
+        "atomic_soft_tabs",
+        "smart_tab",
+        "auto_closing_matching_pairs",
+        "auto_surround",
+        "format_on_save",
+        "normalize_line_endings",
+        "insert_final_newline",
+        "trim_final_newlines",
+        "trim_trailing_whitespace",
+        "wrap_style",
+        "wrap_width",
+        "scroll_beyond_last_line",
+        "cursor_surrounding_lines",
+        "show_indent_guide",
+        "render_whitespace",
+        "modal_mode_relative_line_numbers",
+        "highlight_matching_brackets",
+        "highlight_scope_lines",
+    ];
+
     pub fn font_size(&self) -> usize {
         self.font_size.clamp(6, 32)
     }
 
+    /// The large file threshold in bytes
+    pub fn large_file_threshold_bytes(&self) -> u64 {
+        self.large_file_threshold.saturating_mul(1024 * 1024)
+    }
+
     pub fn line_height(&self) -> usize {
         let line_height = if self.line_height < SCALE_OR_SIZE_LIMIT {
             self.line_height * self.font_size as f64
@@ -297,6 +363,48 @@ pub fn atomic_soft_tab_width(&self) -> Option<usize> {
         }
     }
 
+    /// Resolve the settings of the languages with overrides, which apply on
+    /// top of the other settings
+    pub fn resolve_languages(&mut self) {
+        let mut resolved_languages = HashMap::new();
+        let serde_json::Value::Object(mut base) =
+            serde_json::to_value(&*self).unwrap_or_default()
+        else {
+            return;
+        };
+        base.remove("language");
+        for (name, overrides) in &self.language {
+            let Some(language) = LapceLanguage::from_name(name) else {
+                error!("Unknown language `{name}` in editor settings");
+                continue;
+            };
+            let mut settings = base.clone();
+            settings.extend(overrides.clone());
+            match serde_json::from_value(serde_json::Value::Object(settings)) {
+                Ok(config) => {
+                    resolved_languages.insert(language, config);
+                }
+                Err(err) => {
+                    error!("Failed to load editor settings of `{name}`: {err}");
+                }
+            }
+        }
+        self.resolved_languages = resolved_languages;
+    }
+
+    /// The settings for the language, with its overrides
+    pub fn for_language(&self, language: LapceLanguage) -> &EditorConfig {
+        self.resolved_languages.get(&language).unwrap_or(self)
+    }
+
+    /// The key of the override table of the language in the settings
+    pub fn language_key(language: LapceLanguage) -> String {
+        match language {
+            LapceLanguage::Custom(_) => language.name().to_lowercase(),
+            _ => AsRef::<str>::as_ref(&language).to_lowercase(),
+        }
+    }
+
     pub fn blink_interval(&self) -> u64 {
         if self.blink_interval == 0 {
             return 0;
@@ -304,3 +412,53 @@ pub fn blink_interval(&self) -> u64 {
         self.blink_interval.max(200)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use lapce_core::language::LapceLanguage;
+    use serde_json::json;
+
+    use super::EditorConfig;
+
+    #[test]
+    fn test_resolve_languages() {
+        let overrides = |value: serde_json::Value| match value {
+            serde_json::Value::Object(map) => map,
+            _ => unreachable!(),
+        };
+        let mut config = EditorConfig {
+            tab_width: 4,
+            format_on_save: true,
+            trim_trailing_whitespace: true,
+            language: HashMap::from([
+                ("rust".to_string(), overrides(json!({ "tab-width": 2 }))),
+                (
+                    "Markdown".to_string(),
+                    overrides(json!({ "trim-trailing-whitespace": false })),
+                ),
+                ("unknown".to_string(), overrides(json!({ "tab-width": 8 }))),
+            ]),
+            ..Default::default()
+        };
+        config.resolve_languages();
+        // The unknown language is skipped
+        assert_eq!(config.resolved_languages.len(), 2);
+
+        let rust = config.for_language(LapceLanguage::Rust);
+        assert_eq!(rust.tab_width, 2);
+        // The settings without an override fall through
+        assert!(rust.format_on_save);
+        assert!(rust.trim_trailing_whitespace);
+
+        let markdown = config.for_language(LapceLanguage::Markdown);
+        assert_eq!(markdown.tab_width, 4);
+        assert!(!markdown.trim_trailing_whitespace);
+
+        // The overrides apply only to their language
+        let python = config.for_language(LapceLanguage::Python);
+        assert_eq!(python.tab_width, 4);
+        assert!(python.trim_trailing_whitespace);
+    }
+}
@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+
+use lapce_core::language::FileAssociations;
+use serde::{Deserialize, Serialize};
+use structdesc::FieldNames;
+
+#[derive(FieldNames, Debug, Clone, Deserialize, Serialize, Default)]
+#[serde(rename_all = "kebab-case")]
+pub struct FilesConfig {
+    /// The languages of the files matching the globs, e.g.
+    /// `"*.tpl" = "Go (template)"` or `"Jenkinsfile*" = "Groovy"`
+    #[field_names(skip)]
+    pub associations: HashMap<String, String>,
+
+    #[serde(skip)]
+    #[field_names(skip)]
+    pub resolved_associations: FileAssociations,
+}
+
+impl FilesConfig {
+    pub fn resolve_associations(&mut self) {
+        self.resolved_associations = FileAssociations::new(
+            self.associations
+                .iter()
+                .map(|(glob, language)| (glob.as_str(), language.as_str())),
+        );
+    }
+}
@@ -54,6 +54,8 @@ impl LapceIcons {
     pub const SCM_DIFF_RENAMED: &'static str = "scm.diff.renamed";
     pub const SCM_CHANGE_ADD: &'static str = "scm.change.add";
     pub const SCM_CHANGE_REMOVE: &'static str = "scm.change.remove";
+    pub const SCM_HISTORY: &'static str = "scm.history";
+    pub const SCM_CONFLICT: &'static str = "scm.conflict";
 
     pub const FOLD: &'static str = "fold";
     pub const FOLD_UP: &'static str = "fold.up";
@@ -100,6 +102,15 @@ impl LapceIcons {
     pub const SEARCH_REGEX: &'static str = "search.regex";
     pub const SEARCH_REPLACE: &'static str = "search.replace";
     pub const SEARCH_REPLACE_ALL: &'static str = "search.replace_all";
+    pub const SEARCH_FILTERS: &'static str = "search.filters";
+    pub const SEARCH_INCLUDE_IGNORED: &'static str = "search.include_ignored";
+    pub const SEARCH_OPEN_EDITORS: &'static str = "search.open_editors";
+    pub const SEARCH_OPEN_IN_EDITOR: &'static str = "search.open_in_editor";
+
+    pub const IMAGE_ZOOM_IN: &'static str = "image.zoom_in";
+    pub const IMAGE_ZOOM_OUT: &'static str = "image.zoom_out";
+    pub const IMAGE_FIT: &'static str = "image.fit";
+    pub const IMAGE_ACTUAL_SIZE: &'static str = "image.actual_size";
 
     pub const FILE_TYPE_CODE: &'static str = "file-code";
     pub const FILE_TYPE_MEDIA: &'static str = "file-media";
@@ -54,6 +54,11 @@ pub struct UIConfig {
     #[field_names(desc = "Trim whitespace from search results")]
     pub trim_search_results_whitespace: bool,
 
+    #[field_names(
+        desc = "Set the number of lines around the matches in search editors"
+    )]
+    pub search_editor_context_lines: usize,
+
     #[field_names(desc = "Set the line height for list items")]
     list_line_height: usize,
 
@@ -47,6 +47,7 @@
     indent::IndentStyle,
     language::LapceLanguage,
     line_ending::LineEnding,
+    merge_conflict::{MergeConflict, find_conflicts},
     mode::MotionMode,
     register::Register,
     rope_text_pos::RopeTextPosition,
@@ -56,9 +57,14 @@
     word::{CharClassification, WordCursor, get_char_property},
 };
 use lapce_rpc::{
+    RpcError,
     buffer::BufferId,
+    editorconfig::{
+        EditorConfigProperties, EndOfLine, IndentStyle as EditorConfigIndentStyle,
+    },
     plugin::PluginId,
     proxy::ProxyResponse,
+    source_control::BlameHunk,
     style::{LineStyle, LineStyles, Style},
 };
 use lapce_xi_rope::{
@@ -74,7 +80,7 @@
 
 use crate::{
     command::{CommandKind, LapceCommand},
-    config::{LapceConfig, color::LapceColor},
+    config::{LapceConfig, color::LapceColor, editor::EditorConfig},
     editor::{EditorData, compute_screen_lines, gutter::FoldingRanges},
     find::{Find, FindProgress, FindResult},
     history::DocumentHistory,
@@ -84,6 +90,7 @@
         document_symbol::{SymbolData, SymbolInformationItemData},
         kind::PanelKind,
     },
+    search_editor::search_editor_styles,
     window_tab::{CommonData, Focus},
     workspace::LapceWorkspace,
 };
@@ -161,6 +168,32 @@ pub struct DocInfo {
 
 pub type AllCodeLens = im::HashMap<usize, (PluginId, usize, im::Vector<CodeLens>)>;
 
+/// The git blame of a document at a specific revision
+#[derive(Clone, Default)]
+pub struct DocBlame {
+    pub rev: u64,
+    pub hunks: im::Vector<BlameHunk>,
+}
+
+impl DocBlame {
+    /// Get the blame hunk which contains the given line
+    pub fn hunk_of_line(&self, line: usize) -> Option<&BlameHunk> {
+        let index = self
+            .hunks
+            .binary_search_by(|hunk| {
+                if line < hunk.start_line {
+                    std::cmp::Ordering::Greater
+                } else if line >= hunk.start_line + hunk.lines {
+                    std::cmp::Ordering::Less
+                } else {
+                    std::cmp::Ordering::Equal
+                }
+            })
+            .ok()?;
+        self.hunks.get(index)
+    }
+}
+
 #[derive(Clone)]
 pub struct Doc {
     pub scope: Scope,
@@ -196,6 +229,12 @@ pub struct Doc {
     /// Stores information about different versions of the document from source control.
     histories: RwSignal<im::HashMap<String, DocumentHistory>>,
     pub head_changes: RwSignal<im::Vector<DiffLines>>,
+    /// The revision the head changes are computed against, `HEAD` if `None`
+    pub diff_base: RwSignal<Option<String>>,
+    /// The git blame of the document, only retrieved when it's displayed
+    pub blame: RwSignal<DocBlame>,
+    /// The merge conflict blocks in the buffer
+    pub conflicts: RwSignal<im::Vector<MergeConflict>>,
 
     line_styles: Rc<RefCell<LineStyles>>,
     pub parser: Rc<RefCell<BracketParser>>,
@@ -214,6 +253,26 @@ pub struct Doc {
     pub common: Rc<CommonData>,
 
     pub document_symbol_data: RwSignal<Option<SymbolData>>,
+
+    /// The `.editorconfig` properties of the document, which take precedence
+    /// over the editor settings
+    pub editor_config: RwSignal<EditorConfigProperties>,
+
+    /// The name of the encoding the file is read and saved with
+    pub encoding: RwSignal<String>,
+
+    /// Whether the file is larger than the large file threshold, in which case
+    /// it isn't parsed, has no bracket colorization and isn't synced with the
+    /// language servers
+    pub large_file: RwSignal<bool>,
+
+    /// Whether the file can't be written, as reported by the proxy when it's
+    /// opened, which stays read-only when large file editing is enabled
+    pub file_read_only: RwSignal<bool>,
+
+    /// Whether the file isn't text, in which case it's opened in the hex editor
+    /// instead and this doc stays empty
+    pub binary: RwSignal<bool>,
 }
 impl Doc {
     pub fn new(
@@ -223,8 +282,10 @@ pub fn new(
         editors: Editors,
         common: Rc<CommonData>,
     ) -> Self {
-        let syntax = Syntax::init(&path);
         let config = common.config.get_untracked();
+        let syntax = Syntax::from_language(
+            config.language_for_path(&path).unwrap_or_default(),
+        );
         Doc {
             scope: cx,
             buffer_id: BufferId::next(),
@@ -251,6 +312,9 @@ pub fn new(
             loaded: cx.create_rw_signal(false),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
+            blame: cx.create_rw_signal(DocBlame::default()),
+            conflicts: cx.create_rw_signal(im::Vector::new()),
+            diff_base: cx.create_rw_signal(None),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
             code_actions: cx.create_rw_signal(im::HashMap::new()),
             find_result: FindResult::new(cx),
@@ -260,6 +324,11 @@ pub fn new(
             code_lens: cx.create_rw_signal(im::HashMap::new()),
             document_symbol_data: cx.create_rw_signal(None),
             folding_ranges: cx.create_rw_signal(FoldingRanges::default()),
+            editor_config: cx.create_rw_signal(EditorConfigProperties::default()),
+            encoding: cx.create_rw_signal("UTF-8".to_string()),
+            large_file: cx.create_rw_signal(false),
+            file_read_only: cx.create_rw_signal(false),
+            binary: cx.create_rw_signal(false),
         }
     }
 
@@ -301,6 +370,9 @@ pub fn new_content(
             content: cx.create_rw_signal(content),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
+            blame: cx.create_rw_signal(DocBlame::default()),
+            conflicts: cx.create_rw_signal(im::Vector::new()),
+            diff_base: cx.create_rw_signal(None),
             sticky_headers: Rc::new(RefCell::new(HashMap::new())),
             loaded: cx.create_rw_signal(true),
             find_result: FindResult::new(cx),
@@ -311,6 +383,11 @@ pub fn new_content(
             code_lens: cx.create_rw_signal(im::HashMap::new()),
             document_symbol_data: cx.create_rw_signal(None),
             folding_ranges: cx.create_rw_signal(FoldingRanges::default()),
+            editor_config: cx.create_rw_signal(EditorConfigProperties::default()),
+            encoding: cx.create_rw_signal("UTF-8".to_string()),
+            large_file: cx.create_rw_signal(false),
+            file_read_only: cx.create_rw_signal(false),
+            binary: cx.create_rw_signal(false),
         }
     }
 
@@ -322,7 +399,9 @@ pub fn new_history(
     ) -> Doc {
         let config = common.config.get_untracked();
         let syntax = if let DocContent::History(history) = &content {
-            Syntax::init(&history.path)
+            Syntax::from_language(
+                config.language_for_path(&history.path).unwrap_or_default(),
+            )
         } else {
             Syntax::plaintext()
         };
@@ -354,6 +433,9 @@ pub fn new_history(
             loaded: cx.create_rw_signal(true),
             histories: cx.create_rw_signal(im::HashMap::new()),
             head_changes: cx.create_rw_signal(im::Vector::new()),
+            blame: cx.create_rw_signal(DocBlame::default()),
+            conflicts: cx.create_rw_signal(im::Vector::new()),
+            diff_base: cx.create_rw_signal(None),
             code_actions: cx.create_rw_signal(im::HashMap::new()),
             find_result: FindResult::new(cx),
             preedit: PreeditData::new(cx),
@@ -362,6 +444,11 @@ pub fn new_history(
             code_lens: cx.create_rw_signal(im::HashMap::new()),
             document_symbol_data: cx.create_rw_signal(None),
             folding_ranges: cx.create_rw_signal(FoldingRanges::default()),
+            editor_config: cx.create_rw_signal(EditorConfigProperties::default()),
+            encoding: cx.create_rw_signal("UTF-8".to_string()),
+            large_file: cx.create_rw_signal(false),
+            file_read_only: cx.create_rw_signal(false),
+            binary: cx.create_rw_signal(false),
         }
     }
 
@@ -424,6 +511,88 @@ pub fn set_syntax(&self, syntax: Syntax) {
         });
     }
 
+    pub fn language(&self) -> LapceLanguage {
+        self.syntax.with_untracked(|syntax| syntax.language)
+    }
+
+    /// Update the `.editorconfig` properties of the document, after the file
+    /// changed on disk.
+    pub fn set_editor_config(&self, editor_config: EditorConfigProperties) {
+        batch(|| {
+            let default = IndentStyle::from_str(self.language().indent_unit());
+            self.buffer.update(|buffer| {
+                // The indentation is detected again, as it wasn't detected
+                // when `.editorconfig` set it before
+                buffer.detect_indent(|| default);
+                if let Some(line_ending) = editor_config_line_ending(&editor_config)
+                {
+                    buffer.set_line_ending(line_ending);
+                }
+            });
+            self.editor_config.set(editor_config);
+            self.clear_text_cache();
+        });
+    }
+
+    /// The indentation of the document from `.editorconfig` if it sets one,
+    /// and otherwise the one detected from the content.
+    pub fn indent_style_or(&self, detected: IndentStyle) -> IndentStyle {
+        self.editor_config.with(|editor_config| {
+            editor_config_indent_style(editor_config, detected)
+        })
+    }
+
+    /// The edits normalizing the whitespace of the document on save, as
+    /// `(start, end, replacement)`: trimming trailing whitespace, trimming the
+    /// blank lines at the end and inserting a final newline. `.editorconfig`
+    /// takes precedence over the editor settings.
+    ///
+    /// Multi-line string literals are left alone, as their whitespace is part
+    /// of their value, so trailing whitespace is only trimmed once the syntax
+    /// tree is up to date.
+    pub fn save_whitespace_edits(
+        &self,
+        config: &EditorConfig,
+    ) -> Vec<(usize, usize, String)> {
+        let (trim_trailing_whitespace, insert_final_newline) =
+            self.editor_config.with_untracked(|editor_config| {
+                (
+                    editor_config
+                        .trim_trailing_whitespace
+                        .unwrap_or(config.trim_trailing_whitespace),
+                    editor_config
+                        .insert_final_newline
+                        .unwrap_or(config.insert_final_newline),
+                )
+            });
+        let trim_final_newlines = config.trim_final_newlines;
+        if !trim_trailing_whitespace && !insert_final_newline && !trim_final_newlines
+        {
+            return Vec::new();
+        }
+
+        self.syntax.with_untracked(|syntax| {
+            self.buffer.with_untracked(|buffer| {
+                // The string literals are only known from an up to date syntax
+                // tree. Parsing again here would block the save, so with a
+                // stale tree, like without one, the trailing whitespace is left
+                // untouched rather than risk changing the value of a string.
+                // Languages without a grammar have no string literals to keep.
+                let has_tree = syntax.rev == buffer.rev()
+                    && syntax.layers.as_ref().and_then(|l| l.try_tree()).is_some();
+                let trim_trailing_whitespace = trim_trailing_whitespace
+                    && (syntax.layers.is_none() || has_tree);
+                whitespace_edits(
+                    buffer,
+                    |offset| syntax.in_multiline_string(offset),
+                    trim_trailing_whitespace,
+                    insert_final_newline,
+                    trim_final_newlines,
+                )
+            })
+        })
+    }
+
     /// Set the syntax highlighting this document should use.
     pub fn set_language(&self, language: LapceLanguage) {
         self.syntax.set(Syntax::from_language(language));
@@ -441,12 +610,28 @@ pub fn loaded(&self) -> bool {
     //// Initialize the content with some text, this marks the document as loaded.
     pub fn init_content(&self, content: Rope) {
         batch(|| {
+            self.detect_language(&content);
+            let editor_config = self.editor_config.get_untracked();
             self.syntax.with_untracked(|syntax| {
                 self.buffer.update(|buffer| {
-                    buffer.init_content(content);
-                    buffer.detect_indent(|| {
-                        IndentStyle::from_str(syntax.language.indent_unit())
-                    });
+                    let default =
+                        IndentStyle::from_str(syntax.language.indent_unit());
+                    if editor_config.indent_style.is_some() {
+                        // The indentation from `.editorconfig` takes precedence, so it's
+                        // "detected" while the buffer is still empty
+                        buffer.detect_indent(|| {
+                            editor_config_indent_style(&editor_config, default)
+                        });
+                        buffer.init_content(content);
+                    } else {
+                        buffer.init_content(content);
+                        buffer.detect_indent(|| default);
+                    }
+                    if let Some(line_ending) =
+                        editor_config_line_ending(&editor_config)
+                    {
+                        buffer.set_line_ending(line_ending);
+                    }
                 });
             });
             self.loaded.set(true);
@@ -457,7 +642,171 @@ pub fn init_content(&self, content: Rope) {
         });
     }
 
+    /// Load the content of a large file from the proxy in chunks on another
+    /// thread, rather than as a single message, then initialize the content,
+    /// or replace it if `reload` after the file changed on disk
+    pub fn load_large_file(&self, reload: bool) {
+        let Some(path) = self.content.with_untracked(|c| c.path().cloned()) else {
+            return;
+        };
+        // The proxy reads the file again first, after which its buffer is at
+        // the revision of the reloaded document
+        let rev = self.rev() + 1;
+        let doc = self.clone();
+        let send = create_ext_action(self.scope, move |content: String| {
+            if !reload {
+                doc.init_content(Rope::from(content));
+            } else if doc.rev() + 1 == rev && doc.is_pristine() {
+                let delta = doc
+                    .buffer
+                    .try_update(|buffer| buffer.reload(Rope::from(content), true))
+                    .unwrap();
+                doc.apply_deltas_with(&[delta], false);
+            }
+        });
+        let proxy = self.common.proxy.clone();
+        std::thread::Builder::new()
+            .name("LoadLargeFile".to_owned())
+            .spawn(move || {
+                if reload {
+                    if let Err(err) = proxy.reload_buffer(path.clone(), rev) {
+                        tracing::error!(
+                            "Failed to reload {path:?}: {}",
+                            err.message
+                        );
+                        return;
+                    }
+                }
+                let mut content = String::new();
+                loop {
+                    match proxy.get_buffer_chunk(path.clone(), content.len()) {
+                        Ok(ProxyResponse::BufferChunkResponse { chunk, done }) => {
+                            content.push_str(&chunk);
+                            if done {
+                                break;
+                            }
+                        }
+                        Ok(_) => return,
+                        Err(err) => {
+                            tracing::error!(
+                                "Failed to load {path:?}: {}",
+                                err.message
+                            );
+                            return;
+                        }
+                    }
+                }
+                send(content);
+            })
+            .unwrap();
+    }
+
+    /// Append the text a large file grew by on disk, which follows its content
+    /// of `offset` bytes, unless the document has unsaved changes
+    pub fn handle_large_file_appended(&self, offset: usize, text: &str) {
+        if !self.is_pristine() {
+            return;
+        }
+        // The text may already be appended in part, if the file changed again
+        // before the previous change was appended
+        let len = self.buffer.with_untracked(|buffer| buffer.len());
+        let Some(text) = len
+            .checked_sub(offset)
+            .and_then(|appended| text.get(appended..))
+        else {
+            return;
+        };
+        if text.is_empty() {
+            return;
+        }
+        let delta = self
+            .buffer
+            .try_update(|buffer| {
+                let delta =
+                    buffer.edit(&[(Selection::caret(len), text)], EditType::Other);
+                buffer.set_pristine();
+                delta
+            })
+            .unwrap();
+        self.apply_deltas(&[delta]);
+    }
+
+    /// Allow editing a large file, which is read-only when it's opened, unless
+    /// the file itself can't be written
+    pub fn enable_large_file_editing(&self) {
+        if !self.large_file.get_untracked() || self.file_read_only.get_untracked() {
+            return;
+        }
+        self.content.update(|content| {
+            if let DocContent::File { read_only, .. } = content {
+                *read_only = false;
+            }
+        });
+    }
+
+    /// Apply the file associations changed from the old config, unless the
+    /// language was chosen for the document since
+    pub fn update_associated_language(&self, old_config: &LapceConfig) {
+        let Some(path) = self.content.with_untracked(|c| c.path().cloned()) else {
+            return;
+        };
+        let old_language = old_config.language_for_path(&path);
+        let language = self.common.config.get_untracked().language_for_path(&path);
+        if language == old_language {
+            return;
+        }
+        if old_language.is_some_and(|old_language| old_language != self.language()) {
+            return;
+        }
+        match language {
+            Some(language) => self.set_language(language),
+            None => {
+                self.set_language(LapceLanguage::PlainText);
+                let content = self.buffer.with_untracked(|b| b.text().clone());
+                self.detect_language(&content);
+            }
+        }
+        self.trigger_syntax_change(None);
+    }
+
+    /// Detect the language from the content of files whose path doesn't
+    /// match any language
+    fn detect_language(&self, content: &Rope) {
+        let path = match self.content.get_untracked() {
+            DocContent::File { path, .. } => path,
+            DocContent::History(history) => history.path,
+            _ => return,
+        };
+        if self
+            .common
+            .config
+            .get_untracked()
+            .language_for_path(&path)
+            .is_some()
+        {
+            return;
+        }
+
+        const DETECTION_LINES: usize = 5;
+        let line_count = content.line_of_offset(content.len()) + 1;
+        let mut lines: Vec<Cow<str>> = content
+            .lines(0..content.len())
+            .take(DETECTION_LINES)
+            .collect();
+        if line_count > DETECTION_LINES {
+            let tail = (line_count - DETECTION_LINES).max(DETECTION_LINES);
+            lines.extend(content.lines(content.offset_of_line(tail)..content.len()));
+        }
+        let lines: Vec<&str> = lines.iter().map(|line| line.as_ref()).collect();
+        if let Some(language) = LapceLanguage::from_content(&lines) {
+            self.set_language(language);
+        }
+    }
+
     fn init_parser(&self) {
+        if self.large_file.get_untracked() {
+            return;
+        }
         let code = self.buffer.get_untracked().to_string();
         self.syntax.with_untracked(|syntax| {
             if syntax.styles.is_some() {
@@ -505,6 +854,7 @@ pub fn do_insert(
         }
 
         let old_cursor = cursor.mode.clone();
+        let editor_config = config.editor.for_language(self.language());
         let deltas = self.syntax.with_untracked(|syntax| {
             self.buffer
                 .try_update(|buffer| {
@@ -515,8 +865,8 @@ pub fn do_insert(
                         &|buffer, c, offset| {
                             syntax_prev_unmatched(buffer, syntax, c, offset)
                         },
-                        config.editor.auto_closing_matching_pairs,
-                        config.editor.auto_surround,
+                        editor_config.auto_closing_matching_pairs,
+                        editor_config.auto_surround,
                     )
                 })
                 .unwrap()
@@ -596,6 +946,16 @@ pub fn do_edit(
     }
 
     pub fn apply_deltas(&self, deltas: &[(Rope, RopeDelta, InvalLines)]) {
+        self.apply_deltas_with(deltas, true);
+    }
+
+    /// Apply the deltas, which are sent to the proxy if `sync_proxy`, unlike
+    /// the reload of a large file the proxy has read already
+    fn apply_deltas_with(
+        &self,
+        deltas: &[(Rope, RopeDelta, InvalLines)],
+        sync_proxy: bool,
+    ) {
         let rev = self.rev() - deltas.len() as u64;
         batch(|| {
             for (i, (_, delta, inval)) in deltas.iter().enumerate() {
@@ -606,11 +966,13 @@ pub fn apply_deltas(&self, deltas: &[(Rope, RopeDelta, InvalLines)]) {
                 self.update_find_result(delta);
                 if let DocContent::File { path, .. } = self.content.get_untracked() {
                     self.update_breakpoints(delta, &path, &inval.old_text);
-                    self.common.proxy.update(
-                        path,
-                        delta.clone(),
-                        rev + i as u64 + 1,
-                    );
+                    if sync_proxy {
+                        self.common.proxy.update(
+                            path,
+                            delta.clone(),
+                            rev + i as u64 + 1,
+                        );
+                    }
                 }
             }
         });
@@ -647,7 +1009,9 @@ pub fn line_ending(&self) -> LineEnding {
         batch(|| {
             self.trigger_syntax_change(edits);
             self.trigger_head_change();
+            self.update_conflicts();
             self.check_auto_save();
+            self.update_blame();
             self.get_inlay_hints();
             self.find_result.reset();
             self.get_semantic_styles();
@@ -661,7 +1025,7 @@ pub fn line_ending(&self) -> LineEnding {
     }
 
     fn do_bracket_colorization(&self) {
-        if self.parser.borrow().active {
+        if self.parser.borrow().active && !self.large_file.get_untracked() {
             self.syntax.with_untracked(|syntax| {
                 if syntax.rev == self.rev() && syntax.styles.is_some() {
                     self.parser.borrow_mut().update_code(
@@ -707,7 +1071,7 @@ fn check_auto_save(&self) {
             let doc = self.clone();
             let scope = self.scope;
             let proxy = self.common.proxy.clone();
-            let format = config.editor.format_on_save;
+            let format = config.editor.for_language(self.language()).format_on_save;
             exec_after(
                 Duration::from_millis(config.editor.autosave_interval),
                 move |_| {
@@ -776,6 +1140,9 @@ fn update_inlay_hints(&self, delta: &RopeDelta) {
     }
 
     pub fn trigger_syntax_change(&self, edits: Option<SmallVec<[SyntaxEdit; 3]>>) {
+        if self.large_file.get_untracked() {
+            return;
+        }
         let (rev, text) =
             self.buffer.with_untracked(|b| (b.rev(), b.text().clone()));
 
@@ -859,7 +1226,25 @@ pub fn line_style(&self, line: usize) -> Arc<Vec<LineStyle>> {
 
     /// Request semantic styles for the buffer from the LSP through the proxy.
     pub fn get_semantic_styles(&self) {
-        if !self.loaded() {
+        if !self.loaded() || self.large_file.get_untracked() {
+            return;
+        }
+
+        // Search results have no grammar, their highlights are found here
+        if self.language() == LapceLanguage::SearchResult {
+            let text = self.buffer.with_untracked(|b| b.to_string());
+            let mut styles = SpansBuilder::new(text.len());
+            let config = self.common.config.get_untracked();
+            let language = |path: &Path| {
+                config
+                    .language_for_path(path)
+                    .unwrap_or(LapceLanguage::PlainText)
+            };
+            for style in search_editor_styles(&text, language) {
+                styles.add_span(Interval::new(style.start, style.end), style.style);
+            }
+            self.semantic_styles.set(Some(styles.build()));
+            self.clear_style_cache();
             return;
         }
 
@@ -922,6 +1307,9 @@ pub fn get_code_lens(&self) {
         self.code_lens.update(|code_lens| {
             code_lens.clear();
         });
+        if self.large_file.get_untracked() {
+            return;
+        }
         let rev = self.rev();
         if let DocContent::File { path, .. } = doc.content.get_untracked() {
             let send = create_ext_action(cx, move |result| {
@@ -964,6 +1352,9 @@ pub fn get_code_lens(&self) {
     }
 
     pub fn get_document_symbol(&self) {
+        if self.large_file.get_untracked() {
+            return;
+        }
         let cx = self.scope;
         let doc = self.clone();
         let rev = self.rev();
@@ -1005,7 +1396,7 @@ pub fn get_document_symbol(&self) {
 
     /// Request inlay hints for the buffer from the LSP through the proxy.
     pub fn get_inlay_hints(&self) {
-        if !self.loaded() {
+        if !self.loaded() || self.large_file.get_untracked() {
             return;
         }
 
@@ -1291,6 +1682,11 @@ pub fn update_find(&self) {
 
     /// Get the sticky headers for a particular line, creating them if necessary.
     pub fn sticky_headers(&self, line: usize) -> Option<Vec<usize>> {
+        // The code glance of the sticky headers isn't shown for a large file,
+        // which has no syntax tree
+        if self.large_file.get_untracked() {
+            return None;
+        }
         if let Some(lines) = self.sticky_headers.borrow().get(&line) {
             return lines.clone();
         }
@@ -1318,19 +1714,67 @@ pub fn head_changes(&self) -> RwSignal<im::Vector<DiffLines>> {
         self.head_changes
     }
 
-    /// Retrieve the `head` version of the buffer
+    /// Load the content of a [`DocContent::History`] document from source control.
+    /// Files which don't exist at that version are loaded as empty.
+    pub fn load_history_content(&self) {
+        let DocContent::History(history) = self.content.get_untracked() else {
+            return;
+        };
+        let doc = self.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            let content = match result {
+                Ok(ProxyResponse::BufferHeadResponse { content, .. }) => content,
+                _ => String::new(),
+            };
+            doc.init_content(Rope::from(content));
+        });
+        if history.version == "head" {
+            self.common
+                .proxy
+                .get_buffer_head(history.path, move |result| {
+                    send(result);
+                });
+        } else {
+            self.common.proxy.git_file_at_revision(
+                history.path,
+                history.version,
+                move |result| {
+                    send(result);
+                },
+            );
+        }
+    }
+
+    /// Retrieve the `head` version of the buffer, which is the version at the
+    /// diff base when one is set
     pub fn retrieve_head(&self) {
         if let DocContent::File { path, .. } = self.content.get_untracked() {
             let histories = self.histories;
+            let diff_base = self.diff_base.get_untracked();
 
             let send = {
                 let path = path.clone();
                 let doc = self.clone();
+                let diff_base = diff_base.clone();
                 create_ext_action(self.scope, move |result| {
-                    if let Ok(ProxyResponse::BufferHeadResponse {
-                        content, ..
-                    }) = result
-                    {
+                    if doc.diff_base.get_untracked() != diff_base {
+                        return;
+                    }
+                    let content = match result {
+                        Ok(ProxyResponse::BufferHeadResponse {
+                            content, ..
+                        }) => Some(content),
+                        // The file didn't exist at the diff base, so all of it
+                        // is new
+                        Err(err)
+                            if diff_base.is_some()
+                                && err.code == RpcError::NOT_FOUND =>
+                        {
+                            Some(String::new())
+                        }
+                        _ => None,
+                    };
+                    if let Some(content) = content {
                         let hisotry = DocumentHistory::new(
                             path.clone(),
                             "head".to_string(),
@@ -1341,20 +1785,129 @@ pub fn retrieve_head(&self) {
                         });
 
                         doc.trigger_head_change();
+                        if doc.blame_enabled() {
+                            doc.get_blame();
+                        }
+                    } else {
+                        // Drop the changes against a previous diff base
+                        histories.update(|histories| {
+                            histories.remove("head");
+                        });
+                        doc.head_changes.set(im::Vector::new());
                     }
                 })
             };
 
             let path = path.clone();
             let proxy = self.common.proxy.clone();
-            std::thread::spawn(move || {
-                proxy.get_buffer_head(path, move |result| {
-                    send(result);
-                });
+            std::thread::spawn(move || match diff_base {
+                Some(revision) => {
+                    proxy.git_file_at_revision(path, revision, move |result| {
+                        send(result);
+                    });
+                }
+                None => {
+                    proxy.get_buffer_head(path, move |result| {
+                        send(result);
+                    });
+                }
+            });
+        }
+    }
+
+    /// Compute the head changes against the revision, or against `HEAD` if
+    /// `None`
+    pub fn set_diff_base(&self, revision: Option<String>) {
+        if self.diff_base.get_untracked() == revision {
+            return;
+        }
+        self.diff_base.set(revision);
+        self.retrieve_head();
+    }
+
+    fn blame_enabled(&self) -> bool {
+        let config = self.common.config.get_untracked();
+        config.editor.enable_inline_blame || config.editor.show_blame_gutter
+    }
+
+    /// Retrieve the git blame once the edits have paused, as it's computed
+    /// for the whole buffer
+    fn update_blame(&self) {
+        if !self.blame_enabled() {
+            return;
+        }
+        let rev = self.rev();
+        let doc = self.clone();
+        exec_after(Duration::from_millis(500), move |_| {
+            let current_rev = doc
+                .buffer
+                .try_with_untracked(|b| b.as_ref().map(|b| b.rev()));
+            if current_rev == Some(rev) {
+                doc.get_blame();
+            }
+        });
+    }
+
+    /// Retrieve the git blame of the buffer at the current revision
+    pub fn get_blame(&self) {
+        if let DocContent::File { path, .. } = self.content.get_untracked() {
+            let rev = self.rev();
+            let doc = self.clone();
+            let send = create_ext_action(self.scope, move |result| {
+                if rev != doc.rev() {
+                    return;
+                }
+                if let Ok(ProxyResponse::GitBlameResponse { hunks, .. }) = result {
+                    doc.blame.set(DocBlame {
+                        rev,
+                        hunks: hunks.into(),
+                    });
+                }
+            });
+            self.common.proxy.git_blame(path, move |result| {
+                send(result);
             });
         }
     }
 
+    /// Scan the text for merge conflicts once the edits have paused, rather
+    /// than on every keystroke
+    fn update_conflicts(&self) {
+        if self.large_file.get_untracked() {
+            if self.conflicts.with_untracked(|c| !c.is_empty()) {
+                self.conflicts.set(im::Vector::new());
+            }
+            return;
+        }
+        let rev = self.rev();
+        let doc = self.clone();
+        exec_after(Duration::from_millis(300), move |_| {
+            let current_rev = doc
+                .buffer
+                .try_with_untracked(|b| b.as_ref().map(|b| b.rev()));
+            if current_rev != Some(rev) {
+                return;
+            }
+            let conflicts: im::Vector<MergeConflict> = doc
+                .buffer
+                .with_untracked(|b| find_conflicts(b.text()))
+                .into();
+            if doc.conflicts.with_untracked(|c| *c != conflicts) {
+                doc.conflicts.set(conflicts);
+            }
+        });
+    }
+
+    /// The conflict block which contains the line, from the current text since
+    /// the conflicts shown might not be scanned since the latest edit yet
+    pub fn conflict_of_line(&self, line: usize) -> Option<MergeConflict> {
+        self.buffer.with_untracked(|b| {
+            find_conflicts(b.text())
+                .into_iter()
+                .find(|conflict| conflict.contains_line(line))
+        })
+    }
+
     pub fn trigger_head_change(&self) {
         let history = if let Some(text) =
             self.histories.with_untracked(|histories| {
@@ -1421,6 +1974,61 @@ pub fn save(&self, after_action: impl FnOnce() + 'static) {
         }
     }
 
+    /// Save the file in another encoding, which it keeps being saved with
+    pub fn save_with_encoding(&self, encoding: String) {
+        let content = self.content.get_untracked();
+        if let DocContent::File { path, .. } = content {
+            let rev = self.rev();
+            let buffer = self.buffer;
+            let doc_encoding = self.encoding;
+            let send = create_ext_action(self.scope, move |result| {
+                if let Ok(ProxyResponse::SaveResponse {}) = result {
+                    let current_rev = buffer.with_untracked(|buffer| buffer.rev());
+                    if current_rev == rev {
+                        buffer.update(|buffer| {
+                            buffer.set_pristine();
+                        });
+                    }
+                    doc_encoding.set(encoding);
+                }
+            });
+
+            self.common.proxy.save_with_encoding(
+                rev,
+                path,
+                encoding.clone(),
+                move |result| {
+                    send(result);
+                },
+            )
+        }
+    }
+
+    /// Read the file again in another encoding, replacing the content as an
+    /// edit which can be undone
+    pub fn reopen_with_encoding(&self, encoding: String) {
+        let content = self.content.get_untracked();
+        if let DocContent::File { path, .. } = content {
+            let doc = self.clone();
+            let send = create_ext_action(self.scope, move |result| {
+                if let Ok(ProxyResponse::ReopenWithEncodingResponse {
+                    content,
+                    encoding,
+                }) = result
+                {
+                    doc.reload(Rope::from(content), true);
+                    doc.encoding.set(encoding);
+                }
+            });
+
+            self.common
+                .proxy
+                .reopen_with_encoding(path, encoding, move |result| {
+                    send(result);
+                })
+        }
+    }
+
     pub fn set_inline_completion(
         &self,
         inline_completion: String,
@@ -1981,12 +2589,25 @@ fn indent_line(&self, _: EditorId, line: usize, line_content: &str) -> usize {
     }
 
     fn tab_width(&self, _: EditorId, _line: usize) -> usize {
-        self.config.with_untracked(|config| config.editor.tab_width)
+        if let Some(tab_width) = self
+            .doc
+            .editor_config
+            .with_untracked(|editor_config| editor_config.tab_width)
+        {
+            return tab_width;
+        }
+        self.config.with_untracked(|config| {
+            config.editor.for_language(self.doc.language()).tab_width
+        })
     }
 
     fn atomic_soft_tabs(&self, _: EditorId, _line: usize) -> bool {
-        self.config
-            .with_untracked(|config| config.editor.atomic_soft_tabs)
+        self.config.with_untracked(|config| {
+            config
+                .editor
+                .for_language(self.doc.language())
+                .atomic_soft_tabs
+        })
     }
 
     fn apply_attr_styles(
@@ -2237,3 +2858,142 @@ fn extra_styles_for_range(
             })
         })
 }
+
+/// The indentation set by `.editorconfig`, keeping `detected` for what it
+/// doesn't set.
+fn editor_config_indent_style(
+    editor_config: &EditorConfigProperties,
+    detected: IndentStyle,
+) -> IndentStyle {
+    let size = editor_config
+        .indent_size
+        .map(|size| size.min(u8::MAX as usize) as u8);
+    match (editor_config.indent_style, detected) {
+        (Some(EditorConfigIndentStyle::Tab), _) => IndentStyle::Tabs,
+        (Some(EditorConfigIndentStyle::Space), IndentStyle::Tabs) => {
+            IndentStyle::Spaces(size.unwrap_or(4))
+        }
+        (_, IndentStyle::Spaces(detected)) => {
+            IndentStyle::Spaces(size.unwrap_or(detected))
+        }
+        (None, IndentStyle::Tabs) => IndentStyle::Tabs,
+    }
+}
+
+fn editor_config_line_ending(
+    editor_config: &EditorConfigProperties,
+) -> Option<LineEnding> {
+    match editor_config.end_of_line? {
+        EndOfLine::Lf => Some(LineEnding::Lf),
+        EndOfLine::Crlf => Some(LineEnding::CrLf),
+        // Lone carriage returns aren't supported by the buffer
+        EndOfLine::Cr => None,
+    }
+}
+
+/// The edits normalizing the whitespace of a buffer, as
+/// `(start, end, replacement)`. The trailing whitespace of the lines ending
+/// inside a multi-line string, as told by `in_multiline_string`, is kept.
+fn whitespace_edits(
+    buffer: &Buffer,
+    in_multiline_string: impl Fn(usize) -> bool,
+    trim_trailing_whitespace: bool,
+    insert_final_newline: bool,
+    trim_final_newlines: bool,
+) -> Vec<(usize, usize, String)> {
+    let newline = match buffer.line_ending() {
+        LineEnding::Lf => "\n",
+        LineEnding::CrLf => "\r\n",
+    };
+
+    let last_line = buffer.last_line();
+    let mut content_line = last_line;
+    while content_line > 0 && buffer.is_line_whitespace(content_line) {
+        content_line -= 1;
+    }
+    // The start of the blank lines at the end of the document, which are
+    // replaced by a single newline
+    let tail = (trim_final_newlines && !buffer.is_line_whitespace(content_line))
+        .then(|| buffer.line_end_offset(content_line, true));
+
+    let mut edits = Vec::new();
+    if trim_trailing_whitespace {
+        let last_line = if tail.is_some() {
+            content_line
+        } else {
+            last_line
+        };
+        for line in 0..=last_line {
+            let start = buffer.offset_of_line(line);
+            let end = buffer.line_end_offset(line, true);
+            let content = buffer.slice_to_cow(start..end);
+            let trimmed = start + content.trim_end_matches([' ', '\t']).len();
+            if trimmed < end && !in_multiline_string(trimmed) {
+                edits.push((trimmed, end, String::new()));
+            }
+        }
+    }
+
+    let len = buffer.len();
+    if let Some(tail) = tail {
+        let replacement = if tail < len || insert_final_newline {
+            newline
+        } else {
+            ""
+        };
+        if buffer.slice_to_cow(tail..len) != replacement {
+            edits.push((tail, len, replacement.to_string()));
+        }
+    } else if insert_final_newline
+        && len > 0
+        && !buffer.slice_to_cow(len - 1..len).ends_with('\n')
+    {
+        edits.push((len, len, newline.to_string()));
+    }
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_core::buffer::Buffer;
+
+    use super::whitespace_edits;
+
+    fn edits(
+        text: &str,
+        strings: &[std::ops::Range<usize>],
+        trim_final_newlines: bool,
+    ) -> Vec<(usize, usize, String)> {
+        let buffer = Buffer::new(text);
+        whitespace_edits(
+            &buffer,
+            |offset| strings.iter().any(|range| range.contains(&offset)),
+            true,
+            true,
+            trim_final_newlines,
+        )
+    }
+
+    #[test]
+    fn test_trim_trailing_whitespace() {
+        assert_eq!(
+            edits("a  \nb\t\n", &[], false),
+            vec![(1, 3, String::new()), (5, 6, String::new())]
+        );
+        assert_eq!(edits("a\nb\n", &[], false), vec![]);
+    }
+
+    #[test]
+    fn test_keep_whitespace_in_multiline_string() {
+        // `r"x  \ny"` where the string spans both lines
+        let text = "s = r\"x  \ny\"  \n";
+        assert_eq!(edits(text, &[4..12], false), vec![(12, 14, String::new())]);
+    }
+
+    #[test]
+    fn test_final_newlines() {
+        assert_eq!(edits("a", &[], false), vec![(1, 1, "\n".to_string())]);
+        assert_eq!(edits("a\n\n\n", &[], true), vec![(1, 4, "\n".to_string())]);
+        assert_eq!(edits("a\n", &[], true), vec![]);
+    }
+}
@@ -42,13 +42,18 @@
         EditCommand, FocusCommand, MotionModeCommand, MultiSelectionCommand,
         ScrollCommand,
     },
-    cursor::{Cursor, CursorMode},
+    cursor::{Cursor, CursorAffinity, CursorMode},
     editor::EditType,
+    language::LapceLanguage,
+    merge_conflict::ConflictResolution,
     mode::{Mode, MotionMode},
     rope_text_pos::RopeTextPosition,
     selection::{InsertDrift, SelRegion, Selection},
 };
-use lapce_rpc::{buffer::BufferId, plugin::PluginId, proxy::ProxyResponse};
+use lapce_rpc::{
+    buffer::BufferId, plugin::PluginId, proxy::ProxyResponse,
+    source_control::BlameHunk,
+};
 use lapce_xi_rope::{Rope, RopeDelta, Transformer};
 use lsp_types::{
     CodeActionResponse, CompletionItem, CompletionTextEdit, GotoDefinitionResponse,
@@ -83,11 +88,17 @@
         implementation_view::{init_implementation_root, map_to_location},
         kind::PanelKind,
     },
+    rebase_todo::cycle_action,
+    search_editor::result_location,
     snippet::Snippet,
+    source_control::blame_hover_markdown,
     tracing::*,
     window_tab::{CommonData, Focus, WindowTabData},
 };
 
+/// The space between the end of the line and the inline blame annotation
+pub const INLINE_BLAME_MARGIN: f64 = 30.0;
+
 pub mod diff;
 pub mod gutter;
 pub mod location;
@@ -423,10 +434,9 @@ fn run_edit_command(&self, cmd: &EditCommand) -> CommandExecuted {
         let text = self.editor.rope_text();
         let is_local = doc.content.with_untracked(|content| content.is_local());
         let modal = self.editor.es.with_untracked(|s| s.modal()) && !is_local;
-        let smart_tab = self
-            .common
-            .config
-            .with_untracked(|config| config.editor.smart_tab);
+        let smart_tab = self.common.config.with_untracked(|config| {
+            config.editor.for_language(doc.language()).smart_tab
+        });
         let doc_before_edit = text.text().clone();
         let mut cursor = self.editor.cursor.get_untracked();
         let mut register = self.common.register.get_untracked();
@@ -1232,8 +1242,43 @@ fn on_screen_find(&self, pattern: &str) -> Vec<SelRegion> {
             .collect()
     }
 
+    /// Jump to the match or the context line under the cursor of a search
+    /// editor
+    fn open_search_result(&self) {
+        let offset = self.cursor().with_untracked(|c| c.offset());
+        let (text, line) = self.doc().buffer.with_untracked(|buffer| {
+            (buffer.to_string(), buffer.line_of_offset(offset))
+        });
+        let Some((path, line)) = result_location(&text, line) else {
+            return;
+        };
+        let path = match self.common.workspace.path.as_ref() {
+            Some(workspace) if path.is_relative() => workspace.join(path),
+            _ => path,
+        };
+        self.common
+            .internal_command
+            .send(InternalCommand::JumpToLocation {
+                location: EditorLocation {
+                    path,
+                    position: Some(EditorPosition::Line(line.saturating_sub(1))),
+                    scroll_offset: None,
+                    ignore_unconfirmed: true,
+                    same_editor_tab: false,
+                },
+            });
+    }
+
     fn go_to_definition(&self) {
         let doc = self.doc();
+        if doc
+            .syntax()
+            .with_untracked(|syntax| syntax.language == LapceLanguage::SearchResult)
+        {
+            self.open_search_result();
+            return;
+        }
+
         let path = match if doc.loaded() {
             doc.content.with_untracked(|c| c.path().cloned())
         } else {
@@ -2111,6 +2156,53 @@ pub fn do_text_edit(&self, edits: &[TextEdit]) {
         self.do_edit(&selection, &edits);
     }
 
+    /// Replace the merge conflict block at the line with the chosen side
+    pub fn resolve_conflict(&self, line: usize, resolution: ConflictResolution) {
+        let doc = self.doc();
+        let Some(conflict) = doc.conflict_of_line(line) else {
+            return;
+        };
+        let (start, end, replacement) = doc
+            .buffer
+            .with_untracked(|buffer| conflict.resolve(buffer.text(), resolution));
+        let selection = Selection::caret(start);
+        self.do_edit(
+            &selection,
+            &[(Selection::region(start, end), replacement.as_str())],
+        );
+    }
+
+    /// Replace the action of the rebase todo lines with a cursor or selection
+    /// by the next one, or the previous one if not `forward`
+    pub fn cycle_rebase_action(&self, forward: bool) {
+        let (selection, edits) = self.doc().buffer.with_untracked(|buffer| {
+            let selection = self.cursor().get_untracked().edit_selection(buffer);
+            let mut lines: Vec<usize> = selection
+                .regions()
+                .iter()
+                .flat_map(|region| {
+                    buffer.line_of_offset(region.min())
+                        ..=buffer.line_of_offset(region.max())
+                })
+                .collect();
+            lines.sort();
+            lines.dedup();
+            let edits = lines
+                .into_iter()
+                .filter_map(|line| {
+                    let start = buffer.offset_of_line(line);
+                    let (len, action) =
+                        cycle_action(&buffer.line_content(line), forward)?;
+                    Some((Selection::region(start, start + len), action))
+                })
+                .collect::<Vec<_>>();
+            (selection, edits)
+        });
+        if !edits.is_empty() {
+            self.do_edit(&selection, &edits);
+        }
+    }
+
     fn apply_deltas(&self, deltas: &[(Rope, RopeDelta, InvalLines)]) {
         if !deltas.is_empty() && !self.confirmed.get_untracked() {
             self.confirmed.set(true);
@@ -2338,15 +2430,35 @@ pub fn save(
             return;
         };
 
+        let editor_config = config.editor.for_language(doc.language());
+
+        // The whitespace normalization is applied as a single edit, before the
+        // formatting, and skipped along with it.
+        if allow_formatting {
+            let edits = doc.save_whitespace_edits(editor_config);
+            if !edits.is_empty() {
+                let selection = doc.buffer.with_untracked(|buffer| {
+                    self.cursor().get_untracked().edit_selection(buffer)
+                });
+                let edits: Vec<_> = edits
+                    .iter()
+                    .map(|(start, end, text)| {
+                        (Selection::region(*start, *end), text.as_str())
+                    })
+                    .collect();
+                self.do_edit(&selection, &edits);
+            }
+        }
+
         // If we are disallowing formatting (such as due to a manual save without formatting),
         // then we skip normalizing line endings as a common reason for that is large files.
         // (but if the save is typical, even if config format_on_save is false, we normalize)
-        if allow_formatting && config.editor.normalize_line_endings {
+        if allow_formatting && editor_config.normalize_line_endings {
             self.run_edit_command(&EditCommand::NormalizeLineEndings);
         }
 
         let rev = doc.rev();
-        let format_on_save = allow_formatting && config.editor.format_on_save;
+        let format_on_save = allow_formatting && editor_config.format_on_save;
         if format_on_save {
             let editor = self.clone();
             let send = create_ext_action(self.scope, move |result| {
@@ -2859,12 +2971,92 @@ pub fn pointer_move(&self, pointer_event: &PointerMoveEvent) {
                         }
                     });
                 mouse_hover_timer.set(timer_token);
+            } else if let Some((start_offset, hunk)) =
+                self.inline_blame_at_point(offset, pointer_event.pos)
+            {
+                let editor = self.clone();
+                let mouse_hover_timer = self.common.mouse_hover_timer;
+                let timer_token =
+                    exec_after(Duration::from_millis(hover_delay), move |token| {
+                        if mouse_hover_timer.try_get_untracked() == Some(token)
+                            && editor.editor_tab_id.try_get_untracked().is_some()
+                        {
+                            editor.update_blame_hover(start_offset, &hunk);
+                        }
+                    });
+                mouse_hover_timer.set(timer_token);
             } else {
                 self.common.mouse_hover_timer.set(TimerToken::INVALID);
             }
         }
     }
 
+    /// The line, line end offset, x position and blame hunk of the inline blame
+    /// annotation, which is shown after the end of the cursor's line
+    pub fn inline_blame(&self) -> Option<(usize, usize, f64, BlameHunk)> {
+        let config = self.common.config.get_untracked();
+        if !config.editor.enable_inline_blame
+            || !self.kind.with_untracked(|kind| kind.is_normal())
+        {
+            return None;
+        }
+        let doc = self.doc();
+        let blame = doc.blame.get_untracked();
+        if blame.rev != doc.rev() {
+            return None;
+        }
+        let offset = self.cursor().with_untracked(|c| c.offset());
+        let (line, col, end_offset) = doc.buffer.with_untracked(|buffer| {
+            let line = buffer.line_of_offset(offset);
+            (
+                line,
+                buffer.line_end_col(line, false),
+                buffer.line_end_offset(line, false),
+            )
+        });
+        let hunk = blame.hunk_of_line(line)?.clone();
+        let x = self
+            .editor
+            .line_point_of_line_col(line, col, CursorAffinity::Backward, true)
+            .x
+            + INLINE_BLAME_MARGIN;
+        Some((line, end_offset, x, hunk))
+    }
+
+    /// The hover offset and blame hunk if the point is over the inline blame
+    /// annotation
+    fn inline_blame_at_point(
+        &self,
+        offset: usize,
+        point: Point,
+    ) -> Option<(usize, BlameHunk)> {
+        let (line, end_offset, x, hunk) = self.inline_blame()?;
+        let doc = self.doc();
+        let pointer_line = doc
+            .buffer
+            .with_untracked(|buffer| buffer.line_of_offset(offset));
+        if pointer_line != line || point.x < x {
+            return None;
+        }
+        let start_offset = doc
+            .buffer
+            .with_untracked(|buffer| buffer.prev_code_boundary(end_offset));
+        Some((start_offset, hunk))
+    }
+
+    fn update_blame_hover(&self, offset: usize, hunk: &BlameHunk) {
+        let Some(markdown) = blame_hover_markdown(hunk) else {
+            return;
+        };
+        let content =
+            parse_markdown(&markdown, 1.8, &self.common.config.get_untracked());
+        let hover_data = self.common.hover.clone();
+        hover_data.content.set(content);
+        hover_data.offset.set(offset);
+        hover_data.editor_id.set(self.id());
+        hover_data.active.set(true);
+    }
+
     #[instrument]
     pub fn pointer_up(&self, pointer_event: &PointerInputEvent) {
         self.editor.pointer_up(pointer_event);
@@ -3262,6 +3454,11 @@ fn check_condition(&self, condition: Condition) -> bool {
                 .doc()
                 .content
                 .with_untracked(|content| !content.is_local()),
+            Condition::RebaseTodoFocus => {
+                self.doc().syntax().with_untracked(|syntax| {
+                    syntax.language == LapceLanguage::GitRebase
+                })
+            }
             Condition::SearchFocus => {
                 self.common.find.visual.get_untracked()
                     && self.find_focus.get_untracked()
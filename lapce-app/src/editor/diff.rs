@@ -14,8 +14,7 @@
     diff::{DiffExpand, DiffLines, expand_diff_lines, rope_diff},
     rope_text::RopeText,
 };
-use lapce_rpc::{buffer::BufferId, proxy::ProxyResponse};
-use lapce_xi_rope::Rope;
+use lapce_rpc::buffer::BufferId;
 use serde::{Deserialize, Serialize};
 
 use super::{EditorData, EditorViewKind};
@@ -61,7 +60,7 @@ pub fn to_data(
                 DocContent::Local => {
                     Rc::new(Doc::new_local(cx, data.editors, common.clone()))
                 }
-                DocContent::History(history) => {
+                DocContent::History(_) => {
                     let doc = Doc::new_history(
                         cx,
                         content.clone(),
@@ -69,26 +68,7 @@ pub fn to_data(
                         common.clone(),
                     );
                     let doc = Rc::new(doc);
-
-                    {
-                        let doc = doc.clone();
-                        let send = create_ext_action(cx, move |result| {
-                            if let Ok(ProxyResponse::BufferHeadResponse {
-                                content,
-                                ..
-                            }) = result
-                            {
-                                doc.init_content(Rope::from(content));
-                            }
-                        });
-                        common.proxy.get_buffer_head(
-                            history.path.clone(),
-                            move |result| {
-                                send(result);
-                            },
-                        );
-                    }
-
+                    doc.load_history_content();
                     doc
                 }
                 DocContent::Scratch { name, .. } => {
@@ -10,7 +10,13 @@
 use serde::{Deserialize, Serialize};
 
 use super::{EditorData, view::changes_colors_screen};
-use crate::config::{LapceConfig, color::LapceColor};
+use crate::{
+    config::{LapceConfig, color::LapceColor},
+    source_control::blame_gutter_text,
+};
+
+/// The number of characters the blame gutter has room for
+const BLAME_GUTTER_CHARS: usize = 30;
 
 pub struct EditorGutterView {
     id: ViewId,
@@ -207,6 +213,90 @@ fn debug_name(&self) -> std::borrow::Cow<'static, str> {
     }
 }
 
+pub struct BlameGutterView {
+    id: ViewId,
+    editor: EditorData,
+}
+
+pub fn blame_gutter_view(editor: EditorData) -> BlameGutterView {
+    BlameGutterView {
+        id: ViewId::new(),
+        editor,
+    }
+}
+
+/// The width of the blame gutter, including its padding
+pub fn blame_gutter_width(config: &LapceConfig) -> f64 {
+    let family: Vec<FamilyOwned> =
+        FamilyOwned::parse_list(&config.editor.font_family).collect();
+    let attrs = Attrs::new()
+        .family(&family)
+        .font_size(config.editor.font_size() as f32);
+    let mut text_layout = TextLayout::new();
+    text_layout.set_text(&"0".repeat(BLAME_GUTTER_CHARS), AttrsList::new(attrs));
+    text_layout.size().width + 20.0
+}
+
+impl View for BlameGutterView {
+    fn id(&self) -> ViewId {
+        self.id
+    }
+
+    fn paint(&mut self, cx: &mut floem::context::PaintCx) {
+        let config = self.editor.common.config.get_untracked();
+        if !config.editor.show_blame_gutter {
+            return;
+        }
+        let doc = self.editor.doc();
+        let blame = doc.blame.get_untracked();
+        if blame.rev != doc.rev() {
+            return;
+        }
+
+        let viewport = self.editor.viewport().get_untracked();
+        let screen_lines = self.editor.screen_lines();
+        let line_height = config.editor.line_height() as f64;
+
+        let family: Vec<FamilyOwned> =
+            FamilyOwned::parse_list(&config.editor.font_family).collect();
+        let attrs = Attrs::new()
+            .family(&family)
+            .color(config.color(LapceColor::EDITOR_DIM))
+            .font_size(config.editor.font_size() as f32);
+        let attrs_list = AttrsList::new(attrs);
+
+        screen_lines.with_untracked(|screen_lines| {
+            // Only the first visible line of each hunk is annotated
+            let mut last_hunk = None;
+            for (line, y) in screen_lines.iter_lines_y() {
+                let Some(hunk) = blame.hunk_of_line(line) else {
+                    continue;
+                };
+                if last_hunk == Some(hunk.start_line) {
+                    continue;
+                }
+                last_hunk = Some(hunk.start_line);
+
+                let text: String = blame_gutter_text(hunk)
+                    .chars()
+                    .take(BLAME_GUTTER_CHARS)
+                    .collect();
+                let mut text_layout = TextLayout::new();
+                text_layout.set_text(&text, attrs_list.clone());
+                let height = text_layout.size().height;
+                cx.draw_text(
+                    &text_layout,
+                    Point::new(10.0, y + (line_height - height) / 2.0 - viewport.y0),
+                );
+            }
+        });
+    }
+
+    fn debug_name(&self) -> std::borrow::Cow<'static, str> {
+        "Blame Gutter".into()
+    }
+}
+
 #[derive(Default, Clone)]
 pub struct FoldingRanges(pub Vec<FoldingRange>);
 
@@ -4,11 +4,12 @@
 
 use floem::{
     Renderer, View, ViewId,
-    action::{set_ime_allowed, set_ime_cursor_area},
+    action::{set_ime_allowed, set_ime_cursor_area, show_context_menu},
     context::{PaintCx, StyleCx},
     event::{Event, EventListener, EventPropagation},
     keyboard::Modifiers,
     kurbo::Stroke,
+    menu::{Menu, MenuItem},
     peniko::{
         Color,
         kurbo::{Line, Point, Rect, Size},
@@ -20,6 +21,7 @@
     },
     style::{CursorColor, CursorStyle, Style, TextColor},
     taffy::prelude::NodeId,
+    text::{Attrs, AttrsList, FamilyOwned, TextLayout},
     views::{
         Decorators, clip, container, dyn_stack,
         editor::{
@@ -44,6 +46,7 @@
 use lapce_core::{
     buffer::{Buffer, diff::DiffLines, rope_text::RopeText},
     cursor::{CursorAffinity, CursorMode},
+    merge_conflict::ConflictResolution,
     selection::SelRegion,
 };
 use lapce_rpc::{
@@ -57,10 +60,16 @@
 use crate::{
     app::clickable_icon,
     command::InternalCommand,
-    config::{LapceConfig, color::LapceColor, editor::WrapStyle, icon::LapceIcons},
+    config::{
+        LapceConfig,
+        color::LapceColor,
+        editor::{EditorConfig, WrapStyle},
+        icon::LapceIcons,
+    },
     debug::{DapData, LapceBreakpoint},
     doc::DocContent,
-    editor::gutter::FoldingDisplayItem,
+    editor::gutter::{FoldingDisplayItem, blame_gutter_view, blame_gutter_width},
+    source_control::blame_annotation,
     text_input::TextInputBuilder,
     window_tab::{CommonData, Focus, WindowTabData},
     workspace::LapceWorkspace,
@@ -73,15 +82,15 @@ pub struct StickyHeaderInfo {
     pub y_diff: f64,
 }
 
-fn editor_wrap(config: &LapceConfig) -> WrapMethod {
+fn editor_wrap(editor: &EditorConfig) -> WrapMethod {
     /// Minimum width that we'll allow the view to be wrapped at.
     const MIN_WRAPPED_WIDTH: f32 = 100.0;
 
-    match config.editor.wrap_style {
+    match editor.wrap_style {
         WrapStyle::None => WrapMethod::None,
         WrapStyle::EditorWidth => WrapMethod::EditorWidth,
         WrapStyle::WrapWidth => WrapMethod::WrapWidth {
-            width: (config.editor.wrap_width as f32).max(MIN_WRAPPED_WIDTH),
+            width: (editor.wrap_width as f32).max(MIN_WRAPPED_WIDTH),
         },
     }
 }
@@ -93,10 +102,13 @@ pub fn editor_style(
 ) -> Style {
     let config = config.get();
     let doc = doc.get();
+    let editor = config
+        .editor
+        .for_language(doc.syntax.with(|syntax| syntax.language));
 
     s.set(
         IndentStyleProp,
-        doc.buffer.with_untracked(Buffer::indent_style),
+        doc.indent_style_or(doc.buffer.with_untracked(Buffer::indent_style)),
     )
     .set(CursorColor, config.color(LapceColor::EDITOR_CARET))
     .set(SelectionColor, config.color(LapceColor::EDITOR_SELECTION))
@@ -112,7 +124,7 @@ pub fn editor_style(
         IndentGuideColor,
         config.color(LapceColor::EDITOR_INDENT_GUIDE),
     )
-    .set(ScrollBeyondLastLine, config.editor.scroll_beyond_last_line)
+    .set(ScrollBeyondLastLine, editor.scroll_beyond_last_line)
     .color(config.color(LapceColor::EDITOR_FOREGROUND))
     .set(TextColor, config.color(LapceColor::EDITOR_FOREGROUND))
     .set(PhantomColor, config.color(LapceColor::EDITOR_DIM))
@@ -121,19 +133,13 @@ pub fn editor_style(
         PreeditUnderlineColor,
         config.color(LapceColor::EDITOR_FOREGROUND),
     )
-    .set(ShowIndentGuide, config.editor.show_indent_guide)
+    .set(ShowIndentGuide, editor.show_indent_guide)
     .set(Modal, config.core.modal)
-    .set(
-        ModalRelativeLine,
-        config.editor.modal_mode_relative_line_numbers,
-    )
-    .set(SmartTab, config.editor.smart_tab)
-    .set(WrapProp, editor_wrap(&config))
-    .set(
-        CursorSurroundingLines,
-        config.editor.cursor_surrounding_lines,
-    )
-    .set(RenderWhitespaceProp, config.editor.render_whitespace)
+    .set(ModalRelativeLine, editor.modal_mode_relative_line_numbers)
+    .set(SmartTab, editor.smart_tab)
+    .set(WrapProp, editor_wrap(editor))
+    .set(CursorSurroundingLines, editor.cursor_surrounding_lines)
+    .set(RenderWhitespaceProp, editor.render_whitespace)
 }
 
 pub struct EditorView {
@@ -164,6 +170,12 @@ pub fn editor_view(
         id.request_layout();
     });
 
+    create_effect(move |_| {
+        let blame = doc.with(|doc| doc.blame);
+        blame.track();
+        id.request_paint();
+    });
+
     let hide_cursor = e_data.common.window_common.hide_cursor;
     create_effect(move |_| {
         hide_cursor.track();
@@ -606,6 +618,104 @@ fn paint_find_region(
         }
     }
 
+    /// Paint the current and incoming changes of merge conflicts with distinct
+    /// backgrounds
+    fn paint_merge_conflicts(
+        &self,
+        cx: &mut PaintCx,
+        viewport: Rect,
+        screen_lines: &ScreenLines,
+        config: &LapceConfig,
+    ) {
+        let conflicts = self.editor.doc().conflicts.get_untracked();
+        if conflicts.is_empty() {
+            return;
+        }
+
+        let line_height = config.editor.line_height() as f64;
+        for line_info in screen_lines.iter_line_info() {
+            let line = line_info.vline_info.rvline.line;
+            let Some(conflict) = conflicts
+                .iter()
+                .find(|conflict| conflict.contains_line(line))
+            else {
+                continue;
+            };
+            let color = if line < conflict.current_end() {
+                LapceColor::SOURCE_CONTROL_CONFLICT_CURRENT
+            } else if line > conflict.separator {
+                LapceColor::SOURCE_CONTROL_CONFLICT_INCOMING
+            } else {
+                continue;
+            };
+            let rect = Rect::from_origin_size(
+                (viewport.x0, line_info.vline_y),
+                (viewport.width(), line_height),
+            );
+            cx.fill(&rect, config.color(color), 0.0);
+        }
+    }
+
+    /// Paint the ruler at the `max_line_length` set by `.editorconfig`
+    fn paint_ruler(&self, cx: &mut PaintCx, viewport: Rect, config: &LapceConfig) {
+        let Some(max_line_length) = self
+            .editor
+            .doc()
+            .editor_config
+            .with_untracked(|editor_config| editor_config.max_line_length)
+        else {
+            return;
+        };
+
+        let family: Vec<FamilyOwned> =
+            FamilyOwned::parse_list(&config.editor.font_family).collect();
+        let attrs = Attrs::new()
+            .family(&family)
+            .font_size(config.editor.font_size() as f32);
+        let mut text_layout = TextLayout::new();
+        text_layout.set_text("W", AttrsList::new(attrs));
+
+        let x = max_line_length as f64 * text_layout.size().width;
+        let line = Line::new(Point::new(x, viewport.y0), Point::new(x, viewport.y1));
+        cx.stroke(
+            &line,
+            config.color(LapceColor::LAPCE_BORDER),
+            &Stroke::new(1.0),
+        );
+    }
+
+    fn paint_inline_blame(
+        &self,
+        cx: &mut PaintCx,
+        screen_lines: &ScreenLines,
+        config: &LapceConfig,
+    ) {
+        let Some((_, end_offset, x, hunk)) = self.editor.inline_blame() else {
+            return;
+        };
+        let ed = &self.editor.editor;
+        let rvline = ed.rvline_of_offset(end_offset, CursorAffinity::Backward);
+        let Some(info) = screen_lines.info(rvline) else {
+            return;
+        };
+
+        let family: Vec<FamilyOwned> =
+            FamilyOwned::parse_list(&config.editor.font_family).collect();
+        let attrs = Attrs::new()
+            .family(&family)
+            .color(config.color(LapceColor::EDITOR_DIM))
+            .font_size(config.editor.font_size() as f32);
+        let mut text_layout = TextLayout::new();
+        text_layout.set_text(&blame_annotation(&hunk), AttrsList::new(attrs));
+
+        let line_height = config.editor.line_height() as f64;
+        let height = text_layout.size().height;
+        cx.draw_text(
+            &text_layout,
+            Point::new(x, info.vline_y + (line_height - height) / 2.0),
+        );
+    }
+
     fn paint_sticky_headers(
         &self,
         cx: &mut PaintCx,
@@ -748,7 +858,11 @@ fn paint_scroll_bar(
         let total_height = viewport.height();
         let total_width = viewport.width();
         let line_height = config.editor.line_height();
-        let content_height = if config.editor.scroll_beyond_last_line {
+        let content_height = if config
+            .editor
+            .for_language(doc.language())
+            .scroll_beyond_last_line
+        {
             (total_len * line_height) as f64 + total_height - line_height as f64
         } else {
             (total_len * line_height) as f64
@@ -971,9 +1085,10 @@ fn paint_bracket_highlights_scope_lines(
         screen_lines: &ScreenLines,
     ) {
         let config = self.editor.common.config.get_untracked();
+        let editor_config = config.editor.for_language(self.editor.doc().language());
 
-        if config.editor.highlight_matching_brackets
-            || config.editor.highlight_scope_lines
+        if editor_config.highlight_matching_brackets
+            || editor_config.highlight_scope_lines
         {
             let e_data = &self.editor;
             let ed = &e_data.editor;
@@ -992,7 +1107,7 @@ fn paint_bracket_highlights_scope_lines(
                 })
             });
 
-            if config.editor.highlight_matching_brackets {
+            if editor_config.highlight_matching_brackets {
                 self.paint_char_highlights(
                     cx,
                     screen_lines,
@@ -1000,7 +1115,7 @@ fn paint_bracket_highlights_scope_lines(
                 );
             }
 
-            if config.editor.highlight_scope_lines {
+            if editor_config.highlight_scope_lines {
                 if let Some([start_line_col, end_line_col]) = bracket_line_cols {
                     self.paint_scope_lines(
                         cx,
@@ -1139,10 +1254,13 @@ fn paint(&mut self, cx: &mut PaintCx) {
         // within the active screen lines without issue.
         let screen_lines = ed.screen_lines.get_untracked();
         self.paint_current_line(cx, is_local, &screen_lines);
+        self.paint_ruler(cx, viewport, &config);
         FloemEditorView::paint_selection(cx, ed, &screen_lines);
         let screen_lines = ed.screen_lines.get_untracked();
         self.paint_diff_sections(cx, viewport, &screen_lines, &config);
         let screen_lines = ed.screen_lines.get_untracked();
+        self.paint_merge_conflicts(cx, viewport, &screen_lines, &config);
+        let screen_lines = ed.screen_lines.get_untracked();
         self.paint_find(cx, &screen_lines);
         let screen_lines = ed.screen_lines.get_untracked();
         self.paint_bracket_highlights_scope_lines(cx, viewport, &screen_lines);
@@ -1156,6 +1274,7 @@ fn paint(&mut self, cx: &mut PaintCx) {
             &screen_lines,
         );
         let screen_lines = ed.screen_lines.get_untracked();
+        self.paint_inline_blame(cx, &screen_lines, &config);
         self.paint_sticky_headers(cx, viewport, &screen_lines);
         self.paint_scroll_bar(cx, viewport, is_local, config);
     }
@@ -1308,6 +1427,16 @@ pub fn editor_container_view(
     stack((
         editor_breadcrumbs(workspace, editor.get_untracked(), config),
         stack((
+            blame_gutter_view(editor.get_untracked()).style(move |s| {
+                let config = config.get();
+                s.width(blame_gutter_width(&config) as f32)
+                    .height_pct(100.0)
+                    .apply_if(
+                        !config.editor.show_blame_gutter
+                            || !editor_view.get().is_normal(),
+                        |s| s.hide(),
+                    )
+            }),
             editor_gutter(window_tab_data.clone(), editor),
             editor_gutter_folding_range(
                 window_tab_data.clone(),
@@ -1732,6 +1861,110 @@ fn editor_gutter_code_lens(
     .debug_name("CodeLens Stack")
 }
 
+fn editor_gutter_conflict_view(
+    e_data: RwSignal<EditorData>,
+    line: usize,
+    screen_lines: RwSignal<ScreenLines>,
+    viewport: RwSignal<Rect>,
+    icon_padding: f32,
+) -> impl View {
+    let config = e_data.with_untracked(|e| e.common.config);
+    let view = container(
+        svg(move || config.get().ui_svg(LapceIcons::SCM_CONFLICT)).style(move |s| {
+            let config = config.get();
+            let size = config.ui.icon_size() as f32;
+            s.size(size, size)
+                .color(config.color(LapceColor::SOURCE_CONTROL_MODIFIED))
+        }),
+    )
+    .style(move |s| {
+        let config = config.get();
+        s.padding(4.0)
+            .border_radius(6.0)
+            .hover(|s| {
+                s.cursor(CursorStyle::Pointer)
+                    .background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+            })
+            .active(|s| {
+                s.background(
+                    config.color(LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND),
+                )
+            })
+    })
+    .on_click_stop(move |_| {
+        let resolve = move |resolution| {
+            move || {
+                e_data.get_untracked().resolve_conflict(line, resolution);
+            }
+        };
+        let menu = Menu::new("")
+            .entry(
+                MenuItem::new("Accept Current Change")
+                    .action(resolve(ConflictResolution::Current)),
+            )
+            .entry(
+                MenuItem::new("Accept Incoming Change")
+                    .action(resolve(ConflictResolution::Incoming)),
+            )
+            .entry(
+                MenuItem::new("Accept Both Changes")
+                    .action(resolve(ConflictResolution::Both)),
+            );
+        show_context_menu(menu, None);
+    });
+    container(view).style(move |s| {
+        let line_info = screen_lines.with(|s| s.info_for_line(line));
+        let line_y = line_info.clone().map(|l| l.y).unwrap_or(-100.0);
+        let rect = viewport.get();
+        let config = config.get();
+        let icon_size = config.ui.icon_size();
+        let width = icon_size as f32 + icon_padding * 2.0;
+        s.absolute()
+            .width(width)
+            .height(config.editor.line_height() as f32)
+            .justify_center()
+            .items_center()
+            .margin_top(line_y as f32 - rect.y0 as f32)
+    })
+}
+
+/// The actions to resolve the merge conflicts, at the start of each conflict
+fn editor_gutter_conflicts(
+    e_data: RwSignal<EditorData>,
+    doc: DocSignal,
+    screen_lines: RwSignal<ScreenLines>,
+    viewport: RwSignal<Rect>,
+    icon_padding: f32,
+) -> impl View {
+    let config = e_data.with_untracked(|e| e.common.config);
+
+    dyn_stack(
+        move || {
+            let doc = doc.get();
+            doc.conflicts.get()
+        },
+        move |conflict| (conflict.start, doc.with_untracked(|doc| doc.rev())),
+        move |conflict| {
+            editor_gutter_conflict_view(
+                e_data,
+                conflict.start,
+                screen_lines,
+                viewport,
+                icon_padding,
+            )
+        },
+    )
+    .style(move |s| {
+        let config = config.get();
+        let width = config.ui.icon_size() as f32 + icon_padding * 2.0;
+        s.absolute()
+            .width(width)
+            .height_full()
+            .margin_left(width - 8.0)
+    })
+    .debug_name("Merge Conflict Stack")
+}
+
 fn editor_gutter_folding_range(
     window_tab_data: Rc<WindowTabData>,
     doc: DocSignal,
@@ -1915,6 +2148,13 @@ fn editor_gutter(
                     viewport,
                     icon_padding,
                 ),
+                editor_gutter_conflicts(
+                    e_data,
+                    doc,
+                    screen_lines,
+                    viewport,
+                    icon_padding,
+                ),
                 editor_gutter_view(e_data.get_untracked(), gutter_padding_right)
                     .on_resize(move |rect| {
                         gutter_rect.set(rect);
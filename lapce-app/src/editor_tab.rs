@@ -26,11 +26,14 @@
         diff::{DiffEditorData, DiffEditorInfo},
         location::EditorLocation,
     },
+    hex_editor::HexEditorData,
     id::{
-        DiffEditorId, EditorTabId, KeymapId, SettingsId, SplitId,
-        ThemeColorSettingsId, VoltViewId,
+        DiffEditorId, EditorTabId, HexEditorId, ImageViewerId, KeymapId,
+        MarkdownPreviewId, SettingsId, SplitId, ThemeColorSettingsId, VoltViewId,
     },
-    main_split::{Editors, MainSplitData},
+    image_viewer::ImageViewerData,
+    main_split::{Editors, FileViews, MainSplitData},
+    markdown_preview::MarkdownPreviewData,
     plugin::PluginData,
     window_tab::WindowTabData,
 };
@@ -43,6 +46,9 @@ pub enum EditorTabChildInfo {
     ThemeColorSettings,
     Keymap,
     Volt(VoltID),
+    HexEditor(PathBuf),
+    ImageViewer(PathBuf),
+    MarkdownPreview(PathBuf),
 }
 
 impl EditorTabChildInfo {
@@ -70,6 +76,17 @@ pub fn to_data(
             EditorTabChildInfo::Volt(id) => {
                 EditorTabChild::Volt(VoltViewId::next(), id.to_owned())
             }
+            EditorTabChildInfo::HexEditor(path) => {
+                data.new_hex_editor(path.to_owned())
+            }
+            EditorTabChildInfo::ImageViewer(path) => {
+                data.new_image_viewer(path.to_owned())
+            }
+            EditorTabChildInfo::MarkdownPreview(path) => {
+                EditorTabChild::MarkdownPreview(
+                    data.new_markdown_preview(path.to_owned()),
+                )
+            }
         }
     }
 }
@@ -124,13 +141,24 @@ pub fn to_data(
 }
 
 pub enum EditorTabChildSource {
-    Editor { path: PathBuf, doc: Rc<Doc> },
-    DiffEditor { left: Rc<Doc>, right: Rc<Doc> },
-    NewFileEditor,
+    Editor {
+        path: PathBuf,
+        doc: Rc<Doc>,
+    },
+    DiffEditor {
+        left: Rc<Doc>,
+        right: Rc<Doc>,
+    },
+    /// A new scratch document, named with the prefix and a number
+    NewFileEditor {
+        name_prefix: &'static str,
+    },
     Settings,
     ThemeColorSettings,
     Keymap,
     Volt(VoltID),
+    HexEditor(PathBuf),
+    ImageViewer(PathBuf),
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -141,6 +169,9 @@ pub enum EditorTabChild {
     ThemeColorSettings(ThemeColorSettingsId),
     Keymap(KeymapId),
     Volt(VoltViewId, VoltID),
+    HexEditor(HexEditorId),
+    ImageViewer(ImageViewerId),
+    MarkdownPreview(MarkdownPreviewId),
 }
 
 #[derive(PartialEq)]
@@ -162,6 +193,9 @@ pub fn id(&self) -> u64 {
             EditorTabChild::ThemeColorSettings(id) => id.to_raw(),
             EditorTabChild::Keymap(id) => id.to_raw(),
             EditorTabChild::Volt(id, _) => id.to_raw(),
+            EditorTabChild::HexEditor(id) => id.to_raw(),
+            EditorTabChild::ImageViewer(id) => id.to_raw(),
+            EditorTabChild::MarkdownPreview(id) => id.to_raw(),
         }
     }
 
@@ -195,6 +229,30 @@ pub fn child_info(&self, data: &WindowTabData) -> EditorTabChildInfo {
             }
             EditorTabChild::Keymap(_) => EditorTabChildInfo::Keymap,
             EditorTabChild::Volt(_, id) => EditorTabChildInfo::Volt(id.to_owned()),
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                let hex_editor = data
+                    .main_split
+                    .hex_editors
+                    .get_untracked(*hex_editor_id)
+                    .unwrap();
+                EditorTabChildInfo::HexEditor(hex_editor.path)
+            }
+            EditorTabChild::ImageViewer(image_viewer_id) => {
+                let image_viewer = data
+                    .main_split
+                    .image_viewers
+                    .get_untracked(*image_viewer_id)
+                    .unwrap();
+                EditorTabChildInfo::ImageViewer(image_viewer.path)
+            }
+            EditorTabChild::MarkdownPreview(markdown_preview_id) => {
+                let markdown_preview = data
+                    .main_split
+                    .markdown_previews
+                    .get_untracked(*markdown_preview_id)
+                    .unwrap();
+                EditorTabChildInfo::MarkdownPreview(markdown_preview.path)
+            }
         }
     }
 
@@ -202,6 +260,9 @@ pub fn view_info(
         &self,
         editors: Editors,
         diff_editors: RwSignal<im::HashMap<DiffEditorId, DiffEditorData>>,
+        hex_editors: FileViews<HexEditorData>,
+        image_viewers: FileViews<ImageViewerData>,
+        markdown_previews: FileViews<MarkdownPreviewData>,
         plugin: PluginData,
         config: ReadSignal<Arc<LapceConfig>>,
     ) -> Memo<EditorTabChildViewInfo> {
@@ -398,6 +459,81 @@ pub fn view_info(
                     is_pristine: true,
                 }
             }),
+            EditorTabChild::HexEditor(hex_editor_id) => create_memo(move |_| {
+                let config = config.get();
+                let hex_editor = hex_editors.get(hex_editor_id);
+                let path = hex_editor.as_ref().map(|data| data.path.clone());
+                let is_pristine =
+                    hex_editor.map(|data| data.pristine.get()).unwrap_or(true);
+                let (icon, color) = match &path {
+                    Some(path) => config.file_svg(path),
+                    None => (
+                        config.ui_svg(LapceIcons::FILE),
+                        Some(config.color(LapceColor::LAPCE_ICON_ACTIVE)),
+                    ),
+                };
+                let name = path
+                    .as_ref()
+                    .and_then(|path| path.file_name())
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned();
+                EditorTabChildViewInfo {
+                    icon,
+                    color,
+                    name: format!("{name} (Hex)"),
+                    path,
+                    confirmed: None,
+                    is_pristine,
+                }
+            }),
+            EditorTabChild::ImageViewer(image_viewer_id) => create_memo(move |_| {
+                let config = config.get();
+                let path = image_viewers.get(image_viewer_id).map(|data| data.path);
+                let (icon, color) = match &path {
+                    Some(path) => config.file_svg(path),
+                    None => (
+                        config.ui_svg(LapceIcons::FILE),
+                        Some(config.color(LapceColor::LAPCE_ICON_ACTIVE)),
+                    ),
+                };
+                let name = path
+                    .as_ref()
+                    .and_then(|path| path.file_name())
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned();
+                EditorTabChildViewInfo {
+                    icon,
+                    color,
+                    name,
+                    path,
+                    confirmed: None,
+                    is_pristine: true,
+                }
+            }),
+            EditorTabChild::MarkdownPreview(markdown_preview_id) => {
+                create_memo(move |_| {
+                    let config = config.get();
+                    let path = markdown_previews
+                        .get(markdown_preview_id)
+                        .map(|data| data.path);
+                    let name = path
+                        .as_ref()
+                        .and_then(|path| path.file_name())
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .into_owned();
+                    EditorTabChildViewInfo {
+                        icon: config.ui_svg(LapceIcons::FILE),
+                        color: Some(config.color(LapceColor::LAPCE_ICON_ACTIVE)),
+                        name: format!("Preview {name}"),
+                        path,
+                        confirmed: None,
+                        is_pristine: true,
+                    }
+                })
+            }
         }
     }
 }
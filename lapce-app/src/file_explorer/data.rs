@@ -613,6 +613,15 @@ pub fn secondary_click(&self, path: &Path) {
 
         menu = menu.separator();
 
+        let path = base_path_a.clone();
+        let internal_command = common.internal_command;
+        menu = menu.entry(MenuItem::new("Search in Folder").action(move || {
+            internal_command
+                .send(InternalCommand::SearchInFolder { path: path.clone() });
+        }));
+
+        menu = menu.separator();
+
         let path = path_a.clone();
         menu = menu.entry(MenuItem::new("Copy Path").action(move || {
             let mut clipboard = SystemClipboard::new();
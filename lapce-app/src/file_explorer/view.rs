@@ -1,4 +1,9 @@
-use std::{path::Path, rc::Rc, sync::Arc};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+};
 
 use floem::{
     View,
@@ -6,7 +11,8 @@
     kurbo::Rect,
     peniko::Color,
     reactive::{
-        ReadSignal, RwSignal, SignalGet, SignalUpdate, SignalWith, create_rw_signal,
+        Memo, ReadSignal, RwSignal, SignalGet, SignalUpdate, SignalWith,
+        create_memo, create_rw_signal,
     },
     style::{AlignItems, CursorStyle, Position, Style},
     text::Style as FontStyle,
@@ -18,22 +24,23 @@
 use lapce_core::selection::Selection;
 use lapce_rpc::{
     file::{FileNodeViewData, FileNodeViewKind, Naming},
-    source_control::FileDiffKind,
+    source_control::{DiffInfo, FileDiffKind},
 };
 use lapce_xi_rope::Rope;
+use lsp_types::DiagnosticSeverity;
 
 use super::{data::FileExplorerData, node::FileNodeVirtualList};
 use crate::{
     app::clickable_icon,
     command::InternalCommand,
     config::{LapceConfig, color::LapceColor, icon::LapceIcons},
+    doc::DiagnosticData,
     editor_tab::{EditorTabChild, EditorTabData},
     panel::{
         data::PanelSection, kind::PanelKind, position::PanelPosition,
         view::PanelBuilder,
     },
     plugin::PluginData,
-    source_control::SourceControlData,
     text_input::TextInputBuilder,
     window_tab::{Focus, WindowTabData},
 };
@@ -60,13 +67,131 @@ fn blend_colors(background: Color, foreground: Color) -> Color {
     Color::from_rgba8(r, g, b, 255)
 }
 
+/// The git status a file explorer node is colored by, from the lowest priority
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum GitDecoration {
+    Ignored,
+    Added,
+    Untracked,
+    Deleted,
+    Modified,
+    Conflicted,
+}
+
+/// The status of the changed files of the workspace's repository and its
+/// submodules. The folders containing them are modified, or conflicted if one
+/// of their files is.
+fn git_decorations(
+    repositories: &im::Vector<DiffInfo>,
+) -> HashMap<PathBuf, GitDecoration> {
+    let mut decorations = HashMap::new();
+    let mut insert = |path: &Path, decoration: GitDecoration| {
+        let current = decorations.entry(path.to_path_buf()).or_insert(decoration);
+        *current = (*current).max(decoration);
+    };
+    for diff in repositories {
+        let files = diff
+            .diffs
+            .iter()
+            .map(|diff| {
+                let decoration = match diff.kind() {
+                    FileDiffKind::Added => GitDecoration::Added,
+                    FileDiffKind::Deleted => GitDecoration::Deleted,
+                    FileDiffKind::Modified | FileDiffKind::Renamed => {
+                        GitDecoration::Modified
+                    }
+                };
+                (diff.path(), decoration)
+            })
+            .chain(
+                diff.untracked
+                    .iter()
+                    .map(|path| (path, GitDecoration::Untracked)),
+            )
+            .chain(
+                diff.conflicts
+                    .iter()
+                    .map(|path| (path, GitDecoration::Conflicted)),
+            );
+        for (path, decoration) in files {
+            insert(path, decoration);
+            let folder_decoration = decoration.max(GitDecoration::Modified);
+            for folder in path.ancestors().skip(1) {
+                insert(folder, folder_decoration);
+            }
+        }
+        for path in &diff.ignored {
+            insert(path, GitDecoration::Ignored);
+        }
+    }
+    decorations
+}
+
+/// The git status of the file, which is ignored if it's in an ignored folder
+fn git_decoration(
+    decorations: &HashMap<PathBuf, GitDecoration>,
+    path: &Path,
+) -> Option<GitDecoration> {
+    decorations.get(path).copied().or_else(|| {
+        path.ancestors()
+            .skip(1)
+            .filter_map(|folder| decorations.get(folder))
+            .find(|decoration| **decoration == GitDecoration::Ignored)
+            .copied()
+    })
+}
+
+/// The number of errors and warnings of the files, and of the folders
+/// containing them
+fn diagnostic_counts(
+    diagnostics: &im::HashMap<PathBuf, DiagnosticData>,
+) -> HashMap<PathBuf, (usize, usize)> {
+    let mut counts: HashMap<PathBuf, (usize, usize)> = HashMap::new();
+    for (path, data) in diagnostics {
+        let (errors, warnings) = data.diagnostics.with(|diagnostics| {
+            let count = |severity| {
+                diagnostics
+                    .iter()
+                    .filter(|d| d.severity == Some(severity))
+                    .count()
+            };
+            (
+                count(DiagnosticSeverity::ERROR),
+                count(DiagnosticSeverity::WARNING),
+            )
+        });
+        if errors == 0 && warnings == 0 {
+            continue;
+        }
+        for path in path.ancestors() {
+            let count = counts.entry(path.to_path_buf()).or_default();
+            count.0 += errors;
+            count.1 += warnings;
+        }
+    }
+    counts
+}
+
+/// The decorations of the file explorer nodes, which update with the status of
+/// the repositories and the diagnostics
+#[derive(Clone, Copy)]
+struct FileDecorations {
+    git: Memo<HashMap<PathBuf, GitDecoration>>,
+    diagnostics: Memo<HashMap<PathBuf, (usize, usize)>>,
+}
+
 pub fn file_explorer_panel(
     window_tab_data: Rc<WindowTabData>,
     position: PanelPosition,
 ) -> impl View {
     let config = window_tab_data.common.config;
     let data = window_tab_data.file_explorer.clone();
-    let source_control = window_tab_data.source_control.clone();
+    let repositories = window_tab_data.source_control.repositories;
+    let diagnostics = window_tab_data.main_split.diagnostics;
+    let decorations = FileDecorations {
+        git: create_memo(move |_| repositories.with(git_decorations)),
+        diagnostics: create_memo(move |_| diagnostics.with(diagnostic_counts)),
+    };
     PanelBuilder::new(config, position)
         .add_height_style(
             "Open Editors",
@@ -78,7 +203,7 @@ pub fn file_explorer_panel(
         )
         .add(
             "File Explorer",
-            container(file_explorer_view(data, source_control))
+            container(file_explorer_view(data, decorations))
                 .style(|s| s.size_full()),
             window_tab_data
                 .panel
@@ -125,46 +250,79 @@ fn initialize_naming_editor(
 
 fn file_node_text_color(
     config: ReadSignal<Arc<LapceConfig>>,
-    node: FileNodeViewData,
-    source_control: SourceControlData,
+    path: &Path,
+    decorations: FileDecorations,
 ) -> Color {
-    let diff = source_control.file_diffs.with(|file_diffs| {
-        let FileNodeViewKind::Path(path) = &node.kind else {
-            return None;
-        };
-
-        if node.is_dir {
-            file_diffs
-                .keys()
-                .find(|p| p.as_path().starts_with(path))
-                .map(|_| FileDiffKind::Modified)
-        } else {
-            file_diffs.get(path).map(|(diff, _)| diff.kind())
-        }
-    });
-
-    let color = match diff {
-        Some(FileDiffKind::Modified | FileDiffKind::Renamed) => {
-            LapceColor::SOURCE_CONTROL_MODIFIED
-        }
-        Some(FileDiffKind::Added) => LapceColor::SOURCE_CONTROL_ADDED,
-        Some(FileDiffKind::Deleted) => LapceColor::SOURCE_CONTROL_REMOVED,
+    let git = decorations.git.with(|git| git_decoration(git, path));
+    let (errors, warnings) = decorations
+        .diagnostics
+        .with(|diagnostics| diagnostics.get(path).copied())
+        .unwrap_or_default();
+
+    let color = match git {
+        Some(GitDecoration::Conflicted) => LapceColor::SOURCE_CONTROL_CONFLICTED,
+        _ if errors > 0 => LapceColor::LAPCE_ERROR,
+        _ if warnings > 0 => LapceColor::LAPCE_WARN,
+        Some(GitDecoration::Modified) => LapceColor::SOURCE_CONTROL_MODIFIED,
+        Some(GitDecoration::Added) => LapceColor::SOURCE_CONTROL_ADDED,
+        Some(GitDecoration::Untracked) => LapceColor::SOURCE_CONTROL_UNTRACKED,
+        Some(GitDecoration::Deleted) => LapceColor::SOURCE_CONTROL_REMOVED,
+        Some(GitDecoration::Ignored) => LapceColor::SOURCE_CONTROL_IGNORED,
         None => LapceColor::PANEL_FOREGROUND,
     };
 
     config.get().color(color)
 }
 
+/// The number of errors of the file or the files in the folder, or of warnings
+/// if there are no errors
+fn file_node_diagnostic_badge(
+    config: ReadSignal<Arc<LapceConfig>>,
+    path: PathBuf,
+    decorations: FileDecorations,
+) -> impl View {
+    let counts = move || {
+        decorations
+            .diagnostics
+            .with(|diagnostics| diagnostics.get(&path).copied())
+            .unwrap_or_default()
+    };
+    label(move || {
+        let (errors, warnings) = counts();
+        if errors > 0 {
+            errors.to_string()
+        } else {
+            warnings.to_string()
+        }
+    })
+    .style(move |s| {
+        let (errors, warnings) = counts();
+        let config = config.get();
+        let color = if errors > 0 {
+            LapceColor::LAPCE_ERROR
+        } else {
+            LapceColor::LAPCE_WARN
+        };
+        s.margin_left(6.0)
+            .color(config.color(color))
+            .apply_if(errors == 0 && warnings == 0, |s| s.hide())
+            .selectable(false)
+    })
+}
+
 fn file_node_text_view(
     data: FileExplorerData,
     node: FileNodeViewData,
-    source_control: SourceControlData,
+    decorations: FileDecorations,
 ) -> impl View {
     let config = data.common.config;
     let ui_line_height = data.common.ui_line_height;
 
     match node.kind.clone() {
         FileNodeViewKind::Path(path) => {
+            let color_path = path.clone();
+            let badge =
+                file_node_diagnostic_badge(config, path.clone(), decorations);
             if node.is_root {
                 let file = path.clone();
                 container((
@@ -177,8 +335,8 @@ fn file_node_text_view(
                         s.height(ui_line_height.get())
                             .color(file_node_text_color(
                                 config,
-                                node.clone(),
-                                source_control.clone(),
+                                &color_path,
+                                decorations,
                             ))
                             .padding_right(5.0)
                             .selectable(false)
@@ -194,9 +352,10 @@ fn file_node_text_view(
                                 .selectable(false)
                         },
                     ),
+                    badge,
                 ))
             } else {
-                container(
+                container((
                     label(move || {
                         path.file_name()
                             .map(|f| f.to_string_lossy().to_string())
@@ -206,12 +365,13 @@ fn file_node_text_view(
                         s.height(ui_line_height.get())
                             .color(file_node_text_color(
                                 config,
-                                node.clone(),
-                                source_control.clone(),
+                                &color_path,
+                                decorations,
                             ))
                             .selectable(false)
                     }),
-                )
+                    badge,
+                ))
             }
         }
         FileNodeViewKind::Renaming { path, err } => {
@@ -306,7 +466,7 @@ fn file_node_input_view(data: FileExplorerData, err: Option<String>) -> Containe
 
 fn file_explorer_view(
     data: FileExplorerData,
-    source_control: SourceControlData,
+    decorations: FileDecorations,
 ) -> impl View {
     let root = data.root;
     let ui_line_height = data.common.ui_line_height;
@@ -395,7 +555,7 @@ fn file_explorer_view(
                                 })
                         })
                     },
-                    file_node_text_view(data, node, source_control.clone()),
+                    file_node_text_view(data, node, decorations),
                 ))
                 .style({
                     let kind = kind.clone();
@@ -495,6 +655,9 @@ fn file_explorer_view(
 
 fn open_editors_view(window_tab_data: Rc<WindowTabData>) -> impl View {
     let diff_editors = window_tab_data.main_split.diff_editors;
+    let hex_editors = window_tab_data.main_split.hex_editors;
+    let image_viewers = window_tab_data.main_split.image_viewers;
+    let markdown_previews = window_tab_data.main_split.markdown_previews;
     let editors = window_tab_data.main_split.editors;
     let editor_tabs = window_tab_data.main_split.editor_tabs;
     let config = window_tab_data.common.config;
@@ -509,7 +672,15 @@ fn open_editors_view(window_tab_data: Rc<WindowTabData>) -> impl View {
         let editor_tab_id =
             editor_tab.with_untracked(|editor_tab| editor_tab.editor_tab_id);
         let child_for_close = child.clone();
-        let info = child.view_info(editors, diff_editors, plugin, config);
+        let info = child.view_info(
+            editors,
+            diff_editors,
+            hex_editors,
+            image_viewers,
+            markdown_previews,
+            plugin,
+            config,
+        );
         let hovered = create_rw_signal(false);
 
         stack((
@@ -1,4 +1,10 @@
-use std::{ops::Range, path::PathBuf, rc::Rc};
+use std::{
+    collections::HashMap,
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::atomic::{AtomicU64, Ordering},
+};
 
 use floem::{
     ext_event::create_ext_action,
@@ -7,15 +13,26 @@
     views::VirtualVector,
 };
 use indexmap::IndexMap;
-use lapce_core::{mode::Mode, selection::Selection};
-use lapce_rpc::proxy::{ProxyResponse, SearchMatch};
+use lapce_core::{
+    buffer::rope_text::RopeText, editor::EditType, language::LapceLanguage,
+    mode::Mode, search::SearchPattern, selection::Selection, syntax::Syntax,
+};
+use lapce_rpc::proxy::{
+    ProxyResponse, SearchEditorParams, SearchFilter, SearchMatch,
+};
 use lapce_xi_rope::Rope;
 
 use crate::{
     command::{CommandExecuted, CommandKind},
+    doc::{Doc, DocContent},
     editor::EditorData,
+    editor_tab::EditorTabChild,
     keypress::{KeyPressFocus, condition::Condition},
     main_split::MainSplitData,
+    search_editor::{
+        SEARCH_EDITOR_PREFIX, SearchEditorQuery, results_summary,
+        search_editor_content,
+    },
     window_tab::CommonData,
 };
 
@@ -38,10 +55,44 @@ pub fn height(&self) -> f64 {
     }
 }
 
+/// The input of the search panel which has the focus
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SearchInput {
+    Search,
+    Replace,
+    Include,
+    Exclude,
+}
+
 #[derive(Clone, Debug)]
 pub struct GlobalSearchData {
     pub editor: EditorData,
+    /// The text replacing the matches
+    pub replace_editor: EditorData,
+    /// Whether the replace field and the preview of the replacements are shown
+    pub replace_visible: RwSignal<bool>,
+    /// Comma separated globs of the files to search
+    pub include_editor: EditorData,
+    /// Comma separated globs of the files not to search
+    pub exclude_editor: EditorData,
+    /// Whether the include and exclude fields are shown
+    pub filters_visible: RwSignal<bool>,
+    /// Also search the hidden files and the files ignored by ignore files
+    pub include_ignored: RwSignal<bool>,
+    /// Only search the files open in editors
+    pub only_open_editors: RwSignal<bool>,
+    pub input_focus: RwSignal<SearchInput>,
     pub search_result: RwSignal<IndexMap<PathBuf, SearchMatchData>>,
+    /// The id of the latest search, whose results are streamed by the proxy
+    pub search_id: RwSignal<u64>,
+    /// Whether the proxy is still searching
+    pub searching: RwSignal<bool>,
+    /// The number of matches found so far
+    pub result_count: RwSignal<usize>,
+    /// Whether the search stopped at the limit of matches of the proxy
+    pub limit_hit: RwSignal<bool>,
+    /// The matches received so far of the searches of search editors
+    editor_searches: RwSignal<HashMap<u64, IndexMap<PathBuf, Vec<SearchMatch>>>>,
     pub main_split: MainSplitData,
     pub common: Rc<CommonData>,
 }
@@ -68,7 +119,7 @@ fn run_command(
             CommandKind::Edit(_)
             | CommandKind::Move(_)
             | CommandKind::MultiSelection(_) => {
-                return self.editor.run_command(command, count, mods);
+                return self.active_editor().run_command(command, count, mods);
             }
             CommandKind::MotionMode(_) => {}
         }
@@ -76,7 +127,7 @@ fn run_command(
     }
 
     fn receive_char(&self, c: &str) {
-        self.editor.receive_char(c);
+        self.active_editor().receive_char(c);
     }
 }
 
@@ -108,46 +159,35 @@ impl GlobalSearchData {
     pub fn new(cx: Scope, main_split: MainSplitData) -> Self {
         let common = main_split.common.clone();
         let editor = main_split.editors.make_local(cx, common.clone());
+        let replace_editor = main_split.editors.make_local(cx, common.clone());
+        let include_editor = main_split.editors.make_local(cx, common.clone());
+        let exclude_editor = main_split.editors.make_local(cx, common.clone());
         let search_result = cx.create_rw_signal(IndexMap::new());
 
         let global_search = Self {
             editor,
+            replace_editor,
+            replace_visible: cx.create_rw_signal(false),
+            include_editor,
+            exclude_editor,
+            filters_visible: cx.create_rw_signal(false),
+            include_ignored: cx.create_rw_signal(false),
+            only_open_editors: cx.create_rw_signal(false),
+            input_focus: cx.create_rw_signal(SearchInput::Search),
             search_result,
+            search_id: cx.create_rw_signal(0),
+            searching: cx.create_rw_signal(false),
+            result_count: cx.create_rw_signal(0),
+            limit_hit: cx.create_rw_signal(false),
+            editor_searches: cx.create_rw_signal(HashMap::new()),
             main_split,
             common,
         };
 
         {
             let global_search = global_search.clone();
-            let buffer = global_search.editor.doc().buffer;
             cx.create_effect(move |_| {
-                let pattern = buffer.with(|buffer| buffer.to_string());
-                if pattern.is_empty() {
-                    global_search.search_result.update(|r| r.clear());
-                    return;
-                }
-                let case_sensitive = global_search.common.find.case_sensitive(true);
-                let whole_word = global_search.common.find.whole_words.get();
-                let is_regex = global_search.common.find.is_regex.get();
-                let send = {
-                    let global_search = global_search.clone();
-                    create_ext_action(cx, move |result| {
-                        if let Ok(ProxyResponse::GlobalSearchResponse { matches }) =
-                            result
-                        {
-                            global_search.update_matches(matches);
-                        }
-                    })
-                };
-                global_search.common.proxy.global_search(
-                    pattern,
-                    case_sensitive,
-                    whole_word,
-                    is_regex,
-                    move |result| {
-                        send(result);
-                    },
-                );
+                global_search.search();
             });
         }
 
@@ -163,34 +203,418 @@ pub fn new(cx: Scope, main_split: MainSplitData) -> Self {
         global_search
     }
 
-    fn update_matches(&self, matches: IndexMap<PathBuf, Vec<SearchMatch>>) {
-        let current = self.search_result.get_untracked();
-
-        self.search_result.set(
-            matches
-                .into_iter()
-                .map(|(path, matches)| {
-                    let match_data =
-                        current.get(&path).cloned().unwrap_or_else(|| {
-                            SearchMatchData {
-                                expanded: self.common.scope.create_rw_signal(true),
-                                matches: self
-                                    .common
-                                    .scope
-                                    .create_rw_signal(im::Vector::new()),
-                                line_height: self.common.ui_line_height,
-                            }
-                        });
-
-                    match_data.matches.set(matches.into());
-
-                    (path, match_data)
-                })
-                .collect(),
+    /// Search the workspace for the pattern, which is redone whenever the
+    /// pattern, the search options or the filters change
+    fn search(&self) {
+        let pattern = self.editor.doc().buffer.with(|buffer| buffer.to_string());
+        let search_id = next_search_id();
+        self.search_id.set(search_id);
+        self.search_result.update(|r| r.clear());
+        self.result_count.set(0);
+        self.limit_hit.set(false);
+        self.searching.set(!pattern.is_empty());
+        if pattern.is_empty() {
+            return;
+        }
+        let case_sensitive = self.common.find.case_sensitive(true);
+        let whole_word = self.common.find.whole_words.get();
+        let is_regex = self.common.find.is_regex.get();
+        let filter = self.filter();
+        let send = {
+            let global_search = self.clone();
+            create_ext_action(self.common.scope, move |result| {
+                if global_search.search_id.get_untracked() != search_id {
+                    return;
+                }
+                global_search.searching.set(false);
+                if let Ok(ProxyResponse::GlobalSearchResponse { count, limit_hit }) =
+                    result
+                {
+                    global_search.result_count.set(count);
+                    global_search.limit_hit.set(limit_hit);
+                }
+            })
+        };
+        self.common.proxy.global_search(
+            search_id,
+            pattern,
+            case_sensitive,
+            whole_word,
+            is_regex,
+            filter,
+            None,
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    /// Open the results of the search in a search editor, with the lines
+    /// around the matches
+    pub fn open_search_editor(&self) {
+        let text = |editor: &EditorData| {
+            editor
+                .doc()
+                .buffer
+                .with_untracked(|buffer| buffer.to_string())
+        };
+        let query = SearchEditorQuery {
+            pattern: text(&self.editor),
+            case_sensitive: self.common.find.case_sensitive(false),
+            whole_word: self.common.find.whole_words.get_untracked(),
+            is_regex: self.common.find.is_regex.get_untracked(),
+            include_ignored: self.include_ignored.get_untracked(),
+            include: text(&self.include_editor),
+            exclude: text(&self.exclude_editor),
+            context_lines: self
+                .common
+                .config
+                .get_untracked()
+                .ui
+                .search_editor_context_lines,
+        };
+        if query.pattern.is_empty() {
+            return;
+        }
+
+        let EditorTabChild::Editor(editor_id) =
+            self.main_split.new_scratch_file(SEARCH_EDITOR_PREFIX)
+        else {
+            return;
+        };
+        let Some(editor) = self.main_split.editors.editor_untracked(editor_id)
+        else {
+            return;
+        };
+        let doc = editor.doc();
+        doc.set_syntax(Syntax::from_language(LapceLanguage::SearchResult));
+        doc.trigger_syntax_change(None);
+        self.run_search_editor(doc, query);
+    }
+
+    /// Run the search of the search editor again, from the query in its
+    /// header
+    pub fn rerun_search_editor(&self, doc: Rc<Doc>) {
+        let text = doc.buffer.with_untracked(|buffer| buffer.to_string());
+        if let Some(query) = SearchEditorQuery::parse(&text) {
+            self.run_search_editor(doc, query);
+        }
+    }
+
+    /// Search the workspace and replace the content of the search editor with
+    /// the results once the search is done
+    fn run_search_editor(&self, doc: Rc<Doc>, query: SearchEditorQuery) {
+        let DocContent::Scratch { id: buffer_id, .. } = doc.content.get_untracked()
+        else {
+            return;
+        };
+        let search_id = next_search_id();
+        self.editor_searches.update(|searches| {
+            searches.insert(search_id, IndexMap::new());
+        });
+        let searching = format!("{}\nSearching...\n", query.header());
+        doc.reload(Rope::from(searching), false);
+
+        let send = {
+            let editor_searches = self.editor_searches;
+            let workspace = self.common.workspace.path.clone();
+            let query = query.clone();
+            create_ext_action(self.common.scope, move |result| {
+                let mut results = IndexMap::new();
+                editor_searches.update(|searches| {
+                    results = searches.remove(&search_id).unwrap_or_default();
+                });
+                let summary = match result {
+                    Ok(ProxyResponse::GlobalSearchResponse { count, limit_hit }) => {
+                        results_summary(count, results.len(), limit_hit)
+                    }
+                    Ok(_) => return,
+                    Err(err) => format!("The search failed: {}", err.message),
+                };
+                let content = search_editor_content(
+                    &query,
+                    &summary,
+                    &results,
+                    workspace.as_deref(),
+                );
+                doc.reload(Rope::from(content), false);
+            })
+        };
+        self.common.proxy.global_search(
+            search_id,
+            query.pattern.clone(),
+            query.case_sensitive,
+            query.whole_word,
+            query.is_regex,
+            query.filter(),
+            Some(SearchEditorParams {
+                buffer_id,
+                context_lines: query.context_lines,
+            }),
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    /// The files to search, from the filter fields and toggles
+    fn filter(&self) -> SearchFilter {
+        let globs = |editor: &EditorData| {
+            editor.doc().buffer.with(|buffer| {
+                buffer
+                    .to_string()
+                    .split(',')
+                    .map(|glob| glob.trim().to_string())
+                    .filter(|glob| !glob.is_empty())
+                    .collect()
+            })
+        };
+        let files = self.only_open_editors.get().then(|| {
+            self.main_split
+                .docs
+                .with(|docs| docs.keys().cloned().collect())
+        });
+        SearchFilter {
+            include: globs(&self.include_editor),
+            exclude: globs(&self.exclude_editor),
+            include_ignored: self.include_ignored.get(),
+            files,
+        }
+    }
+
+    fn active_editor(&self) -> &EditorData {
+        match self.input_focus.get_untracked() {
+            SearchInput::Replace if self.replace_visible.get_untracked() => {
+                &self.replace_editor
+            }
+            SearchInput::Include if self.filters_visible.get_untracked() => {
+                &self.include_editor
+            }
+            SearchInput::Exclude if self.filters_visible.get_untracked() => {
+                &self.exclude_editor
+            }
+            _ => &self.editor,
+        }
+    }
+
+    /// Whether the input is shown and has the focus within the panel
+    pub fn is_input_focused(&self, input: SearchInput) -> bool {
+        let focus = match self.input_focus.get() {
+            SearchInput::Replace if !self.replace_visible.get() => {
+                SearchInput::Search
+            }
+            SearchInput::Include | SearchInput::Exclude
+                if !self.filters_visible.get() =>
+            {
+                SearchInput::Search
+            }
+            focus => focus,
+        };
+        focus == input
+    }
+
+    /// Only search the files of the folder
+    pub fn search_in_folder(&self, path: &Path) {
+        let path = self
+            .common
+            .workspace
+            .path
+            .as_ref()
+            .and_then(|workspace| path.strip_prefix(workspace).ok())
+            .unwrap_or(path);
+        let glob = format!(
+            "./{}",
+            globset::escape(&path.to_string_lossy().replace('\\', "/"))
         );
+        self.include_editor.doc().reload(Rope::from(glob), true);
+        self.filters_visible.set(true);
+        self.input_focus.set(SearchInput::Search);
+    }
+
+    /// The pattern of the search, which is `None` if it isn't a valid regex
+    pub fn search_pattern(&self) -> Option<SearchPattern> {
+        let pattern = self.editor.doc().buffer.with(|buffer| buffer.to_string());
+        if pattern.is_empty() {
+            return None;
+        }
+        SearchPattern::new(
+            &pattern,
+            self.common.find.case_sensitive(true),
+            self.common.find.whole_words.get(),
+            self.common.find.is_regex.get(),
+        )
+        .ok()
+    }
+
+    pub fn replacement(&self) -> String {
+        self.replace_editor
+            .doc()
+            .buffer
+            .with(|buffer| buffer.to_string())
+    }
+
+    /// The text replacing the match, with the capture groups expanded
+    pub fn match_replacement(&self, search_match: &SearchMatch) -> Option<String> {
+        let pattern = self.search_pattern()?;
+        pattern
+            .replacements(&search_match.line_content, &self.replacement())
+            .into_iter()
+            .find(|(range, _)| *range == search_match.content_range())
+            .map(|(_, text)| text)
+    }
+
+    /// Replace one match of the file
+    pub fn replace_match(&self, path: &Path, search_match: &SearchMatch) {
+        self.replace(path, Some((search_match.line, search_match.start)));
+    }
+
+    /// Replace all the matches of the file
+    pub fn replace_in_file(&self, path: &Path) {
+        self.replace(path, None);
+    }
+
+    /// Replace all the matches of the search
+    pub fn replace_all(&self) {
+        let Some(pattern) = self.search_pattern() else {
+            return;
+        };
+        let replacement = self.replacement();
+        let paths: Vec<PathBuf> = self
+            .search_result
+            .with_untracked(|result| result.keys().cloned().collect());
+        let docs = self.main_split.docs.get_untracked();
+        // The files which aren't open are all rewritten by one request
+        let mut closed = Vec::new();
+        for path in paths {
+            match docs.get(&path) {
+                Some(doc) => {
+                    replace_in_doc(doc, &pattern, &replacement, None);
+                    self.remove_replaced(&path, None);
+                }
+                None => closed.push(path),
+            }
+        }
+        self.replace_in_files(closed, None);
+    }
+
+    /// Replace the matches of the file, or only the one at the line and
+    /// column. Open documents are edited so that the replacements can be
+    /// undone, other files are rewritten by the proxy.
+    fn replace(&self, path: &Path, only_match: Option<(usize, usize)>) {
+        let Some(pattern) = self.search_pattern() else {
+            return;
+        };
+        let replacement = self.replacement();
+
+        let doc = self.main_split.docs.get_untracked().get(path).cloned();
+        if let Some(doc) = doc {
+            replace_in_doc(&doc, &pattern, &replacement, only_match);
+            // The replaced matches are gone, whether or not the document is
+            // saved
+            self.remove_replaced(path, only_match);
+        } else {
+            self.replace_in_files(vec![path.to_path_buf()], only_match);
+        }
+    }
+
+    /// Have the proxy rewrite the files, whose matches are removed from the
+    /// results once they are replaced
+    fn replace_in_files(
+        &self,
+        paths: Vec<PathBuf>,
+        only_match: Option<(usize, usize)>,
+    ) {
+        if paths.is_empty() {
+            return;
+        }
+        let search = self.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            if let Ok(ProxyResponse::GlobalReplaceResponse { paths }) = result {
+                for path in paths {
+                    search.remove_replaced(&path, only_match);
+                }
+            }
+        });
+        let pattern = self.editor.doc().buffer.with_untracked(|b| b.to_string());
+        self.common.proxy.global_replace(
+            pattern,
+            self.common.find.case_sensitive(false),
+            self.common.find.whole_words.get_untracked(),
+            self.common.find.is_regex.get_untracked(),
+            self.replacement(),
+            paths,
+            only_match,
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    /// Remove the replaced matches of the file, or only the one at the line
+    /// and column, from the results
+    fn remove_replaced(&self, path: &Path, only_match: Option<(usize, usize)>) {
+        self.search_result.update(|result| {
+            let Some(match_data) = result.get(path) else {
+                return;
+            };
+            let count = match_data.matches.with_untracked(|m| m.len());
+            if let Some((line, start)) = only_match {
+                match_data.matches.update(|matches| {
+                    matches.retain(|m| m.line != line || m.start != start)
+                });
+            }
+            if only_match.is_none()
+                || match_data.matches.with_untracked(|m| m.is_empty())
+            {
+                result.shift_remove(path);
+            }
+            let remaining = result
+                .get(path)
+                .map(|match_data| match_data.matches.with_untracked(|m| m.len()))
+                .unwrap_or(0);
+            self.result_count.update(|result_count| {
+                *result_count = result_count.saturating_sub(count - remaining)
+            });
+        });
+    }
+
+    /// Add a batch of the matches streamed by the proxy to the results of the
+    /// panel or of a search editor, unless they are from an older search
+    pub fn receive_results(
+        &self,
+        search_id: u64,
+        matches: IndexMap<PathBuf, Vec<SearchMatch>>,
+        count: usize,
+    ) {
+        if self.search_id.get_untracked() != search_id {
+            // The searches of search editors are shown once they are done
+            self.editor_searches.update(|searches| {
+                if let Some(results) = searches.get_mut(&search_id) {
+                    results.extend(matches);
+                }
+            });
+            return;
+        }
+
+        self.search_result.update(|result| {
+            for (path, matches) in matches {
+                let match_data =
+                    result.entry(path).or_insert_with(|| SearchMatchData {
+                        expanded: self.common.scope.create_rw_signal(true),
+                        matches: self
+                            .common
+                            .scope
+                            .create_rw_signal(im::Vector::new()),
+                        line_height: self.common.ui_line_height,
+                    });
+                match_data.matches.set(matches.into());
+            }
+        });
+        self.result_count.set(count);
     }
 
     pub fn set_pattern(&self, pattern: String) {
+        self.input_focus.set(SearchInput::Search);
         let pattern_len = pattern.len();
         self.editor.doc().reload(Rope::from(pattern), true);
         self.editor
@@ -198,3 +622,39 @@ pub fn set_pattern(&self, pattern: String) {
             .update(|cursor| cursor.set_insert(Selection::region(0, pattern_len)));
     }
 }
+
+/// Replace the matches in the document, or only the one at the line and column
+fn replace_in_doc(
+    doc: &Doc,
+    pattern: &SearchPattern,
+    replacement: &str,
+    only_match: Option<(usize, usize)>,
+) {
+    let edits: Vec<(Selection, String)> = doc.buffer.with_untracked(|buffer| {
+        // The lines of the search matches start at 1
+        let offset = only_match.map(|(line, start)| {
+            buffer.offset_of_line(line.saturating_sub(1)) + start
+        });
+        pattern
+            .replacements(&buffer.to_string(), replacement)
+            .into_iter()
+            .filter(|(range, _)| offset.is_none_or(|offset| range.start == offset))
+            .map(|(range, text)| (Selection::region(range.start, range.end), text))
+            .collect()
+    });
+    if edits.is_empty() {
+        return;
+    }
+    let edits: Vec<(Selection, &str)> = edits
+        .iter()
+        .map(|(selection, text)| (selection.clone(), text.as_str()))
+        .collect();
+    doc.do_raw_edit(&edits, EditType::Completion);
+}
+
+/// The id of a new search, unique among the searches of the search panel and
+/// of the search editors
+fn next_search_id() -> u64 {
+    static SEARCH_ID: AtomicU64 = AtomicU64::new(0);
+    SEARCH_ID.fetch_add(1, Ordering::Relaxed) + 1
+}
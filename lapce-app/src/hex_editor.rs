@@ -0,0 +1,850 @@
+use std::{
+    ops::Range,
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::Duration,
+};
+
+use floem::{
+    View,
+    action::{TimerToken, exec_after},
+    event::EventListener,
+    ext_event::create_ext_action,
+    keyboard::Modifiers,
+    peniko::kurbo::{Point, Size},
+    prelude::SignalTrack,
+    reactive::{RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
+    style::CursorStyle,
+    views::{
+        Decorators, VirtualVector, container, label, scroll, stack, stack_from_iter,
+        text, virtual_stack,
+    },
+};
+use lapce_core::{
+    command::{EditCommand, FocusCommand, ScrollCommand},
+    mode::Mode,
+    movement::{LinePosition, Movement},
+};
+use lapce_rpc::proxy::ProxyResponse;
+
+use crate::{
+    app::clickable_icon,
+    command::{CommandExecuted, CommandKind, LapceCommand},
+    config::{color::LapceColor, icon::LapceIcons},
+    editor::EditorData,
+    id::HexEditorId,
+    keypress::{KeyPressFocus, condition::Condition},
+    main_split::{Editors, FileViewData},
+    text_input::TextInputBuilder,
+    window_tab::{CommonData, Focus},
+};
+
+pub const BYTES_PER_ROW: usize = 16;
+
+/// The most matches a search finds, so that searching a short pattern in a
+/// large file stays responsive
+const MAX_SEARCH_MATCHES: usize = 10_000;
+
+/// How long the search waits for the pattern or the content to stop changing
+/// before scanning the file again
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// The input of the hex editor toolbar which has the focus
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HexInput {
+    GotoOffset,
+    Search,
+}
+
+/// The scope of the content of a file, which its hex editors in different
+/// splits share, disposed with the last of them
+struct HexFile {
+    scope: Scope,
+}
+
+impl Drop for HexFile {
+    fn drop(&mut self) {
+        self.scope.dispose();
+    }
+}
+
+#[derive(Clone)]
+pub struct HexEditorData {
+    pub id: HexEditorId,
+    pub scope: Scope,
+    pub path: PathBuf,
+    file: Rc<HexFile>,
+    pub content: RwSignal<Vec<u8>>,
+    pub loaded: RwSignal<bool>,
+    /// The error of reading the file, shown instead of its content
+    pub error: RwSignal<Option<String>>,
+    /// The offset of the byte under the cursor
+    pub cursor: RwSignal<usize>,
+    /// Whether the next hex digit typed overwrites the low nibble of the byte
+    /// under the cursor, the high nibble having been typed already
+    pub low_nibble: RwSignal<bool>,
+    /// Whether typing overwrites bytes with characters of the ASCII column
+    /// rather than with hex digits
+    pub ascii: RwSignal<bool>,
+    pub pristine: RwSignal<bool>,
+    pub goto_editor: EditorData,
+    pub search_editor: EditorData,
+    pub input_focus: RwSignal<Option<HexInput>>,
+    /// The offsets of the matches of the search pattern
+    pub search_matches: RwSignal<Vec<usize>>,
+    pub search_len: RwSignal<usize>,
+    pub current_match: RwSignal<Option<usize>>,
+    /// The number of rows that fit in the view, which is how far a page moves
+    pub visible_rows: RwSignal<usize>,
+    pub common: Rc<CommonData>,
+}
+
+impl std::fmt::Debug for HexEditorData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HexEditorData")
+            .field("id", &self.id)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl KeyPressFocus for HexEditorData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, condition: Condition) -> bool {
+        match condition {
+            Condition::ModalFocus => self.input_focus.get_untracked().is_some(),
+            Condition::SearchFocus => {
+                self.input_focus.get_untracked() == Some(HexInput::Search)
+            }
+            _ => false,
+        }
+    }
+
+    fn run_command(
+        &self,
+        command: &LapceCommand,
+        count: Option<usize>,
+        mods: Modifiers,
+    ) -> CommandExecuted {
+        if let Some(editor) = self.input_editor() {
+            match &command.kind {
+                CommandKind::Edit(EditCommand::InsertNewLine)
+                    if self.input_focus.get_untracked()
+                        == Some(HexInput::GotoOffset) =>
+                {
+                    self.goto_offset();
+                    return CommandExecuted::Yes;
+                }
+                CommandKind::Focus(FocusCommand::ModalClose) => {
+                    self.input_focus.set(None);
+                    return CommandExecuted::Yes;
+                }
+                CommandKind::Edit(_)
+                | CommandKind::Move(_)
+                | CommandKind::MultiSelection(_) => {
+                    return editor.run_command(command, count, mods);
+                }
+                _ => {}
+            }
+        }
+
+        match &command.kind {
+            CommandKind::Focus(FocusCommand::Save) => {
+                self.save(|| {});
+            }
+            CommandKind::Focus(FocusCommand::Search) => {
+                self.input_focus.set(Some(HexInput::Search));
+            }
+            CommandKind::Focus(FocusCommand::SearchForward) => {
+                self.next_match(true);
+            }
+            CommandKind::Focus(FocusCommand::SearchBackward) => {
+                self.next_match(false);
+            }
+            CommandKind::Move(cmd) => {
+                self.run_movement(cmd.to_movement(count), count.unwrap_or(1));
+            }
+            CommandKind::Scroll(ScrollCommand::PageUp) => {
+                let page = self.visible_rows.get_untracked().max(1) * BYTES_PER_ROW;
+                self.move_cursor(self.cursor.get_untracked().saturating_sub(page));
+            }
+            CommandKind::Scroll(ScrollCommand::PageDown) => {
+                let page = self.visible_rows.get_untracked().max(1) * BYTES_PER_ROW;
+                self.move_cursor(self.cursor.get_untracked() + page);
+            }
+            _ => return CommandExecuted::No,
+        }
+        CommandExecuted::Yes
+    }
+
+    fn receive_char(&self, c: &str) {
+        if let Some(editor) = self.input_editor() {
+            editor.receive_char(c);
+            return;
+        }
+
+        if self.ascii.get_untracked() {
+            for c in c.chars() {
+                if c.is_ascii() {
+                    self.overwrite_byte(c as u8);
+                }
+            }
+        } else {
+            for digit in c.chars().filter_map(|c| c.to_digit(16)) {
+                self.overwrite_nibble(digit as u8);
+            }
+        }
+    }
+}
+
+impl FileViewData for HexEditorData {
+    fn id(&self) -> HexEditorId {
+        self.id
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// A hex editor of the same file for a new split, sharing the content and
+    /// its unsaved changes
+    fn copy(&self, cx: Scope, editors: Editors) -> Self {
+        let data = Self::empty(
+            cx,
+            self.path.clone(),
+            editors,
+            self.common.clone(),
+            Some(self),
+        );
+        data.cursor.set(self.cursor.get_untracked());
+        data.ascii.set(self.ascii.get_untracked());
+        data
+    }
+
+    /// Remove the editors of the toolbar inputs, when the hex editor is closed
+    fn dispose(&self, editors: Editors) {
+        editors.remove(self.goto_editor.id());
+        editors.remove(self.search_editor.id());
+        self.scope.dispose();
+    }
+}
+
+impl HexEditorData {
+    pub fn new(
+        cx: Scope,
+        path: PathBuf,
+        editors: Editors,
+        common: Rc<CommonData>,
+    ) -> Self {
+        let data = Self::empty(cx, path, editors, common, None);
+        data.load();
+        data
+    }
+
+    /// A hex editor without content, or sharing the content of the hex editor
+    /// of the same file
+    fn empty(
+        cx: Scope,
+        path: PathBuf,
+        editors: Editors,
+        common: Rc<CommonData>,
+        shared: Option<&HexEditorData>,
+    ) -> Self {
+        let (file, content, loaded, error, pristine) = match shared {
+            Some(shared) => (
+                shared.file.clone(),
+                shared.content,
+                shared.loaded,
+                shared.error,
+                shared.pristine,
+            ),
+            None => {
+                let file_cx = cx.create_child();
+                (
+                    Rc::new(HexFile { scope: file_cx }),
+                    file_cx.create_rw_signal(Vec::new()),
+                    file_cx.create_rw_signal(false),
+                    file_cx.create_rw_signal(None),
+                    file_cx.create_rw_signal(true),
+                )
+            }
+        };
+        let cx = cx.create_child();
+        let data = Self {
+            id: HexEditorId::next(),
+            scope: cx,
+            path,
+            file,
+            content,
+            loaded,
+            error,
+            cursor: cx.create_rw_signal(0),
+            low_nibble: cx.create_rw_signal(false),
+            ascii: cx.create_rw_signal(false),
+            pristine,
+            goto_editor: editors.make_local(cx, common.clone()),
+            search_editor: editors.make_local(cx, common.clone()),
+            input_focus: cx.create_rw_signal(None),
+            search_matches: cx.create_rw_signal(Vec::new()),
+            search_len: cx.create_rw_signal(0),
+            current_match: cx.create_rw_signal(None),
+            visible_rows: cx.create_rw_signal(0),
+            common,
+        };
+
+        {
+            let data = data.clone();
+            let buffer = data.search_editor.doc().buffer;
+            let timer = cx.create_rw_signal(TimerToken::INVALID);
+            cx.create_effect(move |_| {
+                buffer.track();
+                data.content.track();
+                let data = data.clone();
+                let token = exec_after(SEARCH_DEBOUNCE, move |token| {
+                    if timer.try_get_untracked() == Some(token) {
+                        data.search();
+                    }
+                });
+                timer.set(token);
+            });
+        }
+
+        data
+    }
+
+    /// Read the content of the file through the proxy, discarding the unsaved
+    /// changes
+    pub fn load(&self) {
+        let data = self.clone();
+        let send = create_ext_action(self.scope, move |result| match result {
+            Ok(ProxyResponse::ReadFileBytesResponse { content }) => {
+                data.content.set(content);
+                data.error.set(None);
+                data.loaded.set(true);
+                data.pristine.set(true);
+                data.move_cursor(data.cursor.get_untracked());
+            }
+            Ok(_) => {}
+            Err(err) => {
+                data.error.set(Some(err.message));
+                data.loaded.set(true);
+            }
+        });
+        self.common
+            .proxy
+            .read_file_bytes(self.path.clone(), move |result| {
+                send(result);
+            });
+    }
+
+    /// Write the content to the file, running `after_action` once it's saved
+    pub fn save(&self, after_action: impl FnOnce() + 'static) {
+        if !self.loaded.get_untracked() || self.error.with_untracked(Option::is_some)
+        {
+            return;
+        }
+
+        let pristine = self.pristine;
+        let send = create_ext_action(self.scope, move |result| {
+            if let Ok(ProxyResponse::Success {}) = result {
+                pristine.set(true);
+                after_action();
+            }
+        });
+        self.common.proxy.write_file_bytes(
+            self.path.clone(),
+            self.content.get_untracked(),
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
+    pub fn len(&self) -> usize {
+        self.content.with_untracked(|content| content.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn input_editor(&self) -> Option<&EditorData> {
+        match self.input_focus.get_untracked()? {
+            HexInput::GotoOffset => Some(&self.goto_editor),
+            HexInput::Search => Some(&self.search_editor),
+        }
+    }
+
+    /// Move the cursor to `offset`, clamped to the last byte
+    pub fn move_cursor(&self, offset: usize) {
+        self.cursor.set(offset.min(self.len().saturating_sub(1)));
+        self.low_nibble.set(false);
+    }
+
+    fn run_movement(&self, movement: Movement, count: usize) {
+        let cursor = self.cursor.get_untracked();
+        let row_start = cursor - cursor % BYTES_PER_ROW;
+        let offset = match movement {
+            Movement::Left => cursor.saturating_sub(count),
+            Movement::Right => cursor + count,
+            Movement::Up => cursor.saturating_sub(count * BYTES_PER_ROW),
+            Movement::Down => cursor + count * BYTES_PER_ROW,
+            Movement::StartOfLine | Movement::FirstNonBlank => row_start,
+            Movement::EndOfLine => row_start + BYTES_PER_ROW - 1,
+            Movement::DocumentStart | Movement::Line(LinePosition::First) => 0,
+            Movement::DocumentEnd | Movement::Line(LinePosition::Last) => usize::MAX,
+            Movement::Offset(offset) => offset,
+            _ => return,
+        };
+        self.move_cursor(offset);
+    }
+
+    /// Overwrite a nibble of the byte under the cursor with a hex digit, moving
+    /// to the next byte once both nibbles were typed
+    fn overwrite_nibble(&self, digit: u8) {
+        let cursor = self.cursor.get_untracked();
+        if cursor >= self.len() {
+            return;
+        }
+
+        let low_nibble = self.low_nibble.get_untracked();
+        self.content.update(|content| {
+            let byte = &mut content[cursor];
+            *byte = if low_nibble {
+                (*byte & 0xF0) | digit
+            } else {
+                (digit << 4) | (*byte & 0x0F)
+            };
+        });
+        self.pristine.set(false);
+        if low_nibble {
+            self.move_cursor(cursor + 1);
+        } else {
+            self.low_nibble.set(true);
+        }
+    }
+
+    fn overwrite_byte(&self, byte: u8) {
+        let cursor = self.cursor.get_untracked();
+        if cursor >= self.len() {
+            return;
+        }
+
+        self.content.update(|content| {
+            content[cursor] = byte;
+        });
+        self.pristine.set(false);
+        self.move_cursor(cursor + 1);
+    }
+
+    fn goto_offset(&self) {
+        let input = self
+            .goto_editor
+            .doc()
+            .buffer
+            .with_untracked(|b| b.to_string());
+        if let Some(offset) = parse_offset(&input) {
+            self.move_cursor(offset);
+            self.input_focus.set(None);
+        }
+    }
+
+    fn search(&self) {
+        let input = self
+            .search_editor
+            .doc()
+            .buffer
+            .with_untracked(|b| b.to_string());
+        let pattern = parse_search_pattern(&input).unwrap_or_default();
+        let matches = self.content.with_untracked(|content| {
+            find_matches(content, &pattern, MAX_SEARCH_MATCHES)
+        });
+        self.search_len.set(pattern.len());
+        self.search_matches.set(matches);
+        self.current_match.set(None);
+    }
+
+    /// Move the cursor to the next or previous match from the cursor
+    fn next_match(&self, forward: bool) {
+        let cursor = self.cursor.get_untracked();
+        let index = self.search_matches.with_untracked(|matches| {
+            if matches.is_empty() {
+                return None;
+            }
+            Some(if forward {
+                matches
+                    .iter()
+                    .position(|offset| *offset > cursor)
+                    .unwrap_or(0)
+            } else {
+                matches
+                    .iter()
+                    .rposition(|offset| *offset < cursor)
+                    .unwrap_or(matches.len() - 1)
+            })
+        });
+        if let Some(index) = index {
+            let offset =
+                self.search_matches.with_untracked(|matches| matches[index]);
+            self.current_match.set(Some(index));
+            self.move_cursor(offset);
+        }
+    }
+}
+
+/// An offset in hex, with an optional `0x` prefix
+fn parse_offset(input: &str) -> Option<usize> {
+    let input = input.trim();
+    let input = input
+        .strip_prefix("0x")
+        .or_else(|| input.strip_prefix("0X"))
+        .unwrap_or(input);
+    usize::from_str_radix(input, 16).ok()
+}
+
+/// The bytes to search for, either hex digits with optional spaces, like
+/// `de ad be ef`, or text in double quotes, searched as UTF-8
+fn parse_search_pattern(input: &str) -> Option<Vec<u8>> {
+    let input = input.trim();
+    if let Some(text) = input
+        .strip_prefix('"')
+        .and_then(|input| input.strip_suffix('"'))
+    {
+        return Some(text.as_bytes().to_vec()).filter(|bytes| !bytes.is_empty());
+    }
+
+    let digits: Vec<u8> = input
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<_>>()?;
+    if digits.is_empty() || digits.len() % 2 != 0 {
+        return None;
+    }
+    Some(
+        digits
+            .chunks(2)
+            .map(|pair| (pair[0] << 4) | pair[1])
+            .collect(),
+    )
+}
+
+/// The offsets of the occurrences of `pattern` in `content`, overlapping ones
+/// included
+fn find_matches(content: &[u8], pattern: &[u8], limit: usize) -> Vec<usize> {
+    if pattern.is_empty() {
+        return Vec::new();
+    }
+    content
+        .windows(pattern.len())
+        .enumerate()
+        .filter(|(_, window)| *window == pattern)
+        .map(|(offset, _)| offset)
+        .take(limit)
+        .collect()
+}
+
+/// The rows of the hex view, by their index
+struct HexRows(usize);
+
+impl VirtualVector<usize> for HexRows {
+    fn total_len(&self) -> usize {
+        self.0
+    }
+
+    fn slice(&mut self, range: Range<usize>) -> impl Iterator<Item = usize> {
+        range
+    }
+}
+
+pub fn hex_editor_view(data: HexEditorData) -> impl View {
+    let config = data.common.config;
+    let focus = data.common.focus;
+    let line_height = move || config.get().editor.line_height() as f64;
+
+    let toolbar = {
+        let data = data.clone();
+        let match_data = data.clone();
+        let prev_data = data.clone();
+        let next_data = data.clone();
+        let search_matches = data.search_matches;
+        let current_match = data.current_match;
+        stack((
+            hex_input(data.clone(), HexInput::GotoOffset, data.goto_editor.clone())
+                .style(|s| s.width(200.0)),
+            hex_input(data.clone(), HexInput::Search, data.search_editor.clone())
+                .style(|s| s.width(300.0).margin_left(10.0)),
+            label(move || {
+                let count = search_matches.with(|matches| matches.len());
+                if count == 0 {
+                    return "No results".to_string();
+                }
+                let limit = if count >= MAX_SEARCH_MATCHES { "+" } else { "" };
+                match current_match.get() {
+                    Some(index) => format!("{} of {count}{limit}", index + 1),
+                    None => format!("{count}{limit} results"),
+                }
+            })
+            .style(move |s| {
+                s.margin_left(10.0)
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+                    .apply_if(match_data.search_len.get() == 0, |s| s.hide())
+            }),
+            clickable_icon(
+                || LapceIcons::SEARCH_BACKWARD,
+                move || prev_data.next_match(false),
+                || false,
+                move || search_matches.with(|matches| matches.is_empty()),
+                || "Previous Match",
+                config,
+            )
+            .style(|s| s.margin_left(6.0)),
+            clickable_icon(
+                || LapceIcons::SEARCH_FORWARD,
+                move || next_data.next_match(true),
+                || false,
+                move || search_matches.with(|matches| matches.is_empty()),
+                || "Next Match",
+                config,
+            ),
+            label(move || {
+                let cursor = data.cursor.get();
+                let len = data.content.with(|content| content.len());
+                format!("Offset {cursor:08X} ({cursor}) of {len} bytes")
+            })
+            .style(move |s| {
+                s.margin_left(10.0)
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+            }),
+        ))
+        .style(move |s| {
+            s.items_center()
+                .width_pct(100.0)
+                .padding(10.0)
+                .border_bottom(1.0)
+                .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+        })
+    };
+
+    let rows = {
+        let data = data.clone();
+        let content = data.content;
+        let cursor = data.cursor;
+        let visible_rows = data.visible_rows;
+        scroll(
+            virtual_stack(
+                move || {
+                    let len = content.with(|content| content.len());
+                    HexRows(len.div_ceil(BYTES_PER_ROW))
+                },
+                |row| *row,
+                move |row| hex_row_view(data.clone(), row),
+            )
+            .item_size_fixed(line_height)
+            .style(|s| s.flex_col().min_width_pct(100.0)),
+        )
+        .ensure_visible(move || {
+            let row = cursor.get() / BYTES_PER_ROW;
+            let line_height = line_height();
+            Size::new(1.0, line_height)
+                .to_rect()
+                .with_origin(Point::new(0.0, row as f64 * line_height))
+        })
+        .on_resize(move |rect| {
+            visible_rows.set((rect.height() / line_height()) as usize);
+        })
+        .style(|s| s.absolute().size_full())
+    };
+
+    let loaded = data.loaded;
+    let error = data.error;
+    let input_focus = data.input_focus;
+    stack((
+        toolbar,
+        container(rows)
+            .on_event_cont(EventListener::PointerDown, move |_| {
+                focus.set(Focus::Workbench);
+                input_focus.set(None);
+            })
+            .style(move |s| {
+                s.width_pct(100.0)
+                    .flex_basis(0.0)
+                    .flex_grow(1.0)
+                    .apply_if(error.with(Option::is_some), |s| s.hide())
+            }),
+        label(move || {
+            if !loaded.get() {
+                "Loading...".to_string()
+            } else {
+                error.get().unwrap_or_default()
+            }
+        })
+        .style(move |s| {
+            s.padding(10.0)
+                .apply_if(loaded.get() && error.with(Option::is_none), |s| s.hide())
+        }),
+    ))
+    .style(move |s| {
+        let config = config.get();
+        s.absolute()
+            .size_full()
+            .flex_col()
+            .font_family(config.editor.font_family.clone())
+            .font_size(config.editor.font_size() as f32)
+            .background(config.color(LapceColor::EDITOR_BACKGROUND))
+    })
+    .debug_name("Hex Editor")
+}
+
+/// An input of the toolbar, which gives it the focus when clicked
+fn hex_input(data: HexEditorData, input: HexInput, editor: EditorData) -> impl View {
+    let config = data.common.config;
+    let focus = data.common.focus;
+    let input_focus = data.input_focus;
+    let placeholder = match input {
+        HexInput::GotoOffset => "Go to offset (hex)",
+        HexInput::Search => "Search hex bytes or \"text\"",
+    };
+
+    container(
+        TextInputBuilder::new()
+            .is_focused(move || {
+                focus.get() == Focus::Workbench && input_focus.get() == Some(input)
+            })
+            .build_editor(editor)
+            .placeholder(move || placeholder.to_string())
+            .style(|s| s.width_pct(100.0)),
+    )
+    .on_event_cont(EventListener::PointerDown, move |_| {
+        focus.set(Focus::Workbench);
+        input_focus.set(Some(input));
+    })
+    .style(move |s| {
+        s.items_center()
+            .border(1.0)
+            .border_radius(6.0)
+            .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+    })
+}
+
+fn hex_row_view(data: HexEditorData, row: usize) -> impl View {
+    let config = data.common.config;
+    let start = row * BYTES_PER_ROW;
+
+    let hex_cells = stack_from_iter((start..start + BYTES_PER_ROW).map(|offset| {
+        let data = data.clone();
+        hex_cell(data, offset, false).style(move |s| {
+            s.width(24.0)
+                .justify_center()
+                .apply_if(offset % 8 == 0 && offset % BYTES_PER_ROW != 0, |s| {
+                    s.margin_left(8.0)
+                })
+        })
+    }));
+    let ascii_cells = stack_from_iter(
+        (start..start + BYTES_PER_ROW)
+            .map(|offset| hex_cell(data.clone(), offset, true)),
+    );
+
+    stack((
+        text(format!("{start:08X}")).style(move |s| {
+            s.width(90.0)
+                .padding_left(10.0)
+                .color(config.get().color(LapceColor::EDITOR_DIM))
+        }),
+        hex_cells.style(|s| s.margin_right(20.0)),
+        ascii_cells,
+    ))
+    .style(move |s| {
+        s.items_center()
+            .height(config.get().editor.line_height() as f32)
+    })
+}
+
+/// The hex digits of the byte at `offset`, or its character in the ASCII
+/// column, which moves the cursor there when clicked
+fn hex_cell(data: HexEditorData, offset: usize, ascii: bool) -> impl View {
+    let config = data.common.config;
+    let content = data.content;
+    let cursor = data.cursor;
+    let ascii_signal = data.ascii;
+    let search_matches = data.search_matches;
+    let search_len = data.search_len;
+    let is_match = move || {
+        let len = search_len.get();
+        search_matches.with(|matches| {
+            let i = matches.partition_point(|start| start + len <= offset);
+            matches.get(i).is_some_and(|start| *start <= offset)
+        })
+    };
+
+    label(move || {
+        content.with(|content| match content.get(offset) {
+            Some(byte) if ascii => {
+                if byte.is_ascii_graphic() || *byte == b' ' {
+                    (*byte as char).to_string()
+                } else {
+                    ".".to_string()
+                }
+            }
+            Some(byte) => format!("{byte:02X}"),
+            None => String::new(),
+        })
+    })
+    .on_click_stop(move |_| {
+        data.move_cursor(offset);
+        data.ascii.set(ascii);
+        data.input_focus.set(None);
+    })
+    .style(move |s| {
+        let config = config.get();
+        let at_cursor = cursor.get() == offset;
+        s.cursor(CursorStyle::Text)
+            .apply_if(is_match(), |s| {
+                s.background(config.color(LapceColor::EDITOR_SELECTION))
+            })
+            .apply_if(at_cursor, |s| {
+                if ascii_signal.get() == ascii {
+                    s.background(config.color(LapceColor::EDITOR_CARET))
+                        .color(config.color(LapceColor::EDITOR_BACKGROUND))
+                } else {
+                    s.border(1.0)
+                        .border_color(config.color(LapceColor::EDITOR_CARET))
+                }
+            })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_matches, parse_offset, parse_search_pattern};
+
+    #[test]
+    fn test_parse_offset() {
+        assert_eq!(parse_offset("1f"), Some(0x1F));
+        assert_eq!(parse_offset(" 0x100 "), Some(0x100));
+        assert_eq!(parse_offset("0X10"), Some(0x10));
+        assert_eq!(parse_offset("zz"), None);
+        assert_eq!(parse_offset(""), None);
+    }
+
+    #[test]
+    fn test_search_pattern() {
+        assert_eq!(
+            parse_search_pattern("de ad BE EF"),
+            Some(vec![0xDE, 0xAD, 0xBE, 0xEF])
+        );
+        assert_eq!(parse_search_pattern("\"PNG\""), Some(b"PNG".to_vec()));
+        assert_eq!(parse_search_pattern("abc"), None);
+        assert_eq!(parse_search_pattern("xy"), None);
+        assert_eq!(parse_search_pattern("\"\""), None);
+
+        let content = b"\x00\xAA\xAA\xAA\x01";
+        assert_eq!(find_matches(content, &[0xAA, 0xAA], 10), vec![1, 2]);
+        assert_eq!(find_matches(content, &[0xAA, 0xAA], 1), vec![1]);
+        assert!(find_matches(content, &[], 10).is_empty());
+    }
+}
@@ -8,4 +8,7 @@
 pub type ThemeColorSettingsId = Id;
 pub type VoltViewId = Id;
 pub type DiffEditorId = Id;
+pub type HexEditorId = Id;
+pub type ImageViewerId = Id;
+pub type MarkdownPreviewId = Id;
 pub type TerminalTabId = Id;
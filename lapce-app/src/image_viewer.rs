@@ -0,0 +1,512 @@
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use floem::{
+    View,
+    event::EventListener,
+    ext_event::create_ext_action,
+    keyboard::Modifiers,
+    peniko::kurbo::Size,
+    reactive::{RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
+    views::{
+        Decorators, container, dyn_container, img_dynamic, label, scroll, stack, svg,
+    },
+};
+use image::{DynamicImage, ImageReader};
+use lapce_core::mode::Mode;
+use lapce_rpc::proxy::ProxyResponse;
+
+use crate::{
+    app::clickable_icon,
+    command::{CommandExecuted, InternalCommand, LapceCommand},
+    config::{color::LapceColor, icon::LapceIcons},
+    id::ImageViewerId,
+    keypress::{KeyPressFocus, condition::Condition},
+    main_split::{Editors, FileViewData},
+    window_tab::{CommonData, Focus},
+};
+
+const MIN_ZOOM: f64 = 0.05;
+const MAX_ZOOM: f64 = 32.0;
+const ZOOM_STEP: f64 = 1.25;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImageKind {
+    Png,
+    Jpeg,
+    Gif,
+    WebP,
+    Svg,
+}
+
+impl ImageKind {
+    /// The kind of image of a file, from its extension
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        Some(match extension.as_str() {
+            "png" => Self::Png,
+            "jpg" | "jpeg" => Self::Jpeg,
+            "gif" => Self::Gif,
+            "webp" => Self::WebP,
+            "svg" => Self::Svg,
+            _ => return None,
+        })
+    }
+}
+
+/// The image read from the file, decoded once and shared by the views of it
+#[derive(Clone)]
+pub enum ImageSource {
+    Raster(Rc<DynamicImage>),
+    Svg(Rc<str>),
+}
+
+/// The image read from the file
+#[derive(Clone)]
+pub struct ImageContent {
+    pub source: ImageSource,
+    /// The width and height in pixels
+    pub dimensions: (u32, u32),
+}
+
+impl ImageContent {
+    /// Share the decoded image, on the UI thread
+    pub fn new(decoded: DecodedImage, dimensions: (u32, u32)) -> Self {
+        let source = match decoded {
+            DecodedImage::Raster(image) => ImageSource::Raster(Rc::new(image)),
+            DecodedImage::Svg(text) => ImageSource::Svg(text.into()),
+        };
+        Self { source, dimensions }
+    }
+}
+
+/// The image decoded on the thread which received the file, before it's
+/// shared on the UI thread
+pub enum DecodedImage {
+    Raster(DynamicImage),
+    Svg(String),
+}
+
+#[derive(Clone)]
+pub struct ImageViewerData {
+    pub id: ImageViewerId,
+    pub scope: Scope,
+    pub path: PathBuf,
+    /// Whether the image is an SVG, which is rendered as vector graphics
+    pub svg: bool,
+    pub content: RwSignal<Option<ImageContent>>,
+    /// The size of the file in bytes
+    pub file_size: RwSignal<Option<u64>>,
+    /// The error of reading or decoding the file, shown instead of the image
+    pub error: RwSignal<Option<String>>,
+    pub zoom: RwSignal<f64>,
+    /// Whether the image is scaled down to fit in the view, instead of being
+    /// shown at the zoom level
+    pub fit: RwSignal<bool>,
+    /// The size of the view the image is shown in
+    pub viewport: RwSignal<Size>,
+    pub common: Rc<CommonData>,
+}
+
+impl std::fmt::Debug for ImageViewerData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ImageViewerData")
+            .field("id", &self.id)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl KeyPressFocus for ImageViewerData {
+    fn get_mode(&self) -> Mode {
+        Mode::Insert
+    }
+
+    fn check_condition(&self, _condition: Condition) -> bool {
+        false
+    }
+
+    fn run_command(
+        &self,
+        _command: &LapceCommand,
+        _count: Option<usize>,
+        _mods: Modifiers,
+    ) -> CommandExecuted {
+        CommandExecuted::No
+    }
+
+    fn receive_char(&self, c: &str) {
+        match c {
+            "+" | "=" => self.zoom_by(ZOOM_STEP),
+            "-" => self.zoom_by(1.0 / ZOOM_STEP),
+            "0" => self.actual_size(),
+            "f" => self.fit.set(true),
+            _ => {}
+        }
+    }
+}
+
+impl FileViewData for ImageViewerData {
+    fn id(&self) -> ImageViewerId {
+        self.id
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// A viewer of the same image for a new split
+    fn copy(&self, cx: Scope, _editors: Editors) -> Self {
+        let cx = cx.create_child();
+        Self {
+            id: ImageViewerId::next(),
+            scope: cx,
+            path: self.path.clone(),
+            svg: self.svg,
+            content: cx.create_rw_signal(self.content.get_untracked()),
+            file_size: cx.create_rw_signal(self.file_size.get_untracked()),
+            error: cx.create_rw_signal(self.error.get_untracked()),
+            zoom: cx.create_rw_signal(self.zoom.get_untracked()),
+            fit: cx.create_rw_signal(self.fit.get_untracked()),
+            viewport: cx.create_rw_signal(Size::ZERO),
+            common: self.common.clone(),
+        }
+    }
+
+    fn dispose(&self, _editors: Editors) {
+        self.scope.dispose();
+    }
+}
+
+impl ImageViewerData {
+    pub fn new(cx: Scope, path: PathBuf, common: Rc<CommonData>) -> Self {
+        let cx = cx.create_child();
+        let data = Self {
+            id: ImageViewerId::next(),
+            scope: cx,
+            svg: ImageKind::from_path(&path) == Some(ImageKind::Svg),
+            path,
+            content: cx.create_rw_signal(None),
+            file_size: cx.create_rw_signal(None),
+            error: cx.create_rw_signal(None),
+            zoom: cx.create_rw_signal(1.0),
+            fit: cx.create_rw_signal(true),
+            viewport: cx.create_rw_signal(Size::ZERO),
+            common,
+        };
+        data.load();
+        data
+    }
+
+    /// Read the image through the proxy, so that it works for remote
+    /// workspaces as well, and decode it. It's read again when the file
+    /// changes on disk.
+    pub fn load(&self) {
+        let data = self.clone();
+        let send = create_ext_action(self.scope, move |result| match result {
+            Ok((file_size, decoded)) => {
+                data.file_size.set(Some(file_size));
+                match decoded {
+                    Some((decoded, dimensions)) => {
+                        data.content
+                            .set(Some(ImageContent::new(decoded, dimensions)));
+                        data.error.set(None);
+                    }
+                    None => {
+                        data.content.set(None);
+                        data.error
+                            .set(Some("The image can't be decoded".to_string()));
+                    }
+                }
+            }
+            Err(err) => {
+                data.content.set(None);
+                data.error.set(Some(err));
+            }
+        });
+        let svg = self.svg;
+        self.common
+            .proxy
+            .read_file_bytes(self.path.clone(), move |result| {
+                let result = match result {
+                    Ok(ProxyResponse::ReadFileBytesResponse { content }) => {
+                        Ok((content.len() as u64, decode_image(svg, content)))
+                    }
+                    Ok(_) => Err("Unexpected response".to_string()),
+                    Err(err) => Err(err.message),
+                };
+                send(result);
+            });
+    }
+
+    /// The scale the image is shown at, which is the zoom level unless it's
+    /// fit to the view, where images larger than the view are scaled down
+    pub fn scale(&self) -> f64 {
+        if !self.fit.get() {
+            return self.zoom.get();
+        }
+        let viewport = self.viewport.get();
+        self.content.with(|content| {
+            content
+                .as_ref()
+                .map(|content| fit_scale(content.dimensions, viewport))
+                .unwrap_or(1.0)
+        })
+    }
+
+    pub fn zoom_by(&self, factor: f64) {
+        let scale = self.scale();
+        self.zoom.set((scale * factor).clamp(MIN_ZOOM, MAX_ZOOM));
+        self.fit.set(false);
+    }
+
+    pub fn actual_size(&self) {
+        self.zoom.set(1.0);
+        self.fit.set(false);
+    }
+}
+
+/// The width and height in pixels of an image, read from its header for
+/// raster images
+fn image_dimensions(svg: bool, bytes: &[u8]) -> Option<(u32, u32)> {
+    if svg {
+        let tree = usvg::Tree::from_data(bytes, &usvg::Options::default()).ok()?;
+        let size = tree.size();
+        Some((size.width().round() as u32, size.height().round() as u32))
+    } else {
+        ImageReader::new(Cursor::new(bytes))
+            .with_guessed_format()
+            .ok()?
+            .into_dimensions()
+            .ok()
+    }
+}
+
+/// Decode the content of an image file, with its dimensions
+pub fn decode_image(
+    svg: bool,
+    bytes: Vec<u8>,
+) -> Option<(DecodedImage, (u32, u32))> {
+    let dimensions = image_dimensions(svg, &bytes)?;
+    let decoded = if svg {
+        DecodedImage::Svg(String::from_utf8(bytes).ok()?)
+    } else {
+        DecodedImage::Raster(image::load_from_memory(&bytes).ok()?)
+    };
+    Some((decoded, dimensions))
+}
+
+/// The scale at which an image fits in the viewport, without enlarging it
+fn fit_scale((width, height): (u32, u32), viewport: Size) -> f64 {
+    if width == 0 || height == 0 || viewport.width <= 0.0 || viewport.height <= 0.0 {
+        return 1.0;
+    }
+    (viewport.width / width as f64)
+        .min(viewport.height / height as f64)
+        .min(1.0)
+}
+
+/// A file size in bytes, KB, MB or GB
+fn format_file_size(size: u64) -> String {
+    const UNITS: [&str; 3] = ["KB", "MB", "GB"];
+    if size < 1024 {
+        return format!("{size} B");
+    }
+    let mut size = size as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+pub fn image_viewer_view(data: ImageViewerData) -> impl View {
+    let config = data.common.config;
+    let focus = data.common.focus;
+    let content = data.content;
+    let error = data.error;
+    let viewport = data.viewport;
+    let svg_image = data.svg;
+
+    let image = {
+        let data = data.clone();
+        dyn_container(
+            move || content.get(),
+            move |image_content| {
+                let Some(image_content) = image_content else {
+                    return label(move || {
+                        error.get().unwrap_or_else(|| "Loading...".to_string())
+                    })
+                    .style(|s| s.padding(10.0))
+                    .into_any();
+                };
+
+                let (width, height) = image_content.dimensions;
+                let image = match image_content.source {
+                    ImageSource::Raster(image) => {
+                        img_dynamic(move || image.clone()).into_any()
+                    }
+                    ImageSource::Svg(text) => {
+                        svg(move || text.to_string()).into_any()
+                    }
+                };
+                let data = data.clone();
+                container(image.style(move |s| {
+                    let scale = data.scale();
+                    s.width((width as f64 * scale) as f32)
+                        .height((height as f64 * scale) as f32)
+                }))
+                .style(|s| s.min_size_full().items_center().justify_center())
+                .into_any()
+            },
+        )
+    };
+
+    let toolbar = {
+        let file_size = data.file_size;
+        let zoom_in = data.clone();
+        let zoom_out = data.clone();
+        let actual = data.clone();
+        let fit = data.fit;
+        let scale_data = data.clone();
+        let internal_command = data.common.internal_command;
+        let path = data.path.clone();
+        stack((
+            label(move || {
+                let dimensions = content.with(|content| {
+                    content.as_ref().map(|content| content.dimensions)
+                });
+                let mut info = Vec::new();
+                if let Some((width, height)) = dimensions {
+                    info.push(format!("{width} × {height} px"));
+                }
+                if let Some(size) = file_size.get() {
+                    info.push(format_file_size(size));
+                }
+                info.join("  ·  ")
+            })
+            .style(|s| s.flex_grow(1.0).min_width(0.0).text_ellipsis()),
+            clickable_icon(
+                || LapceIcons::IMAGE_ZOOM_OUT,
+                move || zoom_out.zoom_by(1.0 / ZOOM_STEP),
+                || false,
+                move || content.with(Option::is_none),
+                || "Zoom Out",
+                config,
+            ),
+            label(move || format!("{:.0}%", scale_data.scale() * 100.0))
+                .style(|s| s.min_width(50.0).justify_center()),
+            clickable_icon(
+                || LapceIcons::IMAGE_ZOOM_IN,
+                move || zoom_in.zoom_by(ZOOM_STEP),
+                || false,
+                move || content.with(Option::is_none),
+                || "Zoom In",
+                config,
+            ),
+            clickable_icon(
+                || LapceIcons::IMAGE_FIT,
+                move || fit.set(true),
+                move || fit.get(),
+                move || content.with(Option::is_none),
+                || "Fit to Window",
+                config,
+            )
+            .style(|s| s.margin_left(6.0)),
+            clickable_icon(
+                || LapceIcons::IMAGE_ACTUAL_SIZE,
+                move || actual.actual_size(),
+                || false,
+                move || content.with(Option::is_none),
+                || "Actual Size",
+                config,
+            ),
+            clickable_icon(
+                || LapceIcons::FILE,
+                move || {
+                    internal_command.send(InternalCommand::OpenFileAsText {
+                        path: path.clone(),
+                    });
+                },
+                || false,
+                || false,
+                || "Open as Text",
+                config,
+            )
+            .style(move |s| s.margin_left(6.0).apply_if(!svg_image, |s| s.hide())),
+        ))
+        .style(move |s| {
+            let config = config.get();
+            s.items_center()
+                .width_full()
+                .padding_horiz(10.0)
+                .padding_vert(4.0)
+                .border_top(1.0)
+                .border_color(config.color(LapceColor::LAPCE_BORDER))
+        })
+    };
+
+    stack((
+        container(
+            scroll(image)
+                .on_resize(move |rect| {
+                    viewport.set(rect.size());
+                })
+                .style(|s| s.absolute().size_full()),
+        )
+        .on_event_cont(EventListener::PointerDown, move |_| {
+            focus.set(Focus::Workbench);
+        })
+        .style(|s| s.width_full().flex_basis(0.0).flex_grow(1.0)),
+        toolbar,
+    ))
+    .style(move |s| {
+        s.absolute()
+            .size_full()
+            .flex_col()
+            .background(config.get().color(LapceColor::EDITOR_BACKGROUND))
+    })
+    .debug_name("Image Viewer")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use floem::peniko::kurbo::Size;
+
+    use super::{ImageKind, fit_scale, format_file_size};
+
+    #[test]
+    fn test_image_kind() {
+        assert_eq!(
+            ImageKind::from_path(Path::new("a/logo.PNG")),
+            Some(ImageKind::Png)
+        );
+        assert_eq!(
+            ImageKind::from_path(Path::new("photo.jpeg")),
+            Some(ImageKind::Jpeg)
+        );
+        assert_eq!(
+            ImageKind::from_path(Path::new("icon.svg")),
+            Some(ImageKind::Svg)
+        );
+        assert_eq!(ImageKind::from_path(Path::new("main.rs")), None);
+        assert_eq!(ImageKind::from_path(Path::new("png")), None);
+    }
+
+    #[test]
+    fn test_fit_and_size() {
+        assert_eq!(fit_scale((200, 100), Size::new(100.0, 100.0)), 0.5);
+        assert_eq!(fit_scale((50, 50), Size::new(100.0, 100.0)), 1.0);
+        assert_eq!(fit_scale((50, 50), Size::ZERO), 1.0);
+
+        assert_eq!(format_file_size(512), "512 B");
+        assert_eq!(format_file_size(1536), "1.5 KB");
+        assert_eq!(format_file_size(3 * 1024 * 1024), "3.0 MB");
+    }
+}
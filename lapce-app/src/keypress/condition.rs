@@ -71,6 +71,8 @@ pub enum Condition {
     SearchFocus,
     #[strum(serialize = "replace_focus")]
     ReplaceFocus,
+    #[strum(serialize = "rebase_todo_focus")]
+    RebaseTodoFocus,
 }
 
 #[cfg(test)]
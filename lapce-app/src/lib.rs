@@ -4,6 +4,7 @@
 pub mod code_action;
 pub mod code_lens;
 pub mod command;
+pub mod commit_history;
 pub mod completion;
 pub mod config;
 pub mod db;
@@ -15,9 +16,11 @@
 pub mod find;
 pub mod focus_text;
 pub mod global_search;
+pub mod hex_editor;
 pub mod history;
 pub mod hover;
 pub mod id;
+pub mod image_viewer;
 pub mod inline_completion;
 pub mod keymap;
 pub mod keypress;
@@ -25,11 +28,14 @@
 pub mod lsp;
 pub mod main_split;
 pub mod markdown;
+pub mod markdown_preview;
 pub mod palette;
 pub mod panel;
 pub mod plugin;
 pub mod proxy;
+pub mod rebase_todo;
 pub mod rename;
+pub mod search_editor;
 pub mod settings;
 pub mod snippet;
 pub mod source_control;
@@ -11,7 +11,7 @@
     keyboard::Modifiers,
     peniko::kurbo::{Point, Rect, Vec2},
     reactive::{Memo, RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
-    views::editor::id::EditorId,
+    views::editor::id::{EditorId, Id},
 };
 use itertools::Itertools;
 use lapce_core::{
@@ -21,8 +21,10 @@
 use lapce_rpc::{
     buffer::BufferId,
     core::FileChanged,
+    editorconfig::EditorConfigProperties,
     plugin::{PluginId, VoltID},
     proxy::ProxyResponse,
+    source_control::FileDiff,
 };
 use lapce_xi_rope::{Rope, spans::SpansBuilder};
 use lsp_types::{
@@ -46,11 +48,14 @@
     editor_tab::{
         EditorTabChild, EditorTabChildSource, EditorTabData, EditorTabInfo,
     },
+    hex_editor::HexEditorData,
     id::{
-        DiffEditorId, EditorTabId, KeymapId, SettingsId, SplitId,
-        ThemeColorSettingsId, VoltViewId,
+        DiffEditorId, EditorTabId, HexEditorId, ImageViewerId, KeymapId,
+        MarkdownPreviewId, SettingsId, SplitId, ThemeColorSettingsId, VoltViewId,
     },
+    image_viewer::{ImageKind, ImageViewerData},
     keypress::{EventRef, KeyPressData, KeyPressHandle},
+    markdown_preview::MarkdownPreviewData,
     panel::implementation_view::ReferencesRoot,
     window_tab::{CommonData, Focus, WindowTabData},
 };
@@ -387,6 +392,87 @@ pub fn with_editors_untracked<O>(
     }
 }
 
+/// The data of a tab which shows a file other than in an editor, like the hex
+/// editor, the image viewer and the markdown preview
+pub trait FileViewData: Clone + 'static {
+    fn id(&self) -> Id;
+
+    fn path(&self) -> &Path;
+
+    /// A view of the same file for a new split
+    fn copy(&self, cx: Scope, editors: Editors) -> Self;
+
+    /// Release what the view holds, when its tab is closed
+    fn dispose(&self, editors: Editors);
+}
+
+/// All the file views of one kind in a main split
+pub struct FileViews<T: 'static>(pub RwSignal<im::HashMap<Id, T>>);
+
+impl<T> Clone for FileViews<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for FileViews<T> {}
+
+impl<T: FileViewData> FileViews<T> {
+    fn new(cx: Scope) -> Self {
+        Self(cx.create_rw_signal(im::HashMap::new()))
+    }
+
+    /// Add a view to the views.
+    /// Returns the id of the view.
+    pub fn insert(&self, view: T) -> Id {
+        let id = view.id();
+        self.0.update(|views| {
+            views.insert(id, view);
+        });
+        id
+    }
+
+    /// Copy an existing view for a new split, which is inserted as well
+    pub fn copy(&self, id: Id, cx: Scope, editors: Editors) -> Option<Id> {
+        let view = self.get_untracked(id)?.copy(cx, editors);
+        Some(self.insert(view))
+    }
+
+    pub fn remove(&self, id: Id, editors: Editors) {
+        let removed = self.0.try_update(|views| views.remove(&id)).flatten();
+        if let Some(view) = removed {
+            view.dispose(editors);
+        }
+    }
+
+    /// Get the view (tracking the signal)
+    pub fn get(&self, id: Id) -> Option<T> {
+        self.0.with(|views| views.get(&id).cloned())
+    }
+
+    /// Get the view (not tracking the signal)
+    pub fn get_untracked(&self, id: Id) -> Option<T> {
+        self.0.with_untracked(|views| views.get(&id).cloned())
+    }
+
+    /// Whether the view shows the file
+    pub fn is_of(&self, id: Id, path: &Path) -> bool {
+        self.0
+            .with_untracked(|views| views.get(&id).is_some_and(|v| v.path() == path))
+    }
+
+    /// The views which show the file
+    pub fn of_path(&self, path: &Path) -> Vec<T> {
+        self.0.with_untracked(|views| {
+            views
+                .values()
+                .filter(|view| view.path() == path)
+                .cloned()
+                .collect()
+        })
+    }
+}
+
 #[derive(Clone)]
 pub struct MainSplitData {
     pub scope: Scope,
@@ -396,6 +482,9 @@ pub struct MainSplitData {
     pub editor_tabs: RwSignal<im::HashMap<EditorTabId, RwSignal<EditorTabData>>>,
     pub editors: Editors,
     pub diff_editors: RwSignal<im::HashMap<DiffEditorId, DiffEditorData>>,
+    pub hex_editors: FileViews<HexEditorData>,
+    pub image_viewers: FileViews<ImageViewerData>,
+    pub markdown_previews: FileViews<MarkdownPreviewData>,
     pub docs: RwSignal<im::HashMap<PathBuf, Rc<Doc>>>,
     pub scratch_docs: RwSignal<im::HashMap<String, Rc<Doc>>>,
     pub diagnostics: RwSignal<im::HashMap<PathBuf, DiagnosticData>>,
@@ -429,6 +518,9 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
         let editors = Editors::new(cx);
         let diff_editors: RwSignal<im::HashMap<DiffEditorId, DiffEditorData>> =
             cx.create_rw_signal(im::HashMap::new());
+        let hex_editors = FileViews::new(cx);
+        let image_viewers = FileViews::new(cx);
+        let markdown_previews = FileViews::new(cx);
         let docs: RwSignal<im::HashMap<PathBuf, Rc<Doc>>> =
             cx.create_rw_signal(im::HashMap::new());
         let scratch_docs = cx.create_rw_signal(im::HashMap::new());
@@ -485,6 +577,9 @@ pub fn new(cx: Scope, common: Rc<CommonData>) -> Self {
             editor_tabs,
             editors,
             diff_editors,
+            hex_editors,
+            image_viewers,
+            markdown_previews,
             docs,
             scratch_docs,
             active_editor,
@@ -538,6 +633,16 @@ pub fn key_down<'a>(
             EditorTabChild::ThemeColorSettings(_) => None,
             EditorTabChild::Keymap(_) => None,
             EditorTabChild::Volt(_, _) => None,
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                let hex_editor = self.hex_editors.get_untracked(hex_editor_id)?;
+                Some(keypress.key_down(event, &hex_editor))
+            }
+            EditorTabChild::ImageViewer(image_viewer_id) => {
+                let image_viewer =
+                    self.image_viewers.get_untracked(image_viewer_id)?;
+                Some(keypress.key_down(event, &image_viewer))
+            }
+            EditorTabChild::MarkdownPreview(_) => None,
         }
     }
 
@@ -640,14 +745,34 @@ pub fn get_doc(
             {
                 let doc = doc.clone();
                 let local_doc = doc.clone();
+                let main_split = self.clone();
+                let local_path = path.clone();
                 let send = create_ext_action(cx, move |result| {
                     if let Ok(ProxyResponse::NewBufferResponse {
                         content,
                         read_only,
+                        editor_config,
+                        encoding,
+                        large_file,
+                        binary,
                     }) = result
                     {
-                        local_doc.init_content(Rope::from(content));
-                        if read_only {
+                        local_doc.editor_config.set(editor_config);
+                        local_doc.encoding.set(encoding);
+                        local_doc.file_read_only.set(read_only);
+                        if large_file {
+                            local_doc.large_file.set(true);
+                            local_doc.load_large_file(false);
+                        } else {
+                            local_doc.init_content(Rope::from(content));
+                            // Only requested once it's known that it isn't a
+                            // large file, which has no language server features
+                            local_doc.get_code_lens();
+                            local_doc.get_folding_range();
+                            local_doc.get_document_symbol();
+                        }
+                        // Large files are read-only until editing is enabled
+                        if read_only || large_file {
                             local_doc.content.update(|content| {
                                 if let DocContent::File { read_only, .. } = content {
                                     *read_only = true;
@@ -656,18 +781,28 @@ pub fn get_doc(
                         } else if let Some(unsaved) = unsaved {
                             local_doc.reload(Rope::from(unsaved), false);
                         }
+                        if binary {
+                            local_doc.binary.set(true);
+                            main_split.open_binary_file(&local_path);
+                        }
                     }
                 });
 
-                self.common
-                    .proxy
-                    .new_buffer(doc.buffer_id, path, move |result| {
+                let large_file_threshold = self
+                    .common
+                    .config
+                    .get_untracked()
+                    .editor
+                    .large_file_threshold_bytes();
+                self.common.proxy.new_buffer(
+                    doc.buffer_id,
+                    path,
+                    large_file_threshold,
+                    move |result| {
                         send(result);
-                    });
+                    },
+                );
             }
-            doc.get_code_lens();
-            doc.get_folding_range();
-            doc.get_document_symbol();
             (doc, true)
         }
     }
@@ -680,8 +815,48 @@ pub fn go_to_location(
         if self.common.focus.get_untracked() != Focus::Workbench {
             self.common.focus.set(Focus::Workbench);
         }
+        // Images are previewed, unless a position in their text is asked for
+        if location.position.is_none()
+            && ImageKind::from_path(&location.path).is_some()
+        {
+            self.get_editor_tab_child(
+                EditorTabChildSource::ImageViewer(location.path),
+                location.ignore_unconfirmed,
+                location.same_editor_tab,
+            );
+            return;
+        }
+        self.go_to_location_in_editor(location, edits);
+    }
+
+    /// Open a file in a text editor, even if it's an image
+    pub fn open_file_as_text(&self, path: PathBuf) {
+        if self.common.focus.get_untracked() != Focus::Workbench {
+            self.common.focus.set(Focus::Workbench);
+        }
+        self.go_to_location_in_editor(
+            EditorLocation {
+                path,
+                position: None,
+                scroll_offset: None,
+                ignore_unconfirmed: false,
+                same_editor_tab: false,
+            },
+            None,
+        );
+    }
+
+    fn go_to_location_in_editor(
+        &self,
+        location: EditorLocation,
+        edits: Option<Vec<TextEdit>>,
+    ) {
         let path = location.path.clone();
         let (doc, new_doc) = self.get_doc(path.clone(), None);
+        if doc.binary.get_untracked() {
+            self.open_hex_editor(path);
+            return;
+        }
 
         let child = self.get_editor_tab_child(
             EditorTabChildSource::Editor { path, doc },
@@ -696,29 +871,60 @@ pub fn go_to_location(
     }
 
     pub fn open_file_changes(&self, path: PathBuf) {
+        self.open_file_changes_against(path, "head".to_string());
+    }
+
+    /// Open the changes of the file in the diff editor, compared to its
+    /// version at the revision
+    pub fn open_file_changes_against(&self, path: PathBuf, revision: String) {
         let (right, _) = self.get_doc(path.clone(), None);
         let left = Doc::new_history(
             self.scope,
             DocContent::History(DocHistory {
                 path: path.clone(),
-                version: "head".to_string(),
+                version: revision,
             }),
             self.editors,
             self.common.clone(),
         );
         let left = Rc::new(left);
+        left.load_history_content();
 
-        let send = {
-            let left = left.clone();
-            create_ext_action(self.scope, move |result| {
-                if let Ok(ProxyResponse::BufferHeadResponse { content, .. }) = result
-                {
-                    left.init_content(Rope::from(content));
-                }
-            })
+        self.get_editor_tab_child(
+            EditorTabChildSource::DiffEditor { left, right },
+            false,
+            false,
+        );
+    }
+
+    /// Open the changes a commit made to a file in the diff editor, compared to
+    /// the commit's first parent
+    pub fn open_commit_file_changes(&self, commit: String, diff: FileDiff) {
+        let (left_path, right_path) = match &diff {
+            FileDiff::Renamed(new_path, old_path) => {
+                (old_path.clone(), new_path.clone())
+            }
+            _ => (diff.path().clone(), diff.path().clone()),
         };
-        self.common.proxy.get_buffer_head(path, move |result| {
-            send(result);
+        let [left, right] = [
+            DocHistory {
+                path: left_path,
+                version: format!("{commit}^"),
+            },
+            DocHistory {
+                path: right_path,
+                version: commit,
+            },
+        ]
+        .map(|history| {
+            let doc = Rc::new(Doc::new_history(
+                self.scope,
+                DocContent::History(history),
+                self.editors,
+                self.common.clone(),
+            ));
+            doc.load_history_content();
+            doc
         });
 
         self.get_editor_tab_child(
@@ -781,6 +987,8 @@ fn get_editor_tab_child(
 
         let editors = self.editors;
         let diff_editors = self.diff_editors.get_untracked();
+        let hex_editors = self.hex_editors;
+        let image_viewers = self.image_viewers;
 
         let active_editor_tab = if let Some(editor_tab) = active_editor_tab {
             editor_tab
@@ -817,6 +1025,14 @@ fn get_editor_tab_child(
                     .unwrap_or(false)
             };
 
+        let is_hex_editor_of = |hex_editor_id: &HexEditorId, path: &Path| {
+            hex_editors.is_of(*hex_editor_id, path)
+        };
+
+        let is_image_viewer_of = |image_viewer_id: &ImageViewerId, path: &Path| {
+            image_viewers.is_of(*image_viewer_id, path)
+        };
+
         let selected = if !config.editor.show_tab {
             active_editor_tab.with_untracked(|editor_tab| {
                 for (i, (_, _, child)) in editor_tab.children.iter().enumerate() {
@@ -871,6 +1087,25 @@ fn get_editor_tab_child(
                         EditorTabChild::ThemeColorSettings(_) => true,
                         EditorTabChild::Keymap(_) => true,
                         EditorTabChild::Volt(_, _) => true,
+                        EditorTabChild::HexEditor(hex_editor_id) => {
+                            let same_path =
+                                if let EditorTabChildSource::HexEditor(path) =
+                                    &source
+                                {
+                                    is_hex_editor_of(hex_editor_id, path)
+                                } else {
+                                    false
+                                };
+
+                            same_path
+                                || hex_editors
+                                    .get_untracked(*hex_editor_id)
+                                    .is_none_or(|hex_editor| {
+                                        hex_editor.pristine.get_untracked()
+                                    })
+                        }
+                        EditorTabChild::ImageViewer(_) => true,
+                        EditorTabChild::MarkdownPreview(_) => true,
                     };
 
                     if can_be_selected {
@@ -927,7 +1162,7 @@ fn get_editor_tab_child(
                         })
                     }
                 }
-                EditorTabChildSource::NewFileEditor => {
+                EditorTabChildSource::NewFileEditor { .. } => {
                     if ignore_unconfirmed {
                         None
                     } else {
@@ -1036,6 +1271,62 @@ fn get_editor_tab_child(
                         })
                     }
                 }
+                EditorTabChildSource::HexEditor(path) => {
+                    if let Some(index) =
+                        active_editor_tab.with_untracked(|editor_tab| {
+                            editor_tab.children.iter().position(|(_, _, child)| {
+                                if let EditorTabChild::HexEditor(hex_editor_id) =
+                                    child
+                                {
+                                    is_hex_editor_of(hex_editor_id, path)
+                                } else {
+                                    false
+                                }
+                            })
+                        })
+                    {
+                        Some(index)
+                    } else if ignore_unconfirmed {
+                        None
+                    } else {
+                        active_editor_tab.with_untracked(|editor_tab| {
+                            editor_tab
+                                .get_unconfirmed_editor_tab_child(
+                                    editors,
+                                    &diff_editors,
+                                )
+                                .map(|(i, _)| i)
+                        })
+                    }
+                }
+                EditorTabChildSource::ImageViewer(path) => {
+                    if let Some(index) =
+                        active_editor_tab.with_untracked(|editor_tab| {
+                            editor_tab.children.iter().position(|(_, _, child)| {
+                                if let EditorTabChild::ImageViewer(image_viewer_id) =
+                                    child
+                                {
+                                    is_image_viewer_of(image_viewer_id, path)
+                                } else {
+                                    false
+                                }
+                            })
+                        })
+                    {
+                        Some(index)
+                    } else if ignore_unconfirmed {
+                        None
+                    } else {
+                        active_editor_tab.with_untracked(|editor_tab| {
+                            editor_tab
+                                .get_unconfirmed_editor_tab_child(
+                                    editors,
+                                    &diff_editors,
+                                )
+                                .map(|(i, _)| i)
+                        })
+                    }
+                }
             }
         };
 
@@ -1054,8 +1345,8 @@ fn get_editor_tab_child(
 
                     EditorTabChild::Editor(editor_id)
                 }
-                EditorTabChildSource::NewFileEditor => {
-                    let name = self.get_name_for_new_file();
+                EditorTabChildSource::NewFileEditor { name_prefix } => {
+                    let name = self.get_name_for_new_file(name_prefix);
                     let doc_content = DocContent::Scratch {
                         id: BufferId::next(),
                         name: name.clone(),
@@ -1109,6 +1400,12 @@ fn get_editor_tab_child(
                     });
                     EditorTabChild::DiffEditor(diff_editor_id)
                 }
+                EditorTabChildSource::HexEditor(path) => {
+                    self.new_hex_editor(path.to_owned())
+                }
+                EditorTabChildSource::ImageViewer(path) => {
+                    self.new_image_viewer(path.to_owned())
+                }
             };
 
         if let Some(selected) = selected {
@@ -1129,6 +1426,9 @@ fn get_editor_tab_child(
                         EditorTabChild::ThemeColorSettings(_) => {}
                         EditorTabChild::Keymap(_) => {}
                         EditorTabChild::Volt(_, _) => {}
+                        EditorTabChild::HexEditor(_) => {}
+                        EditorTabChild::ImageViewer(_) => {}
+                        EditorTabChild::MarkdownPreview(_) => {}
                     }
                     (editor_tab_id, current_child.clone())
                 });
@@ -1169,6 +1469,14 @@ fn get_editor_tab_child(
                 (EditorTabChild::Settings(_), EditorTabChildSource::Settings) => {
                     true
                 }
+                (
+                    EditorTabChild::HexEditor(hex_editor_id),
+                    EditorTabChildSource::HexEditor(path),
+                ) => is_hex_editor_of(hex_editor_id, path),
+                (
+                    EditorTabChild::ImageViewer(image_viewer_id),
+                    EditorTabChildSource::ImageViewer(path),
+                ) => is_image_viewer_of(image_viewer_id, path),
                 _ => false,
             };
             if is_same {
@@ -1195,6 +1503,16 @@ fn get_editor_tab_child(
                 EditorTabChild::ThemeColorSettings(_) => {}
                 EditorTabChild::Keymap(_) => {}
                 EditorTabChild::Volt(_, _) => {}
+                EditorTabChild::HexEditor(hex_editor_id) => {
+                    self.hex_editors.remove(*hex_editor_id, self.editors);
+                }
+                EditorTabChild::ImageViewer(image_viewer_id) => {
+                    self.image_viewers.remove(*image_viewer_id, self.editors);
+                }
+                EditorTabChild::MarkdownPreview(markdown_preview_id) => {
+                    self.markdown_previews
+                        .remove(*markdown_preview_id, self.editors);
+                }
             }
 
             // Now loading the new child
@@ -1270,7 +1588,32 @@ fn get_editor_tab_child(
                                         false
                                     }
                                 }),
-                            EditorTabChildSource::NewFileEditor => None,
+                            EditorTabChildSource::HexEditor(path) => editor_tab
+                                .children
+                                .iter()
+                                .position(|(_, _, child)| {
+                                    if let EditorTabChild::HexEditor(hex_editor_id) =
+                                        child
+                                    {
+                                        is_hex_editor_of(hex_editor_id, path)
+                                    } else {
+                                        false
+                                    }
+                                }),
+                            EditorTabChildSource::ImageViewer(path) => editor_tab
+                                .children
+                                .iter()
+                                .position(|(_, _, child)| {
+                                    if let EditorTabChild::ImageViewer(
+                                        image_viewer_id,
+                                    ) = child
+                                    {
+                                        is_image_viewer_of(image_viewer_id, path)
+                                    } else {
+                                        false
+                                    }
+                                }),
+                            EditorTabChildSource::NewFileEditor { .. } => None,
                         })
                     {
                         self.active_editor_tab.set(Some(*editor_tab_id));
@@ -1433,6 +1776,17 @@ pub fn split(
         &self,
         direction: SplitDirection,
         editor_tab_id: EditorTabId,
+    ) -> Option<()> {
+        self.split_with_child(direction, editor_tab_id, None)
+    }
+
+    /// Split an editor tab, with the child given in the new editor tab, or a
+    /// copy of the active child of the editor tab if there's none
+    fn split_with_child(
+        &self,
+        direction: SplitDirection,
+        editor_tab_id: EditorTabId,
+        child: Option<EditorTabChild>,
     ) -> Option<()> {
         let editor_tabs = self.editor_tabs.get_untracked();
         let editor_tab = editor_tabs.get(&editor_tab_id).copied()?;
@@ -1452,7 +1806,7 @@ pub fn split(
 
         if split_direction == direction {
             let new_editor_tab = editor_tab.with_untracked(|editor_tab| {
-                self.split_editor_tab(self.scope, split_id, editor_tab)
+                self.split_editor_tab(self.scope, split_id, editor_tab, child)
             })?;
             let new_editor_tab_id =
                 new_editor_tab.with_untracked(|editor_tab| editor_tab.editor_tab_id);
@@ -1470,7 +1824,7 @@ pub fn split(
             });
         } else if children_len == 1 {
             let new_editor_tab = editor_tab.with_untracked(|editor_tab| {
-                self.split_editor_tab(self.scope, split_id, editor_tab)
+                self.split_editor_tab(self.scope, split_id, editor_tab, child)
             })?;
             let new_editor_tab_id =
                 new_editor_tab.with_untracked(|editor_tab| editor_tab.editor_tab_id);
@@ -1491,7 +1845,7 @@ pub fn split(
                 editor_tab.split = new_split_id;
             });
             let new_editor_tab = editor_tab.with_untracked(|editor_tab| {
-                self.split_editor_tab(self.scope, new_split_id, editor_tab)
+                self.split_editor_tab(self.scope, new_split_id, editor_tab, child)
             })?;
             let new_editor_tab_id =
                 new_editor_tab.with_untracked(|editor_tab| editor_tab.editor_tab_id);
@@ -1538,11 +1892,51 @@ fn split_editor_tab(
         cx: Scope,
         split_id: SplitId,
         editor_tab: &EditorTabData,
+        child: Option<EditorTabChild>,
     ) -> Option<RwSignal<EditorTabData>> {
-        let (_, _, child) = editor_tab.children.get(editor_tab.active)?;
-
         let editor_tab_id = EditorTabId::next();
 
+        let new_child = match child {
+            Some(child) => child,
+            None => {
+                let (_, _, child) = editor_tab.children.get(editor_tab.active)?;
+                self.copy_editor_tab_child(cx, editor_tab_id, child)?
+            }
+        };
+
+        let editor_tab = {
+            let cx = self.scope.create_child();
+            let editor_tab = EditorTabData {
+                scope: cx,
+                split: split_id,
+                editor_tab_id,
+                active: 0,
+                children: vec![(
+                    cx.create_rw_signal(0),
+                    cx.create_rw_signal(Rect::ZERO),
+                    new_child,
+                )],
+                window_origin: Point::ZERO,
+                layout_rect: Rect::ZERO,
+                locations: cx.create_rw_signal(editor_tab.locations.get_untracked()),
+                current_location: cx
+                    .create_rw_signal(editor_tab.current_location.get_untracked()),
+            };
+            cx.create_rw_signal(editor_tab)
+        };
+        self.editor_tabs.update(|editor_tabs| {
+            editor_tabs.insert(editor_tab_id, editor_tab);
+        });
+        Some(editor_tab)
+    }
+
+    /// A copy of an editor tab child for a new editor tab
+    fn copy_editor_tab_child(
+        &self,
+        cx: Scope,
+        editor_tab_id: EditorTabId,
+        child: &EditorTabChild,
+    ) -> Option<EditorTabChild> {
         let new_child = match child {
             EditorTabChild::Editor(editor_id) => {
                 let editor_id = self
@@ -1574,32 +1968,25 @@ fn split_editor_tab(
             EditorTabChild::Volt(_, id) => {
                 EditorTabChild::Volt(VoltViewId::next(), id.to_owned())
             }
+            EditorTabChild::HexEditor(hex_editor_id) => EditorTabChild::HexEditor(
+                self.hex_editors.copy(*hex_editor_id, cx, self.editors)?,
+            ),
+            EditorTabChild::ImageViewer(image_viewer_id) => {
+                EditorTabChild::ImageViewer(self.image_viewers.copy(
+                    *image_viewer_id,
+                    cx,
+                    self.editors,
+                )?)
+            }
+            EditorTabChild::MarkdownPreview(markdown_preview_id) => {
+                EditorTabChild::MarkdownPreview(self.markdown_previews.copy(
+                    *markdown_preview_id,
+                    cx,
+                    self.editors,
+                )?)
+            }
         };
-
-        let editor_tab = {
-            let cx = self.scope.create_child();
-            let editor_tab = EditorTabData {
-                scope: cx,
-                split: split_id,
-                editor_tab_id,
-                active: 0,
-                children: vec![(
-                    cx.create_rw_signal(0),
-                    cx.create_rw_signal(Rect::ZERO),
-                    new_child,
-                )],
-                window_origin: Point::ZERO,
-                layout_rect: Rect::ZERO,
-                locations: cx.create_rw_signal(editor_tab.locations.get_untracked()),
-                current_location: cx
-                    .create_rw_signal(editor_tab.current_location.get_untracked()),
-            };
-            cx.create_rw_signal(editor_tab)
-        };
-        self.editor_tabs.update(|editor_tabs| {
-            editor_tabs.insert(editor_tab_id, editor_tab);
-        });
-        Some(editor_tab)
+        Some(new_child)
     }
 
     pub fn split_move(
@@ -1874,48 +2261,127 @@ pub fn editor_tab_close(&self, editor_tab_id: EditorTabId) -> Option<()> {
         Some(())
     }
 
+    /// The name of the unsaved content of `child` and the action saving it,
+    /// if closing it would lose changes
     fn editor_tab_child_close_warning(
         &self,
+        editor_tab_id: EditorTabId,
         child: &EditorTabChild,
-    ) -> Option<(String, Rc<Doc>, EditorData)> {
+    ) -> Option<(String, Rc<dyn Fn()>)> {
+        let internal_command = self.common.internal_command;
+        let main_split = self.clone();
         match child {
             EditorTabChild::Editor(editor_id) => {
                 let editor = self.editors.editor_untracked(*editor_id)?;
                 let doc = editor.doc();
                 let doc_content = doc.content.get_untracked();
                 let is_dirty = !doc.is_pristine();
-                if is_dirty {
-                    let exists = self.editors.with_editors_untracked(|editors| {
-                        editors.iter().any(|(id, editor)| {
-                            let doc = editor.doc();
-                            id != editor_id
-                                && doc.content.with_untracked(|content| {
-                                    content == &doc_content
-                                })
-                        })
-                    });
-                    if !exists {
-                        return match doc_content {
-                            DocContent::File { path, .. } => Some((
-                                path.file_name()?.to_str()?.to_string(),
-                                doc,
-                                editor,
-                            )),
-                            DocContent::Local => None,
-                            DocContent::History(_) => None,
-                            DocContent::Scratch { name, .. } => {
-                                Some((name, doc, editor))
-                            }
-                        };
+                if !is_dirty {
+                    return None;
+                }
+                let exists = self.editors.with_editors_untracked(|editors| {
+                    editors.iter().any(|(id, editor)| {
+                        let doc = editor.doc();
+                        id != editor_id
+                            && doc
+                                .content
+                                .with_untracked(|content| content == &doc_content)
+                    })
+                });
+                if exists {
+                    return None;
+                }
+                match doc_content {
+                    DocContent::Scratch { name, .. } => {
+                        let child = child.clone();
+                        let save_action: Rc<dyn Fn()> = Rc::new(move || {
+                            let child = child.clone();
+                            let main_split = main_split.clone();
+                            let doc = doc.clone();
+                            internal_command.send(InternalCommand::HideAlert);
+                            save_as(
+                                FileDialogOptions::new().title("Save File"),
+                                move |file: Option<FileInfo>| {
+                                    let main_split = main_split.clone();
+                                    let child = child.clone();
+                                    let local_main_split = main_split.clone();
+                                    if let Some(mut file) = file {
+                                        main_split.save_as(
+                                            doc.clone(),
+                                            if let Some(path) = file.path.pop() {
+                                                path
+                                            } else {
+                                                tracing::error!("No path");
+                                                return;
+                                            },
+                                            move || {
+                                                local_main_split
+                                                    .clone()
+                                                    .editor_tab_child_close(
+                                                        editor_tab_id,
+                                                        child.clone(),
+                                                        false,
+                                                    );
+                                            },
+                                        );
+                                    }
+                                },
+                            );
+                        });
+                        Some((name, save_action))
+                    }
+                    DocContent::File { path, .. } => {
+                        let name = path.file_name()?.to_str()?.to_string();
+                        let editors = self.editors;
+                        let editor_id = editor.id();
+                        let save_action: Rc<dyn Fn()> = Rc::new(move || {
+                            internal_command.send(InternalCommand::HideAlert);
+                            editor.save(false, move || {
+                                if let Some(editor) =
+                                    editors.editor_untracked(editor_id)
+                                {
+                                    editor.clone().run_focus_command(
+                                        &FocusCommand::SplitClose,
+                                        None,
+                                        Modifiers::empty(),
+                                    );
+                                }
+                            });
+                        });
+                        Some((name, save_action))
                     }
+                    DocContent::Local => None,
+                    DocContent::History(_) => None,
                 }
-                None
+            }
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                let hex_editor = self.hex_editors.get_untracked(*hex_editor_id)?;
+                if hex_editor.pristine.get_untracked() {
+                    return None;
+                }
+                let name = hex_editor.path.file_name()?.to_str()?.to_string();
+                let child = child.clone();
+                let save_action: Rc<dyn Fn()> = Rc::new(move || {
+                    internal_command.send(InternalCommand::HideAlert);
+                    let main_split = main_split.clone();
+                    let child = child.clone();
+                    hex_editor.save(move || {
+                        main_split.editor_tab_child_close(
+                            editor_tab_id,
+                            child,
+                            false,
+                        );
+                    });
+                });
+                Some((name, save_action))
             }
             EditorTabChild::DiffEditor(_) => None,
             EditorTabChild::Settings(_) => None,
             EditorTabChild::ThemeColorSettings(_) => None,
             EditorTabChild::Keymap(_) => None,
             EditorTabChild::Volt(_, _) => None,
+            EditorTabChild::ImageViewer(_) => None,
+            EditorTabChild::MarkdownPreview(_) => None,
         }
     }
 
@@ -2010,110 +2476,39 @@ pub fn editor_tab_child_close(
         force: bool,
     ) -> Option<()> {
         if !force {
-            if let Some((name, doc, editor)) =
-                self.editor_tab_child_close_warning(&child)
+            if let Some((name, save_action)) =
+                self.editor_tab_child_close_warning(editor_tab_id, &child)
             {
                 let internal_command = self.common.internal_command;
                 let main_split = self.clone();
-
-                let doc_content = doc.content.get_untracked();
-                let save_button = match doc_content {
-                    DocContent::Scratch { .. } => {
-                        let child = child.clone();
-                        let doc = doc.clone();
-                        let save_action = Rc::new(move || {
-                            let child = child.clone();
-                            let main_split = main_split.clone();
-                            let doc = doc.clone();
-                            internal_command.send(InternalCommand::HideAlert);
-                            save_as(
-                                FileDialogOptions::new().title("Save File"),
-                                move |file: Option<FileInfo>| {
-                                    let main_split = main_split.clone();
-                                    let child = child.clone();
-                                    let local_main_split = main_split.clone();
-                                    if let Some(mut file) = file {
-                                        main_split.save_as(
-                                            doc.clone(),
-                                            if let Some(path) = file.path.pop() {
-                                                path
-                                            } else {
-                                                tracing::error!("No path");
-                                                return;
-                                            },
-                                            move || {
-                                                local_main_split
-                                                    .clone()
-                                                    .editor_tab_child_close(
-                                                        editor_tab_id,
-                                                        child.clone(),
-                                                        false,
-                                                    );
-                                            },
-                                        );
-                                    }
-                                },
-                            );
-                        });
-                        Some(AlertButton {
-                            text: "Save".to_string(),
-                            action: save_action,
-                        })
-                    }
-                    DocContent::File { .. } => {
-                        let editor = editor.clone();
-                        let editors = self.editors;
-                        let editor_id = editor.id();
-                        let save_action = Rc::new(move || {
-                            internal_command.send(InternalCommand::HideAlert);
-                            editor.save(false, move || {
-                                if let Some(editor) =
-                                    editors.editor_untracked(editor_id)
-                                {
-                                    editor.clone().run_focus_command(
-                                        &FocusCommand::SplitClose,
-                                        None,
-                                        Modifiers::empty(),
+                let child = child.clone();
+                self.common
+                    .internal_command
+                    .send(InternalCommand::ShowAlert {
+                        title: format!(
+                            "Do you want to save the changes you made to {name}?"
+                        ),
+                        msg: "Your changes will be lost if you don't save them."
+                            .to_string(),
+                        buttons: vec![
+                            AlertButton {
+                                text: "Save".to_string(),
+                                action: save_action,
+                            },
+                            AlertButton {
+                                text: "Don't Save".to_string(),
+                                action: Rc::new(move || {
+                                    internal_command
+                                        .send(InternalCommand::HideAlert);
+                                    main_split.editor_tab_child_close(
+                                        editor_tab_id,
+                                        child.clone(),
+                                        true,
                                     );
-                                }
-                            });
-                        });
-                        Some(AlertButton {
-                            text: "Save".to_string(),
-                            action: save_action,
-                        })
-                    }
-                    DocContent::Local => None,
-                    DocContent::History(_) => None,
-                };
-                if let Some(save_button) = save_button {
-                    let main_split = self.clone();
-                    let child = child.clone();
-                    self.common
-                        .internal_command
-                        .send(InternalCommand::ShowAlert {
-                            title: format!(
-                                "Do you want to save the changes you made to {name}?"
-                            ),
-                            msg: "Your changes will be lost if you don't save them."
-                                .to_string(),
-                            buttons: vec![
-                                save_button,
-                                AlertButton {
-                                    text: "Don't Save".to_string(),
-                                    action: Rc::new(move || {
-                                        internal_command
-                                            .send(InternalCommand::HideAlert);
-                                        main_split.editor_tab_child_close(
-                                            editor_tab_id,
-                                            child.clone(),
-                                            true,
-                                        );
-                                    }),
-                                },
-                            ],
-                        });
-                }
+                                }),
+                            },
+                        ],
+                    });
 
                 return Some(());
             }
@@ -2154,6 +2549,16 @@ pub fn editor_tab_child_close(
             EditorTabChild::ThemeColorSettings(_) => {}
             EditorTabChild::Keymap(_) => {}
             EditorTabChild::Volt(_, _) => {}
+            EditorTabChild::HexEditor(hex_editor_id) => {
+                self.hex_editors.remove(hex_editor_id, self.editors);
+            }
+            EditorTabChild::ImageViewer(image_viewer_id) => {
+                self.image_viewers.remove(image_viewer_id, self.editors);
+            }
+            EditorTabChild::MarkdownPreview(markdown_preview_id) => {
+                self.markdown_previews
+                    .remove(markdown_preview_id, self.editors);
+            }
         }
 
         if editor_tab_children_len == 0 {
@@ -2355,6 +2760,19 @@ pub fn get_diagnostic_data(&self, path: &Path) -> DiagnosticData {
         }
     }
 
+    pub fn editor_config_changed(
+        &self,
+        path: &Path,
+        properties: &EditorConfigProperties,
+        encoding: &str,
+    ) {
+        let doc = self.docs.with_untracked(|docs| docs.get(path).cloned());
+        if let Some(doc) = doc {
+            doc.set_editor_config(properties.clone());
+            doc.encoding.set(encoding.to_string());
+        }
+    }
+
     pub fn open_file_changed(&self, path: &Path, content: &FileChanged) {
         tracing::debug!("open_file_changed {:?}", path);
         match content {
@@ -2366,6 +2784,33 @@ pub fn open_file_changed(&self, path: &Path, content: &FileChanged) {
                 };
                 doc.handle_file_changed(Rope::from(content));
             }
+            FileChanged::Append { offset, text } => {
+                if let Some(doc) =
+                    self.docs.with_untracked(|docs| docs.get(path).cloned())
+                {
+                    doc.handle_large_file_appended(*offset, text);
+                }
+            }
+            FileChanged::Reload => {
+                if let Some(doc) =
+                    self.docs.with_untracked(|docs| docs.get(path).cloned())
+                {
+                    if doc.is_pristine() {
+                        doc.load_large_file(true);
+                    }
+                }
+            }
+            FileChanged::Bytes => {
+                // Unsaved hex edits are kept, as for documents with changes
+                for hex_editor in self.hex_editors.of_path(path) {
+                    if hex_editor.pristine.get_untracked() {
+                        hex_editor.load();
+                    }
+                }
+                for image_viewer in self.image_viewers.of_path(path) {
+                    image_viewer.load();
+                }
+            }
             FileChanged::Delete => {
                 if self.docs.with_untracked(|x| x.get(path).is_none()) {
                     return;
@@ -2429,8 +2874,124 @@ pub fn open_keymap(&self) {
         self.get_editor_tab_child(EditorTabChildSource::Keymap, false, false);
     }
 
+    pub fn open_hex_editor(&self, path: PathBuf) {
+        self.get_editor_tab_child(
+            EditorTabChildSource::HexEditor(path),
+            false,
+            false,
+        );
+    }
+
+    pub fn new_hex_editor(&self, path: PathBuf) -> EditorTabChild {
+        let hex_editor =
+            HexEditorData::new(self.scope, path, self.editors, self.common.clone());
+        EditorTabChild::HexEditor(self.hex_editors.insert(hex_editor))
+    }
+
+    pub fn new_image_viewer(&self, path: PathBuf) -> EditorTabChild {
+        let image_viewer =
+            ImageViewerData::new(self.scope, path, self.common.clone());
+        EditorTabChild::ImageViewer(self.image_viewers.insert(image_viewer))
+    }
+
+    /// Show a preview of a markdown file in a new editor tab next to the
+    /// active one, or the existing preview of it
+    pub fn open_markdown_preview_to_side(&self, path: PathBuf) -> Option<()> {
+        for (_, editor_tab) in self.editor_tabs.get_untracked() {
+            let index = editor_tab.with_untracked(|editor_tab| {
+                editor_tab.children.iter().position(|(_, _, child)| {
+                    if let EditorTabChild::MarkdownPreview(markdown_preview_id) =
+                        child
+                    {
+                        self.markdown_previews.is_of(*markdown_preview_id, &path)
+                    } else {
+                        false
+                    }
+                })
+            });
+            if let Some(index) = index {
+                editor_tab.update(|editor_tab| {
+                    editor_tab.active = index;
+                });
+                return Some(());
+            }
+        }
+
+        let active_editor_tab = self.active_editor_tab.get_untracked()?;
+        let markdown_preview_id = self.new_markdown_preview(path);
+        let split = self.split_with_child(
+            SplitDirection::Vertical,
+            active_editor_tab,
+            Some(EditorTabChild::MarkdownPreview(markdown_preview_id)),
+        );
+        if split.is_none() {
+            self.markdown_previews
+                .remove(markdown_preview_id, self.editors);
+        }
+        split
+    }
+
+    pub fn new_markdown_preview(&self, path: PathBuf) -> MarkdownPreviewId {
+        let (doc, _) = self.get_doc(path.clone(), None);
+        let markdown_preview = MarkdownPreviewData::new(
+            self.scope,
+            path,
+            doc,
+            self.active_editor,
+            self.common.clone(),
+        );
+        self.markdown_previews.insert(markdown_preview)
+    }
+
+    /// Replace the editors of a file found to not be text, which were opened
+    /// before its content was read, with hex editors
+    fn open_binary_file(&self, path: &Path) {
+        for (_, editor_tab) in self.editor_tabs.get_untracked() {
+            let editor_ids: Vec<(usize, EditorId)> =
+                editor_tab.with_untracked(|editor_tab| {
+                    editor_tab
+                        .children
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(i, (_, _, child))| match child {
+                            EditorTabChild::Editor(editor_id) => {
+                                let editor =
+                                    self.editors.editor_untracked(*editor_id)?;
+                                let is_path =
+                                    editor.doc().content.with_untracked(|content| {
+                                        content.path().is_some_and(|p| p == path)
+                                    });
+                                is_path.then_some((i, *editor_id))
+                            }
+                            _ => None,
+                        })
+                        .collect()
+                });
+            for (i, editor_id) in editor_ids {
+                let child = self.new_hex_editor(path.to_path_buf());
+                editor_tab.update(|editor_tab| {
+                    editor_tab.children[i] = (
+                        editor_tab.scope.create_rw_signal(i),
+                        editor_tab.scope.create_rw_signal(Rect::ZERO),
+                        child,
+                    );
+                });
+                self.remove_editor(editor_id);
+            }
+        }
+    }
+
     pub fn new_file(&self) -> EditorTabChild {
-        self.get_editor_tab_child(EditorTabChildSource::NewFileEditor, false, false)
+        self.new_scratch_file("Untitled-")
+    }
+
+    /// Open a new scratch document, named with the prefix and a number
+    pub fn new_scratch_file(&self, name_prefix: &'static str) -> EditorTabChild {
+        self.get_editor_tab_child(
+            EditorTabChildSource::NewFileEditor { name_prefix },
+            false,
+            false,
+        )
     }
 
     pub fn save_as(&self, doc: Rc<Doc>, path: PathBuf, action: impl Fn() + 'static) {
@@ -2442,6 +3003,7 @@ pub fn save_as(&self, doc: Rc<Doc>, path: PathBuf, action: impl Fn() + 'static)
         );
         match doc_content {
             DocContent::Scratch { .. } => {
+                let config = self.common.config;
                 let send = {
                     let path = path.clone();
                     create_ext_action(self.scope, move |result| {
@@ -2452,7 +3014,12 @@ pub fn save_as(&self, doc: Rc<Doc>, path: PathBuf, action: impl Fn() + 'static)
                                 err
                             );
                         } else {
-                            let syntax = Syntax::init(&path);
+                            let syntax = Syntax::from_language(
+                                config
+                                    .get_untracked()
+                                    .language_for_path(&path)
+                                    .unwrap_or_default(),
+                            );
                             doc.content.set(DocContent::File {
                                 path: path.clone(),
                                 read_only: false,
@@ -2497,6 +3064,7 @@ pub fn save_as2(
         );
         match doc_content {
             DocContent::Scratch { .. } => {
+                let config = self.common.config;
                 let send = {
                     let path = path.clone();
                     create_ext_action(self.scope, move |result| {
@@ -2507,7 +3075,12 @@ pub fn save_as2(
                                 err
                             );
                         } else {
-                            let syntax = Syntax::init(&path);
+                            let syntax = Syntax::from_language(
+                                config
+                                    .get_untracked()
+                                    .language_for_path(&path)
+                                    .unwrap_or_default(),
+                            );
                             doc.content.set(DocContent::File {
                                 path: path.clone(),
                                 read_only: false,
@@ -2538,9 +3111,7 @@ pub fn save_as2(
         }
     }
 
-    fn get_name_for_new_file(&self) -> String {
-        const PREFIX: &str = "Untitled-";
-
+    fn get_name_for_new_file(&self, prefix: &str) -> String {
         // Checking just the current scratch_docs rather than all the different document
         // collections seems to be the right thing to do. The user may have genuine 'new N'
         // files tucked away somewhere in their workspace.
@@ -2549,12 +3120,10 @@ fn get_name_for_new_file(&self) -> String {
                 .values()
                 .filter_map(|doc| {
                     doc.content.with_untracked(|content| match content {
+                        // Scratch docs are titled with one of the prefixes and
+                        // a number, and the user cannot change the name.
                         DocContent::Scratch { name, .. } => {
-                            // The unwraps are safe because scratch docs are always
-                            // titled the same format and the user cannot change the name.
-                            let num_part = name.strip_prefix(PREFIX).unwrap();
-                            let num = num_part.parse::<i32>().unwrap();
-                            Some(num)
+                            name.strip_prefix(prefix)?.parse::<i32>().ok()
                         }
                         _ => None,
                     })
@@ -2564,7 +3133,7 @@ fn get_name_for_new_file(&self) -> String {
                 + 1
         });
 
-        format!("{PREFIX}{new_num}")
+        format!("{prefix}{new_num}")
     }
 
     pub fn can_jump_location_backward(&self, tracked: bool) -> bool {
@@ -2747,6 +3316,9 @@ fn editor_tab_child_set_parent(
             EditorTabChild::ThemeColorSettings(_) => {}
             EditorTabChild::Keymap(_) => {}
             EditorTabChild::Volt(_, _) => {}
+            EditorTabChild::HexEditor(_) => {}
+            EditorTabChild::ImageViewer(_) => {}
+            EditorTabChild::MarkdownPreview(_) => {}
         }
         Some(())
     }
@@ -1,3 +1,5 @@
+use std::ops::Range;
+
 use floem::text::{
     Attrs, AttrsList, FamilyOwned, LineHeightValue, Style, TextLayout, Weight,
 };
@@ -11,17 +13,53 @@
 
 #[derive(Clone)]
 pub enum MarkdownContent {
-    Text(TextLayout),
-    Image { url: String, title: String },
+    /// Text with the links in it
+    Text(TextLayout, Vec<MarkdownLink>),
+    Image {
+        url: String,
+        title: String,
+    },
     Separator,
 }
 
+/// A link in the text of markdown
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MarkdownLink {
+    /// The range of the link's text in the text layout
+    pub range: Range<usize>,
+    pub url: String,
+}
+
 pub fn parse_markdown(
     text: &str,
     line_height: f64,
     config: &LapceConfig,
 ) -> Vec<MarkdownContent> {
+    parse(text, line_height, config, false)
+        .into_iter()
+        .map(|(_, content)| content)
+        .collect()
+}
+
+/// Parse markdown with the text of each top level block, like a paragraph or
+/// a heading, kept apart, along with the offset in the markdown it starts at
+pub fn parse_markdown_blocks(
+    text: &str,
+    line_height: f64,
+    config: &LapceConfig,
+) -> Vec<(usize, MarkdownContent)> {
+    parse(text, line_height, config, true)
+}
+
+fn parse(
+    text: &str,
+    line_height: f64,
+    config: &LapceConfig,
+    split_blocks: bool,
+) -> Vec<(usize, MarkdownContent)> {
     let mut res = Vec::new();
+    // The offset in the markdown of the content being built
+    let mut content_start = None;
 
     let mut current_text = String::new();
     let code_font_family: Vec<FamilyOwned> =
@@ -32,6 +70,7 @@ pub fn parse_markdown(
         .font_size(config.ui.font_size() as f32)
         .line_height(LineHeightValue::Normal(line_height as f32));
     let mut attr_list = AttrsList::new(default_attrs.clone());
+    let mut links = Vec::new();
 
     let mut builder_dirty = false;
 
@@ -51,7 +90,9 @@ pub fn parse_markdown(
     // Whether we should add a newline on the next entry
     // This is used so that we don't emit newlines at the very end of the generation
     let mut add_newline = false;
-    for event in parser {
+    for (event, range) in parser.into_offset_iter() {
+        content_start.get_or_insert(range.start);
+
         // Add the newline since we're going to be outputting more
         if add_newline {
             current_text.push('\n');
@@ -116,23 +157,57 @@ pub fn parse_markdown(
                             if builder_dirty {
                                 let mut text_layout = TextLayout::new();
                                 text_layout.set_text(&current_text, attr_list);
-                                res.push(MarkdownContent::Text(text_layout));
+                                res.push((
+                                    content_start.unwrap_or(range.start),
+                                    MarkdownContent::Text(
+                                        text_layout,
+                                        std::mem::take(&mut links),
+                                    ),
+                                ));
                                 attr_list = AttrsList::new(default_attrs.clone());
                                 current_text.clear();
                                 pos = 0;
                                 builder_dirty = false;
                             }
 
-                            res.push(MarkdownContent::Image {
-                                url: dest.to_string(),
-                                title: title.to_string(),
+                            res.push((
+                                range.start,
+                                MarkdownContent::Image {
+                                    url: dest.to_string(),
+                                    title: title.to_string(),
+                                },
+                            ));
+                            content_start = None;
+                        }
+                        Tag::Link { dest_url, .. } => {
+                            links.push(MarkdownLink {
+                                range: start_offset..pos.max(start_offset),
+                                url: dest_url.to_string(),
                             });
+                            builder_dirty = true;
                         }
                         _ => {
                             // Presumably?
                             builder_dirty = true;
                         }
                     }
+
+                    if split_blocks && tag_stack.is_empty() && builder_dirty {
+                        let mut text_layout = TextLayout::new();
+                        text_layout.set_text(&current_text, attr_list);
+                        res.push((
+                            content_start.take().unwrap_or(range.start),
+                            MarkdownContent::Text(
+                                text_layout,
+                                std::mem::take(&mut links),
+                            ),
+                        ));
+                        attr_list = AttrsList::new(default_attrs.clone());
+                        current_text.clear();
+                        pos = 0;
+                        builder_dirty = false;
+                        add_newline = false;
+                    }
                 } else {
                     tracing::warn!("Unbalanced markdown tag")
                 }
@@ -193,7 +268,10 @@ pub fn parse_markdown(
     if builder_dirty {
         let mut text_layout = TextLayout::new();
         text_layout.set_text(&current_text, attr_list);
-        res.push(MarkdownContent::Text(text_layout));
+        res.push((
+            content_start.unwrap_or(0),
+            MarkdownContent::Text(text_layout, links),
+        ));
     }
 
     res
@@ -339,5 +417,5 @@ pub fn from_plaintext(
                 .line_height(LineHeightValue::Normal(line_height as f32)),
         ),
     );
-    vec![MarkdownContent::Text(text_layout)]
+    vec![MarkdownContent::Text(text_layout, Vec::new())]
 }
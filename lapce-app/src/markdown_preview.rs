@@ -0,0 +1,607 @@
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+
+use floem::{
+    View,
+    action::{TimerToken, exec_after},
+    event::{Event, EventListener},
+    ext_event::create_ext_action,
+    peniko::kurbo::{Point, Rect, Vec2},
+    prelude::SignalTrack,
+    reactive::{
+        Memo, RwSignal, Scope, SignalGet, SignalUpdate, SignalWith, create_effect,
+        create_rw_signal,
+    },
+    views::{
+        Decorators, container, dyn_container, dyn_stack, empty, img_dynamic, label,
+        rich_text, scroll, svg,
+    },
+};
+use lapce_rpc::proxy::ProxyResponse;
+
+use crate::{
+    command::InternalCommand,
+    config::{LapceConfig, color::LapceColor},
+    doc::Doc,
+    editor::EditorData,
+    id::MarkdownPreviewId,
+    image_viewer::{ImageContent, ImageKind, ImageSource, decode_image},
+    main_split::{Editors, FileViewData},
+    markdown::{MarkdownContent, parse_markdown_blocks},
+    window_tab::{CommonData, Focus},
+};
+
+/// How long after the last edit the preview is rendered again
+const RENDER_DELAY: Duration = Duration::from_millis(300);
+
+/// The space around the blocks of the preview
+const PADDING: f64 = 20.0;
+
+/// A top level block of the document, like a paragraph or a heading
+#[derive(Clone)]
+pub struct MarkdownBlock {
+    /// Stays the same across renders while the source of the block and the
+    /// style are unchanged, so that its view is kept
+    pub key: u64,
+    /// The line of the document the block starts at
+    pub line: usize,
+    pub content: MarkdownContent,
+}
+
+#[derive(Clone)]
+pub struct MarkdownPreviewData {
+    pub id: MarkdownPreviewId,
+    pub scope: Scope,
+    pub path: PathBuf,
+    pub doc: Rc<Doc>,
+    pub content: RwSignal<Vec<MarkdownBlock>>,
+    /// The images of the document read from the workspace, which are `None`
+    /// while they're being read or if they can't be
+    pub images: RwSignal<im::HashMap<PathBuf, Option<ImageContent>>>,
+    /// The preview scrolls along with the active editor when it's showing the
+    /// document
+    pub active_editor: Memo<Option<EditorData>>,
+    render_timer: RwSignal<TimerToken>,
+    /// Changed with the config, as the blocks are rendered in its style
+    style_revision: RwSignal<u64>,
+    pub common: Rc<CommonData>,
+}
+
+impl FileViewData for MarkdownPreviewData {
+    fn id(&self) -> MarkdownPreviewId {
+        self.id
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// A preview of the same document for a new split
+    fn copy(&self, cx: Scope, _editors: Editors) -> Self {
+        Self::new(
+            cx,
+            self.path.clone(),
+            self.doc.clone(),
+            self.active_editor,
+            self.common.clone(),
+        )
+    }
+
+    fn dispose(&self, _editors: Editors) {
+        self.scope.dispose();
+    }
+}
+
+impl MarkdownPreviewData {
+    pub fn new(
+        cx: Scope,
+        path: PathBuf,
+        doc: Rc<Doc>,
+        active_editor: Memo<Option<EditorData>>,
+        common: Rc<CommonData>,
+    ) -> Self {
+        let cx = cx.create_child();
+        let data = Self {
+            id: MarkdownPreviewId::next(),
+            scope: cx,
+            path,
+            doc,
+            content: cx.create_rw_signal(Vec::new()),
+            images: cx.create_rw_signal(im::HashMap::new()),
+            active_editor,
+            render_timer: cx.create_rw_signal(TimerToken::INVALID),
+            style_revision: cx.create_rw_signal(0),
+            common,
+        };
+
+        {
+            let data = data.clone();
+            cx.create_effect(move |last: Option<Arc<LapceConfig>>| {
+                data.doc.buffer.track();
+                let config = data.common.config.get();
+                let Some(last) = last else {
+                    data.render();
+                    return config;
+                };
+                if !Arc::ptr_eq(&last, &config) {
+                    data.style_revision.update(|revision| *revision += 1);
+                }
+                let preview = data.clone();
+                let token = exec_after(RENDER_DELAY, move |token| {
+                    if preview.render_timer.try_get_untracked() == Some(token) {
+                        preview.render();
+                    }
+                });
+                data.render_timer.set(token);
+                config
+            });
+        }
+
+        data
+    }
+
+    fn render(&self) {
+        let config = self.common.config.get_untracked();
+        let style_revision = self.style_revision.get_untracked();
+        let blocks = self.doc.buffer.with_untracked(|buffer| {
+            let text = buffer.to_string();
+            let contents = parse_markdown_blocks(&text, 1.8, &config);
+            let ends: Vec<usize> = contents
+                .iter()
+                .skip(1)
+                .map(|(offset, _)| *offset)
+                .chain([text.len()])
+                .collect();
+            // Identical blocks are told apart by how many came before them
+            let mut occurrences: HashMap<&str, usize> = HashMap::new();
+            contents
+                .into_iter()
+                .zip(ends)
+                .map(|((offset, content), end)| {
+                    let source = text.get(offset..end).unwrap_or_default();
+                    let occurrence = occurrences.entry(source).or_default();
+                    *occurrence += 1;
+                    let mut hasher = DefaultHasher::new();
+                    (style_revision, source, *occurrence).hash(&mut hasher);
+                    MarkdownBlock {
+                        key: hasher.finish(),
+                        line: buffer.line_of_offset(offset),
+                        content,
+                    }
+                })
+                .collect()
+        });
+        self.content.set(blocks);
+    }
+
+    /// Whether the editor is of the previewed document
+    fn is_source(&self, editor: &EditorData) -> bool {
+        editor
+            .doc()
+            .content
+            .with_untracked(|content| content.path() == Some(&self.path))
+    }
+
+    /// Read an image of the document through the proxy, unless it's already
+    /// been read
+    fn load_image(&self, path: PathBuf) {
+        let loaded = self
+            .images
+            .with_untracked(|images| images.contains_key(&path));
+        if loaded {
+            return;
+        }
+        self.images.update(|images| {
+            images.insert(path.clone(), None);
+        });
+
+        let images = self.images;
+        let image_path = path.clone();
+        let send = create_ext_action(self.scope, move |decoded| {
+            let image = decoded
+                .map(|(decoded, dimensions)| ImageContent::new(decoded, dimensions));
+            images.update(|images| {
+                images.insert(image_path, image);
+            });
+        });
+        let svg = ImageKind::from_path(&path) == Some(ImageKind::Svg);
+        self.common.proxy.read_file_bytes(path, move |result| {
+            let decoded = match result {
+                Ok(ProxyResponse::ReadFileBytesResponse { content }) => {
+                    decode_image(svg, content)
+                }
+                _ => None,
+            };
+            send(decoded);
+        });
+    }
+
+    fn open_link(&self, url: &str) {
+        if is_external_link(url) {
+            if let Err(err) = open::that(url) {
+                tracing::error!("Failed to open link: {}", err);
+            }
+            return;
+        }
+        let workspace = self.common.workspace.path.as_deref();
+        if let Some(path) = resolve_link(url, &self.path, workspace) {
+            self.common
+                .internal_command
+                .send(InternalCommand::OpenFile { path });
+        }
+    }
+}
+
+/// Whether a link points to a site or an app rather than a file, which is
+/// when it starts with a scheme such as `https:` or `mailto:`
+fn is_external_link(url: &str) -> bool {
+    url.split_once(':').is_some_and(|(scheme, _)| {
+        // A single letter is the drive of a Windows path
+        scheme.len() > 1
+            && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    })
+}
+
+/// The file a link or image of a markdown file points to. Relative links are
+/// resolved against the directory of the file, and absolute ones against the
+/// root of the workspace.
+pub fn resolve_link(
+    url: &str,
+    file: &Path,
+    workspace: Option<&Path>,
+) -> Option<PathBuf> {
+    if is_external_link(url) {
+        return None;
+    }
+    let url = url.split(['#', '?']).next().unwrap_or_default();
+    if url.is_empty() {
+        return None;
+    }
+    let url = percent_encoding::percent_decode_str(url)
+        .decode_utf8()
+        .ok()?;
+
+    let path = match url.strip_prefix('/') {
+        Some(url) => workspace?.join(url),
+        None => file.parent()?.join(&*url),
+    };
+
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+    }
+    Some(resolved)
+}
+
+/// Map a value through the straight lines between points, which are in
+/// increasing order
+fn interpolate(points: &[(f64, f64)], x: f64) -> f64 {
+    let Some(&(first_x, first_y)) = points.first() else {
+        return 0.0;
+    };
+    if x <= first_x {
+        return first_y;
+    }
+    for window in points.windows(2) {
+        let ((x0, y0), (x1, y1)) = (window[0], window[1]);
+        if x < x1 {
+            return y0 + (x - x0) / (x1 - x0) * (y1 - y0);
+        }
+    }
+    points.last().map_or(first_y, |(_, y)| *y)
+}
+
+/// The lines of the document the blocks start at, with where they're shown
+/// in the preview, ending with the end of both
+fn block_anchors(
+    blocks: &[MarkdownBlock],
+    heights: &im::HashMap<u64, f64>,
+    lines: usize,
+) -> Vec<(f64, f64)> {
+    let mut y = PADDING;
+    let mut anchors = Vec::with_capacity(blocks.len() + 1);
+    for block in blocks {
+        anchors.push((block.line as f64, y));
+        y += heights.get(&block.key).copied().unwrap_or_default();
+    }
+    anchors.push((lines as f64, y));
+    anchors
+}
+
+/// The top and bottom of a line of an editor, which spans several visual
+/// lines when it's wrapped
+fn editor_line_bounds(editor: &EditorData, line: usize) -> (f64, f64) {
+    let line_height = editor.common.config.get_untracked().editor.line_height();
+    let last_line = editor
+        .doc()
+        .buffer
+        .with_untracked(|buffer| buffer.last_line());
+    let line = line.min(last_line);
+    let top = editor.editor.vline_of_line(line).0 * line_height;
+    let bottom = if line < last_line {
+        editor.editor.vline_of_line(line + 1).0 * line_height
+    } else {
+        top + line_height
+    };
+    (top as f64, bottom.max(top + line_height) as f64)
+}
+
+/// The line at a height of an editor, with the part of the line above it
+fn editor_line_of_y(editor: &EditorData, y: f64) -> f64 {
+    let mode = editor.cursor().with_untracked(|cursor| cursor.get_mode());
+    let (offset, _) = editor.editor.offset_of_point(mode, Point::new(0.0, y));
+    let line = editor
+        .doc()
+        .buffer
+        .with_untracked(|buffer| buffer.line_of_offset(offset));
+    let (top, bottom) = editor_line_bounds(editor, line);
+    line as f64 + ((y - top) / (bottom - top)).clamp(0.0, 1.0)
+}
+
+/// The height of a line of an editor, with a part of the line
+fn editor_y_of_line(editor: &EditorData, line: f64) -> f64 {
+    let (top, bottom) = editor_line_bounds(editor, line.max(0.0) as usize);
+    top + line.fract() * (bottom - top)
+}
+
+fn markdown_image_view(
+    data: MarkdownPreviewData,
+    url: String,
+    title: String,
+) -> impl View {
+    let config = data.common.config;
+    let workspace = data.common.workspace.path.clone();
+    let Some(path) = resolve_link(&url, &data.path, workspace.as_deref()) else {
+        // Images from other sites aren't fetched
+        let text = if title.is_empty() { url } else { title };
+        return label(move || text.clone())
+            .style(move |s| {
+                s.color(config.get().color(LapceColor::EDITOR_LINK))
+                    .padding_vert(5.0)
+            })
+            .into_any();
+    };
+
+    data.load_image(path.clone());
+    let images = data.images;
+    dyn_container(
+        move || images.with(|images| images.get(&path).cloned().flatten()),
+        move |image| {
+            let Some(image) = image else {
+                return empty().into_any();
+            };
+            let (width, height) = image.dimensions;
+            let aspect_ratio = width as f32 / height.max(1) as f32;
+            let image = match image.source {
+                ImageSource::Raster(image) => {
+                    img_dynamic(move || image.clone()).into_any()
+                }
+                ImageSource::Svg(text) => svg(move || text.to_string()).into_any(),
+            };
+            image
+                .style(move |s| {
+                    s.width(width as f32)
+                        .max_width_full()
+                        .aspect_ratio(aspect_ratio)
+                })
+                .into_any()
+        },
+    )
+    .style(|s| s.padding_vert(5.0))
+    .into_any()
+}
+
+pub fn markdown_preview_view(data: MarkdownPreviewData) -> impl View {
+    let config = data.common.config;
+    let focus = data.common.focus;
+    let content = data.content;
+    let scroll_to = create_rw_signal(None);
+    let heights = create_rw_signal(im::HashMap::new());
+    // The preview scrolls the editor while it's hovered, and follows it
+    // otherwise
+    let hovered = create_rw_signal(false);
+
+    let anchors = {
+        let doc = data.doc.clone();
+        move || {
+            let lines = doc.buffer.with_untracked(|buffer| buffer.last_line() + 1);
+            content.with_untracked(|blocks| {
+                heights
+                    .with_untracked(|heights| block_anchors(blocks, heights, lines))
+            })
+        }
+    };
+
+    // Forget the heights of the blocks which are gone
+    create_effect(move |_| {
+        let keys: HashSet<u64> =
+            content.with(|blocks| blocks.iter().map(|block| block.key).collect());
+        heights.update(|heights| heights.retain(|key, _| keys.contains(key)));
+    });
+
+    // Keep the same lines of the document in view as in its editor
+    {
+        let data = data.clone();
+        let anchors = anchors.clone();
+        create_effect(move |_| {
+            let Some(editor) = data.active_editor.get() else {
+                return;
+            };
+            let editor_viewport = editor.viewport().get();
+            if !data.is_source(&editor) || hovered.get_untracked() {
+                return;
+            }
+            let line = editor_line_of_y(&editor, editor_viewport.y0);
+            let y = interpolate(&anchors(), line);
+            scroll_to.set(Some(Point::new(0.0, y - PADDING)));
+        });
+    }
+
+    let scroll_editor = {
+        let data = data.clone();
+        move |rect: Rect| {
+            let Some(editor) = data
+                .active_editor
+                .get_untracked()
+                .filter(|editor| data.is_source(editor))
+            else {
+                return;
+            };
+            let anchors: Vec<(f64, f64)> =
+                anchors().into_iter().map(|(line, y)| (y, line)).collect();
+            let line = interpolate(&anchors, rect.y0 + PADDING);
+            let x = editor.viewport().get_untracked().x0;
+            editor
+                .scroll_to()
+                .set(Some(Vec2::new(x, editor_y_of_line(&editor, line))));
+        }
+    };
+
+    let blocks = dyn_stack(
+        move || content.get(),
+        |block| block.key,
+        move |block| {
+            let key = block.key;
+            let view = match block.content {
+                MarkdownContent::Text(text_layout, links) => {
+                    let data = data.clone();
+                    let width = create_rw_signal(0.0);
+                    let hit_layout = text_layout.clone();
+                    container(
+                        rich_text(move || text_layout.clone())
+                            .on_resize(move |rect| {
+                                width.set(rect.width());
+                            })
+                            .on_event_cont(EventListener::PointerUp, move |event| {
+                                if let Event::PointerUp(pointer_event) = event {
+                                    let mut text_layout = hit_layout.clone();
+                                    text_layout.set_size(
+                                        width.get_untracked() as f32,
+                                        f32::MAX,
+                                    );
+                                    let index = text_layout
+                                        .hit_point(pointer_event.pos)
+                                        .index;
+                                    if let Some(link) = links
+                                        .iter()
+                                        .find(|link| link.range.contains(&index))
+                                    {
+                                        data.open_link(&link.url);
+                                    }
+                                }
+                            })
+                            .style(|s| s.width_full()),
+                    )
+                    .style(|s| s.width_full().padding_vert(5.0))
+                    .into_any()
+                }
+                MarkdownContent::Image { url, title } => {
+                    markdown_image_view(data.clone(), url, title).into_any()
+                }
+                MarkdownContent::Separator => empty()
+                    .style(move |s| {
+                        s.width_full()
+                            .margin_vert(5.0)
+                            .height(1.0)
+                            .background(config.get().color(LapceColor::LAPCE_BORDER))
+                    })
+                    .into_any(),
+            };
+            view.on_resize(move |rect| {
+                heights.update(|heights| {
+                    heights.insert(key, rect.height());
+                });
+            })
+        },
+    )
+    .style(|s| s.flex_col().width_full().padding(PADDING as f32));
+
+    container(
+        scroll(blocks)
+            .on_scroll(move |rect| {
+                if hovered.get_untracked() {
+                    scroll_editor(rect);
+                }
+            })
+            .scroll_to(move || scroll_to.get())
+            .style(|s| s.absolute().size_full()),
+    )
+    .on_event_cont(EventListener::PointerDown, move |_| {
+        focus.set(Focus::Workbench);
+    })
+    .on_event_cont(EventListener::PointerEnter, move |_| {
+        hovered.set(true);
+    })
+    .on_event_cont(EventListener::PointerLeave, move |_| {
+        hovered.set(false);
+    })
+    .style(move |s| {
+        s.absolute()
+            .size_full()
+            .background(config.get().color(LapceColor::EDITOR_BACKGROUND))
+    })
+    .debug_name("Markdown Preview")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::{interpolate, is_external_link, resolve_link};
+
+    #[test]
+    fn test_resolve_link() {
+        let file = Path::new("/work/docs/guide.md");
+        let workspace = Some(Path::new("/work"));
+
+        assert_eq!(
+            resolve_link("images/logo.png", file, workspace),
+            Some(PathBuf::from("/work/docs/images/logo.png"))
+        );
+        assert_eq!(
+            resolve_link("../README.md#install", file, workspace),
+            Some(PathBuf::from("/work/README.md"))
+        );
+        assert_eq!(
+            resolve_link("/assets/My%20Image.svg", file, workspace),
+            Some(PathBuf::from("/work/assets/My Image.svg"))
+        );
+        assert_eq!(resolve_link("/assets/a.png", file, None), None);
+        assert_eq!(resolve_link("#usage", file, workspace), None);
+        assert_eq!(resolve_link("https://lapce.dev", file, workspace), None);
+    }
+
+    #[test]
+    fn test_is_external_link() {
+        assert!(is_external_link("https://lapce.dev/docs"));
+        assert!(is_external_link("mailto:dev@lapce.dev"));
+        assert!(!is_external_link("docs/guide.md"));
+        assert!(!is_external_link("C:/docs/guide.md"));
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let points = [(0.0, 20.0), (4.0, 120.0), (4.0, 300.0), (10.0, 420.0)];
+        assert_eq!(interpolate(&points, -1.0), 20.0);
+        assert_eq!(interpolate(&points, 2.0), 70.0);
+        assert_eq!(interpolate(&points, 4.0), 300.0);
+        assert_eq!(interpolate(&points, 7.0), 360.0);
+        assert_eq!(interpolate(&points, 12.0), 420.0);
+        assert_eq!(interpolate(&[], 1.0), 0.0);
+    }
+}
@@ -27,7 +27,7 @@
     line_ending::LineEnding, mode::Mode, movement::Movement, selection::Selection,
     syntax::Syntax,
 };
-use lapce_rpc::proxy::ProxyResponse;
+use lapce_rpc::{file::ENCODINGS, proxy::ProxyResponse};
 use lapce_xi_rope::Rope;
 use lsp_types::{DocumentSymbol, DocumentSymbolResponse};
 use nucleo::Utf32Str;
@@ -40,7 +40,8 @@
 };
 use crate::{
     command::{
-        CommandExecuted, CommandKind, InternalCommand, LapceCommand, WindowCommand,
+        CommandExecuted, CommandKind, InternalCommand, LapceCommand,
+        LapceWorkbenchCommand, WindowCommand,
     },
     db::LapceDb,
     debug::{RunDebugConfigs, RunDebugMode},
@@ -412,8 +413,23 @@ fn run_inner(&self, kind: PaletteKind) {
             PaletteKind::LineEnding => {
                 self.get_line_endings();
             }
+            PaletteKind::ReopenWithEncoding | PaletteKind::SaveWithEncoding => {
+                self.get_encodings();
+            }
             PaletteKind::SCMReferences => {
-                self.get_scm_references();
+                self.get_scm_references(false);
+            }
+            PaletteKind::SCMCompareActiveFile | PaletteKind::SCMCompareWorkspace => {
+                self.get_scm_references(true);
+            }
+            PaletteKind::SCMWorktrees => {
+                self.get_worktrees();
+            }
+            PaletteKind::SCMCreateWorktree => {
+                self.get_scm_branches();
+            }
+            PaletteKind::SCMRebase => {
+                self.get_scm_references(false);
             }
             PaletteKind::TerminalProfile => self.get_terminal_profiles(),
         }
@@ -1047,10 +1063,41 @@ fn get_line_endings(&self) {
         self.items.set(items);
     }
 
-    fn get_scm_references(&self) {
+    fn get_encodings(&self) {
+        let items = ENCODINGS
+            .iter()
+            .map(|name| PaletteItem {
+                content: PaletteItemContent::Encoding {
+                    name: name.to_string(),
+                },
+                filter_text: name.to_string(),
+                score: 0,
+                indices: Vec::new(),
+            })
+            .collect();
+        if let Some(editor) = self.main_split.active_editor.get_untracked() {
+            let encoding = editor.doc().encoding.get_untracked();
+            self.preselect_matching(&items, &encoding);
+        }
+        self.items.set(items);
+    }
+
+    /// Get the branches and tags, preceded by `HEAD` when comparing, so that
+    /// the comparison can be switched back to the last commit
+    fn get_scm_references(&self, include_head: bool) {
         let branches = self.source_control.branches.get_untracked();
         let tags = self.source_control.tags.get_untracked();
         let mut items: im::Vector<PaletteItem> = im::Vector::new();
+        if include_head {
+            items.push_back(PaletteItem {
+                content: PaletteItemContent::SCMReference {
+                    name: "HEAD".to_string(),
+                },
+                filter_text: "HEAD".to_string(),
+                score: 0,
+                indices: Vec::new(),
+            });
+        }
         for refs in branches.into_iter() {
             items.push_back(PaletteItem {
                 content: PaletteItemContent::SCMReference {
@@ -1074,6 +1121,58 @@ fn get_scm_references(&self) {
         self.items.set(items);
     }
 
+    /// Get the local branches, for which working trees can be created
+    fn get_scm_branches(&self) {
+        let items = self
+            .source_control
+            .branches
+            .get_untracked()
+            .into_iter()
+            .map(|name| PaletteItem {
+                content: PaletteItemContent::SCMReference { name: name.clone() },
+                filter_text: name,
+                score: 0,
+                indices: Vec::new(),
+            })
+            .collect();
+        self.items.set(items);
+    }
+
+    /// Get the working trees of the selected repository, which are opened
+    /// as workspaces of the same kind
+    fn get_worktrees(&self) {
+        let kind = self.workspace.kind.clone();
+        let set_items = self.items.write_only();
+        let send =
+            create_ext_action(self.common.scope, move |worktrees: Vec<PathBuf>| {
+                let items = worktrees
+                    .into_iter()
+                    .map(|path| PaletteItem {
+                        filter_text: path.to_string_lossy().into_owned(),
+                        content: PaletteItemContent::Workspace {
+                            workspace: LapceWorkspace {
+                                kind: kind.clone(),
+                                path: Some(path),
+                                last_open: 0,
+                            },
+                        },
+                        score: 0,
+                        indices: Vec::new(),
+                    })
+                    .collect::<im::Vector<_>>();
+                set_items.set(items);
+            });
+        self.common.proxy.git_worktrees(
+            self.source_control.repository.get_untracked(),
+            move |result| {
+                if let Ok(ProxyResponse::GitWorktreesResponse { worktrees }) = result
+                {
+                    send(worktrees);
+                }
+            },
+        );
+    }
+
     fn get_terminal_profiles(&self) {
         let profiles = self.common.config.get().terminal.profiles.clone();
         let mut items: im::Vector<PaletteItem> = im::Vector::new();
@@ -1313,15 +1412,63 @@ fn select(&self) {
                         buffer.set_line_ending(*kind);
                     });
                 }
+                PaletteItemContent::Encoding { name } => {
+                    let Some(editor) = self.main_split.active_editor.get_untracked()
+                    else {
+                        return;
+                    };
+                    let doc = editor.doc();
+
+                    match self.kind.get_untracked() {
+                        PaletteKind::ReopenWithEncoding => {
+                            doc.reopen_with_encoding(name.to_owned());
+                        }
+                        PaletteKind::SaveWithEncoding => {
+                            doc.save_with_encoding(name.to_owned());
+                        }
+                        _ => {}
+                    }
+                }
                 PaletteItemContent::SCMReference { name } => {
-                    self.common
-                        .lapce_command
-                        .send(crate::command::LapceCommand {
-                        kind: CommandKind::Workbench(
-                            crate::command::LapceWorkbenchCommand::CheckoutReference,
-                        ),
-                        data: Some(serde_json::json!(name.to_owned())),
-                    });
+                    let revision = (name != "HEAD").then(|| name.to_owned());
+                    match self.kind.get_untracked() {
+                        PaletteKind::SCMCompareActiveFile => {
+                            self.common.internal_command.send(
+                                InternalCommand::CompareActiveFileWithRevision {
+                                    revision,
+                                },
+                            );
+                        }
+                        PaletteKind::SCMCompareWorkspace => {
+                            self.common.internal_command.send(
+                                InternalCommand::CompareWorkspaceWithRevision {
+                                    revision,
+                                },
+                            );
+                        }
+                        PaletteKind::SCMCreateWorktree => {
+                            self.common.internal_command.send(
+                                InternalCommand::CreateWorktree {
+                                    branch: name.to_owned(),
+                                },
+                            );
+                        }
+                        PaletteKind::SCMRebase => {
+                            self.common.internal_command.send(
+                                InternalCommand::PrepareRebase {
+                                    onto: name.to_owned(),
+                                },
+                            );
+                        }
+                        _ => {
+                            self.common.lapce_command.send(LapceCommand {
+                                kind: CommandKind::Workbench(
+                                    LapceWorkbenchCommand::CheckoutReference,
+                                ),
+                                data: Some(serde_json::json!(name.to_owned())),
+                            });
+                        }
+                    }
                 }
                 PaletteItemContent::TerminalProfile { name: _, profile } => self
                     .common
@@ -1394,6 +1541,7 @@ fn preview(&self) {
                 PaletteItemContent::WslHost { .. } => {}
                 PaletteItemContent::Language { .. } => {}
                 PaletteItemContent::LineEnding { .. } => {}
+                PaletteItemContent::Encoding { .. } => {}
                 PaletteItemContent::Reference { location, .. } => {
                     self.has_preview.set(true);
                     let (doc, new_doc) =
@@ -77,6 +77,9 @@ pub enum PaletteItemContent {
     LineEnding {
         kind: LineEnding,
     },
+    Encoding {
+        name: String,
+    },
     SCMReference {
         name: String,
     },
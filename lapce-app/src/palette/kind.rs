@@ -20,7 +20,21 @@ pub enum PaletteKind {
     IconTheme,
     Language,
     LineEnding,
+    /// The encoding to read the active file again with
+    ReopenWithEncoding,
+    /// The encoding to save the active file with
+    SaveWithEncoding,
     SCMReferences,
+    /// The revision to compare the active file with
+    SCMCompareActiveFile,
+    /// The revision to compare the workspace with
+    SCMCompareWorkspace,
+    /// The working trees of the workspace's repository
+    SCMWorktrees,
+    /// The branch to create a working tree for
+    SCMCreateWorktree,
+    /// The revision to rebase onto
+    SCMRebase,
     TerminalProfile,
     DiffFiles,
     HelpAndFile,
@@ -46,7 +60,14 @@ pub fn symbol(&self) -> &'static str {
             | PaletteKind::IconTheme
             | PaletteKind::Language
             | PaletteKind::LineEnding
+            | PaletteKind::ReopenWithEncoding
+            | PaletteKind::SaveWithEncoding
             | PaletteKind::SCMReferences
+            | PaletteKind::SCMCompareActiveFile
+            | PaletteKind::SCMCompareWorkspace
+            | PaletteKind::SCMWorktrees
+            | PaletteKind::SCMCreateWorktree
+            | PaletteKind::SCMRebase
             | PaletteKind::HelpAndFile
             | PaletteKind::DiffFiles => "",
             #[cfg(windows)]
@@ -98,9 +119,28 @@ pub fn command(self) -> Option<LapceWorkbenchCommand> {
             PaletteKind::LineEnding => {
                 Some(LapceWorkbenchCommand::ChangeFileLineEnding)
             }
+            PaletteKind::ReopenWithEncoding => {
+                Some(LapceWorkbenchCommand::ReopenWithEncoding)
+            }
+            PaletteKind::SaveWithEncoding => {
+                Some(LapceWorkbenchCommand::SaveWithEncoding)
+            }
             PaletteKind::SCMReferences => {
                 Some(LapceWorkbenchCommand::PaletteSCMReferences)
             }
+            PaletteKind::SCMCompareActiveFile => {
+                Some(LapceWorkbenchCommand::PaletteSCMCompareActiveFile)
+            }
+            PaletteKind::SCMCompareWorkspace => {
+                Some(LapceWorkbenchCommand::PaletteSCMCompareWorkspace)
+            }
+            PaletteKind::SCMWorktrees => {
+                Some(LapceWorkbenchCommand::PaletteSCMWorktrees)
+            }
+            PaletteKind::SCMCreateWorktree => {
+                Some(LapceWorkbenchCommand::PaletteSCMCreateWorktree)
+            }
+            PaletteKind::SCMRebase => Some(LapceWorkbenchCommand::PaletteSCMRebase),
             PaletteKind::TerminalProfile => None, // InternalCommand::NewTerminal
             PaletteKind::DiffFiles => Some(LapceWorkbenchCommand::DiffFiles),
         }
@@ -129,7 +169,15 @@ pub fn get_input<'a>(&self, input: &'a str) -> &'a str {
             | PaletteKind::IconTheme
             | PaletteKind::Language
             | PaletteKind::LineEnding
-            | PaletteKind::SCMReferences | PaletteKind::HelpAndFile
+            | PaletteKind::ReopenWithEncoding
+            | PaletteKind::SaveWithEncoding
+            | PaletteKind::SCMReferences
+            | PaletteKind::SCMCompareActiveFile
+            | PaletteKind::SCMCompareWorkspace
+            | PaletteKind::SCMWorktrees
+            | PaletteKind::SCMCreateWorktree
+            | PaletteKind::SCMRebase
+            | PaletteKind::HelpAndFile
             | PaletteKind::DiffFiles => input,
             PaletteKind::PaletteHelp
             | PaletteKind::Command
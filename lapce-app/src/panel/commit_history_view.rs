@@ -0,0 +1,361 @@
+use std::{rc::Rc, sync::Arc};
+
+use floem::{
+    View, ViewId,
+    context::PaintCx,
+    event::EventListener,
+    kurbo::{Circle, Line, Stroke},
+    reactive::{ReadSignal, SignalGet, SignalWith, create_memo},
+    style::CursorStyle,
+    views::{Decorators, container, dyn_stack, label, scroll, stack, svg},
+};
+use lapce_rpc::source_control::{FileDiff, GitCommit};
+
+use super::{kind::PanelKind, position::PanelPosition};
+use crate::{
+    commit_history::{CommitGraphRow, CommitHistoryData},
+    config::{LapceConfig, color::LapceColor, icon::LapceIcons},
+    source_control::relative_time,
+    window_tab::{Focus, WindowTabData},
+};
+
+/// The width of a lane in the commit graph
+const LANE_WIDTH: f64 = 12.0;
+
+const LANE_COLORS: [&str; 6] = [
+    LapceColor::TERMINAL_BLUE,
+    LapceColor::TERMINAL_GREEN,
+    LapceColor::TERMINAL_YELLOW,
+    LapceColor::TERMINAL_MAGENTA,
+    LapceColor::TERMINAL_CYAN,
+    LapceColor::TERMINAL_RED,
+];
+
+pub fn commit_history_panel(
+    window_tab_data: Rc<WindowTabData>,
+    _position: PanelPosition,
+) -> impl View {
+    let config = window_tab_data.common.config;
+    let focus = window_tab_data.common.focus;
+    let workspace = window_tab_data.workspace.clone();
+    let commit_history = window_tab_data.commit_history.clone();
+    let path = commit_history.path;
+
+    stack((
+        label(move || match path.get() {
+            Some(path) => {
+                let path = workspace
+                    .path
+                    .as_ref()
+                    .and_then(|workspace| path.strip_prefix(workspace).ok())
+                    .unwrap_or(&path);
+                format!("History of {}", path.display())
+            }
+            None => "Commit History".to_string(),
+        })
+        .style(move |s| {
+            s.padding_horiz(10.0)
+                .padding_vert(6.0)
+                .width_pct(100.0)
+                .background(config.get().color(LapceColor::EDITOR_BACKGROUND))
+        }),
+        commit_list_view(commit_history),
+    ))
+    .on_event_stop(EventListener::PointerDown, move |_| {
+        if focus.get_untracked() != Focus::Panel(PanelKind::CommitHistory) {
+            focus.set(Focus::Panel(PanelKind::CommitHistory));
+        }
+    })
+    .style(|s| s.flex_col().size_pct(100.0, 100.0))
+    .debug_name("Commit History Panel")
+}
+
+fn commit_list_view(commit_history: CommitHistoryData) -> impl View {
+    let config = commit_history.common.config;
+    let commits = commit_history.commits;
+    let has_more = commit_history.has_more;
+    let loading = commit_history.loading;
+
+    container(
+        scroll(
+            stack((
+                dyn_stack(move || commits.get(), |(commit, _)| commit.id.clone(), {
+                    let commit_history = commit_history.clone();
+                    move |(commit, row)| {
+                        commit_item_view(commit_history.clone(), commit, row)
+                    }
+                })
+                .style(|s| s.flex_col().width_pct(100.0)),
+                label(move || {
+                    if loading.get() {
+                        "Loading...".to_string()
+                    } else if commits.with(|commits| commits.is_empty()) {
+                        "No commits".to_string()
+                    } else {
+                        "Load more commits".to_string()
+                    }
+                })
+                .on_click_stop({
+                    let commit_history = commit_history.clone();
+                    move |_| {
+                        if has_more.get_untracked() {
+                            commit_history.load_more();
+                        }
+                    }
+                })
+                .style(move |s| {
+                    let config = config.get();
+                    let show = loading.get()
+                        || has_more.get()
+                        || commits.with(|commits| commits.is_empty());
+                    s.padding_horiz(10.0)
+                        .width_pct(100.0)
+                        .color(config.color(LapceColor::EDITOR_DIM))
+                        .apply_if(has_more.get() && !loading.get(), |s| {
+                            s.hover(|s| {
+                                s.cursor(CursorStyle::Pointer).color(
+                                    config.color(LapceColor::EDITOR_FOREGROUND),
+                                )
+                            })
+                        })
+                        .apply_if(!show, |s| s.hide())
+                        .selectable(false)
+                }),
+            ))
+            .style(|s| s.flex_col().width_pct(100.0).line_height(1.6)),
+        )
+        .style(|s| s.absolute().size_pct(100.0, 100.0)),
+    )
+    .style(|s| s.size_pct(100.0, 100.0))
+}
+
+fn commit_item_view(
+    commit_history: CommitHistoryData,
+    commit: GitCommit,
+    row: CommitGraphRow,
+) -> impl View {
+    let config = commit_history.common.config;
+    let selected = commit_history.selected;
+    let path = commit_history.path;
+    let id = commit.id.clone();
+    let is_selected = create_memo(move |_| {
+        selected.with(|selected| {
+            selected.as_ref().map(|(selected, _)| selected) == Some(&id)
+        })
+    });
+    let summary = commit.summary.clone();
+    let author = commit.author.clone();
+    let time = commit.time;
+    let graph_width = row.width() as f64 * LANE_WIDTH;
+
+    stack((
+        stack((
+            commit_graph_view(row, config).style(move |s| {
+                s.width(graph_width as f32)
+                    .min_width(graph_width as f32)
+                    .height_pct(100.0)
+                    .margin_right(6.0)
+                    .apply_if(path.with(|path| path.is_some()), |s| s.hide())
+            }),
+            label(move || summary.clone()).style(|s| {
+                s.text_ellipsis()
+                    .flex_grow(1.0)
+                    .flex_basis(0.0)
+                    .min_width(0.0)
+                    .margin_right(6.0)
+                    .selectable(false)
+            }),
+            label(move || format!("{author}, {}", relative_time(time))).style(
+                move |s| {
+                    s.text_ellipsis()
+                        .color(config.get().color(LapceColor::EDITOR_DIM))
+                        .selectable(false)
+                },
+            ),
+        ))
+        .on_click_stop({
+            let commit_history = commit_history.clone();
+            let commit = commit.clone();
+            move |_| {
+                commit_history.select_commit(&commit);
+            }
+        })
+        .style(move |s| {
+            let config = config.get();
+            s.padding_horiz(10.0)
+                .width_pct(100.0)
+                .items_center()
+                .apply_if(is_selected.get(), |s| {
+                    s.background(config.color(LapceColor::PANEL_CURRENT_BACKGROUND))
+                })
+                .hover(|s| {
+                    s.cursor(CursorStyle::Pointer).background(
+                        config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    )
+                })
+        }),
+        commit_files_view(commit_history, commit.id)
+            .style(move |s| s.apply_if(!is_selected.get(), |s| s.hide())),
+    ))
+    .style(|s| s.flex_col().width_pct(100.0))
+}
+
+fn commit_files_view(commit_history: CommitHistoryData, id: String) -> impl View {
+    let config = commit_history.common.config;
+    let workspace = commit_history.common.workspace.clone();
+    let selected = commit_history.selected;
+    let files = {
+        let id = id.clone();
+        move || {
+            selected.with(|selected| match selected {
+                Some((selected, files)) if *selected == id => files.clone(),
+                _ => im::Vector::new(),
+            })
+        }
+    };
+
+    dyn_stack(
+        files,
+        |diff| diff.clone(),
+        move |diff: FileDiff| {
+            let path = diff.path().clone();
+            let path = workspace
+                .path
+                .as_ref()
+                .and_then(|workspace| path.strip_prefix(workspace).ok())
+                .unwrap_or(&path)
+                .to_path_buf();
+            let file_name = path
+                .file_name()
+                .and_then(|s| s.to_str())
+                .unwrap_or("")
+                .to_string();
+            let folder = path
+                .parent()
+                .and_then(|s| s.to_str())
+                .unwrap_or("")
+                .to_string();
+            let diff_for_style = diff.clone();
+            let diff_for_click = diff.clone();
+            let commit_history = commit_history.clone();
+            let id = id.clone();
+
+            stack((
+                svg(move || {
+                    let svg = match &diff {
+                        FileDiff::Modified(_) => LapceIcons::SCM_DIFF_MODIFIED,
+                        FileDiff::Added(_) => LapceIcons::SCM_DIFF_ADDED,
+                        FileDiff::Deleted(_) => LapceIcons::SCM_DIFF_REMOVED,
+                        FileDiff::Renamed(_, _) => LapceIcons::SCM_DIFF_RENAMED,
+                    };
+                    config.get().ui_svg(svg)
+                })
+                .style(move |s| {
+                    let config = config.get();
+                    let size = config.ui.icon_size() as f32;
+                    let color = match &diff_for_style {
+                        FileDiff::Modified(_) | FileDiff::Renamed(_, _) => {
+                            LapceColor::SOURCE_CONTROL_MODIFIED
+                        }
+                        FileDiff::Added(_) => LapceColor::SOURCE_CONTROL_ADDED,
+                        FileDiff::Deleted(_) => LapceColor::SOURCE_CONTROL_REMOVED,
+                    };
+                    s.min_width(size)
+                        .size(size, size)
+                        .margin_right(6.0)
+                        .color(config.color(color))
+                }),
+                label(move || file_name.clone()).style(|s| {
+                    s.text_ellipsis().margin_right(6.0).selectable(false)
+                }),
+                label(move || folder.clone()).style(move |s| {
+                    s.text_ellipsis()
+                        .flex_grow(1.0)
+                        .flex_basis(0.0)
+                        .min_width(0.0)
+                        .color(config.get().color(LapceColor::EDITOR_DIM))
+                        .selectable(false)
+                }),
+            ))
+            .on_click_stop(move |_| {
+                commit_history.open_file_changes(&id, &diff_for_click);
+            })
+            .style(move |s| {
+                let config = config.get();
+                s.padding_left(30.0)
+                    .padding_right(10.0)
+                    .width_pct(100.0)
+                    .items_center()
+                    .hover(|s| {
+                        s.cursor(CursorStyle::Pointer).background(
+                            config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
+            })
+        },
+    )
+    .style(|s| s.flex_col().width_pct(100.0))
+}
+
+fn commit_graph_view(
+    row: CommitGraphRow,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> CommitGraphView {
+    CommitGraphView {
+        id: ViewId::new(),
+        row,
+        config,
+    }
+}
+
+struct CommitGraphView {
+    id: ViewId,
+    row: CommitGraphRow,
+    config: ReadSignal<Arc<LapceConfig>>,
+}
+
+impl View for CommitGraphView {
+    fn id(&self) -> ViewId {
+        self.id
+    }
+
+    fn paint(&mut self, cx: &mut PaintCx) {
+        let Some(height) = self.id.get_layout().map(|l| l.size.height as f64) else {
+            return;
+        };
+        let config = self.config.get_untracked();
+        let x = |lane: usize| lane as f64 * LANE_WIDTH + LANE_WIDTH / 2.0;
+        let color =
+            |lane: usize| config.color(LANE_COLORS[lane % LANE_COLORS.len()]);
+        let stroke = Stroke::new(1.5);
+        let middle = height / 2.0;
+        let lane = self.row.lane;
+
+        for through in self.row.through.iter().copied() {
+            cx.stroke(
+                &Line::new((x(through), 0.0), (x(through), height)),
+                color(through),
+                &stroke,
+            );
+        }
+        for incoming in self.row.incoming.iter().copied() {
+            cx.stroke(
+                &Line::new((x(incoming), 0.0), (x(lane), middle)),
+                color(incoming),
+                &stroke,
+            );
+        }
+        for outgoing in self.row.outgoing.iter().copied() {
+            cx.stroke(
+                &Line::new((x(lane), middle), (x(outgoing), height)),
+                color(outgoing),
+                &stroke,
+            );
+        }
+        cx.fill(&Circle::new((x(lane), middle), 4.0), color(lane), 0.0);
+    }
+
+    fn debug_name(&self) -> std::borrow::Cow<'static, str> {
+        "Commit Graph".into()
+    }
+}
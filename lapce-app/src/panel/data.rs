@@ -39,7 +39,8 @@ pub fn default_panel_order() -> PanelOrder {
             PanelKind::Problem,
             PanelKind::CallHierarchy,
             PanelKind::References,
-            PanelKind::Implementation
+            PanelKind::Implementation,
+            PanelKind::CommitHistory,
         ],
     );
     order.insert(
@@ -57,6 +58,9 @@ pub enum PanelSection {
     Error,
     Warn,
     Changes,
+    Conflicts,
+    Rebase,
+    Compare,
     Installed,
     Available,
     Process,
@@ -3,10 +3,11 @@
 use floem::{
     View,
     event::EventListener,
-    reactive::{ReadSignal, SignalGet, SignalUpdate},
+    reactive::{ReadSignal, SignalGet, SignalUpdate, SignalWith},
     style::{CursorStyle, Style},
     views::{Decorators, container, label, scroll, stack, svg, virtual_stack},
 };
+use lapce_rpc::proxy::SearchMatch;
 use lapce_xi_rope::find::CaseMatching;
 
 use super::{kind::PanelKind, position::PanelPosition};
@@ -14,9 +15,12 @@
     app::clickable_icon,
     command::InternalCommand,
     config::{LapceConfig, color::LapceColor, icon::LapceIcons},
-    editor::location::{EditorLocation, EditorPosition},
+    editor::{
+        EditorData,
+        location::{EditorLocation, EditorPosition},
+    },
     focus_text::focus_text,
-    global_search::{GlobalSearchData, SearchMatchData},
+    global_search::{GlobalSearchData, SearchInput, SearchMatchData},
     listener::Listener,
     text_input::TextInputBuilder,
     window_tab::{Focus, WindowTabData},
@@ -29,84 +33,252 @@ pub fn global_search_panel(
 ) -> impl View {
     let global_search = window_tab_data.global_search.clone();
     let editor = global_search.editor.clone();
+    let replace_editor = global_search.replace_editor.clone();
+    let include_editor = global_search.include_editor.clone();
+    let exclude_editor = global_search.exclude_editor.clone();
+    let replace_visible = global_search.replace_visible;
+    let filters_visible = global_search.filters_visible;
+    let include_ignored = global_search.include_ignored;
+    let only_open_editors = global_search.only_open_editors;
     let config = global_search.common.config;
     let workspace = global_search.common.workspace.clone();
     let internal_command = global_search.common.internal_command;
     let case_matching = global_search.common.find.case_matching;
     let whole_word = global_search.common.find.whole_words;
     let is_regex = global_search.common.find.is_regex;
-
-    let focus = global_search.common.focus;
-    let is_focused = move || focus.get() == Focus::Panel(PanelKind::Search);
+    let replace_all_data = global_search.clone();
+    let open_editor_data = global_search.clone();
 
     stack((
-        container(
-            stack((
-                TextInputBuilder::new()
-                    .is_focused(is_focused)
-                    .build_editor(editor.clone())
-                    .style(|s| s.width_pct(100.0)),
+        stack((
+            search_input(
+                global_search.clone(),
+                SearchInput::Search,
+                editor,
+                stack((
+                    clickable_icon(
+                        || LapceIcons::SEARCH_CASE_SENSITIVE,
+                        move || {
+                            let new = match case_matching.get_untracked() {
+                                CaseMatching::Exact => CaseMatching::CaseInsensitive,
+                                CaseMatching::CaseInsensitive => CaseMatching::Exact,
+                            };
+                            case_matching.set(new);
+                        },
+                        move || case_matching.get() == CaseMatching::Exact,
+                        || false,
+                        || "Case Sensitive",
+                        config,
+                    )
+                    .style(|s| s.padding_vert(4.0)),
+                    clickable_icon(
+                        || LapceIcons::SEARCH_WHOLE_WORD,
+                        move || {
+                            whole_word.update(|whole_word| {
+                                *whole_word = !*whole_word;
+                            });
+                        },
+                        move || whole_word.get(),
+                        || false,
+                        || "Whole Word",
+                        config,
+                    )
+                    .style(|s| s.padding_left(6.0)),
+                    clickable_icon(
+                        || LapceIcons::SEARCH_REGEX,
+                        move || {
+                            is_regex.update(|is_regex| {
+                                *is_regex = !*is_regex;
+                            });
+                        },
+                        move || is_regex.get(),
+                        || false,
+                        || "Use Regex",
+                        config,
+                    )
+                    .style(|s| s.padding_left(6.0)),
+                    clickable_icon(
+                        || LapceIcons::SEARCH_REPLACE,
+                        move || {
+                            replace_visible.update(|visible| *visible = !*visible);
+                        },
+                        move || replace_visible.get(),
+                        || false,
+                        || "Toggle Replace",
+                        config,
+                    )
+                    .style(|s| s.padding_left(6.0)),
+                    clickable_icon(
+                        || LapceIcons::SEARCH_FILTERS,
+                        move || {
+                            filters_visible.update(|visible| *visible = !*visible);
+                        },
+                        move || filters_visible.get(),
+                        || false,
+                        || "Toggle Search Filters",
+                        config,
+                    )
+                    .style(|s| s.padding_left(6.0)),
+                    clickable_icon(
+                        || LapceIcons::SEARCH_OPEN_IN_EDITOR,
+                        move || open_editor_data.open_search_editor(),
+                        || false,
+                        || false,
+                        || "Open Results in Editor",
+                        config,
+                    )
+                    .style(|s| s.padding_left(6.0)),
+                ))
+                .style(|s| s.items_center()),
+            ),
+            search_input(
+                global_search.clone(),
+                SearchInput::Replace,
+                replace_editor,
                 clickable_icon(
-                    || LapceIcons::SEARCH_CASE_SENSITIVE,
-                    move || {
-                        let new = match case_matching.get_untracked() {
-                            CaseMatching::Exact => CaseMatching::CaseInsensitive,
-                            CaseMatching::CaseInsensitive => CaseMatching::Exact,
-                        };
-                        case_matching.set(new);
-                    },
-                    move || case_matching.get() == CaseMatching::Exact,
+                    || LapceIcons::SEARCH_REPLACE_ALL,
+                    move || replace_all_data.replace_all(),
                     || false,
-                    || "Case Sensitive",
+                    || false,
+                    || "Replace All",
                     config,
                 )
                 .style(|s| s.padding_vert(4.0)),
+            )
+            .style(move |s| {
+                s.margin_top(6.0)
+                    .apply_if(!replace_visible.get(), |s| s.hide())
+            }),
+            search_input(
+                global_search.clone(),
+                SearchInput::Include,
+                include_editor,
                 clickable_icon(
-                    || LapceIcons::SEARCH_WHOLE_WORD,
+                    || LapceIcons::SEARCH_OPEN_EDITORS,
                     move || {
-                        whole_word.update(|whole_word| {
-                            *whole_word = !*whole_word;
-                        });
+                        only_open_editors.update(|only| *only = !*only);
                     },
-                    move || whole_word.get(),
+                    move || only_open_editors.get(),
                     || false,
-                    || "Whole Word",
+                    || "Search Only in Open Editors",
                     config,
                 )
-                .style(|s| s.padding_left(6.0)),
+                .style(|s| s.padding_vert(4.0)),
+            )
+            .style(move |s| {
+                s.margin_top(6.0)
+                    .apply_if(!filters_visible.get(), |s| s.hide())
+            }),
+            search_input(
+                global_search.clone(),
+                SearchInput::Exclude,
+                exclude_editor,
                 clickable_icon(
-                    || LapceIcons::SEARCH_REGEX,
+                    || LapceIcons::SEARCH_INCLUDE_IGNORED,
                     move || {
-                        is_regex.update(|is_regex| {
-                            *is_regex = !*is_regex;
-                        });
+                        include_ignored.update(|include| *include = !*include);
                     },
-                    move || is_regex.get(),
+                    move || include_ignored.get(),
                     || false,
-                    || "Use Regex",
+                    || "Search Ignored and Hidden Files",
                     config,
                 )
-                .style(|s| s.padding_left(6.0)),
-            ))
-            .on_event_cont(EventListener::PointerDown, move |_| {
-                focus.set(Focus::Panel(PanelKind::Search));
-            })
+                .style(|s| s.padding_vert(4.0)),
+            )
             .style(move |s| {
-                s.width_pct(100.0)
-                    .padding_right(6.0)
-                    .items_center()
-                    .border(1.0)
-                    .border_radius(6.0)
-                    .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+                s.margin_top(6.0)
+                    .apply_if(!filters_visible.get(), |s| s.hide())
             }),
-        )
-        .style(|s| s.width_pct(100.0).padding(10.0)),
+        ))
+        .style(|s| s.flex_col().width_pct(100.0).padding(10.0)),
+        search_status(global_search.clone()),
         search_result(workspace, global_search, internal_command, config),
     ))
     .style(|s| s.absolute().size_pct(100.0, 100.0).flex_col())
     .debug_name("Global Search Panel")
 }
 
+/// The number of matches found so far, and whether the search is still going
+/// or stopped at the limit of matches
+fn search_status(global_search: GlobalSearchData) -> impl View {
+    let config = global_search.common.config;
+    let searching = global_search.searching;
+    let result_count = global_search.result_count;
+    let limit_hit = global_search.limit_hit;
+    let search_result = global_search.search_result;
+    let buffer = global_search.editor.doc().buffer;
+
+    label(move || {
+        let count = result_count.get();
+        let files = search_result.with(|result| result.len());
+        let mut status = if count == 0 && !searching.get() {
+            "No results".to_string()
+        } else {
+            format!(
+                "{count} {} in {files} {}",
+                if count == 1 { "result" } else { "results" },
+                if files == 1 { "file" } else { "files" },
+            )
+        };
+        if searching.get() {
+            status = format!("Searching... {status}");
+        } else if limit_hit.get() {
+            status.push_str(", stopped at the result limit");
+        }
+        status
+    })
+    .style(move |s| {
+        let is_empty = buffer.with(|buffer| buffer.len() == 0);
+        s.padding_horiz(10.0)
+            .padding_bottom(6.0)
+            .text_ellipsis()
+            .color(config.get().color(LapceColor::EDITOR_DIM))
+            .apply_if(is_empty, |s| s.hide())
+    })
+}
+
+/// An input of the search panel, which gives it the focus when clicked
+fn search_input(
+    global_search: GlobalSearchData,
+    input: SearchInput,
+    editor: EditorData,
+    icons: impl View + 'static,
+) -> impl View {
+    let config = global_search.common.config;
+    let focus = global_search.common.focus;
+    let input_focus = global_search.input_focus;
+    let placeholder = match input {
+        SearchInput::Search => "Search",
+        SearchInput::Replace => "Replace",
+        SearchInput::Include => "Files to include, e.g. src, *.rs",
+        SearchInput::Exclude => "Files to exclude",
+    };
+
+    stack((
+        TextInputBuilder::new()
+            .is_focused(move || {
+                focus.get() == Focus::Panel(PanelKind::Search)
+                    && global_search.is_input_focused(input)
+            })
+            .build_editor(editor)
+            .placeholder(move || placeholder.to_string())
+            .style(|s| s.width_pct(100.0)),
+        icons,
+    ))
+    .on_event_cont(EventListener::PointerDown, move |_| {
+        focus.set(Focus::Panel(PanelKind::Search));
+        input_focus.set(input);
+    })
+    .style(move |s| {
+        s.width_pct(100.0)
+            .padding_right(6.0)
+            .items_center()
+            .border(1.0)
+            .border_radius(6.0)
+            .border_color(config.get().color(LapceColor::LAPCE_BORDER))
+    })
+}
+
 fn search_result(
     workspace: Arc<LapceWorkspace>,
     global_search_data: GlobalSearchData,
@@ -114,6 +286,8 @@ fn search_result(
     config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View {
     let ui_line_height = global_search_data.common.ui_line_height;
+    let replace_visible = global_search_data.replace_visible;
+    let global_search = global_search_data.clone();
     container({
         scroll({
             virtual_stack(
@@ -144,6 +318,9 @@ fn search_result(
                         .to_string();
 
                     let expanded = match_data.expanded;
+                    let file_path = full_path.clone();
+                    let replace_file_data = global_search.clone();
+                    let global_search = global_search.clone();
 
                     stack((
                         stack((
@@ -190,7 +367,23 @@ fn search_result(
                                     .text_ellipsis()
                                 }),
                             ))
-                            .style(move |s| s.min_width(0.0).items_center()),
+                            .style(move |s| {
+                                s.min_width(0.0).flex_grow(1.0).items_center()
+                            }),
+                            clickable_icon(
+                                || LapceIcons::SEARCH_REPLACE_ALL,
+                                move || {
+                                    replace_file_data.replace_in_file(&file_path)
+                                },
+                                || false,
+                                || false,
+                                || "Replace in File",
+                                config,
+                            )
+                            .style(move |s| {
+                                s.margin_right(6.0)
+                                    .apply_if(!replace_visible.get(), |s| s.hide())
+                            }),
                         ))
                         .on_click_stop(move |_| {
                             expanded.update(|expanded| *expanded = !*expanded);
@@ -219,56 +412,108 @@ fn search_result(
                             move |m| {
                                 let path = full_path.clone();
                                 let line_number = m.line;
-                                let start = m.start;
-                                let end = m.end;
+                                let range = m.content_range();
                                 let line_content = m.line_content.clone();
+                                let preview_match = m.clone();
+                                let replace_match = m.clone();
+                                let replace_path = full_path.clone();
+                                let preview_data = global_search.clone();
+                                let replace_data = global_search.clone();
 
-                                focus_text(
-                                    move || {
-                                        let config = config.get();
-                                        let content = if config
-                                            .ui
-                                            .trim_search_results_whitespace
-                                        {
-                                            m.line_content.trim()
-                                        } else {
-                                            &m.line_content
-                                        };
-                                        format!("{}: {content}", m.line,)
-                                    },
-                                    move || {
-                                        let config = config.get();
-                                        let mut offset = if config
-                                            .ui
-                                            .trim_search_results_whitespace
-                                        {
-                                            line_content.trim_start().len() as i32
-                                                - line_content.len() as i32
-                                        } else {
-                                            0
-                                        };
-                                        offset +=
-                                            line_number.to_string().len() as i32 + 2;
+                                stack((
+                                    focus_text(
+                                        move || {
+                                            let config = config.get();
+                                            let content = if config
+                                                .ui
+                                                .trim_search_results_whitespace
+                                            {
+                                                m.line_content.trim()
+                                            } else {
+                                                &m.line_content
+                                            };
+                                            format!("{}: {content}", m.line,)
+                                        },
+                                        move || {
+                                            let config = config.get();
+                                            let mut offset = if config
+                                                .ui
+                                                .trim_search_results_whitespace
+                                            {
+                                                line_content.trim_start().len()
+                                                    as i32
+                                                    - line_content.len() as i32
+                                            } else {
+                                                0
+                                            };
+                                            offset += line_number.to_string().len()
+                                                as i32
+                                                + 2;
 
-                                        ((start as i32 + offset) as usize
-                                            ..(end as i32 + offset) as usize)
-                                            .collect()
-                                    },
-                                    move || {
-                                        config.get().color(LapceColor::EDITOR_FOCUS)
-                                    },
-                                )
+                                            ((range.start as i32 + offset) as usize
+                                                ..(range.end as i32 + offset)
+                                                    as usize)
+                                                .collect()
+                                        },
+                                        move || {
+                                            config
+                                                .get()
+                                                .color(LapceColor::EDITOR_FOCUS)
+                                        },
+                                    )
+                                    .style(
+                                        move |s| {
+                                            s.apply_if(replace_visible.get(), |s| {
+                                                s.hide()
+                                            })
+                                        },
+                                    ),
+                                    replace_preview(
+                                        preview_data,
+                                        preview_match,
+                                        config,
+                                    )
+                                    .style(
+                                        move |s| {
+                                            s.flex_grow(1.0).apply_if(
+                                                !replace_visible.get(),
+                                                |s| s.hide(),
+                                            )
+                                        },
+                                    ),
+                                    clickable_icon(
+                                        || LapceIcons::SEARCH_REPLACE,
+                                        move || {
+                                            replace_data.replace_match(
+                                                &replace_path,
+                                                &replace_match,
+                                            )
+                                        },
+                                        || false,
+                                        || false,
+                                        || "Replace",
+                                        config,
+                                    )
+                                    .style(
+                                        move |s| {
+                                            s.margin_right(6.0).apply_if(
+                                                !replace_visible.get(),
+                                                |s| s.hide(),
+                                            )
+                                        },
+                                    ),
+                                ))
                                 .style(move |s| {
                                     let config = config.get();
                                     let icon_size = config.ui.icon_size() as f32;
-                                    s.margin_left(10.0 + icon_size + 6.0).hover(
-                                        |s| {
+                                    s.margin_left(10.0 + icon_size + 6.0)
+                                        .items_center()
+                                        .hover(|s| {
                                             s.cursor(CursorStyle::Pointer)
                                                 .background(config.color(
                                                 LapceColor::PANEL_HOVERED_BACKGROUND,
                                             ))
-                                        },
-                                    )
+                                        })
                                 })
                                 .on_click_stop(
                                     move |_| {
@@ -307,3 +552,49 @@ fn search_result(
     })
     .style(|s| s.size_pct(100.0, 100.0))
 }
+
+/// The line of the match with the matched text followed by the text replacing
+/// it, colored as removed and added
+fn replace_preview(
+    global_search: GlobalSearchData,
+    search_match: SearchMatch,
+    config: ReadSignal<Arc<LapceConfig>>,
+) -> impl View {
+    let content = &search_match.line_content;
+    let range = search_match.content_range();
+    let (before, matched, after) = match (
+        content.get(..range.start),
+        content.get(range.clone()),
+        content.get(range.end..),
+    ) {
+        (Some(before), Some(matched), Some(after)) => {
+            (before.to_string(), matched.to_string(), after.to_string())
+        }
+        _ => (content.clone(), String::new(), String::new()),
+    };
+    let line = search_match.line;
+
+    stack((
+        label(move || {
+            let before = if config.get().ui.trim_search_results_whitespace {
+                before.trim_start()
+            } else {
+                &before
+            };
+            format!("{line}: {before}")
+        }),
+        label(move || matched.clone()).style(move |s| {
+            s.color(config.get().color(LapceColor::SOURCE_CONTROL_REMOVED))
+        }),
+        label(move || {
+            global_search
+                .match_replacement(&search_match)
+                .unwrap_or_default()
+        })
+        .style(move |s| {
+            s.color(config.get().color(LapceColor::SOURCE_CONTROL_ADDED))
+        }),
+        label(move || after.clone()).style(|s| s.text_ellipsis().min_width(0.0)),
+    ))
+    .style(|s| s.min_width(0.0).items_center())
+}
@@ -19,6 +19,7 @@ pub enum PanelKind {
     DocumentSymbol,
     References,
     Implementation,
+    CommitHistory,
 }
 
 impl PanelKind {
@@ -35,6 +36,7 @@ pub fn svg_name(&self) -> &'static str {
             PanelKind::DocumentSymbol => LapceIcons::DOCUMENT_SYMBOL,
             PanelKind::References => LapceIcons::REFERENCES,
             PanelKind::Implementation => LapceIcons::IMPLEMENTATION,
+            PanelKind::CommitHistory => LapceIcons::SCM_HISTORY,
         }
     }
 
@@ -61,6 +63,7 @@ pub fn default_position(&self) -> PanelPosition {
             PanelKind::DocumentSymbol => PanelPosition::RightTop,
             PanelKind::References => PanelPosition::BottomLeft,
             PanelKind::Implementation => PanelPosition::BottomLeft,
+            PanelKind::CommitHistory => PanelPosition::BottomLeft,
         }
     }
 }
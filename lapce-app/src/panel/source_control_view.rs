@@ -7,16 +7,19 @@
     menu::{Menu, MenuItem},
     peniko::kurbo::Rect,
     prelude::SignalTrack,
-    reactive::{SignalGet, SignalUpdate, SignalWith, create_memo, create_rw_signal},
+    reactive::{
+        RwSignal, SignalGet, SignalUpdate, SignalWith, create_memo, create_rw_signal,
+    },
     style::{CursorStyle, Style},
+    text::{Attrs, AttrsList, FamilyOwned, TextLayout},
     views::{
         Decorators, container, dyn_stack,
         editor::view::{LineRegion, cursor_caret},
-        label, scroll, stack, svg, text,
+        empty, label, scroll, stack, svg, text,
     },
 };
 use lapce_core::buffer::rope_text::RopeText;
-use lapce_rpc::source_control::FileDiff;
+use lapce_rpc::source_control::{DiffInfo, FileDiff, RebaseAction};
 
 use super::{
     data::PanelSection, kind::PanelKind, position::PanelPosition,
@@ -27,7 +30,7 @@
     config::{color::LapceColor, icon::LapceIcons},
     editor::view::editor_view,
     settings::checkbox,
-    source_control::SourceControlData,
+    source_control::{SourceControlData, lint_commit_message},
     window_tab::{Focus, WindowTabData},
 };
 
@@ -57,6 +60,22 @@ pub fn source_control_panel(
         doc.buffer.with(|b| b.len() == 0)
     });
     let debug_breakline = create_memo(move |_| None);
+    let char_width = create_memo(move |_| {
+        let config = config.get();
+        let family: Vec<FamilyOwned> =
+            FamilyOwned::parse_list(&config.editor.font_family).collect();
+        let attrs = Attrs::new()
+            .family(&family)
+            .font_size(config.editor.font_size() as f32);
+        let mut text_layout = TextLayout::new();
+        text_layout.set_text("W", AttrsList::new(attrs));
+        text_layout.size().width
+    });
+    let lint_warnings = create_memo(move |_| {
+        let doc = doc.get();
+        let message = doc.buffer.with(|b| b.to_string());
+        lint_commit_message(&message, &config.get().core)
+    });
 
     stack((
         stack((
@@ -77,6 +96,19 @@ pub fn source_control_panel(
                                 .apply_if(!is_empty.get(), |s| s.hide())
                                 .selectable(false)
                         }),
+                        // The ruler of the maximum length of the subject line
+                        empty().style(move |s| {
+                            let config = config.get();
+                            let length = config.core.commit_subject_length;
+                            s.absolute()
+                                .margin_left(
+                                    (length as f64 * char_width.get()) as f32,
+                                )
+                                .width(1.0)
+                                .height_pct(100.0)
+                                .background(config.color(LapceColor::LAPCE_BORDER))
+                                .apply_if(length == 0, |s| s.hide())
+                        }),
                     ))
                     .style(|s| {
                         s.absolute()
@@ -149,6 +181,20 @@ pub fn source_control_panel(
                     .border_color(config.color(LapceColor::LAPCE_BORDER))
                     .background(config.color(LapceColor::EDITOR_BACKGROUND))
             }),
+            commit_options_view(source_control.clone()),
+            dyn_stack(
+                move || lint_warnings.get(),
+                |warning| warning.clone(),
+                move |warning| {
+                    label(move || warning.clone()).style(move |s| {
+                        let config = config.get();
+                        s.margin_top(4.0)
+                            .color(config.color(LapceColor::LAPCE_WARN))
+                            .selectable(false)
+                    })
+                },
+            )
+            .style(|s| s.flex_col().width_pct(100.0)),
             {
                 let source_control = source_control.clone();
                 label(|| "Commit".to_string())
@@ -180,6 +226,55 @@ pub fn source_control_panel(
             },
         ))
         .style(|s| s.flex_col().width_pct(100.0).padding(10.0)),
+        {
+            let conflicts = source_control.conflicts;
+            foldable_panel_section(
+                text("Merge Conflicts"),
+                conflicts_view(source_control.clone()),
+                window_tab_data.panel.section_open(PanelSection::Conflicts),
+                config,
+            )
+            .style(move |s| {
+                s.flex_col()
+                    .width_pct(100.0)
+                    .apply_if(conflicts.with(|c| c.is_empty()), |s| s.hide())
+            })
+        },
+        {
+            let rebase_todo = source_control.rebase_todo;
+            let rebasing = source_control.rebasing;
+            foldable_panel_section(
+                text("Rebase"),
+                rebase_view(source_control.clone()),
+                window_tab_data.panel.section_open(PanelSection::Rebase),
+                config,
+            )
+            .style(move |s| {
+                s.flex_col().width_pct(100.0).apply_if(
+                    rebase_todo.with(|todo| todo.is_none()) && !rebasing.get(),
+                    |s| s.hide(),
+                )
+            })
+        },
+        {
+            let compare_base = source_control.compare_base;
+            foldable_panel_section(
+                label(move || {
+                    format!(
+                        "Changes Against {}",
+                        compare_base.get().unwrap_or_default()
+                    )
+                }),
+                compare_view(source_control.clone()),
+                window_tab_data.panel.section_open(PanelSection::Compare),
+                config,
+            )
+            .style(move |s| {
+                s.flex_col()
+                    .width_pct(100.0)
+                    .apply_if(compare_base.with(|base| base.is_none()), |s| s.hide())
+            })
+        },
         foldable_panel_section(
             text("Changes"),
             file_diffs_view(source_control),
@@ -197,8 +292,405 @@ pub fn source_control_panel(
     .debug_name("Source Control Panel")
 }
 
+/// The options of the next commit
+fn commit_options_view(source_control: SourceControlData) -> impl View {
+    let config = source_control.common.config;
+    let amend = source_control.amend;
+    let sign_off = source_control.sign_off;
+
+    let option = move |checked: RwSignal<bool>,
+                       text: &'static str,
+                       on_click: Box<dyn Fn()>| {
+        stack((
+            checkbox(move || checked.get(), config),
+            label(move || text.to_string())
+                .style(|s| s.margin_left(6.0).selectable(false)),
+        ))
+        .on_click_stop(move |_| on_click())
+        .style(|s| {
+            s.margin_right(10.0)
+                .items_center()
+                .hover(|s| s.cursor(CursorStyle::Pointer))
+        })
+    };
+
+    stack((
+        option(
+            amend,
+            "Amend Last Commit",
+            Box::new(move || source_control.toggle_amend()),
+        ),
+        option(
+            sign_off,
+            "Sign Off",
+            Box::new(move || sign_off.update(|sign_off| *sign_off = !*sign_off)),
+        ),
+    ))
+    .style(|s| s.margin_top(10.0).width_pct(100.0).items_center())
+}
+
+/// The header of the changes of a repository when the workspace has
+/// submodules, which selects the repository to commit to and check out on
+/// click
+fn repository_header(
+    source_control: SourceControlData,
+    index: usize,
+    diff: DiffInfo,
+) -> impl View {
+    let repositories = source_control.repositories;
+    let repository = source_control.repository;
+    let config = source_control.common.config;
+
+    let root = diff.root.clone();
+    let name = if index == 0 {
+        root.file_name()
+            .unwrap_or(root.as_os_str())
+            .to_string_lossy()
+            .to_string()
+    } else {
+        repositories
+            .with_untracked(|repositories| {
+                repositories.front().and_then(|workspace| {
+                    root.strip_prefix(&workspace.root)
+                        .ok()
+                        .map(|path| path.to_string_lossy().to_string())
+                })
+            })
+            .unwrap_or_else(|| root.to_string_lossy().to_string())
+    };
+    let head = diff.head.clone();
+    let changes = diff.diffs.len();
+    let is_selected = create_memo(move |_| {
+        repository.with(|repository| match repository {
+            Some(repository) => *repository == root,
+            None => index == 0,
+        })
+    });
+    let root = diff.root.clone();
+
+    stack((
+        label(move || name.clone())
+            .style(|s| s.text_ellipsis().margin_right(6.0).selectable(false)),
+        label(move || head.clone()).style(move |s| {
+            s.text_ellipsis()
+                .flex_grow(1.0)
+                .flex_basis(0.0)
+                .color(config.get().color(LapceColor::EDITOR_DIM))
+                .min_width(0.0)
+                .selectable(false)
+        }),
+        label(move || changes.to_string()).style(move |s| {
+            s.margin_left(6.0)
+                .color(config.get().color(LapceColor::EDITOR_DIM))
+                .apply_if(changes == 0, |s| s.hide())
+                .selectable(false)
+        }),
+    ))
+    .on_click_stop(move |_| {
+        source_control.select_repository(root.clone());
+    })
+    .style(move |s| {
+        let config = config.get();
+        s.padding_horiz(10.0)
+            .width_pct(100.0)
+            .items_center()
+            .apply_if(is_selected.get(), |s| {
+                s.background(config.color(LapceColor::PANEL_CURRENT_BACKGROUND))
+            })
+            .apply_if(repositories.with(|r| r.len() <= 1), |s| s.hide())
+            .hover(|s| {
+                s.cursor(CursorStyle::Pointer)
+                    .background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+            })
+    })
+}
+
+/// The unmerged paths, which are opened on click and staged by "Mark Resolved"
+fn conflicts_view(source_control: SourceControlData) -> impl View {
+    let conflicts = source_control.conflicts;
+    let config = source_control.common.config;
+    let workspace = source_control.common.workspace.clone();
+    let internal_command = source_control.common.internal_command;
+
+    let view_fn = move |full_path: PathBuf| {
+        let path = if let Some(workspace_path) = workspace.path.as_ref() {
+            full_path
+                .strip_prefix(workspace_path)
+                .unwrap_or(&full_path)
+                .to_path_buf()
+        } else {
+            full_path.clone()
+        };
+        let file_name = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string();
+        let folder = path
+            .parent()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string();
+        let style_path = path.clone();
+        let path_for_click = full_path.clone();
+        let path_for_menu = full_path.clone();
+        let source_control_for_menu = source_control.clone();
+        let source_control = source_control.clone();
+
+        stack((
+            svg(move || config.get().file_svg(&path).0).style(move |s| {
+                let config = config.get();
+                let size = config.ui.icon_size() as f32;
+                let color = config.file_svg(&style_path).1;
+                s.min_width(size)
+                    .size(size, size)
+                    .margin_right(6.0)
+                    .apply_opt(color, Style::color)
+            }),
+            label(move || file_name.clone())
+                .style(|s| s.text_ellipsis().margin_right(6.0).selectable(false)),
+            label(move || folder.clone()).style(move |s| {
+                s.text_ellipsis()
+                    .flex_grow(1.0)
+                    .flex_basis(0.0)
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+                    .min_width(0.0)
+                    .selectable(false)
+            }),
+            label(|| "Mark Resolved".to_string())
+                .on_click_stop(move |_| {
+                    source_control.mark_resolved(full_path.clone());
+                })
+                .style(move |s| {
+                    let config = config.get();
+                    s.margin_left(6.0)
+                        .padding_horiz(4.0)
+                        .border_radius(4.0)
+                        .color(config.color(LapceColor::EDITOR_DIM))
+                        .hover(|s| {
+                            s.cursor(CursorStyle::Pointer)
+                                .color(config.color(LapceColor::EDITOR_FOREGROUND))
+                                .background(config.color(
+                                    LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND,
+                                ))
+                        })
+                        .selectable(false)
+                }),
+        ))
+        .on_click_stop(move |_| {
+            internal_command.send(InternalCommand::OpenFile {
+                path: path_for_click.clone(),
+            });
+        })
+        .on_event_cont(EventListener::PointerDown, move |event| {
+            if let Event::PointerDown(pointer_event) = event {
+                if pointer_event.button.is_secondary() {
+                    let source_control = source_control_for_menu.clone();
+                    let path = path_for_menu.clone();
+                    let menu = Menu::new("").entry(
+                        MenuItem::new("Mark Resolved").action(move || {
+                            source_control.mark_resolved(path.clone());
+                        }),
+                    );
+                    show_context_menu(menu, None);
+                }
+            }
+        })
+        .style(move |s| {
+            let config = config.get();
+            s.padding_horiz(10.0)
+                .width_pct(100.0)
+                .items_center()
+                .hover(|s| {
+                    s.background(config.color(LapceColor::PANEL_HOVERED_BACKGROUND))
+                })
+        })
+    };
+
+    dyn_stack(move || conflicts.get(), |path| path.clone(), view_fn)
+        .style(|s| s.line_height(1.6).flex_col().width_pct(100.0))
+}
+
+/// The state of the rebase, with the actions to start a prepared one or to
+/// resume one which stopped
+fn rebase_view(source_control: SourceControlData) -> impl View {
+    let config = source_control.common.config;
+    let internal_command = source_control.common.internal_command;
+    let rebase_todo = source_control.rebase_todo;
+    let rebasing = source_control.rebasing;
+
+    let button = move |text: &'static str,
+                       // Whether the action is for a rebase which stopped
+                       stopped: bool,
+                       on_click: Box<dyn Fn()>| {
+        label(move || text.to_string())
+            .on_click_stop(move |_| on_click())
+            .style(move |s| {
+                let config = config.get();
+                s.margin_right(6.0)
+                    .padding_horiz(6.0)
+                    .border(1.0)
+                    .border_radius(4.0)
+                    .border_color(config.color(LapceColor::LAPCE_BORDER))
+                    .hover(|s| {
+                        s.cursor(CursorStyle::Pointer).background(
+                            config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                        )
+                    })
+                    .active(|s| {
+                        s.background(
+                            config
+                                .color(LapceColor::PANEL_HOVERED_ACTIVE_BACKGROUND),
+                        )
+                    })
+                    .apply_if(rebasing.get() != stopped, |s| s.hide())
+                    .selectable(false)
+            })
+    };
+
+    let action = move |action: RebaseAction| -> Box<dyn Fn()> {
+        let source_control = source_control.clone();
+        Box::new(move || source_control.rebase_action(action))
+    };
+
+    stack((
+        label(move || {
+            if rebasing.get() {
+                "Resolve the conflicts or amend the commit, then continue"
+                    .to_string()
+            } else {
+                format!(
+                    "Edit the todo list, then rebase onto {}",
+                    rebase_todo
+                        .with(|todo| todo.as_ref().map(|(onto, _)| onto.clone()))
+                        .unwrap_or_default()
+                )
+            }
+        })
+        .style(move |s| {
+            s.text_ellipsis()
+                .width_pct(100.0)
+                .color(config.get().color(LapceColor::EDITOR_DIM))
+                .selectable(false)
+        }),
+        stack((
+            button(
+                "Start Rebase",
+                false,
+                Box::new(move || {
+                    internal_command.send(InternalCommand::StartRebase)
+                }),
+            ),
+            button("Cancel", false, Box::new(move || rebase_todo.set(None))),
+            button("Continue", true, action(RebaseAction::Continue)),
+            button("Skip", true, action(RebaseAction::Skip)),
+            button("Abort", true, action(RebaseAction::Abort)),
+        ))
+        .style(|s| s.margin_top(6.0).items_center()),
+    ))
+    .style(|s| {
+        s.flex_col()
+            .line_height(1.6)
+            .width_pct(100.0)
+            .padding_horiz(10.0)
+    })
+}
+
+/// The files which differ from the revision the workspace is compared with,
+/// which open the diff editor against that revision on click
+fn compare_view(source_control: SourceControlData) -> impl View {
+    let compare_base = source_control.compare_base;
+    let compare_diffs = source_control.compare_diffs;
+    let config = source_control.common.config;
+    let workspace = source_control.common.workspace.clone();
+    let internal_command = source_control.common.internal_command;
+
+    let view_fn = move |diff: FileDiff| {
+        let full_path = diff.path().clone();
+        let path = if let Some(workspace_path) = workspace.path.as_ref() {
+            full_path
+                .strip_prefix(workspace_path)
+                .unwrap_or(&full_path)
+                .to_path_buf()
+        } else {
+            full_path.clone()
+        };
+        let file_name = path
+            .file_name()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string();
+        let folder = path
+            .parent()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+            .to_string();
+        let diff_for_style = diff.clone();
+
+        stack((
+            svg(move || {
+                let svg = match &diff {
+                    FileDiff::Modified(_) => LapceIcons::SCM_DIFF_MODIFIED,
+                    FileDiff::Added(_) => LapceIcons::SCM_DIFF_ADDED,
+                    FileDiff::Deleted(_) => LapceIcons::SCM_DIFF_REMOVED,
+                    FileDiff::Renamed(_, _) => LapceIcons::SCM_DIFF_RENAMED,
+                };
+                config.get().ui_svg(svg)
+            })
+            .style(move |s| {
+                let config = config.get();
+                let size = config.ui.icon_size() as f32;
+                let color = match &diff_for_style {
+                    FileDiff::Modified(_) | FileDiff::Renamed(_, _) => {
+                        LapceColor::SOURCE_CONTROL_MODIFIED
+                    }
+                    FileDiff::Added(_) => LapceColor::SOURCE_CONTROL_ADDED,
+                    FileDiff::Deleted(_) => LapceColor::SOURCE_CONTROL_REMOVED,
+                };
+                s.min_width(size)
+                    .size(size, size)
+                    .margin_right(6.0)
+                    .color(config.color(color))
+            }),
+            label(move || file_name.clone())
+                .style(|s| s.text_ellipsis().margin_right(6.0).selectable(false)),
+            label(move || folder.clone()).style(move |s| {
+                s.text_ellipsis()
+                    .flex_grow(1.0)
+                    .flex_basis(0.0)
+                    .color(config.get().color(LapceColor::EDITOR_DIM))
+                    .min_width(0.0)
+                    .selectable(false)
+            }),
+        ))
+        .on_click_stop(move |_| {
+            if let Some(revision) = compare_base.get_untracked() {
+                internal_command.send(InternalCommand::OpenFileChangesAgainst {
+                    path: full_path.clone(),
+                    revision,
+                });
+            }
+        })
+        .style(move |s| {
+            let config = config.get();
+            s.padding_horiz(10.0)
+                .width_pct(100.0)
+                .items_center()
+                .hover(|s| {
+                    s.cursor(CursorStyle::Pointer).background(
+                        config.color(LapceColor::PANEL_HOVERED_BACKGROUND),
+                    )
+                })
+        })
+    };
+
+    dyn_stack(move || compare_diffs.get(), |diff| diff.clone(), view_fn)
+        .style(|s| s.line_height(1.6).flex_col().width_pct(100.0))
+}
+
 fn file_diffs_view(source_control: SourceControlData) -> impl View {
     let file_diffs = source_control.file_diffs;
+    let repositories = source_control.repositories;
     let config = source_control.common.config;
     let workspace = source_control.common.workspace.clone();
     let panel_rect = create_rw_signal(Rect::ZERO);
@@ -345,15 +837,57 @@ fn file_diffs_view(source_control: SourceControlData) -> impl View {
         })
     };
 
-    container({
-        scroll({
+    // The changes of the workspace's repository and of each submodule
+    let repository_fn = move |(index, diff): (usize, DiffInfo)| {
+        let root = diff.root.clone();
+        let view_fn = view_fn.clone();
+        stack((
+            repository_header(source_control.clone(), index, diff),
             dyn_stack(
-                move || file_diffs.get(),
+                move || {
+                    let paths: Vec<PathBuf> = repositories.with(|repositories| {
+                        repositories
+                            .iter()
+                            .find(|diff| diff.root == root)
+                            .map(|diff| {
+                                diff.diffs.iter().map(|d| d.path().clone()).collect()
+                            })
+                            .unwrap_or_default()
+                    });
+                    file_diffs.with(|file_diffs| {
+                        paths
+                            .into_iter()
+                            .filter_map(|path| {
+                                let diff = file_diffs.get(&path)?.clone();
+                                Some((path, diff))
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                },
                 |(path, (diff, checked))| {
                     (path.to_path_buf(), diff.clone(), *checked)
                 },
                 view_fn,
             )
+            .style(|s| s.flex_col().width_pct(100.0)),
+        ))
+        .style(|s| s.flex_col().width_pct(100.0))
+    };
+
+    container({
+        scroll({
+            dyn_stack(
+                move || repositories.get().into_iter().enumerate(),
+                |(index, diff)| {
+                    (
+                        *index,
+                        diff.root.clone(),
+                        diff.head.clone(),
+                        diff.diffs.len(),
+                    )
+                },
+                repository_fn,
+            )
             .style(|s| s.line_height(1.6).flex_col().width_pct(100.0))
         })
         .style(|s| s.absolute().size_pct(100.0, 100.0))
@@ -0,0 +1,48 @@
+/// The actions of a line of an interactive rebase todo list with their short
+/// forms, in the order they are cycled through
+const ACTIONS: [(&str, &str); 6] = [
+    ("pick", "p"),
+    ("reword", "r"),
+    ("edit", "e"),
+    ("squash", "s"),
+    ("fixup", "f"),
+    ("drop", "d"),
+];
+
+/// The length of the action the todo line starts with, and the action which
+/// replaces it, the next one or the previous one if not `forward`. Lines
+/// without a commit, like comments, have no action.
+pub fn cycle_action(line: &str, forward: bool) -> Option<(usize, &'static str)> {
+    let word = line.split_whitespace().next()?;
+    let index = ACTIONS
+        .iter()
+        .position(|(action, short)| *action == word || *short == word)?;
+    let next = if forward {
+        (index + 1) % ACTIONS.len()
+    } else {
+        (index + ACTIONS.len() - 1) % ACTIONS.len()
+    };
+    let start = line.len() - line.trim_start().len();
+    Some((start + word.len(), ACTIONS[next].0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::cycle_action;
+
+    #[test]
+    fn test_cycle_action() {
+        assert_eq!(
+            cycle_action("pick 1a2b3c Fix typo", true),
+            Some((4, "reword"))
+        );
+        assert_eq!(cycle_action("p 1a2b3c Fix typo", false), Some((1, "drop")));
+        assert_eq!(
+            cycle_action("drop 1a2b3c Fix typo", true),
+            Some((4, "pick"))
+        );
+        assert_eq!(cycle_action("r 1a2b3c Fix typo", true), Some((1, "edit")));
+        assert_eq!(cycle_action("# p, pick <commit> = use commit", true), None);
+        assert_eq!(cycle_action("", true), None);
+    }
+}
@@ -0,0 +1,548 @@
+use std::{
+    collections::BTreeMap,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use indexmap::IndexMap;
+use lapce_core::{language::LapceLanguage, syntax::Syntax};
+use lapce_rpc::{
+    proxy::{SearchFilter, SearchMatch},
+    style::{LineStyle, Style},
+};
+use lapce_xi_rope::Rope;
+use regex::{Regex, RegexBuilder};
+
+/// The prefix of the names of the search editors which aren't saved yet
+pub const SEARCH_EDITOR_PREFIX: &str = "Search-";
+
+/// The query of a search editor, which is written in the header of its
+/// document so that the search can be run again from it
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SearchEditorQuery {
+    pub pattern: String,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    pub is_regex: bool,
+    pub include_ignored: bool,
+    /// Comma separated globs of the files to search
+    pub include: String,
+    /// Comma separated globs of the files not to search
+    pub exclude: String,
+    pub context_lines: usize,
+}
+
+impl SearchEditorQuery {
+    /// The header of the document
+    pub fn header(&self) -> String {
+        let flags: Vec<&str> = [
+            (self.case_sensitive, "CaseSensitive"),
+            (self.whole_word, "WholeWord"),
+            (self.is_regex, "RegExp"),
+            (self.include_ignored, "IgnoredFiles"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, flag)| enabled.then_some(flag))
+        .collect();
+        format!(
+            "# Query: {}\n# Flags: {}\n# Including: {}\n# Excluding: {}\n# Context Lines: {}\n",
+            escape(&self.pattern),
+            flags.join(" "),
+            self.include,
+            self.exclude,
+            self.context_lines,
+        )
+    }
+
+    /// Parse the query from the header at the start of the document, which
+    /// has no query if the pattern is missing
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut query = SearchEditorQuery::default();
+        for line in text.lines() {
+            let Some(line) = line.strip_prefix("# ") else {
+                break;
+            };
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.strip_prefix(' ').unwrap_or(value);
+            match key {
+                "Query" => query.pattern = unescape(value),
+                "Flags" => {
+                    for flag in value.split_whitespace() {
+                        match flag {
+                            "CaseSensitive" => query.case_sensitive = true,
+                            "WholeWord" => query.whole_word = true,
+                            "RegExp" => query.is_regex = true,
+                            "IgnoredFiles" => query.include_ignored = true,
+                            _ => {}
+                        }
+                    }
+                }
+                "Including" => query.include = value.trim().to_string(),
+                "Excluding" => query.exclude = value.trim().to_string(),
+                "Context Lines" => {
+                    query.context_lines = value.trim().parse().unwrap_or(0)
+                }
+                _ => {}
+            }
+        }
+        (!query.pattern.is_empty()).then_some(query)
+    }
+
+    pub fn filter(&self) -> SearchFilter {
+        let globs = |globs: &str| {
+            globs
+                .split(',')
+                .map(|glob| glob.trim().to_string())
+                .filter(|glob| !glob.is_empty())
+                .collect()
+        };
+        SearchFilter {
+            include: globs(&self.include),
+            exclude: globs(&self.exclude),
+            include_ignored: self.include_ignored,
+            files: None,
+        }
+    }
+
+    /// The regex finding the matches of the query in the result lines
+    fn matcher(&self) -> Option<Regex> {
+        let pattern = if self.is_regex {
+            self.pattern.clone()
+        } else {
+            regex::escape(&self.pattern)
+        };
+        let pattern = if self.whole_word {
+            format!(r"\b(?:{pattern})\b")
+        } else {
+            pattern
+        };
+        RegexBuilder::new(&pattern)
+            .case_insensitive(!self.case_sensitive)
+            .build()
+            .ok()
+    }
+}
+
+/// Escape the pattern so that it fits on the line of the header
+fn escape(pattern: &str) -> String {
+    pattern.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn unescape(pattern: &str) -> String {
+    let mut unescaped = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+/// The summary of the results, which follows the header
+pub fn results_summary(count: usize, files: usize, limit_hit: bool) -> String {
+    let mut summary = format!(
+        "{count} {} - {files} {}",
+        if count == 1 { "result" } else { "results" },
+        if files == 1 { "file" } else { "files" },
+    );
+    if limit_hit {
+        summary.push_str(", stopped at the result limit");
+    }
+    summary
+}
+
+/// The document of a search editor: the header of the query, the summary, and
+/// the matches grouped by file with their context lines. Match lines have a
+/// `:` after their line number, and `...` separates lines which aren't
+/// contiguous.
+pub fn search_editor_content(
+    query: &SearchEditorQuery,
+    summary: &str,
+    results: &IndexMap<PathBuf, Vec<SearchMatch>>,
+    workspace: Option<&Path>,
+) -> String {
+    let mut content = query.header();
+    content.push('\n');
+    content.push_str(summary);
+    content.push('\n');
+
+    for (path, matches) in results {
+        let path = workspace
+            .and_then(|workspace| path.strip_prefix(workspace).ok())
+            .unwrap_or(path);
+        content.push('\n');
+        content.push_str(&path.to_string_lossy());
+        content.push_str(":\n");
+
+        // The lines of the file with whether they are a match
+        let mut lines: BTreeMap<usize, (bool, &str)> = BTreeMap::new();
+        for m in matches {
+            let before = m.line.saturating_sub(m.context_before.len());
+            for (i, line) in m.context_before.iter().enumerate() {
+                lines.entry(before + i).or_insert((false, line));
+            }
+            lines.insert(
+                m.line,
+                (true, m.line_content.trim_end_matches(['\n', '\r'])),
+            );
+            for (i, line) in m.context_after.iter().enumerate() {
+                lines.entry(m.line + 1 + i).or_insert((false, line));
+            }
+        }
+
+        let width = lines
+            .keys()
+            .next_back()
+            .map(|line| line.to_string().len())
+            .unwrap_or(1);
+        let mut previous = None;
+        for (line, (is_match, text)) in lines {
+            if previous.is_some_and(|previous| previous + 1 != line) {
+                content.push_str("  ...\n");
+            }
+            let separator = if is_match { ':' } else { ' ' };
+            content.push_str(&format!("  {line:>width$}{separator} {text}\n"));
+            previous = Some(line);
+        }
+    }
+    content
+}
+
+/// The file, relative to the workspace unless outside of it, and the line
+/// number of the match or context line at the line of a search editor document
+pub fn result_location(text: &str, line: usize) -> Option<(PathBuf, usize)> {
+    let lines: Vec<&str> = text.lines().collect();
+    let number = result_line_number(lines.get(line)?)?;
+    let file = lines[..line]
+        .iter()
+        .rev()
+        .find(|line| !line.starts_with(' ') && line.ends_with(':'))?;
+    Some((PathBuf::from(file.strip_suffix(':')?), number))
+}
+
+/// The highlights of a search editor document, which has no tree-sitter
+/// grammar: the header and the summary are comments, the file names, the line
+/// numbers and the matches of the query are highlighted, and the lines of each
+/// file are highlighted as code of the file's language
+pub fn search_editor_styles(
+    text: &str,
+    language: impl Fn(&Path) -> LapceLanguage,
+) -> Vec<LineStyle> {
+    let matcher = SearchEditorQuery::parse(text).and_then(|query| query.matcher());
+
+    let mut styles = Vec::new();
+    // The lines of the file whose results are being read
+    let mut code: Option<CodeLines> = None;
+    let mut start = 0;
+    for line in text.split_inclusive('\n') {
+        let line_start = start;
+        start += line.len();
+        let line = line.trim_end_matches(['\n', '\r']);
+        if line.is_empty() {
+            continue;
+        }
+        if !line.starts_with(' ') {
+            let scope = if line.starts_with('#') || !line.ends_with(':') {
+                "comment"
+            } else {
+                if let Some(code) = code.take() {
+                    code.highlight(&mut styles);
+                }
+                let path = Path::new(line.trim_end_matches(':'));
+                code = Some(CodeLines::new(language(path)));
+                "text.title"
+            };
+            styles.push(line_style(line_start, line_start + line.len(), scope));
+            continue;
+        }
+        if line.trim() == "..." {
+            styles.push(line_style(line_start, line_start + line.len(), "comment"));
+            continue;
+        }
+        if result_line_number(line).is_none() {
+            continue;
+        }
+
+        let number_start = line.len() - line.trim_start().len();
+        let number_end = number_start
+            + line[number_start..]
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(line.len() - number_start);
+        styles.push(line_style(
+            line_start + number_start,
+            line_start + number_end,
+            "number",
+        ));
+        // The text of a line follows `: ` for a match, or two spaces
+        let text_start = (number_end + 2).min(line.len());
+        let mut matches = Vec::new();
+        if line[number_end..].starts_with(':') {
+            if let Some(matcher) = matcher.as_ref() {
+                matches.extend(
+                    matcher
+                        .find_iter(&line[text_start..])
+                        .filter(|m| !m.is_empty())
+                        .map(|m| {
+                            line_start + text_start + m.start()
+                                ..line_start + text_start + m.end()
+                        }),
+                );
+            }
+        }
+        if let Some(code) = code.as_mut() {
+            code.push(line_start + text_start, &line[text_start..], matches);
+        }
+    }
+    if let Some(code) = code {
+        code.highlight(&mut styles);
+    }
+    styles.sort_by_key(|style| style.start);
+    styles
+}
+
+fn line_style(start: usize, end: usize, scope: &str) -> LineStyle {
+    LineStyle {
+        start,
+        end,
+        style: Style {
+            fg_color: Some(scope.to_string()),
+        },
+    }
+}
+
+/// The lines of a file in a search editor document, parsed together to be
+/// highlighted with the file's grammar
+struct CodeLines {
+    language: LapceLanguage,
+    text: String,
+    /// The offset of each line in `text` and in the document, its length and
+    /// the matches of the query in it
+    lines: Vec<(usize, usize, usize, Vec<Range<usize>>)>,
+}
+
+impl CodeLines {
+    fn new(language: LapceLanguage) -> Self {
+        Self {
+            language,
+            text: String::new(),
+            lines: Vec::new(),
+        }
+    }
+
+    fn push(&mut self, offset: usize, line: &str, matches: Vec<Range<usize>>) {
+        self.lines
+            .push((self.text.len(), offset, line.len(), matches));
+        self.text.push_str(line);
+        self.text.push('\n');
+    }
+
+    /// Add the highlights of the code, and of the matches which take
+    /// precedence over them
+    fn highlight(self, styles: &mut Vec<LineStyle>) {
+        let mut syntax = Syntax::from_language(self.language);
+        syntax.parse(0, Rope::from(&self.text), None);
+
+        let mut spans = syntax
+            .styles
+            .iter()
+            .flat_map(|spans| spans.iter())
+            .peekable();
+        for (start, offset, len, matches) in self.lines {
+            let end = start + len;
+            while let Some((interval, style)) = spans.peek() {
+                if interval.start >= end {
+                    break;
+                }
+                let (span_start, span_end) =
+                    (interval.start.max(start), interval.end.min(end));
+                if span_start < span_end {
+                    if let Some(scope) = style.fg_color.as_deref() {
+                        push_outside(
+                            styles,
+                            offset + span_start - start..offset + span_end - start,
+                            &matches,
+                            scope,
+                        );
+                    }
+                }
+                // A span reaching into the next line is kept for it
+                if interval.end > end {
+                    break;
+                }
+                spans.next();
+            }
+            for m in matches {
+                styles.push(line_style(m.start, m.end, "keyword"));
+            }
+        }
+    }
+}
+
+/// Add the style of the range, except for where it overlaps the matches
+fn push_outside(
+    styles: &mut Vec<LineStyle>,
+    range: Range<usize>,
+    matches: &[Range<usize>],
+    scope: &str,
+) {
+    let mut start = range.start;
+    for m in matches {
+        if m.end <= start || m.start >= range.end {
+            continue;
+        }
+        if m.start > start {
+            styles.push(line_style(start, m.start, scope));
+        }
+        start = m.end;
+    }
+    if start < range.end {
+        styles.push(line_style(start, range.end, scope));
+    }
+}
+
+/// The line number of a match or context line, e.g. `  12: foo` or `  11  bar`
+fn result_line_number(line: &str) -> Option<usize> {
+    let line = line.strip_prefix("  ")?.trim_start();
+    let end = line
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(line.len());
+    let (number, rest) = line.split_at(end);
+    if !(rest.is_empty() || rest.starts_with(':') || rest.starts_with(' ')) {
+        return None;
+    }
+    number.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use indexmap::IndexMap;
+    use lapce_core::language::LapceLanguage;
+    use lapce_rpc::proxy::SearchMatch;
+
+    use super::{
+        SearchEditorQuery, result_location, search_editor_content,
+        search_editor_styles,
+    };
+
+    #[test]
+    fn test_query_header() {
+        let query = SearchEditorQuery {
+            pattern: "fn \\w+\nfoo".to_string(),
+            case_sensitive: true,
+            is_regex: true,
+            include: "src, *.rs".to_string(),
+            context_lines: 2,
+            ..Default::default()
+        };
+        assert_eq!(SearchEditorQuery::parse(&query.header()), Some(query));
+        assert_eq!(SearchEditorQuery::parse("# Query: \n"), None);
+    }
+
+    #[test]
+    fn test_search_editor_content() {
+        let query = SearchEditorQuery {
+            pattern: "foo".to_string(),
+            context_lines: 1,
+            ..Default::default()
+        };
+        let mut results = IndexMap::new();
+        results.insert(
+            PathBuf::from("/ws/src/main.rs"),
+            vec![
+                SearchMatch {
+                    line: 9,
+                    start: 4,
+                    end: 7,
+                    line_content: "    foo();\n".to_string(),
+                    content_start: 0,
+                    context_before: vec!["fn main() {".to_string()],
+                    context_after: vec!["    bar();".to_string()],
+                },
+                SearchMatch {
+                    line: 20,
+                    start: 0,
+                    end: 3,
+                    line_content: "foo\n".to_string(),
+                    content_start: 0,
+                    context_before: vec!["".to_string()],
+                    context_after: vec![],
+                },
+            ],
+        );
+        let content = search_editor_content(
+            &query,
+            "2 results - 1 file",
+            &results,
+            Some(Path::new("/ws")),
+        );
+        let expected = format!(
+            "{}\n2 results - 1 file\n\nsrc/main.rs:\n   8  fn main() {{\n   9:     foo();\n  10      bar();\n  ...\n  19  \n  20: foo\n",
+            query.header()
+        );
+        assert_eq!(content, expected);
+
+        let line = content
+            .lines()
+            .position(|line| line == "  20: foo")
+            .unwrap();
+        assert_eq!(
+            result_location(&content, line),
+            Some((PathBuf::from("src/main.rs"), 20))
+        );
+        assert_eq!(
+            result_location(&content, line - 3),
+            Some((PathBuf::from("src/main.rs"), 10))
+        );
+        assert_eq!(result_location(&content, line - 2), None);
+        assert_eq!(result_location(&content, 0), None);
+    }
+
+    #[test]
+    fn test_search_editor_styles() {
+        let query = SearchEditorQuery {
+            pattern: "foo".to_string(),
+            ..Default::default()
+        };
+        let content = format!(
+            "{}\n1 result - 1 file\n\nsrc/main.rs:\n   9: Foo(foo)\n  ...\n  12  foo\n",
+            query.header()
+        );
+        let styles = search_editor_styles(&content, LapceLanguage::from_path);
+        let styles: Vec<(&str, &str)> = styles
+            .iter()
+            .map(|style| {
+                (
+                    &content[style.start..style.end],
+                    style.style.fg_color.as_deref().unwrap(),
+                )
+            })
+            .collect();
+        let header = query.header();
+        let header: Vec<(&str, &str)> =
+            header.lines().map(|line| (line, "comment")).collect();
+        assert_eq!(styles[..header.len()], header[..]);
+        assert_eq!(
+            styles[header.len()..],
+            [
+                ("1 result - 1 file", "comment"),
+                ("src/main.rs:", "text.title"),
+                ("9", "number"),
+                ("Foo", "keyword"),
+                ("foo", "keyword"),
+                ("  ...", "comment"),
+                ("12", "number"),
+            ]
+        );
+    }
+}
@@ -20,7 +20,7 @@
 };
 use indexmap::IndexMap;
 use inflector::Inflector;
-use lapce_core::{buffer::rope_text::RopeText, mode::Mode};
+use lapce_core::{buffer::rope_text::RopeText, language::LapceLanguage, mode::Mode};
 use lapce_rpc::plugin::VoltID;
 use lapce_xi_rope::Rope;
 use serde::Serialize;
@@ -82,6 +82,10 @@ struct SettingsItem {
     header: bool,
 }
 
+/// The kind of the dropdown which picks the language of the editor settings
+/// overrides
+const EDITOR_LANGUAGE_KIND: &str = "editor-language";
+
 #[derive(Clone, Debug)]
 struct SettingsData {
     items: RwSignal<im::Vector<SettingsItem>>,
@@ -89,6 +93,8 @@ struct SettingsData {
     plugin_items: RwSignal<im::Vector<SettingsItem>>,
     plugin_kinds: RwSignal<im::Vector<(String, RwSignal<Point>)>>,
     filtered_items: RwSignal<im::Vector<SettingsItem>>,
+    /// The language whose editor settings overrides are shown
+    editor_language: RwSignal<String>,
     common: Rc<CommonData>,
 }
 
@@ -150,33 +156,52 @@ fn into_settings_map(
         let filtered_items = cx.create_rw_signal(im::Vector::new());
         let items = cx.create_rw_signal(im::Vector::new());
         let kinds = cx.create_rw_signal(im::Vector::new());
+        let editor_language =
+            cx.create_rw_signal(LapceLanguage::PlainText.name().to_string());
         cx.create_effect(move |_| {
             let config = config.get();
+            let language_name = editor_language.get();
+            let language =
+                LapceLanguage::from_name(&language_name).unwrap_or_default();
 
             let mut data_items = im::Vector::new();
             let mut data_kinds = im::Vector::new();
             let mut item_height_accum = 0.0;
-            for (kind, fields, descs, mut settings_map) in [
+            for (kind, key, fields, descs, mut settings_map) in [
                 (
                     "Core",
+                    "core".to_string(),
                     &CoreConfig::FIELDS[..],
                     &CoreConfig::DESCS[..],
                     into_settings_map(&config.core),
                 ),
                 (
                     "Editor",
+                    "editor".to_string(),
                     &EditorConfig::FIELDS[..],
                     &EditorConfig::DESCS[..],
                     into_settings_map(&config.editor),
                 ),
+                (
+                    "Editor Language",
+                    format!(
+                        "editor.language.{}",
+                        EditorConfig::language_key(language)
+                    ),
+                    &EditorConfig::FIELDS[..],
+                    &EditorConfig::DESCS[..],
+                    into_settings_map(config.editor.for_language(language)),
+                ),
                 (
                     "UI",
+                    "ui".to_string(),
                     &UIConfig::FIELDS[..],
                     &UIConfig::DESCS[..],
                     into_settings_map(&config.ui),
                 ),
                 (
                     "Terminal",
+                    "terminal".to_string(),
                     &TerminalConfig::FIELDS[..],
                     &TerminalConfig::DESCS[..],
                     into_settings_map(&config.terminal),
@@ -196,11 +221,45 @@ fn into_settings_map(
                     header: true,
                 });
                 data_kinds.push_back((kind.to_string(), pos));
+                if key.starts_with("editor.language.") {
+                    let languages: im::Vector<String> = LapceLanguage::languages()
+                        .into_iter()
+                        .map(|l| l.to_string())
+                        .collect();
+                    let name = format!("{kind}: Language");
+                    let desc =
+                        "The language whose editor settings are overridden below";
+                    data_items.push_back(SettingsItem {
+                        kind: EDITOR_LANGUAGE_KIND.to_string(),
+                        filter_text: format!("{kind} {name} {desc}").to_lowercase(),
+                        name,
+                        field: "language".to_string(),
+                        description: desc.to_string(),
+                        value: SettingsValue::Dropdown(DropdownInfo {
+                            active_index: languages
+                                .iter()
+                                .position(|l| *l == language.name())
+                                .unwrap_or(0),
+                            items: languages,
+                        }),
+                        serde_value: Value::String(language_name.clone()),
+                        pos: cx.create_rw_signal(Point::ZERO),
+                        size: cx.create_rw_signal(Size::ZERO),
+                        header: false,
+                    });
+                    item_height_accum += 50.0;
+                }
+                let language_fields = key.starts_with("editor.language.");
                 for (name, desc) in fields.iter().zip(descs.iter()) {
+                    if language_fields
+                        && !EditorConfig::LANGUAGE_FIELDS.contains(name)
+                    {
+                        continue;
+                    }
                     let field = name.replace('_', "-");
 
                     let (value, serde_value) = if let Some(dropdown) =
-                        config.get_dropdown_info(&kind.to_lowercase(), &field)
+                        config.get_dropdown_info(&key, &field)
                     {
                         let index = dropdown.active_index;
                         (
@@ -216,7 +275,7 @@ fn into_settings_map(
                         "{kind}: {}",
                         name.replace('_', " ").to_title_case()
                     );
-                    let kind = kind.to_lowercase();
+                    let kind = key.clone();
                     let filter_text = format!("{kind} {name} {desc}").to_lowercase();
                     let filter_text =
                         format!("{filter_text}{}", filter_text.replace(' ', ""));
@@ -317,6 +376,7 @@ fn into_settings_map(
             plugin_kinds,
             items,
             kinds,
+            editor_language,
             common,
         }
     }
@@ -661,6 +721,7 @@ fn settings_item_view(
                     current_value,
                     dropdown,
                     expanded,
+                    settings_data.editor_language,
                     settings_data.common.window_common.size,
                     config,
                 )
@@ -1183,6 +1244,7 @@ fn dropdown_view(
     current_value: RwSignal<String>,
     dropdown: &DropdownInfo,
     expanded: RwSignal<bool>,
+    editor_language: RwSignal<String>,
     window_size: RwSignal<Size>,
     config: ReadSignal<Arc<LapceConfig>>,
 ) -> impl View + use<> {
@@ -1205,6 +1267,7 @@ fn dropdown_view(
                         current_value,
                         &dropdown.clone(),
                         expanded,
+                        editor_language,
                         dropdown_scroll_focus,
                         dropdown_input_focus,
                         window_origin,
@@ -1291,6 +1354,7 @@ fn dropdown_scroll(
     current_value: RwSignal<String>,
     dropdown: &DropdownInfo,
     expanded: RwSignal<bool>,
+    editor_language: RwSignal<String>,
     dropdown_scroll_focus: RwSignal<bool>,
     dropdown_input_focus: RwSignal<bool>,
     window_origin: RwSignal<Point>,
@@ -1309,7 +1373,9 @@ fn dropdown_scroll(
         label(move || local_item_string.clone())
             .on_click_stop(move |_| {
                 current_value.set(item_string.clone());
-                if let Ok(value) = serde::Serialize::serialize(
+                if kind == EDITOR_LANGUAGE_KIND {
+                    editor_language.set(item_string.clone());
+                } else if let Ok(value) = serde::Serialize::serialize(
                     &item_string,
                     toml_edit::ser::ValueSerializer::new(),
                 ) {
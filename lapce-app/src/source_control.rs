@@ -1,28 +1,70 @@
-use std::{path::PathBuf, rc::Rc};
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use floem::{
+    action::{TimerToken, exec_after},
+    ext_event::create_ext_action,
     keyboard::Modifiers,
-    reactive::{RwSignal, Scope, SignalWith},
+    reactive::{RwSignal, Scope, SignalGet, SignalUpdate, SignalWith},
 };
 use indexmap::IndexMap;
 use lapce_core::mode::Mode;
-use lapce_rpc::source_control::FileDiff;
+use lapce_rpc::{
+    proxy::ProxyResponse,
+    source_control::{BlameHunk, DiffInfo, FileDiff, RebaseAction},
+};
+use lapce_xi_rope::Rope;
 
 use crate::{
     command::{CommandExecuted, CommandKind},
+    config::core::CoreConfig,
     editor::EditorData,
     keypress::{KeyPressFocus, condition::Condition},
     main_split::Editors,
     window_tab::CommonData,
 };
 
+/// How long the working copy has to stay unchanged before the files differing
+/// from the compared revision are listed again
+const COMPARE_REFRESH_DELAY: Duration = Duration::from_millis(500);
+
 #[derive(Clone, Debug)]
 pub struct SourceControlData {
     // VCS modified files & whether they should be included in the next commit
     pub file_diffs: RwSignal<IndexMap<PathBuf, (FileDiff, bool)>>,
+    /// The unmerged paths left by a merge, rebase or pull
+    pub conflicts: RwSignal<im::Vector<PathBuf>>,
     pub branch: RwSignal<String>,
     pub branches: RwSignal<im::Vector<String>>,
     pub tags: RwSignal<im::Vector<String>>,
+    /// The status of the workspace's repository followed by its submodules
+    pub repositories: RwSignal<im::Vector<DiffInfo>>,
+    /// The root of the repository the panel commits to and checks out, the
+    /// workspace's if `None`
+    pub repository: RwSignal<Option<PathBuf>>,
+    /// Whether the next commit replaces the last one
+    pub amend: RwSignal<bool>,
+    /// Whether the next commit gets a `Signed-off-by` trailer
+    pub sign_off: RwSignal<bool>,
+    /// The content of `commit.template`, which the commit message starts with
+    pub template: RwSignal<Option<String>>,
+    /// The message of the commit being amended
+    amend_message: RwSignal<Option<String>>,
+    /// The revision the workspace is compared with
+    pub compare_base: RwSignal<Option<String>>,
+    /// The files which differ from the compared revision
+    pub compare_diffs: RwSignal<im::Vector<FileDiff>>,
+    /// The pending refresh of `compare_diffs` after the working copy changed
+    compare_timer: RwSignal<TimerToken>,
+    /// The revision a prepared rebase is onto, and its todo list
+    pub rebase_todo: RwSignal<Option<(String, PathBuf)>>,
+    /// Whether a rebase stopped at a conflict or an edited commit
+    pub rebasing: RwSignal<bool>,
+    /// The commit a rebase stopped at for its message to be reworded
+    rewording: RwSignal<Option<String>>,
     pub editor: EditorData,
     pub common: Rc<CommonData>,
 }
@@ -62,43 +104,568 @@ fn receive_char(&self, c: &str) {
 
 impl SourceControlData {
     pub fn new(cx: Scope, editors: Editors, common: Rc<CommonData>) -> Self {
-        Self {
+        let data = Self {
             file_diffs: cx.create_rw_signal(IndexMap::new()),
+            conflicts: cx.create_rw_signal(im::Vector::new()),
             branch: cx.create_rw_signal("".to_string()),
             branches: cx.create_rw_signal(im::Vector::new()),
             tags: cx.create_rw_signal(im::Vector::new()),
+            repositories: cx.create_rw_signal(im::Vector::new()),
+            repository: cx.create_rw_signal(None),
+            amend: cx.create_rw_signal(false),
+            sign_off: cx.create_rw_signal(
+                common.config.get_untracked().core.commit_sign_off,
+            ),
+            template: cx.create_rw_signal(None),
+            amend_message: cx.create_rw_signal(None),
+            compare_base: cx.create_rw_signal(None),
+            compare_diffs: cx.create_rw_signal(im::Vector::new()),
+            compare_timer: cx.create_rw_signal(TimerToken::INVALID),
+            rebase_todo: cx.create_rw_signal(None),
+            rebasing: cx.create_rw_signal(false),
+            rewording: cx.create_rw_signal(None),
             editor: editors.make_local(cx, common.clone()),
             common,
+        };
+        data.load_template();
+        data
+    }
+
+    /// The commit message in the commit box
+    pub fn message(&self) -> String {
+        self.editor
+            .doc()
+            .buffer
+            .with_untracked(|buffer| buffer.to_string())
+    }
+
+    fn set_message(&self, message: &str) {
+        self.editor.doc().reload(Rope::from(message), true);
+        self.editor
+            .cursor()
+            .update(|cursor| cursor.set_offset(0, false, false));
+    }
+
+    /// Seed the commit box with the content of `commit.template`
+    fn load_template(&self) {
+        let data = self.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            if let Ok(ProxyResponse::GitCommitTemplateResponse { template }) = result
+            {
+                if let Some(template) = template.as_ref() {
+                    if data.message().is_empty() {
+                        data.set_message(template);
+                    }
+                }
+                data.template.set(template);
+            }
+        });
+        self.common.proxy.git_commit_template(move |result| {
+            send(result);
+        });
+    }
+
+    /// Toggle amending the last commit, which fills the commit box with its
+    /// message if the message hasn't been written yet
+    pub fn toggle_amend(&self) {
+        let amend = !self.amend.get_untracked();
+        self.amend.set(amend);
+
+        let message = self.message();
+        let template = self.template.get_untracked().unwrap_or_default();
+        if !amend {
+            if self.amend_message.get_untracked().as_ref() == Some(&message) {
+                self.set_message(&template);
+            }
+            self.amend_message.set(None);
+            return;
         }
+
+        let data = self.clone();
+        let send = create_ext_action(self.common.scope, move |result| {
+            if let Ok(ProxyResponse::GitLastCommitMessageResponse { message }) =
+                result
+            {
+                if !data.amend.get_untracked() {
+                    return;
+                }
+                let current = data.message();
+                if current.trim().is_empty() || current == template {
+                    data.set_message(&message);
+                    data.amend_message.set(Some(message));
+                }
+            }
+        });
+        self.common.proxy.git_last_commit_message(
+            self.repository.get_untracked(),
+            move |result| {
+                send(result);
+            },
+        );
     }
 
     pub fn commit(&self) {
+        // Only the changes of the selected repository are committed to it
+        let Some(repository) = self.selected_repository() else {
+            return;
+        };
         let diffs: Vec<FileDiff> = self.file_diffs.with_untracked(|file_diffs| {
-            file_diffs
+            repository
+                .diffs
                 .iter()
-                .filter_map(
-                    |(_, (diff, checked))| {
-                        if *checked { Some(diff) } else { None }
-                    },
-                )
-                .cloned()
+                .filter_map(|diff| match file_diffs.get(diff.path()) {
+                    Some((diff, true)) => Some(diff.clone()),
+                    _ => None,
+                })
                 .collect()
         });
-        if diffs.is_empty() {
+        let amend = self.amend.get_untracked();
+        // Amending can change only the message of the last commit
+        if diffs.is_empty() && !amend {
             return;
         }
 
-        let message = self
-            .editor
-            .doc()
-            .buffer
-            .with_untracked(|buffer| buffer.to_string());
+        let message = self.message();
         let message = message.trim();
         if message.is_empty() {
             return;
         }
 
-        self.editor.reset();
-        self.common.proxy.git_commit(message.to_string(), diffs);
+        self.common.proxy.git_commit(
+            message.to_string(),
+            diffs,
+            amend,
+            self.sign_off.get_untracked(),
+            self.repository.get_untracked(),
+        );
+        self.amend.set(false);
+        self.amend_message.set(None);
+        self.set_message(&self.template.get_untracked().unwrap_or_default());
+    }
+
+    /// Update the status of the workspace's repository and its submodules
+    pub fn set_diff_info(&self, diff: DiffInfo) {
+        let repositories = flatten_repositories(diff);
+
+        // The submodule may have been removed
+        let repository = self.repository.get_untracked();
+        if repository.as_ref().is_some_and(|repository| {
+            !repositories.iter().any(|diff| &diff.root == repository)
+        }) {
+            self.repository.set(None);
+        }
+
+        self.repositories.set(repositories);
+        self.show_repository();
+    }
+
+    /// Select the repository which is then committed to and checked out
+    pub fn select_repository(&self, root: PathBuf) {
+        let is_workspace = self
+            .repositories
+            .with_untracked(|repositories| {
+                repositories.front().map(|diff| diff.root == root)
+            })
+            .unwrap_or(true);
+        self.repository.set((!is_workspace).then_some(root));
+        self.show_repository();
+    }
+
+    /// The status of the repository which is committed to and checked out
+    fn selected_repository(&self) -> Option<DiffInfo> {
+        let repository = self.repository.get_untracked();
+        self.repositories.with_untracked(|repositories| {
+            match repository.as_ref() {
+                Some(root) => repositories.iter().find(|diff| &diff.root == root),
+                None => repositories.front(),
+            }
+            .cloned()
+        })
+    }
+
+    /// Show the changes of all the repositories, grouped by repository, and
+    /// the references and conflicts of the selected one
+    fn show_repository(&self) {
+        let Some(diff) = self.selected_repository() else {
+            return;
+        };
+
+        self.branch.set(diff.head.clone());
+        self.branches.set(diff.branches.iter().cloned().collect());
+        self.tags.set(diff.tags.iter().cloned().collect());
+        let repositories = self.repositories.get_untracked();
+        self.file_diffs.update(|file_diffs| {
+            *file_diffs = repositories
+                .iter()
+                .flat_map(|repository| repository.diffs.iter().cloned())
+                .map(|diff| {
+                    let checked =
+                        file_diffs.get(diff.path()).is_none_or(|(_, c)| *c);
+                    (diff.path().clone(), (diff, checked))
+                })
+                .collect();
+        });
+        self.conflicts.set(diff.conflicts.iter().cloned().collect());
+        self.rebasing.set(diff.rebasing);
+
+        // The reworded commit is amended, with its message in the commit box
+        if diff.rewording != self.rewording.get_untracked() {
+            let rewording = diff.rewording.is_some();
+            self.rewording.set(diff.rewording);
+            if rewording && !self.amend.get_untracked() {
+                self.toggle_amend();
+            }
+        }
+    }
+
+    /// List the files which differ from the revision, or stop comparing if
+    /// `None`
+    pub fn compare_with_revision(&self, revision: Option<String>) {
+        self.compare_base.set(revision);
+        self.refresh_compare();
+    }
+
+    /// Update the files which differ from the compared revision once the
+    /// working copy stops changing, as every change of it is notified
+    pub fn refresh_compare_later(&self) {
+        if self.compare_base.with_untracked(Option::is_none) {
+            return;
+        }
+        let data = self.clone();
+        let compare_timer = self.compare_timer;
+        let token = exec_after(COMPARE_REFRESH_DELAY, move |token| {
+            if compare_timer.try_get_untracked() == Some(token) {
+                data.refresh_compare();
+            }
+        });
+        self.compare_timer.set(token);
+    }
+
+    /// Update the files which differ from the compared revision
+    pub fn refresh_compare(&self) {
+        let Some(revision) = self.compare_base.get_untracked() else {
+            self.compare_diffs.set(im::Vector::new());
+            return;
+        };
+
+        let compare_base = self.compare_base;
+        let compare_diffs = self.compare_diffs;
+        let send = {
+            let revision = revision.clone();
+            create_ext_action(self.common.scope, move |result| {
+                if compare_base.get_untracked().as_ref() != Some(&revision) {
+                    return;
+                }
+                if let Ok(ProxyResponse::GitDiffRevisionResponse { diffs }) = result
+                {
+                    compare_diffs.set(diffs.into());
+                }
+            })
+        };
+        self.common
+            .proxy
+            .git_diff_revision(revision, move |result| {
+                send(result);
+            });
+    }
+
+    /// Stage the unmerged file, which the proxy only does once no conflict
+    /// markers remain in it
+    pub fn mark_resolved(&self, path: PathBuf) {
+        // The conflicts listed are the selected repository's
+        self.common
+            .proxy
+            .git_mark_resolved(path, self.repository.get_untracked());
+    }
+
+    /// The root of the repository the changed file is listed in, `None` if
+    /// it's the workspace's
+    pub fn repository_of(&self, path: &Path) -> Option<PathBuf> {
+        self.repositories
+            .with_untracked(|repositories| repository_of(repositories, path))
+    }
+
+    /// Continue, skip or abort the rebase which stopped
+    pub fn rebase_action(&self, action: RebaseAction) {
+        self.common
+            .proxy
+            .git_rebase_action(action, self.repository.get_untracked());
+    }
+}
+
+/// The rules commit messages can be checked against, enabled by the
+/// `commit-lint-rules` setting
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommitLintRule {
+    SubjectLength,
+    BodyLineLength,
+    BlankLineAfterSubject,
+    SubjectTrailingPeriod,
+    SubjectCapitalized,
+}
+
+impl CommitLintRule {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "subject-length" => Some(CommitLintRule::SubjectLength),
+            "body-line-length" => Some(CommitLintRule::BodyLineLength),
+            "blank-line-after-subject" => {
+                Some(CommitLintRule::BlankLineAfterSubject)
+            }
+            "subject-trailing-period" => Some(CommitLintRule::SubjectTrailingPeriod),
+            "subject-capitalized" => Some(CommitLintRule::SubjectCapitalized),
+            _ => None,
+        }
+    }
+}
+
+/// The warnings for the commit message from the enabled lint rules. Comment
+/// lines are ignored since they're stripped from the commit.
+/// The status of the repository followed by its submodules, depth first, each
+/// listing only its own changes
+fn flatten_repositories(diff: DiffInfo) -> im::Vector<DiffInfo> {
+    let mut repositories = im::Vector::new();
+    let mut pending = vec![diff];
+    while let Some(mut diff) = pending.pop() {
+        pending.extend(std::mem::take(&mut diff.submodules).into_iter().rev());
+        repositories.push_back(diff);
+    }
+    repositories
+}
+
+/// The root of the submodule which lists the changed file, `None` if it's the
+/// first repository, the workspace's
+fn repository_of(
+    repositories: &im::Vector<DiffInfo>,
+    path: &Path,
+) -> Option<PathBuf> {
+    repositories
+        .iter()
+        .skip(1)
+        .find(|repository| repository.diffs.iter().any(|diff| diff.path() == path))
+        .map(|repository| repository.root.clone())
+}
+
+pub fn lint_commit_message(message: &str, config: &CoreConfig) -> Vec<String> {
+    let lines: Vec<(usize, &str)> = message
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.starts_with('#'))
+        .collect();
+    let Some((_, subject)) = lines.first().copied() else {
+        return Vec::new();
+    };
+    if subject.trim().is_empty() {
+        return Vec::new();
+    }
+
+    let mut warnings = Vec::new();
+    for rule in config
+        .commit_lint_rules
+        .iter()
+        .filter_map(|rule| CommitLintRule::from_name(rule))
+    {
+        match rule {
+            CommitLintRule::SubjectLength => {
+                let max = config.commit_subject_length;
+                if max > 0 && subject.chars().count() > max {
+                    warnings.push(format!(
+                        "The subject is longer than {max} characters"
+                    ));
+                }
+            }
+            CommitLintRule::BodyLineLength => {
+                let max = config.commit_body_line_length;
+                if let Some((i, _)) = lines
+                    .iter()
+                    .skip(1)
+                    .find(|(_, line)| max > 0 && line.chars().count() > max)
+                {
+                    warnings.push(format!(
+                        "Line {} is longer than {max} characters",
+                        i + 1
+                    ));
+                }
+            }
+            CommitLintRule::BlankLineAfterSubject => {
+                if lines
+                    .get(1)
+                    .is_some_and(|(_, line)| !line.trim().is_empty())
+                {
+                    warnings.push(
+                        "The subject should be followed by a blank line".to_string(),
+                    );
+                }
+            }
+            CommitLintRule::SubjectTrailingPeriod => {
+                if subject.trim_end().ends_with('.') {
+                    warnings
+                        .push("The subject shouldn't end with a period".to_string());
+                }
+            }
+            CommitLintRule::SubjectCapitalized => {
+                if subject.chars().next().is_some_and(|c| c.is_lowercase()) {
+                    warnings.push(
+                        "The subject should start with a capital letter".to_string(),
+                    );
+                }
+            }
+        }
+    }
+    warnings
+}
+
+/// The text of the inline blame annotation, e.g. "Jane Doe, 3 days ago • Fix typo"
+pub fn blame_annotation(hunk: &BlameHunk) -> String {
+    match &hunk.commit {
+        Some(commit) => format!(
+            "{}, {} • {}",
+            commit.author,
+            relative_time(commit.time),
+            commit.summary
+        ),
+        None => "You, Uncommitted changes".to_string(),
+    }
+}
+
+/// The text shown in the blame gutter for the first line of a hunk
+pub fn blame_gutter_text(hunk: &BlameHunk) -> String {
+    match &hunk.commit {
+        Some(commit) => format!("{} {}", format_time(commit.time), commit.author),
+        None => "Not Committed Yet".to_string(),
+    }
+}
+
+/// The markdown shown when hovering the inline blame annotation
+pub fn blame_hover_markdown(hunk: &BlameHunk) -> Option<String> {
+    let commit = hunk.commit.as_ref()?;
+    Some(format!(
+        "**{}** <{}>, {} ({})\n\n`{}`\n\n{}",
+        commit.author,
+        commit.email,
+        format_time(commit.time),
+        relative_time(commit.time),
+        &commit.id[..commit.id.len().min(8)],
+        commit.message.trim(),
+    ))
+}
+
+fn format_time(time: i64) -> String {
+    chrono::DateTime::from_timestamp(time, 0)
+        .map(|time| {
+            time.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d")
+                .to_string()
+        })
+        .unwrap_or_default()
+}
+
+pub fn relative_time(time: i64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(time);
+    let (n, unit) = match (now - time).max(0) {
+        secs if secs < 60 => return "just now".to_string(),
+        secs if secs < 60 * 60 => (secs / 60, "minute"),
+        secs if secs < 60 * 60 * 24 => (secs / (60 * 60), "hour"),
+        secs if secs < 60 * 60 * 24 * 7 => (secs / (60 * 60 * 24), "day"),
+        secs if secs < 60 * 60 * 24 * 30 => (secs / (60 * 60 * 24 * 7), "week"),
+        secs if secs < 60 * 60 * 24 * 365 => (secs / (60 * 60 * 24 * 30), "month"),
+        secs => (secs / (60 * 60 * 24 * 365), "year"),
+    };
+    format!("{n} {unit}{} ago", if n == 1 { "" } else { "s" })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use lapce_rpc::source_control::{DiffInfo, FileDiff};
+
+    use super::{flatten_repositories, lint_commit_message, repository_of};
+    use crate::config::core::CoreConfig;
+
+    #[test]
+    fn test_group_by_repository() {
+        let diff =
+            |root: &str, paths: &[&str], submodules: Vec<DiffInfo>| DiffInfo {
+                root: PathBuf::from(root),
+                diffs: paths
+                    .iter()
+                    .map(|path| FileDiff::Modified(Path::new(root).join(path)))
+                    .collect(),
+                submodules,
+                ..Default::default()
+            };
+        let repositories = flatten_repositories(diff(
+            "/ws",
+            &["README.md", "lib"],
+            vec![
+                diff(
+                    "/ws/lib",
+                    &["src/lib.rs"],
+                    vec![diff("/ws/lib/vendor", &["a.c"], Vec::new())],
+                ),
+                diff("/ws/docs", &["index.md"], Vec::new()),
+            ],
+        ));
+
+        let roots: Vec<&Path> =
+            repositories.iter().map(|r| r.root.as_path()).collect();
+        assert_eq!(
+            roots,
+            ["/ws", "/ws/lib", "/ws/lib/vendor", "/ws/docs"].map(Path::new)
+        );
+        assert!(repositories.iter().all(|r| r.submodules.is_empty()));
+
+        let repository = |path: &str| repository_of(&repositories, Path::new(path));
+        assert_eq!(repository("/ws/README.md"), None);
+        // The submodule's folder is a change of the superproject
+        assert_eq!(repository("/ws/lib"), None);
+        assert_eq!(
+            repository("/ws/lib/src/lib.rs"),
+            Some(PathBuf::from("/ws/lib"))
+        );
+        assert_eq!(
+            repository("/ws/lib/vendor/a.c"),
+            Some(PathBuf::from("/ws/lib/vendor"))
+        );
+        assert_eq!(
+            repository("/ws/docs/index.md"),
+            Some(PathBuf::from("/ws/docs"))
+        );
+    }
+
+    #[test]
+    fn test_lint_commit_message() {
+        let config = CoreConfig {
+            commit_subject_length: 20,
+            commit_body_line_length: 10,
+            commit_lint_rules: vec![
+                "subject-length".to_string(),
+                "body-line-length".to_string(),
+                "blank-line-after-subject".to_string(),
+                "subject-trailing-period".to_string(),
+            ],
+            ..Default::default()
+        };
+
+        assert!(
+            lint_commit_message("Fix typo\n\nShort body\n# a comment", &config)
+                .is_empty()
+        );
+        assert_eq!(
+            lint_commit_message(
+                "Fix a very long typo in the docs.\nA body line too long",
+                &config
+            ),
+            vec![
+                "The subject is longer than 20 characters",
+                "Line 2 is longer than 10 characters",
+                "The subject should be followed by a blank line",
+                "The subject shouldn't end with a period",
+            ]
+        );
     }
 }
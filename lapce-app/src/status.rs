@@ -369,6 +369,17 @@ pub fn status(
                 palette_clone.run(PaletteKind::LineEnding);
             });
             let palette_clone = palette.clone();
+            let encoding_info = status_text(config, editor, move || {
+                if let Some(editor) = editor.get() {
+                    editor.doc_signal().get().encoding.get()
+                } else {
+                    String::new()
+                }
+            })
+            .on_click_stop(move |_| {
+                palette_clone.run(PaletteKind::ReopenWithEncoding);
+            });
+            let palette_clone = palette.clone();
             let language_info = status_text(config, editor, move || {
                 if let Some(editor) = editor.get() {
                     let doc = editor.doc_signal().get();
@@ -380,7 +391,7 @@ pub fn status(
             .on_click_stop(move |_| {
                 palette_clone.run(PaletteKind::Language);
             });
-            (cursor_info, line_ending_info, language_info)
+            (cursor_info, line_ending_info, encoding_info, language_info)
         })
         .style(|s| {
             s.height_pct(100.0)
@@ -25,14 +25,17 @@
         WriteSignal, use_context,
     },
     text::{Attrs, AttrsList, FamilyOwned, LineHeightValue, TextLayout},
-    views::editor::core::buffer::rope_text::RopeText,
+    views::editor::{
+        core::{buffer::rope_text::RopeText, register::Clipboard},
+        text::SystemClipboard,
+    },
 };
 use im::HashMap;
 use indexmap::IndexMap;
 use itertools::Itertools;
 use lapce_core::{
-    command::FocusCommand, cursor::CursorAffinity, directory::Directory, meta,
-    mode::Mode, register::Register,
+    command::FocusCommand, cursor::CursorAffinity, directory::Directory,
+    language::LapceLanguage, meta, mode::Mode, register::Register,
 };
 use lapce_rpc::{
     RpcError,
@@ -41,12 +44,12 @@
     file::{Naming, PathObject},
     plugin::PluginId,
     proxy::{ProxyResponse, ProxyRpcHandler, ProxyStatus},
-    source_control::FileDiff,
+    source_control::{FileDiff, RebaseAction},
     terminal::TermId,
 };
 use lsp_types::{
-    CodeActionOrCommand, CodeLens, Diagnostic, ProgressParams, ProgressToken,
-    ShowMessageParams,
+    CodeActionOrCommand, CodeLens, Diagnostic, MessageType, ProgressParams,
+    ProgressToken, ShowMessageParams,
 };
 use serde_json::Value;
 use tracing::{Level, debug, error, event};
@@ -59,6 +62,7 @@
         CommandExecuted, CommandKind, InternalCommand, LapceCommand,
         LapceWorkbenchCommand, WindowCommand,
     },
+    commit_history::CommitHistoryData,
     completion::{CompletionData, CompletionStatus},
     config::LapceConfig,
     db::LapceDb,
@@ -179,6 +183,7 @@ pub struct WindowTabData {
     pub code_action: RwSignal<CodeActionData>,
     pub code_lens: RwSignal<Option<ViewId>>,
     pub source_control: SourceControlData,
+    pub commit_history: CommitHistoryData,
     pub rename: RenameData,
     pub global_search: GlobalSearchData,
     pub call_hierarchy_data: CallHierarchyData,
@@ -336,6 +341,10 @@ pub fn new(
             config.plugins.clone(),
             term_tx.clone(),
         );
+        proxy.proxy_rpc.register_languages(config.languages.clone());
+        proxy
+            .proxy_rpc
+            .register_file_associations(config.files.associations.clone());
         let (config, set_config) = cx.create_signal(Arc::new(config));
 
         let focus = cx.create_rw_signal(Focus::Workbench);
@@ -555,6 +564,7 @@ pub fn new(
             code_action,
             code_lens: cx.create_rw_signal(None),
             source_control,
+            commit_history: CommitHistoryData::new(cx, common.clone()),
             plugin,
             rename,
             global_search,
@@ -659,7 +669,25 @@ pub fn reload_config(&self) {
                 change_plugins.push(key.clone());
             }
         }
+        let old_config = self.common.config.get_untracked();
+        if config.languages != old_config.languages {
+            self.common
+                .proxy
+                .register_languages(config.languages.clone());
+        }
+        let associations_changed =
+            config.files.associations != old_config.files.associations;
+        if associations_changed {
+            self.common
+                .proxy
+                .register_file_associations(config.files.associations.clone());
+        }
         self.set_config.set(Arc::new(config.clone()));
+        if associations_changed {
+            for doc in self.main_split.docs.get_untracked().values() {
+                doc.update_associated_language(&old_config);
+            }
+        }
         if !change_plugins.is_empty() {
             self.common
                 .proxy
@@ -1127,6 +1155,21 @@ pub fn run_workbench_command(
             PaletteSCMReferences => {
                 self.palette.run(PaletteKind::SCMReferences);
             }
+            PaletteSCMCompareActiveFile => {
+                self.palette.run(PaletteKind::SCMCompareActiveFile);
+            }
+            PaletteSCMCompareWorkspace => {
+                self.palette.run(PaletteKind::SCMCompareWorkspace);
+            }
+            PaletteSCMWorktrees => {
+                self.palette.run(PaletteKind::SCMWorktrees);
+            }
+            PaletteSCMCreateWorktree => {
+                self.palette.run(PaletteKind::SCMCreateWorktree);
+            }
+            PaletteSCMRebase => {
+                self.palette.run(PaletteKind::SCMRebase);
+            }
             ChangeColorTheme => {
                 self.palette.run(PaletteKind::ColorTheme);
             }
@@ -1139,6 +1182,45 @@ pub fn run_workbench_command(
             ChangeFileLineEnding => {
                 self.palette.run(PaletteKind::LineEnding);
             }
+            ReopenWithEncoding => {
+                self.palette.run(PaletteKind::ReopenWithEncoding);
+            }
+            SaveWithEncoding => {
+                self.palette.run(PaletteKind::SaveWithEncoding);
+            }
+            EnableLargeFileEditing => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.doc().enable_large_file_editing();
+                }
+            }
+            OpenHexEditor => {
+                if let Some(path) =
+                    self.main_split.active_editor.get_untracked().and_then(
+                        |editor| {
+                            editor
+                                .doc()
+                                .content
+                                .with_untracked(|content| content.path().cloned())
+                        },
+                    )
+                {
+                    self.main_split.open_hex_editor(path);
+                }
+            }
+            OpenMarkdownPreviewToSide => {
+                let path = self.main_split.active_editor.get_untracked().and_then(
+                    |editor| {
+                        let doc = editor.doc();
+                        if doc.language() != LapceLanguage::Markdown {
+                            return None;
+                        }
+                        doc.content.with_untracked(|content| content.path().cloned())
+                    },
+                );
+                if let Some(path) = path {
+                    self.main_split.open_markdown_preview_to_side(path);
+                }
+            }
             DiffFiles => self.palette.run(PaletteKind::DiffFiles),
 
             // ==== Running / Debugging ====
@@ -1288,6 +1370,14 @@ pub fn run_workbench_command(
             ToggleSearchVisual => {
                 self.toggle_panel_visual(PanelKind::Search);
             }
+            OpenSearchEditor => {
+                self.global_search.open_search_editor();
+            }
+            RerunSearchEditor => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    self.global_search.rerun_search_editor(editor.doc());
+                }
+            }
             FocusEditor => {
                 self.common.focus.set(Focus::Workbench);
             }
@@ -1308,7 +1398,10 @@ pub fn run_workbench_command(
             CheckoutReference => match data {
                 Some(reference) => {
                     if let Some(reference) = reference.as_str() {
-                        self.proxy.proxy_rpc.git_checkout(reference.to_string());
+                        self.proxy.proxy_rpc.git_checkout(
+                            reference.to_string(),
+                            self.source_control.repository.get_untracked(),
+                        );
                     }
                 }
                 None => error!("No ref provided"),
@@ -1317,7 +1410,14 @@ pub fn run_workbench_command(
                 self.source_control.commit();
             }
             SourceControlCopyActiveFileRemoteUrl => {
-                // TODO:
+                self.active_file_remote_url(true, |file_url| {
+                    SystemClipboard::new().put_string(file_url);
+                });
+            }
+            SourceControlCopyActiveFileRemoteBranchUrl => {
+                self.active_file_remote_url(false, |file_url| {
+                    SystemClipboard::new().put_string(file_url);
+                });
             }
             SourceControlDiscardActiveFileChanges => {
                 // TODO:
@@ -1331,12 +1431,18 @@ pub fn run_workbench_command(
                             self.common.proxy.trash_path(path, Box::new(|_| {}));
                         }
                         FileDiff::Modified(path) | FileDiff::Deleted(path) => {
-                            self.common.proxy.git_discard_files_changes(vec![path]);
+                            let repository =
+                                self.source_control.repository_of(&path);
+                            self.common
+                                .proxy
+                                .git_discard_files_changes(vec![path], repository);
                         }
                         FileDiff::Renamed(old_path, new_path) => {
+                            let repository =
+                                self.source_control.repository_of(&old_path);
                             self.common
                                 .proxy
-                                .git_discard_files_changes(vec![old_path]);
+                                .git_discard_files_changes(vec![old_path], repository);
                             self.common.proxy.trash_path(new_path, Box::new(|_| {}));
                         }
                     }
@@ -1345,6 +1451,82 @@ pub fn run_workbench_command(
             SourceControlDiscardWorkspaceChanges => {
                 // TODO:
             }
+            SourceControlShowHistory => {
+                self.commit_history.show(None);
+                self.show_panel(PanelKind::CommitHistory);
+            }
+            SourceControlShowFileHistory => {
+                let path = self
+                    .main_split
+                    .active_editor
+                    .get_untracked()
+                    .and_then(|editor| {
+                        editor
+                            .doc()
+                            .content
+                            .with_untracked(|content| content.path().cloned())
+                    });
+                if let Some(path) = path {
+                    self.commit_history.show(Some(path));
+                    self.show_panel(PanelKind::CommitHistory);
+                }
+            }
+            SourceControlToggleInlineBlame => {
+                let mut new_config = self.common.config.get_untracked();
+                let enabled = !new_config.editor.enable_inline_blame;
+                Arc::make_mut(&mut new_config).editor.enable_inline_blame = enabled;
+                self.set_config.set(new_config);
+                LapceConfig::update_file(
+                    "editor",
+                    "enable-inline-blame",
+                    toml_edit::Value::from(enabled),
+                );
+                if enabled {
+                    for (_, doc) in self.main_split.docs.get_untracked() {
+                        doc.get_blame();
+                    }
+                }
+            }
+            SourceControlStartRebase => {
+                self.common
+                    .internal_command
+                    .send(InternalCommand::StartRebase);
+            }
+            SourceControlContinueRebase => {
+                self.source_control.rebase_action(RebaseAction::Continue);
+            }
+            SourceControlSkipRebase => {
+                self.source_control.rebase_action(RebaseAction::Skip);
+            }
+            SourceControlAbortRebase => {
+                self.source_control.rebase_action(RebaseAction::Abort);
+            }
+            RebaseTodoNextAction => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.cycle_rebase_action(true);
+                }
+            }
+            RebaseTodoPreviousAction => {
+                if let Some(editor) = self.main_split.active_editor.get_untracked() {
+                    editor.cycle_rebase_action(false);
+                }
+            }
+            SourceControlToggleBlameGutter => {
+                let mut new_config = self.common.config.get_untracked();
+                let enabled = !new_config.editor.show_blame_gutter;
+                Arc::make_mut(&mut new_config).editor.show_blame_gutter = enabled;
+                self.set_config.set(new_config);
+                LapceConfig::update_file(
+                    "editor",
+                    "show-blame-gutter",
+                    toml_edit::Value::from(enabled),
+                );
+                if enabled {
+                    for (_, doc) in self.main_split.docs.get_untracked() {
+                        doc.get_blame();
+                    }
+                }
+            }
 
             // ==== UI ====
             ShowAbout => {
@@ -1448,30 +1630,11 @@ pub fn run_workbench_command(
                 }
             }
             SourceControlOpenActiveFileRemoteUrl => {
-                if let Some(editor_data) =
-                    self.main_split.active_editor.get_untracked()
-                {
-                    if let DocContent::File {path, ..} = editor_data.doc().content.get_untracked() {
-                        let offset = editor_data.cursor().with_untracked(|c| c.offset());
-                        let line = editor_data.doc()
-                            .buffer
-                            .with_untracked(|buffer| buffer.line_of_offset(offset));
-                        self.common.proxy.git_get_remote_file_url(
-                            path,
-                            create_ext_action(self.scope, move |result| {
-                                if let Ok(ProxyResponse::GitGetRemoteFileUrl {
-                                              file_url
-                                          }) = result
-                                {
-                                    if let Err(err) = open::that(format!("{}#L{}", file_url, line)) {
-                                        error!("Failed to open file in github: {}",  err);
-                                    }
-                                }
-                            }),
-                        );
-
+                self.active_file_remote_url(true, |file_url| {
+                    if let Err(err) = open::that(file_url) {
+                        error!("Failed to open remote file url: {}", err);
                     }
-                }
+                });
             }
             RevealInFileExplorer => {
                 if let Some(editor_data) =
@@ -1637,9 +1800,18 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                     None,
                 );
             }
+            InternalCommand::OpenFileAsText { path } => {
+                self.main_split.open_file_as_text(path);
+            }
             InternalCommand::OpenFileChanges { path } => {
                 self.main_split.open_file_changes(path);
             }
+            InternalCommand::OpenFileChangesAgainst { path, revision } => {
+                self.main_split.open_file_changes_against(path, revision);
+            }
+            InternalCommand::OpenCommitFileChanges { commit, diff } => {
+                self.main_split.open_commit_file_changes(commit, diff);
+            }
             InternalCommand::ReloadFileExplorer => {
                 self.file_explorer.reload();
             }
@@ -2010,6 +2182,119 @@ pub fn run_internal_command(&self, cmd: InternalCommand) {
                 left_path,
                 right_path,
             } => self.main_split.open_diff_files(left_path, right_path),
+            InternalCommand::SearchInFolder { path } => {
+                self.global_search.search_in_folder(&path);
+                self.show_panel(PanelKind::Search);
+            }
+            InternalCommand::CompareActiveFileWithRevision { revision } => {
+                let Some(editor) = self.main_split.active_editor.get_untracked()
+                else {
+                    return;
+                };
+                let doc = editor.doc();
+                let DocContent::File { path, .. } = doc.content.get_untracked()
+                else {
+                    return;
+                };
+                doc.set_diff_base(revision.clone());
+                self.main_split.open_file_changes_against(
+                    path,
+                    revision.unwrap_or_else(|| "head".to_string()),
+                );
+            }
+            InternalCommand::CreateWorktree { branch } => {
+                let kind = self.workspace.kind.clone();
+                let window_command = self.common.window_common.window_command;
+                let messages = self.messages;
+                let send =
+                    create_ext_action(self.scope, move |result| match result {
+                        Ok(ProxyResponse::GitCreateWorktreeResponse { path }) => {
+                            window_command.send(WindowCommand::SetWorkspace {
+                                workspace: LapceWorkspace {
+                                    kind,
+                                    path: Some(path),
+                                    last_open: 0,
+                                },
+                            });
+                        }
+                        Ok(_) => {}
+                        Err(err) => {
+                            messages.update(|messages| {
+                                messages.push((
+                                    "Git Worktree failure".to_string(),
+                                    ShowMessageParams {
+                                        typ: MessageType::ERROR,
+                                        message: err.message,
+                                    },
+                                ));
+                            });
+                        }
+                    });
+                self.common.proxy.git_create_worktree(
+                    branch,
+                    self.source_control.repository.get_untracked(),
+                    move |result| {
+                        send(result);
+                    },
+                );
+            }
+            InternalCommand::PrepareRebase { onto } => {
+                let rebase_todo = self.source_control.rebase_todo;
+                let internal_command = self.common.internal_command;
+                let messages = self.messages;
+                let revision = onto.clone();
+                let send =
+                    create_ext_action(self.scope, move |result| match result {
+                        Ok(ProxyResponse::GitRebaseTodoResponse { path }) => {
+                            rebase_todo.set(Some((revision, path.clone())));
+                            internal_command
+                                .send(InternalCommand::OpenFile { path });
+                        }
+                        Ok(_) => {}
+                        Err(err) => {
+                            messages.update(|messages| {
+                                messages.push((
+                                    "Git Rebase failure".to_string(),
+                                    ShowMessageParams {
+                                        typ: MessageType::ERROR,
+                                        message: err.message,
+                                    },
+                                ));
+                            });
+                        }
+                    });
+                self.common.proxy.git_rebase_todo(
+                    onto,
+                    self.source_control.repository.get_untracked(),
+                    move |result| {
+                        send(result);
+                    },
+                );
+            }
+            InternalCommand::StartRebase => {
+                let Some((onto, path)) =
+                    self.source_control.rebase_todo.get_untracked()
+                else {
+                    return;
+                };
+                // The todo list may have been edited without being saved
+                let todo =
+                    self.main_split.docs.get_untracked().get(&path).map(|doc| {
+                        doc.buffer.with_untracked(|buffer| buffer.to_string())
+                    });
+                self.common.proxy.git_rebase(
+                    onto,
+                    todo,
+                    self.source_control.repository.get_untracked(),
+                );
+                self.source_control.rebase_todo.set(None);
+            }
+            InternalCommand::CompareWorkspaceWithRevision { revision } => {
+                if revision.is_some() {
+                    self.panel.show_panel(&PanelKind::SourceControl);
+                }
+                self.source_control.compare_with_revision(revision);
+            }
             InternalCommand::ExecuteProcess { program, arguments } => {
                 let mut cmd = match std::process::Command::new(program)
                     .args(arguments)
@@ -2086,31 +2371,25 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
                 self.common.proxy_status.set(Some(status.to_owned()));
             }
             CoreNotification::DiffInfo { diff } => {
-                self.source_control.branch.set(diff.head.clone());
-                self.source_control
-                    .branches
-                    .set(diff.branches.iter().cloned().collect());
-                self.source_control
-                    .tags
-                    .set(diff.tags.iter().cloned().collect());
-                self.source_control.file_diffs.update(|file_diffs| {
-                    *file_diffs = diff
-                        .diffs
-                        .iter()
-                        .cloned()
-                        .map(|diff| {
-                            let checked =
-                                file_diffs.get(diff.path()).is_none_or(|(_, c)| *c);
-                            (diff.path().clone(), (diff, checked))
-                        })
-                        .collect();
-                });
+                self.source_control.set_diff_info(diff.clone());
+                self.source_control.refresh_compare_later();
 
                 let docs = self.main_split.docs.get_untracked();
                 for (_, doc) in docs {
                     doc.retrieve_head();
                 }
             }
+            CoreNotification::GlobalSearchResults {
+                search_id,
+                matches,
+                count,
+            } => {
+                self.global_search.receive_results(
+                    *search_id,
+                    matches.clone(),
+                    *count,
+                );
+            }
             CoreNotification::CompletionResponse {
                 request_id,
                 input,
@@ -2259,6 +2538,14 @@ fn handle_core_notification(&self, rpc: &CoreNotification) {
             CoreNotification::OpenFileChanged { path, content } => {
                 self.main_split.open_file_changed(path, content);
             }
+            CoreNotification::EditorConfigChanged {
+                path,
+                properties,
+                encoding,
+            } => {
+                self.main_split
+                    .editor_config_changed(path, properties, encoding);
+            }
             CoreNotification::VoltInstalled { volt, icon } => {
                 self.plugin.volt_installed(volt, icon);
             }
@@ -2636,7 +2923,8 @@ fn toggle_panel_focus(&self, kind: PanelKind) {
             | PanelKind::CallHierarchy
             | PanelKind::DocumentSymbol
             | PanelKind::References
-            | PanelKind::Implementation => {
+            | PanelKind::Implementation
+            | PanelKind::CommitHistory => {
                 // Some panels don't accept focus (yet). Fall back to visibility check
                 // in those cases.
                 self.panel.is_panel_visible(&kind)
@@ -2716,6 +3004,12 @@ pub fn show_panel(&self, kind: PanelKind) {
                     .get_default_profile(),
             );
         }
+        if kind == PanelKind::CommitHistory
+            && !self.commit_history.loading.get_untracked()
+            && self.commit_history.commits.with_untracked(|c| c.is_empty())
+        {
+            self.commit_history.show(None);
+        }
         self.panel.show_panel(&kind);
         if kind == PanelKind::Search
             && self.common.focus.get_untracked() == Focus::Workbench
@@ -2906,6 +3200,51 @@ pub fn update_code_lens_id(&self, view_id: Option<ViewId>) {
         }
     }
 
+    /// Get the url of the active file on the remote's forge, anchored to the
+    /// selected lines. A permalink refers to the current commit, otherwise the
+    /// url refers to the upstream branch.
+    fn active_file_remote_url(
+        &self,
+        permalink: bool,
+        f: impl FnOnce(String) + 'static,
+    ) {
+        let Some(editor_data) = self.main_split.active_editor.get_untracked() else {
+            return;
+        };
+        let doc = editor_data.doc();
+        let DocContent::File { path, .. } = doc.content.get_untracked() else {
+            return;
+        };
+        let lines = doc.buffer.with_untracked(|buffer| {
+            let selection =
+                editor_data.cursor().get_untracked().edit_selection(buffer);
+            let region = selection.first()?;
+            let start = buffer.line_of_offset(region.min());
+            let mut end = buffer.line_of_offset(region.max());
+            // A selection of whole lines ends at the start of the next line
+            if end > start && buffer.offset_of_line(end) == region.max() {
+                end -= 1;
+            }
+            Some((start + 1, end + 1))
+        });
+
+        let forges = self.common.config.get_untracked().core.git_forges.clone();
+        let send = create_ext_action(self.scope, move |result| {
+            if let Ok(ProxyResponse::GitGetRemoteFileUrl { file_url }) = result {
+                f(file_url);
+            }
+        });
+        self.common.proxy.git_get_remote_file_url(
+            path,
+            lines,
+            permalink,
+            forges,
+            move |result| {
+                send(result);
+            },
+        );
+    }
+
     pub fn show_code_lens(
         &self,
         mouse_click: bool,
@@ -1,13 +1,17 @@
 use std::{
     collections::{HashMap, HashSet, hash_map::Entry},
     fmt::Write,
-    path::Path,
+    path::{Path, PathBuf},
     str::FromStr,
+    sync::{Arc, Mutex, RwLock},
 };
 
+use globset::{GlobBuilder, GlobMatcher};
+pub use lapce_rpc::plugin::LanguageDefinition;
 use lapce_rpc::style::{LineStyle, Style};
 use once_cell::sync::Lazy;
 use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use strum_macros::{AsRefStr, Display, EnumMessage, EnumString, IntoStaticStr};
 use tracing::{Level, event};
 use tree_sitter::{Point, TreeCursor};
@@ -167,8 +171,9 @@ struct CommentProperties {
     multi_line_prefix: Option<&'static str>,
 }
 
-/// NOTE: Keep the enum variants "fieldless" so they can cast to usize as array
-/// indices into the LANGUAGES array.  See method `LapceLanguage::properties`.
+/// NOTE: Keep the enum variants "fieldless", except for `Custom`, so that they
+/// match the elements of the LANGUAGES array in the same order.  See method
+/// `LapceLanguage::properties`.
 ///
 /// Do not assign values to the variants because the number of variants and
 /// number of elements in the LANGUAGES array change as different features
@@ -402,6 +407,8 @@ pub enum LapceLanguage {
     Scheme,
     #[strum(message = "SCSS")]
     Scss,
+    #[strum(message = "Search Result")]
+    SearchResult,
     #[strum(message = "Shell Script (POSIX)")]
     ShellScript,
     #[strum(message = "Smithy")]
@@ -444,6 +451,29 @@ pub enum LapceLanguage {
     Yaml,
     #[strum(message = "Zig")]
     Zig,
+
+    // Do not move
+    /// A language registered at runtime, see [`LapceLanguage::register`]. The
+    /// index depends on the order the languages are registered in, which
+    /// differs between the app and the proxy, so languages are serialized by
+    /// name.
+    #[remain::unsorted]
+    Custom(u16),
+}
+
+impl Serialize for LapceLanguage {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.name())
+    }
+}
+
+impl<'de> Deserialize<'de> for LapceLanguage {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        LapceLanguage::from_name(&name).ok_or_else(|| {
+            serde::de::Error::custom(format!("unknown language `{name}`"))
+        })
+    }
 }
 
 /// NOTE: Elements in the array must be in the same order as the enum variants of
@@ -823,7 +853,7 @@ pub enum LapceLanguage {
     SyntaxProperties {
         id: LapceLanguage::GitRebase,
         indent: Indent::tab(),
-        files: &[],
+        files: &["git-rebase-todo"],
         extensions: &[],
         comment: comment_properties!(),
         tree_sitter: TreeSitterProperties::DEFAULT,
@@ -1492,6 +1522,14 @@ pub enum LapceLanguage {
         comment: comment_properties!("//"),
         tree_sitter: TreeSitterProperties::DEFAULT,
     },
+    SyntaxProperties {
+        id: LapceLanguage::SearchResult,
+        indent: Indent::space(2),
+        files: &[],
+        extensions: &["lapce-search"],
+        comment: comment_properties!("#"),
+        tree_sitter: TreeSitterProperties::DEFAULT,
+    },
     SyntaxProperties {
         id: LapceLanguage::ShellScript,
         indent: Indent::space(2),
@@ -1689,7 +1727,9 @@ pub fn from_path_raw(path: &Path) -> Option<LapceLanguage> {
             .and_then(|s| s.to_str().map(|s| s.to_lowercase()));
         // NOTE: This is a linear search.  It is assumed that this function
         // isn't called in any tight loop.
-        for properties in LANGUAGES {
+        let registry = registered_languages();
+        let registered = registry.languages.iter().map(|l| &l.properties);
+        for properties in registered.chain(LANGUAGES) {
             if properties.files.iter().any(|f| Some(*f) == filename) {
                 return Some(properties.id);
             }
@@ -1705,18 +1745,127 @@ pub fn from_path_raw(path: &Path) -> Option<LapceLanguage> {
         None
     }
 
+    /// The language with the name, which is either the name of the variant or
+    /// the display name, e.g. `GoTemplate` or `Go (template)`
     pub fn from_name(name: &str) -> Option<LapceLanguage> {
+        if let Some(language) = registered_languages().find(name) {
+            return Some(language.properties.id);
+        }
         match LapceLanguage::from_str(name.to_lowercase().as_str()) {
+            // Registered languages are only found by their names above
+            Ok(LapceLanguage::Custom(_)) => None,
             Ok(v) => Some(v),
             Err(e) => {
-                event!(Level::DEBUG, "failed parsing `{name}` LapceLanguage: {e}");
-                None
+                let language = LANGUAGES.iter().map(|l| l.id).find(|l| {
+                    strum::EnumMessage::get_message(l)
+                        .is_some_and(|m| m.eq_ignore_ascii_case(name))
+                });
+                if language.is_none() {
+                    event!(
+                        Level::DEBUG,
+                        "failed parsing `{name}` LapceLanguage: {e}"
+                    );
+                }
+                language
             }
         }
     }
 
+    /// Detect the language from the first and last lines of a file, for files
+    /// whose path doesn't match any language. This looks at the shebang, Vim
+    /// and Emacs modelines, and the XML declaration or HTML doctype.
+    pub fn from_content(lines: &[&str]) -> Option<LapceLanguage> {
+        static SHEBANG_REGEX: Lazy<Regex> =
+            Lazy::new(|| Regex::new(crate::syntax::highlight::SHEBANG).unwrap());
+        static VIM_MODELINE_REGEX: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(
+                r"(?:^|\s)(?:vi|vim|ex)(?:[<=>]?\d+)?:.*?\b(?:ft|filetype|syn|syntax)=([\w.+-]+)",
+            )
+            .unwrap()
+        });
+        static EMACS_MODELINE_REGEX: Lazy<Regex> =
+            Lazy::new(|| Regex::new(r"-\*-\s*(.*?)\s*-\*-").unwrap());
+        static EMACS_MODE_REGEX: Lazy<Regex> = Lazy::new(|| {
+            Regex::new(r"(?i)(?:^|;)\s*mode\s*:\s*([\w.+-]+)").unwrap()
+        });
+
+        let first = lines.first()?.trim_start_matches('\u{feff}');
+        if first.starts_with("#!") {
+            if let Some(language) = SHEBANG_REGEX
+                .captures(first)
+                .and_then(|cap| Self::from_alias(&cap[1]))
+            {
+                return Some(language);
+            }
+        }
+
+        for line in lines {
+            if let Some(language) = VIM_MODELINE_REGEX
+                .captures(line)
+                .and_then(|cap| Self::from_alias(&cap[1]))
+            {
+                return Some(language);
+            }
+            if let Some(cap) = EMACS_MODELINE_REGEX.captures(line) {
+                let mode = if cap[1].contains(':') {
+                    EMACS_MODE_REGEX
+                        .captures(&cap[1])
+                        .map(|mode| mode[1].to_string())
+                } else {
+                    Some(cap[1].to_string())
+                };
+                if let Some(language) = mode.and_then(|mode| Self::from_alias(&mode))
+                {
+                    return Some(language);
+                }
+            }
+        }
+
+        let first = first.trim_start().to_lowercase();
+        if first.starts_with("<!doctype html") || first.starts_with("<html") {
+            Some(LapceLanguage::Html)
+        } else if first.starts_with("<?xml") || first.starts_with("<!doctype") {
+            Some(LapceLanguage::Xml)
+        } else {
+            None
+        }
+    }
+
+    /// The language of an interpreter in a shebang, or of the name of a
+    /// language in a modeline, which is often the usual extension of its files
+    fn from_alias(name: &str) -> Option<LapceLanguage> {
+        let name = name.to_lowercase();
+        let name = name.strip_suffix("-mode").unwrap_or(&name);
+        let language = match name {
+            "sh" | "ash" | "dash" | "ksh" | "mksh" | "zsh" | "shell-script" => {
+                LapceLanguage::Bash
+            }
+            "node" | "nodejs" | "deno" | "bun" | "js" => LapceLanguage::Javascript,
+            "ts-node" | "ts" => LapceLanguage::Typescript,
+            "pypy" => LapceLanguage::Python,
+            "luajit" => LapceLanguage::Lua,
+            "make" | "gmake" => LapceLanguage::Make,
+            "pwsh" => LapceLanguage::PowerShell,
+            "nu" => LapceLanguage::Nushell,
+            "tclsh" | "wish" => LapceLanguage::Tcl,
+            "rscript" => LapceLanguage::R,
+            "escript" => LapceLanguage::Erlang,
+            "runghc" | "runhaskell" => LapceLanguage::Haskell,
+            _ => {
+                return Self::from_name(name).or_else(|| {
+                    Self::from_path_raw(Path::new(&format!("file.{name}")))
+                });
+            }
+        };
+        Some(language)
+    }
+
     pub fn languages() -> Vec<&'static str> {
-        let mut langs = vec![];
+        let mut langs: Vec<&'static str> = registered_languages()
+            .languages
+            .iter()
+            .map(|l| l.name)
+            .collect();
         for l in LANGUAGES {
             // Get only languages with display name to hide inline grammars
             if let Some(lang) = strum::EnumMessage::get_message(&l.id) {
@@ -1726,25 +1875,55 @@ pub fn languages() -> Vec<&'static str> {
         langs
     }
 
-    // NOTE: Instead of using `&LANGUAGES[*self as usize]` directly, the
-    // `debug_assertion` gives better feedback should something has gone wrong
-    // badly.
-    fn properties(&self) -> &SyntaxProperties {
-        let i = *self as usize;
-        let l = &LANGUAGES[i];
-        debug_assert!(
-            l.id == *self,
-            "LANGUAGES[{i}]: Setting::id mismatch: {:?} != {:?}",
-            l.id,
-            self
-        );
-        l
+    /// Register a language at runtime, or replace the properties of the
+    /// registered language with the same name. The highlighting of a replaced
+    /// language is only updated after a restart.
+    pub fn register(definition: LanguageDefinition) -> LapceLanguage {
+        REGISTERED_LANGUAGES.write().unwrap().register(definition)
+    }
+
+    fn registered(&self) -> Option<Arc<RegisteredLanguage>> {
+        match self {
+            LapceLanguage::Custom(i) => registered_languages().get(*i),
+            _ => None,
+        }
+    }
+
+    // NOTE: The LANGUAGES array is sorted in the same order as the enum
+    // variants, and the panic gives better feedback should something has gone
+    // wrong badly.
+    fn properties(&self) -> SyntaxProperties {
+        if let LapceLanguage::Custom(_) = self {
+            return self
+                .registered()
+                .map(|l| l.properties)
+                .unwrap_or(LANGUAGES[0]);
+        }
+        let i = LANGUAGES
+            .binary_search_by(|l| l.id.cmp(self))
+            .unwrap_or_else(|_| {
+                panic!("LANGUAGES: {self:?} is missing or out of order")
+            });
+        LANGUAGES[i]
     }
 
     pub fn name(&self) -> &'static str {
+        if let Some(language) = self.registered() {
+            return language.name;
+        }
         strum::EnumMessage::get_message(self).unwrap_or(self.into())
     }
 
+    /// The file extensions of the language, in lowercase
+    pub fn extensions(&self) -> &'static [&'static str] {
+        self.properties().extensions
+    }
+
+    /// The file names of the language, e.g. `Dockerfile`
+    pub fn files(&self) -> &'static [&'static str] {
+        self.properties().files
+    }
+
     pub fn sticky_header_tags(&self) -> &[&'static str] {
         self.properties().tree_sitter.sticky_headers
     }
@@ -1763,8 +1942,15 @@ pub fn indent_unit(&self) -> &str {
     fn get_grammar(&self) -> Option<tree_sitter::Language> {
         let grammar_name = self.grammar_name();
         let grammar_fn_name = self.grammar_fn_name();
+        if grammar_name.is_empty() {
+            return None;
+        }
 
-        if let Some(grammars_dir) = Directory::grammars_directory() {
+        let grammars_dir = self
+            .registered()
+            .and_then(|l| l.grammar_dir.clone())
+            .or_else(Directory::grammars_directory);
+        if let Some(grammars_dir) = grammars_dir {
             match self::load_grammar(&grammar_name, &grammar_fn_name, &grammars_dir)
             {
                 Ok(grammar) => {
@@ -1808,8 +1994,13 @@ fn grammar_fn_name(&self) -> String {
     fn get_grammar_query(&self) -> (String, String) {
         let query_name = self.query_name();
 
-        // Try reading highlights from user config dir
-        if let Some(queries_dir) = Directory::queries_directory() {
+        // Try reading highlights from the queries of a registered language, or
+        // else from user config dir
+        let queries_dir = self
+            .registered()
+            .and_then(|l| l.queries_dir.clone())
+            .or_else(Directory::queries_directory);
+        if let Some(queries_dir) = queries_dir {
             return (
                 read_grammar_query(
                     &queries_dir,
@@ -1856,6 +2047,245 @@ pub(crate) fn walk_tree(
     }
 }
 
+struct RegisteredLanguage {
+    /// The definition the language was registered with, to not register it
+    /// again when it's unchanged
+    definition: LanguageDefinition,
+    name: &'static str,
+    properties: SyntaxProperties,
+    grammar_dir: Option<PathBuf>,
+    queries_dir: Option<PathBuf>,
+}
+
+impl RegisteredLanguage {
+    fn new(id: LapceLanguage, definition: LanguageDefinition) -> Self {
+        fn leak_all(v: Vec<String>) -> &'static [&'static str] {
+            v.into_iter().map(leak).collect::<Vec<_>>().leak()
+        }
+
+        let grammar = definition.grammar.as_ref().and_then(|grammar| {
+            let name = grammar.file_stem()?.to_str()?;
+            let name = name
+                .strip_prefix("libtree-sitter-")
+                .or_else(|| name.strip_prefix("tree-sitter-"))
+                .unwrap_or(name);
+            Some((leak(name.to_string()), grammar.parent()?.to_path_buf()))
+        });
+        let queries = definition.queries.as_ref().and_then(|queries| {
+            Some((
+                leak(queries.file_name()?.to_str()?.to_string()),
+                queries.parent()?.to_path_buf(),
+            ))
+        });
+        let original = definition.clone();
+        let (block_start, block_end) = definition.block_comment.unzip();
+
+        RegisteredLanguage {
+            definition: original,
+            name: leak(definition.name),
+            properties: SyntaxProperties {
+                id,
+                comment: CommentProperties {
+                    single_line_start: definition.comment.map(leak),
+                    single_line_end: None,
+                    multi_line_start: block_start.map(leak),
+                    multi_line_end: block_end.map(leak),
+                    multi_line_prefix: None,
+                },
+                indent: definition.indent.map(leak).unwrap_or(Indent::space(4)),
+                files: leak_all(definition.files),
+                extensions: leak_all(
+                    definition
+                        .extensions
+                        .into_iter()
+                        .map(|e| e.to_lowercase())
+                        .collect(),
+                ),
+                tree_sitter: TreeSitterProperties {
+                    // A registered language without a grammar isn't
+                    // highlighted, rather than loading a grammar named `custom`
+                    grammar: Some(
+                        grammar.as_ref().map(|(name, _)| *name).unwrap_or(""),
+                    ),
+                    grammar_fn: Some(
+                        definition
+                            .grammar_fn
+                            .map(leak)
+                            .or(grammar.as_ref().map(|(name, _)| *name))
+                            .unwrap_or(""),
+                    ),
+                    query: Some(
+                        queries.as_ref().map(|(name, _)| *name).unwrap_or(""),
+                    ),
+                    ..TreeSitterProperties::DEFAULT
+                },
+            },
+            grammar_dir: grammar.map(|(_, dir)| dir),
+            queries_dir: queries.map(|(_, dir)| dir),
+        }
+    }
+}
+
+/// Leak the string, as the properties of a registered language are referenced
+/// for as long as the language is used. The same string is only leaked once,
+/// so registering a changed definition again doesn't leak its unchanged names.
+fn leak(s: String) -> &'static str {
+    static LEAKED: Lazy<Mutex<HashSet<&'static str>>> = Lazy::new(Default::default);
+    let mut leaked = LEAKED.lock().unwrap();
+    if let Some(s) = leaked.get(s.as_str()) {
+        return *s;
+    }
+    let s: &'static str = Box::leak(s.into_boxed_str());
+    leaked.insert(s);
+    s
+}
+
+/// The languages registered at runtime, whose index is the one of
+/// [`LapceLanguage::Custom`]
+struct LanguageRegistry {
+    languages: Vec<Arc<RegisteredLanguage>>,
+}
+
+impl LanguageRegistry {
+    const fn new() -> Self {
+        Self {
+            languages: Vec::new(),
+        }
+    }
+
+    /// Register the language, unless it's registered with the same definition.
+    /// A changed definition replaces the language in its slot, keeping its id.
+    fn register(&mut self, definition: LanguageDefinition) -> LapceLanguage {
+        let index = self
+            .languages
+            .iter()
+            .position(|l| l.name.eq_ignore_ascii_case(&definition.name))
+            .unwrap_or(self.languages.len());
+        let id = LapceLanguage::Custom(index as u16);
+        if let Some(language) = self.languages.get(index) {
+            if language.definition == definition {
+                return id;
+            }
+        }
+
+        let language = Arc::new(RegisteredLanguage::new(id, definition));
+        event!(Level::DEBUG, "registered language `{}`", language.name);
+        if index < self.languages.len() {
+            self.languages[index] = language;
+        } else {
+            self.languages.push(language);
+        }
+        id
+    }
+
+    fn get(&self, index: u16) -> Option<Arc<RegisteredLanguage>> {
+        self.languages.get(index as usize).cloned()
+    }
+
+    fn find(&self, name: &str) -> Option<Arc<RegisteredLanguage>> {
+        self.languages
+            .iter()
+            .find(|l| l.name.eq_ignore_ascii_case(name))
+            .cloned()
+    }
+}
+
+static REGISTERED_LANGUAGES: RwLock<LanguageRegistry> =
+    RwLock::new(LanguageRegistry::new());
+
+fn registered_languages() -> std::sync::RwLockReadGuard<'static, LanguageRegistry> {
+    REGISTERED_LANGUAGES.read().unwrap()
+}
+
+/// The associations registered by the app, for the proxy which doesn't load
+/// the settings itself
+static REGISTERED_ASSOCIATIONS: RwLock<FileAssociations> =
+    RwLock::new(FileAssociations { globs: Vec::new() });
+
+/// User defined associations of globs of files with languages, which take
+/// precedence over the languages of the file names and extensions. A glob
+/// without a `/` is matched against the file name, e.g. `"*.tpl"` or
+/// `"Jenkinsfile*"`, and other globs against the whole path.
+#[derive(Clone, Debug, Default)]
+pub struct FileAssociations {
+    globs: Vec<(GlobMatcher, bool, LapceLanguage)>,
+}
+
+impl FileAssociations {
+    /// Create the associations from the globs and the names of their
+    /// languages, skipping invalid globs and unknown languages. When several
+    /// globs match, the longest one is the most specific and wins.
+    pub fn new<'a>(
+        associations: impl IntoIterator<Item = (&'a str, &'a str)>,
+    ) -> Self {
+        let mut globs: Vec<_> = associations
+            .into_iter()
+            .filter_map(|(glob, name)| {
+                let Some(language) = LapceLanguage::from_name(name) else {
+                    event!(
+                        Level::WARN,
+                        "unknown language `{name}` for files `{glob}`"
+                    );
+                    return None;
+                };
+                let is_path = glob.contains('/');
+                let pattern = if is_path
+                    && !glob.starts_with('/')
+                    && !glob.starts_with("**")
+                {
+                    format!("**/{}", glob.trim_start_matches("./"))
+                } else {
+                    glob.to_string()
+                };
+                match GlobBuilder::new(&pattern).literal_separator(true).build() {
+                    Ok(matcher) => Some((
+                        glob.len(),
+                        matcher.compile_matcher(),
+                        is_path,
+                        language,
+                    )),
+                    Err(e) => {
+                        event!(Level::WARN, "invalid glob `{glob}`: {e}");
+                        None
+                    }
+                }
+            })
+            .collect();
+        globs.sort_by(|a, b| b.0.cmp(&a.0));
+        Self {
+            globs: globs
+                .into_iter()
+                .map(|(_, matcher, is_path, language)| (matcher, is_path, language))
+                .collect(),
+        }
+    }
+
+    /// The language associated with the path
+    pub fn language(&self, path: &Path) -> Option<LapceLanguage> {
+        let file_name = path.file_name()?;
+        self.globs
+            .iter()
+            .find(|(matcher, is_path, _)| {
+                if *is_path {
+                    matcher.is_match(path)
+                } else {
+                    matcher.is_match(file_name)
+                }
+            })
+            .map(|(_, _, language)| *language)
+    }
+
+    /// Replace the registered associations with these
+    pub fn register(self) {
+        *REGISTERED_ASSOCIATIONS.write().unwrap() = self;
+    }
+
+    /// The language the registered associations give the path
+    pub fn registered_language(path: &Path) -> Option<LapceLanguage> {
+        REGISTERED_ASSOCIATIONS.read().unwrap().language(path)
+    }
+}
+
 fn load_grammar(
     grammar_name: &str,
     grammar_fn_name: &str,
@@ -2045,13 +2475,161 @@ fn read_grammar_query(queries_dir: &Path, name: &str, kind: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use std::path::PathBuf;
+    use std::{
+        path::{Path, PathBuf},
+        sync::Arc,
+    };
 
-    use super::LapceLanguage;
+    use super::{
+        FileAssociations, LANGUAGES, LanguageDefinition, LanguageRegistry,
+        LapceLanguage,
+    };
 
     #[test]
     fn test_lanaguage_from_path() {
         let l = LapceLanguage::from_path(&PathBuf::new().join("test.rs"));
         assert_eq!(l, LapceLanguage::Rust);
     }
+
+    #[test]
+    fn test_languages_sorted() {
+        // `LapceLanguage::properties` binary searches the array
+        for pair in LANGUAGES.windows(2) {
+            assert!(
+                pair[0].id < pair[1].id,
+                "LANGUAGES: {:?} is out of order",
+                pair[1].id
+            );
+        }
+    }
+
+    #[test]
+    fn test_register_language() {
+        // A registry of its own, to not change the languages of other tests
+        let mut registry = LanguageRegistry::new();
+        let definition = LanguageDefinition {
+            name: "Test DSL".to_string(),
+            extensions: vec!["TestDsl".to_string()],
+            comment: Some("--".to_string()),
+            indent: Some("\t".to_string()),
+            grammar: Some(PathBuf::from("/grammars/libtree-sitter-testdsl.so")),
+            ..Default::default()
+        };
+        let language = registry.register(definition.clone());
+        assert_eq!(language, LapceLanguage::Custom(0));
+        let registered = registry.find("test dsl").unwrap();
+        assert_eq!(registered.name, "Test DSL");
+        assert_eq!(registered.properties.id, language);
+        assert_eq!(registered.properties.comment.single_line_start, Some("--"));
+        assert_eq!(registered.properties.indent, "\t");
+        assert_eq!(registered.properties.extensions, &["testdsl"]);
+        assert_eq!(registered.properties.tree_sitter.grammar, Some("testdsl"));
+        assert_eq!(
+            registered.properties.tree_sitter.grammar_fn,
+            Some("testdsl")
+        );
+        assert_eq!(registered.grammar_dir, Some(PathBuf::from("/grammars")));
+
+        // Registering the same definition again keeps the registered language
+        assert_eq!(registry.register(definition), language);
+        assert!(Arc::ptr_eq(&registry.get(0).unwrap(), &registered));
+
+        let replaced = registry.register(LanguageDefinition {
+            name: "Test DSL".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(replaced, language);
+        assert_eq!(registry.languages.len(), 1);
+        let replaced = registry.get(0).unwrap();
+        assert!(!Arc::ptr_eq(&replaced, &registered));
+        // The unchanged name isn't leaked again
+        assert!(std::ptr::eq(replaced.name, registered.name));
+        let registered = replaced;
+        assert_eq!(registered.properties.comment.single_line_start, None);
+        assert_eq!(registered.properties.tree_sitter.grammar, Some(""));
+
+        let other = registry.register(LanguageDefinition {
+            name: "Other DSL".to_string(),
+            ..Default::default()
+        });
+        assert_eq!(other, LapceLanguage::Custom(1));
+        assert!(registry.find("custom").is_none());
+        assert_eq!(LapceLanguage::from_name("custom"), None);
+        assert_eq!(LapceLanguage::from_name("rust"), Some(LapceLanguage::Rust));
+    }
+
+    #[test]
+    fn test_language_serialized_by_name() {
+        let language = LapceLanguage::register(LanguageDefinition {
+            name: "Serialized DSL".to_string(),
+            ..Default::default()
+        });
+        for language in [LapceLanguage::GoTemplate, language] {
+            let json = serde_json::to_string(&language).unwrap();
+            assert_eq!(json, format!("\"{}\"", language.name()));
+            assert_eq!(
+                serde_json::from_str::<LapceLanguage>(&json).unwrap(),
+                language
+            );
+        }
+        assert!(serde_json::from_str::<LapceLanguage>("\"Unknown\"").is_err());
+    }
+
+    #[test]
+    fn test_file_associations() {
+        let associations = FileAssociations::new([
+            ("*.tpl", "Go (template)"),
+            ("Jenkinsfile*", "groovy"),
+            ("config/*.conf", "ini"),
+            ("config/special.conf", "toml"),
+            ("*.txt", "unknown"),
+        ]);
+        let language = |path: &str| associations.language(Path::new(path));
+        assert_eq!(language("/ws/page.tpl"), Some(LapceLanguage::GoTemplate));
+        assert_eq!(
+            language("/ws/Jenkinsfile.prod"),
+            Some(LapceLanguage::Groovy)
+        );
+        assert_eq!(language("/ws/config/a.conf"), Some(LapceLanguage::Ini));
+        assert_eq!(
+            language("/ws/config/special.conf"),
+            Some(LapceLanguage::Toml)
+        );
+        assert_eq!(language("/ws/a.conf"), None);
+        assert_eq!(language("/ws/a.txt"), None);
+    }
+
+    #[test]
+    fn test_language_from_content() {
+        let language = |content: &str| {
+            LapceLanguage::from_content(&content.lines().collect::<Vec<_>>())
+        };
+        assert_eq!(language("#!/bin/sh\necho"), Some(LapceLanguage::Bash));
+        assert_eq!(
+            language("#!/usr/bin/env -S deno run\n"),
+            Some(LapceLanguage::Javascript)
+        );
+        assert_eq!(
+            language("#!/usr/bin/python3.11\n"),
+            Some(LapceLanguage::Python)
+        );
+        assert_eq!(language("# vim: set ft=ruby:"), Some(LapceLanguage::Ruby));
+        assert_eq!(
+            language("foo\n// vim: ts=4 filetype=cpp"),
+            Some(LapceLanguage::Cpp)
+        );
+        assert_eq!(
+            language("# -*- mode: python; coding: utf-8 -*-"),
+            Some(LapceLanguage::Python)
+        );
+        assert_eq!(language(";; -*- c++ -*-"), Some(LapceLanguage::Cpp));
+        assert_eq!(
+            language("<?xml version=\"1.0\"?>\n<a/>"),
+            Some(LapceLanguage::Xml)
+        );
+        assert_eq!(language("<!DOCTYPE html>\n"), Some(LapceLanguage::Html));
+        assert_eq!(language("# -*- coding: utf-8 -*-"), None);
+        assert_eq!(language("plain text"), None);
+        assert_eq!(language(""), None);
+    }
 }
@@ -4,8 +4,10 @@
 pub mod encoding;
 pub mod language;
 pub mod lens;
+pub mod merge_conflict;
 pub mod meta;
 pub mod rope_text_pos;
+pub mod search;
 pub mod style;
 pub mod syntax;
 // This is primarily being re-exported to avoid changing every single usage
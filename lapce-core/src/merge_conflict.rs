@@ -0,0 +1,149 @@
+use lapce_xi_rope::Rope;
+
+const CURRENT_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SEPARATOR_MARKER: &str = "=======";
+const INCOMING_MARKER: &str = ">>>>>>>";
+
+/// A block of conflicting changes left by a merge, rebase or pull.
+/// All the fields are the lines of the conflict markers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MergeConflict {
+    /// The `<<<<<<<` line which starts the current change
+    pub start: usize,
+    /// The `|||||||` line which starts the common ancestor, in diff3 style
+    pub base: Option<usize>,
+    /// The `=======` line which starts the incoming change
+    pub separator: usize,
+    /// The `>>>>>>>` line which ends the incoming change
+    pub end: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConflictResolution {
+    Current,
+    Incoming,
+    Both,
+}
+
+impl MergeConflict {
+    /// The end of the current change, exclusive
+    pub fn current_end(&self) -> usize {
+        self.base.unwrap_or(self.separator)
+    }
+
+    pub fn contains_line(&self, line: usize) -> bool {
+        self.start <= line && line <= self.end
+    }
+
+    /// The range of the whole conflict block, and the text which should replace
+    /// it for the resolution
+    pub fn resolve(
+        &self,
+        text: &Rope,
+        resolution: ConflictResolution,
+    ) -> (usize, usize, String) {
+        let lines = |start: usize, end: usize| {
+            text.slice_to_cow(text.offset_of_line(start)..text.offset_of_line(end))
+                .to_string()
+        };
+        let current = lines(self.start + 1, self.current_end());
+        let incoming = lines(self.separator + 1, self.end);
+        let replacement = match resolution {
+            ConflictResolution::Current => current,
+            ConflictResolution::Incoming => incoming,
+            ConflictResolution::Both => current + &incoming,
+        };
+        let start = text.offset_of_line(self.start);
+        let end = text.offset_of_line(self.end + 1);
+        (start, end, replacement)
+    }
+}
+
+fn is_marker(line: &str, marker: &str) -> bool {
+    line.strip_prefix(marker)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
+
+/// Find the conflict blocks in the text. Blocks which aren't complete are
+/// ignored.
+pub fn find_conflicts(text: &Rope) -> Vec<MergeConflict> {
+    let mut conflicts = Vec::new();
+    let mut start = None;
+    let mut base = None;
+    let mut separator = None;
+    for (line, content) in text.lines_raw(0..text.len()).enumerate() {
+        if is_marker(&content, CURRENT_MARKER) {
+            start = Some(line);
+            base = None;
+            separator = None;
+        } else if start.is_none() {
+            continue;
+        } else if separator.is_none() && is_marker(&content, BASE_MARKER) {
+            base = Some(line);
+        } else if separator.is_none() && is_marker(&content, SEPARATOR_MARKER) {
+            separator = Some(line);
+        } else if is_marker(&content, INCOMING_MARKER) {
+            if let (Some(start), Some(separator)) = (start, separator) {
+                conflicts.push(MergeConflict {
+                    start,
+                    base,
+                    separator,
+                    end: line,
+                });
+            }
+            start = None;
+            base = None;
+            separator = None;
+        }
+    }
+    conflicts
+}
+
+/// Whether the text has any complete conflict block. A lone `=======` line
+/// isn't a conflict, since it's also a setext heading in markdown.
+pub fn has_conflicts(text: &Rope) -> bool {
+    !find_conflicts(text).is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use lapce_xi_rope::Rope;
+
+    use super::{ConflictResolution, MergeConflict, find_conflicts, has_conflicts};
+
+    #[test]
+    fn test_find_and_resolve_conflicts() {
+        let text = Rope::from(
+            "a\n<<<<<<< HEAD\nb\n||||||| base\nc\n=======\nd\n>>>>>>> feature\ne\n",
+        );
+        let conflicts = find_conflicts(&text);
+        let conflict = MergeConflict {
+            start: 1,
+            base: Some(3),
+            separator: 5,
+            end: 7,
+        };
+        assert_eq!(conflicts, vec![conflict]);
+
+        let resolve = |resolution| {
+            let (start, end, replacement) = conflict.resolve(&text, resolution);
+            let mut text = text.clone();
+            text.edit(start..end, replacement);
+            text.to_string()
+        };
+        assert_eq!(resolve(ConflictResolution::Current), "a\nb\ne\n");
+        assert_eq!(resolve(ConflictResolution::Incoming), "a\nd\ne\n");
+        assert_eq!(resolve(ConflictResolution::Both), "a\nb\nd\ne\n");
+    }
+
+    #[test]
+    fn test_incomplete_conflict() {
+        let text = Rope::from("<<<<<<< HEAD\na\n=======\nb\n");
+        assert!(find_conflicts(&text).is_empty());
+        assert!(!has_conflicts(&text));
+
+        let text = Rope::from("Title\n=======\n\ntext\n");
+        assert!(!has_conflicts(&text));
+    }
+}
@@ -0,0 +1,112 @@
+use std::ops::Range;
+
+use regex::{Regex, RegexBuilder};
+
+/// The pattern of a workspace search, which finds the matches of each line
+/// the way the proxy's search does, and computes their replacements
+#[derive(Clone, Debug)]
+pub struct SearchPattern {
+    regex: Regex,
+    is_regex: bool,
+}
+
+impl SearchPattern {
+    pub fn new(
+        pattern: &str,
+        case_sensitive: bool,
+        whole_word: bool,
+        is_regex: bool,
+    ) -> Result<Self, regex::Error> {
+        let pattern = if is_regex {
+            pattern.to_string()
+        } else {
+            regex::escape(pattern)
+        };
+        let pattern = if whole_word {
+            format!(r"\b(?:{pattern})\b")
+        } else {
+            pattern
+        };
+        let regex = RegexBuilder::new(&pattern)
+            .case_insensitive(!case_sensitive)
+            .build()?;
+        Ok(Self { regex, is_regex })
+    }
+
+    /// The ranges of the matches in the text, each with the text replacing it.
+    /// The `$1` or `${name}` capture groups of a regex are expanded in the
+    /// replacement.
+    pub fn replacements(
+        &self,
+        text: &str,
+        replacement: &str,
+    ) -> Vec<(Range<usize>, String)> {
+        let mut replacements = Vec::new();
+        let mut offset = 0;
+        for line in text.split_inclusive('\n') {
+            let content = line.trim_end_matches(['\n', '\r']);
+            for captures in self.regex.captures_iter(content) {
+                let Some(m) = captures.get(0) else {
+                    continue;
+                };
+                if m.is_empty() {
+                    continue;
+                }
+                let new_text = if self.is_regex {
+                    let mut new_text = String::new();
+                    captures.expand(replacement, &mut new_text);
+                    new_text
+                } else {
+                    replacement.to_string()
+                };
+                replacements.push((offset + m.start()..offset + m.end(), new_text));
+            }
+            offset += line.len();
+        }
+        replacements
+    }
+
+    /// The text with the matches replaced, and the number of replacements
+    pub fn replace_all(&self, text: &str, replacement: &str) -> (String, usize) {
+        let replacements = self.replacements(text, replacement);
+        let mut new_text = String::with_capacity(text.len());
+        let mut last = 0;
+        for (range, replacement) in &replacements {
+            new_text.push_str(&text[last..range.start]);
+            new_text.push_str(replacement);
+            last = range.end;
+        }
+        new_text.push_str(&text[last..]);
+        (new_text, replacements.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SearchPattern;
+
+    #[test]
+    fn test_replacements() {
+        let pattern = SearchPattern::new(r"(\w+)\.rs", true, false, true).unwrap();
+        assert_eq!(
+            pattern.replacements("a.rs b.rs\nc.rs", "$1.md"),
+            vec![
+                (0..4, "a.md".to_string()),
+                (5..9, "b.md".to_string()),
+                (10..14, "c.md".to_string()),
+            ]
+        );
+
+        let pattern = SearchPattern::new("$1", false, false, false).unwrap();
+        assert_eq!(
+            pattern.replace_all("cost $1\r\nCOST $1", "$2"),
+            ("cost $2\r\nCOST $2".to_string(), 2)
+        );
+
+        let pattern = SearchPattern::new("foo", false, true, false).unwrap();
+        assert_eq!(
+            pattern.replace_all("foo Foo food", "bar"),
+            ("bar bar food".to_string(), 2)
+        );
+    }
+}
@@ -89,7 +89,8 @@ pub enum InjectionLanguageMarker<'a> {
     Shebang(String),
 }
 
-const SHEBANG: &str = r"#!\s*(?:\S*[/\\](?:env\s+(?:\-\S+\s+)*)?)?([^\s\.\d]+)";
+pub(crate) const SHEBANG: &str =
+    r"#!\s*(?:\S*[/\\](?:env\s+(?:\-\S+\s+)*)?)?([^\s\.\d]+)";
 
 const CANCELLATION_CHECK_INTERVAL: usize = 100;
 
@@ -1150,6 +1150,29 @@ fn find_tag_in_children(&self, node: Node, tag: &str) -> Option<usize> {
         None
     }
 
+    /// Whether the byte at `offset` is inside a string literal spanning
+    /// multiple lines, such as a raw string or a heredoc.
+    pub fn in_multiline_string(&self, offset: usize) -> bool {
+        let Some(tree) = self.layers.as_ref().and_then(|layers| layers.try_tree())
+        else {
+            return false;
+        };
+        let mut node = tree
+            .root_node()
+            .descendant_for_byte_range(offset, offset + 1);
+        while let Some(current) = node {
+            if is_multiline_string_node(
+                current.kind(),
+                current.start_position().row,
+                current.end_position().row,
+            ) {
+                return true;
+            }
+            node = current.parent();
+        }
+        false
+    }
+
     pub fn sticky_headers(&self, offset: usize) -> Option<Vec<usize>> {
         let tree = self.layers.as_ref()?.try_tree()?;
         let mut node = tree.root_node().descendant_for_byte_range(offset, offset)?;
@@ -1241,6 +1264,11 @@ pub fn find_enclosing_pair(&self, offset: usize) -> Option<(usize, usize)> {
     }
 }
 
+/// Whether a syntax node is a string literal spanning multiple lines
+fn is_multiline_string_node(kind: &str, start_row: usize, end_row: usize) -> bool {
+    (kind.contains("string") || kind.contains("heredoc")) && start_row != end_row
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1259,6 +1287,18 @@ fn test_lens() {
         assert_eq!(33, lens.height_of_line(5));
     }
 
+    #[test]
+    fn test_in_multiline_string() {
+        assert!(is_multiline_string_node("raw_string_literal", 1, 3));
+        assert!(is_multiline_string_node("heredoc_body", 0, 2));
+        assert!(!is_multiline_string_node("string_literal", 2, 2));
+        assert!(!is_multiline_string_node("block_comment", 0, 4));
+
+        // Without a syntax tree nothing is known to be in a string
+        let syntax = Syntax::plaintext();
+        assert!(!syntax.in_multiline_string(0));
+    }
+
     #[test]
     fn test_lens_iter() {
         let lens = Syntax::lens_from_normal_lines(5, 25, 2, &[0, 2, 4]);
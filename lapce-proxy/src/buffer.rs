@@ -10,11 +10,13 @@
 
 use anyhow::{Result, anyhow};
 use floem_editor_core::buffer::rope_text::CharIndicesJoin;
-use lapce_core::encoding::offset_utf8_to_utf16;
-use lapce_rpc::buffer::BufferId;
+use lapce_core::{encoding::offset_utf8_to_utf16, language::FileAssociations};
+use lapce_rpc::{buffer::BufferId, core::FileChanged};
 use lapce_xi_rope::{RopeDelta, interval::IntervalBounds, rope::Rope};
 use lsp_types::*;
 
+use crate::encoding::FileEncoding;
+
 #[derive(Clone)]
 pub struct Buffer {
     pub language_id: &'static str,
@@ -24,29 +26,60 @@ pub struct Buffer {
     pub path: PathBuf,
     pub rev: u64,
     pub mod_time: Option<SystemTime>,
+    /// The encoding the file was read with and is saved with
+    pub encoding: FileEncoding,
+    /// Whether the file is larger than the large file threshold, in which case
+    /// it isn't synced with the plugins
+    pub large_file: bool,
+    /// Whether the file isn't text, in which case the buffer is empty and the
+    /// file is opened in the hex editor instead
+    pub binary: bool,
+}
+
+/// The error of reading a file which isn't text
+#[derive(Debug)]
+pub struct BinaryContent;
+
+impl std::fmt::Display for BinaryContent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "binary content")
+    }
 }
 
+impl std::error::Error for BinaryContent {}
+
 impl Buffer {
-    pub fn new(id: BufferId, path: PathBuf) -> Buffer {
-        let (s, read_only) = match load_file(&path) {
-            Ok(s) => (s, false),
-            Err(err) => {
-                use std::io::ErrorKind;
-                match err.downcast_ref::<std::io::Error>() {
-                    Some(err) => match err.kind() {
-                        ErrorKind::PermissionDenied => {
-                            ("Permission Denied".to_string(), true)
-                        }
-                        ErrorKind::NotFound => ("".to_string(), false),
-                        ErrorKind::OutOfMemory => {
-                            ("File too big (out of memory)".to_string(), false)
-                        }
-                        _ => (format!("Not supported: {err}"), true),
-                    },
-                    None => (format!("Not supported: {err}"), true),
+    /// Read the buffer of the file at `path`, in `encoding` if the content is
+    /// valid in it and otherwise in the detected encoding.
+    pub fn new(
+        id: BufferId,
+        path: PathBuf,
+        encoding: Option<FileEncoding>,
+    ) -> Buffer {
+        let (s, read_only, encoding, binary) =
+            match read_path_with_encoding(&path, encoding) {
+                Ok((s, encoding)) => (s, false, encoding, false),
+                Err(err) if err.is::<BinaryContent>() => {
+                    (String::new(), true, encoding.unwrap_or_default(), true)
                 }
-            }
-        };
+                Err(err) => {
+                    use std::io::ErrorKind;
+                    let (s, read_only) = match err.downcast_ref::<std::io::Error>() {
+                        Some(err) => match err.kind() {
+                            ErrorKind::PermissionDenied => {
+                                ("Permission Denied".to_string(), true)
+                            }
+                            ErrorKind::NotFound => ("".to_string(), false),
+                            ErrorKind::OutOfMemory => {
+                                ("File too big (out of memory)".to_string(), false)
+                            }
+                            _ => (format!("Not supported: {err}"), true),
+                        },
+                        None => (format!("Not supported: {err}"), true),
+                    };
+                    (s, read_only, encoding.unwrap_or_default(), false)
+                }
+            };
         let rope = Rope::from(s);
         let rev = u64::from(!rope.is_empty());
         let language_id = language_id_from_path(&path).unwrap_or("");
@@ -59,6 +92,9 @@ pub fn new(id: BufferId, path: PathBuf) -> Buffer {
             language_id,
             rev,
             mod_time,
+            encoding,
+            large_file: false,
+            binary,
         }
     }
 
@@ -96,13 +132,28 @@ pub fn save(&mut self, rev: u64, create_parents: bool) -> Result<()> {
             }
         }
 
+        // Encoded before the file is truncated, as it fails for the characters
+        // the encoding can't represent
+        let encoded = if self.encoding == FileEncoding::UTF8 {
+            None
+        } else {
+            Some(
+                self.encoding
+                    .encode(&self.rope.slice_to_cow(..self.rope.len()))?,
+            )
+        };
+
         let mut f = fs::OpenOptions::new()
             .create(true)
             .write(true)
             .truncate(true)
             .open(&path)?;
-        for chunk in self.rope.iter_chunks(..self.rope.len()) {
-            f.write_all(chunk.as_bytes())?;
+        if let Some(encoded) = encoded {
+            f.write_all(&encoded)?;
+        } else {
+            for chunk in self.rope.iter_chunks(..self.rope.len()) {
+                f.write_all(chunk.as_bytes())?;
+            }
         }
 
         self.mod_time = get_mod_time(&path);
@@ -192,11 +243,56 @@ pub fn is_empty(&self) -> bool {
     }
 }
 
+/// The content of the rope from `offset`, up to `size` bytes and ending at a
+/// character boundary, and whether it's the end of the content. The content
+/// of a large file is sent in these chunks.
+pub fn rope_chunk(rope: &Rope, offset: usize, size: usize) -> (String, bool) {
+    let len = rope.len();
+    let offset = offset.min(len);
+    let end = rope
+        .at_or_next_codepoint_boundary((offset + size).min(len))
+        .unwrap_or(len);
+    (rope.slice_to_cow(offset..end).into_owned(), end == len)
+}
+
+/// The change of a large file from its buffer to its `content` on disk, which
+/// is only the appended text when the file grew, e.g. a log, rather than the
+/// whole content. `None` if the content is the same.
+pub fn large_file_change(rope: &Rope, content: &str) -> Option<FileChanged> {
+    let len = rope.len();
+    if content.len() < len || !content.is_char_boundary(len) {
+        return Some(FileChanged::Reload);
+    }
+    let mut offset = 0;
+    for chunk in rope.iter_chunks(..) {
+        if !content[offset..].starts_with(chunk) {
+            return Some(FileChanged::Reload);
+        }
+        offset += chunk.len();
+    }
+    if content.len() == len {
+        return None;
+    }
+    Some(FileChanged::Append {
+        offset: len,
+        text: content[len..].to_string(),
+    })
+}
+
 pub fn load_file(path: &Path) -> Result<String> {
     read_path_to_string(path)
 }
 
 pub fn read_path_to_string<P: AsRef<Path>>(path: P) -> Result<String> {
+    Ok(read_path_with_encoding(path, None)?.0)
+}
+
+/// Read a file in `encoding` if its content is valid in it, and otherwise in
+/// the encoding detected from its content.
+pub fn read_path_with_encoding<P: AsRef<Path>>(
+    path: P,
+    encoding: Option<FileEncoding>,
+) -> Result<(String, FileEncoding)> {
     let path = path.as_ref();
 
     let mut file = File::open(path)?;
@@ -204,13 +300,31 @@ pub fn read_path_to_string<P: AsRef<Path>>(path: P) -> Result<String> {
     let mut buffer = Vec::new();
     file.read_to_end(&mut buffer)?;
 
-    // Parse the file contents as utf8
-    let contents = String::from_utf8(buffer)?;
-
-    Ok(contents.to_string())
+    FileEncoding::detect_and_decode(&buffer, encoding)
+        .ok_or_else(|| BinaryContent.into())
 }
 
+/// The language id of the file for the language servers, from the language
+/// the file associations give it or else from its name
 pub fn language_id_from_path(path: &Path) -> Option<&'static str> {
+    FileAssociations::registered_language(path)
+        .and_then(|language| {
+            // The id of a file the language is known by, or of the extension
+            // named after the language, like `groovy`
+            let name = language.name().to_lowercase();
+            language
+                .extensions()
+                .iter()
+                .copied()
+                .chain([name.as_str()])
+                .map(|extension| PathBuf::from(format!("file.{extension}")))
+                .chain(language.files().iter().map(PathBuf::from))
+                .find_map(|path| language_id_from_file_name(&path))
+        })
+        .or_else(|| language_id_from_file_name(path))
+}
+
+fn language_id_from_file_name(path: &Path) -> Option<&'static str> {
     // recommended language_id values
     // https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#textDocumentItem
     Some(match path.extension() {
@@ -347,3 +461,54 @@ pub fn get_mod_time<P: AsRef<Path>>(path: P) -> Option<SystemTime> {
         .and_then(|meta| meta.modified())
         .ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use lapce_core::language::FileAssociations;
+    use lapce_rpc::core::FileChanged;
+    use lapce_xi_rope::Rope;
+
+    use super::{language_id_from_path, large_file_change, rope_chunk};
+
+    #[test]
+    fn test_rope_chunk() {
+        let rope = Rope::from("abcdé");
+        assert_eq!(rope_chunk(&rope, 0, 2), ("ab".to_string(), false));
+        // The chunk ends after the character it would split
+        assert_eq!(rope_chunk(&rope, 2, 3), ("cdé".to_string(), true));
+        assert_eq!(rope_chunk(&rope, 10, 2), ("".to_string(), true));
+    }
+
+    #[test]
+    fn test_large_file_change() {
+        let rope = Rope::from("line 1\nline 2\n");
+        assert!(large_file_change(&rope, "line 1\nline 2\n").is_none());
+        assert!(matches!(
+            large_file_change(&rope, "line 1\nline 2\nline 3\n"),
+            Some(FileChanged::Append { offset: 14, text }) if text == "line 3\n"
+        ));
+        assert!(matches!(
+            large_file_change(&rope, "line 1\nline 3\nline 2\n"),
+            Some(FileChanged::Reload)
+        ));
+        assert!(matches!(
+            large_file_change(&rope, "line 1\n"),
+            Some(FileChanged::Reload)
+        ));
+    }
+
+    #[test]
+    fn test_language_id_from_path() {
+        FileAssociations::new([("*.gotpl", "Go"), ("Jenkinsfile*", "Groovy")])
+            .register();
+        assert_eq!(language_id_from_path(Path::new("/a/b.gotpl")), Some("go"));
+        assert_eq!(
+            language_id_from_path(Path::new("/a/Jenkinsfile.ci")),
+            Some("groovy")
+        );
+        assert_eq!(language_id_from_path(Path::new("/a/b.rs")), Some("rust"));
+        assert_eq!(language_id_from_path(Path::new("/a/b.unknown")), None);
+    }
+}
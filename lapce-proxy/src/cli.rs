@@ -1,4 +1,7 @@
-use std::path::PathBuf;
+use std::{
+    path::{Path, PathBuf},
+    process::exit,
+};
 
 use anyhow::{Error, Result, anyhow};
 use lapce_core::directory::Directory;
@@ -77,6 +80,20 @@ pub fn parse_file_line_column(path: &str) -> Result<PathObject, Error> {
     })
 }
 
+/// Run as the sequence editor of an interactive rebase, git passes the path of
+/// the todo list it's editing, see [`crate::vcs::git::git_rebase`]
+pub fn edit_rebase_todo(todo: &Path, paths: &[PathObject]) -> ! {
+    let result = match paths.first() {
+        Some(git_todo) => crate::vcs::git::write_rebase_todo(todo, &git_todo.path),
+        None => Err(anyhow!("no rebase todo list to edit")),
+    };
+    if let Err(e) = result {
+        eprintln!("failed to write the rebase todo list: {e}");
+        exit(1);
+    }
+    exit(0);
+}
+
 pub fn try_open_in_existing_process(paths: &[PathObject]) -> Result<()> {
     let local_socket = Directory::local_socket()
         .ok_or_else(|| anyhow!("can't get local socket folder"))?;
@@ -1,36 +1,40 @@
 use std::{
     collections::{HashMap, HashSet},
+    ffi::OsString,
     fs, io,
     path::{Path, PathBuf},
     sync::{
         Arc,
-        atomic::{AtomicU64, Ordering},
+        atomic::{AtomicBool, AtomicU64, Ordering},
     },
     thread,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use alacritty_terminal::{event::WindowSize, event_loop::Msg};
-use anyhow::{Context, Result, anyhow};
-use crossbeam_channel::Sender;
-use git2::{
-    DiffOptions, ErrorCode::NotFound, Oid, Repository, build::CheckoutBuilder,
-};
+use crossbeam_channel::{RecvTimeoutError, Sender};
 use grep_matcher::Matcher;
-use grep_regex::RegexMatcherBuilder;
-use grep_searcher::{SearcherBuilder, sinks::UTF8};
+use grep_regex::{RegexMatcher, RegexMatcherBuilder};
+use grep_searcher::{
+    Searcher, SearcherBuilder, Sink, SinkContext, SinkContextKind, SinkMatch,
+};
 use indexmap::IndexMap;
+use lapce_core::{
+    language::{FileAssociations, LapceLanguage},
+    search::SearchPattern,
+};
 use lapce_rpc::{
     RequestId, RpcError,
     buffer::BufferId,
     core::{CoreNotification, CoreRpcHandler, FileChanged},
+    editorconfig::EditorConfigProperties,
     file::FileNodeItem,
     file_line::FileLine,
     proxy::{
         ProxyHandler, ProxyNotification, ProxyRequest, ProxyResponse,
-        ProxyRpcHandler, SearchMatch,
+        ProxyRpcHandler, SearchFilter, SearchMatch,
     },
-    source_control::{DiffInfo, FileDiff},
+    source_control::DiffInfo,
     style::{LineStyle, SemanticStyles},
     terminal::TermId,
 };
@@ -43,23 +47,52 @@
 use parking_lot::Mutex;
 
 use crate::{
-    buffer::{Buffer, get_mod_time, load_file},
+    buffer::{
+        Buffer, get_mod_time, large_file_change, load_file, read_path_with_encoding,
+        rope_chunk,
+    },
+    editorconfig,
+    encoding::FileEncoding,
     plugin::{PluginCatalogRpcHandler, catalog::PluginCatalog},
     terminal::{Terminal, TerminalSender},
+    vcs::{
+        self,
+        git::{
+            BlameCache, GitLogWalk, git_blame, git_commit_files,
+            git_commit_template, git_create_worktree, git_diff_revision,
+            git_get_remote_file_url, git_init, git_last_commit_message, git_log,
+            git_mark_resolved, git_rebase, git_rebase_action, git_rebase_todo,
+            git_worktrees,
+        },
+    },
     watcher::{FileWatcher, Notify, WatchToken},
 };
 
 const OPEN_FILE_EVENT_TOKEN: WatchToken = WatchToken(1);
 const WORKSPACE_EVENT_TOKEN: WatchToken = WatchToken(2);
 
+/// The size in bytes of the chunks the content of a large file is sent in
+const BUFFER_CHUNK_SIZE: usize = 1024 * 1024;
+
 pub struct Dispatcher {
     workspace: Option<PathBuf>,
     pub proxy_rpc: ProxyRpcHandler,
     core_rpc: CoreRpcHandler,
     catalog_rpc: PluginCatalogRpcHandler,
     buffers: HashMap<PathBuf, Buffer>,
+    editor_config_files: HashSet<PathBuf>,
+    /// The files read as bytes by the hex editor or the image viewer, which
+    /// are told when the file changes on disk
+    byte_files: HashSet<PathBuf>,
     terminals: HashMap<TermId, TerminalSender>,
     file_watcher: FileWatcher,
+    blame_cache: Arc<Mutex<HashMap<PathBuf, BlameCache>>>,
+    git_log_cache: Arc<Mutex<HashMap<Option<PathBuf>, GitLogWalk>>>,
+    /// The id of the latest search of the search panel, which cancels the
+    /// earlier ones
+    search_panel_id: Arc<AtomicU64>,
+    /// The id of the latest search of each search editor
+    search_editor_ids: HashMap<BufferId, Arc<AtomicU64>>,
     window_id: usize,
     tab_id: usize,
 }
@@ -85,8 +118,15 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     self.proxy_rpc.clone(),
                 ));
                 if let Some(workspace) = self.workspace.as_ref() {
-                    self.file_watcher
-                        .watch(workspace, true, WORKSPACE_EVENT_TOKEN);
+                    // Reading the status of a Jujutsu repository snapshots the
+                    // working copy into `.jj`, which would be reported as a
+                    // change again
+                    self.file_watcher.watch_filtered(
+                        workspace,
+                        true,
+                        WORKSPACE_EVENT_TOKEN,
+                        |path| !path.components().any(|c| c.as_os_str() == ".jj"),
+                    );
                 }
 
                 let plugin_rpc = self.catalog_rpc.clone();
@@ -117,13 +157,40 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     .notification(CoreNotification::OpenPaths { paths });
             }
             OpenFileChanged { path } => {
+                if path
+                    .file_name()
+                    .is_some_and(|name| name == editorconfig::FILE_NAME)
+                {
+                    self.editor_config_changed(&path);
+                    if !self.buffers.contains_key(&path) {
+                        return;
+                    }
+                }
                 if path.exists() {
+                    if self.byte_files.contains(&path) {
+                        self.core_rpc
+                            .open_file_changed(path.clone(), FileChanged::Bytes);
+                    }
                     if let Some(buffer) = self.buffers.get(&path) {
-                        if get_mod_time(&buffer.path) == buffer.mod_time {
+                        if buffer.binary
+                            || get_mod_time(&buffer.path) == buffer.mod_time
+                        {
                             return;
                         }
-                        match load_file(&buffer.path) {
-                            Ok(content) => {
+                        match read_path_with_encoding(
+                            &buffer.path,
+                            Some(buffer.encoding),
+                        ) {
+                            // Only the text a large file grew by is sent, not
+                            // the whole content
+                            Ok((content, _)) if buffer.large_file => {
+                                if let Some(change) =
+                                    large_file_change(&buffer.rope, &content)
+                                {
+                                    self.core_rpc.open_file_changed(path, change);
+                                }
+                            }
+                            Ok((content, _)) => {
                                 self.core_rpc.open_file_changed(
                                     path,
                                     FileChanged::Change(content),
@@ -139,6 +206,7 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     }
                 } else {
                     self.buffers.remove(&path);
+                    self.byte_files.remove(&path);
                     self.core_rpc.open_file_changed(path, FileChanged::Delete);
                 }
             }
@@ -169,19 +237,34 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                 let buffer = self.buffers.get_mut(&path).unwrap();
                 let old_text = buffer.rope.clone();
                 buffer.update(&delta, rev);
-                self.catalog_rpc.did_change_text_document(
-                    &path,
-                    rev,
-                    delta,
-                    old_text,
-                    buffer.rope.clone(),
-                );
+                if !buffer.large_file {
+                    self.catalog_rpc.did_change_text_document(
+                        &path,
+                        rev,
+                        delta,
+                        old_text,
+                        buffer.rope.clone(),
+                    );
+                }
             }
             UpdatePluginConfigs { configs } => {
                 if let Err(err) = self.catalog_rpc.update_plugin_configs(configs) {
                     tracing::error!("{:?}", err);
                 }
             }
+            RegisterLanguages { languages } => {
+                for language in languages {
+                    LapceLanguage::register(language);
+                }
+            }
+            RegisterFileAssociations { associations } => {
+                FileAssociations::new(
+                    associations
+                        .iter()
+                        .map(|(glob, language)| (glob.as_str(), language.as_str())),
+                )
+                .register();
+            }
             NewTerminal { term_id, profile } => {
                 let mut terminal = match Terminal::new(term_id, profile, 50, 10) {
                     Ok(terminal) => terminal,
@@ -335,47 +418,132 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
                     tracing::error!("{:?}", err);
                 }
             }
-            GitCommit { message, diffs } => {
-                if let Some(workspace) = self.workspace.as_ref() {
-                    match git_commit(workspace, &message, diffs) {
-                        Ok(()) => (),
-                        Err(e) => {
-                            self.core_rpc.show_message(
-                                "Git Commit failure".to_owned(),
-                                ShowMessageParams {
-                                    typ: MessageType::ERROR,
-                                    message: e.to_string(),
-                                },
-                            );
+            GitCommit {
+                message,
+                diffs,
+                amend,
+                sign_off,
+                repository,
+            } => {
+                if let Some(workspace) = self.workspace.clone() {
+                    let core_rpc = self.core_rpc.clone();
+                    thread::spawn(move || {
+                        let vcs = vcs::backend(
+                            repository.as_deref().unwrap_or(&workspace),
+                        );
+                        match vcs.commit(&message, diffs, amend, sign_off) {
+                            Ok(()) => (),
+                            Err(e) => {
+                                core_rpc.show_message(
+                                    format!("{} Commit failure", vcs.name()),
+                                    ShowMessageParams {
+                                        typ: MessageType::ERROR,
+                                        message: e.to_string(),
+                                    },
+                                );
+                            }
                         }
-                    }
+                    });
                 }
             }
-            GitCheckout { reference } => {
+            GitCheckout {
+                reference,
+                repository,
+            } => {
+                if let Some(workspace) = self.workspace.clone() {
+                    thread::spawn(move || {
+                        let vcs = vcs::backend(
+                            repository.as_deref().unwrap_or(&workspace),
+                        );
+                        match vcs.checkout(&reference) {
+                            Ok(()) => (),
+                            Err(e) => eprintln!("{e:?}"),
+                        }
+                    });
+                }
+            }
+            GitDiscardFilesChanges { files, repository } => {
+                if let Some(workspace) = self.workspace.clone() {
+                    thread::spawn(move || {
+                        let vcs = vcs::backend(
+                            repository.as_deref().unwrap_or(&workspace),
+                        );
+                        match vcs.discard_files_changes(&files) {
+                            Ok(()) => (),
+                            Err(e) => eprintln!("{e:?}"),
+                        }
+                    });
+                }
+            }
+            GitDiscardWorkspaceChanges { repository } => {
+                if let Some(workspace) = self.workspace.clone() {
+                    thread::spawn(move || {
+                        let vcs = vcs::backend(
+                            repository.as_deref().unwrap_or(&workspace),
+                        );
+                        match vcs.discard_workspace_changes() {
+                            Ok(()) => (),
+                            Err(e) => eprintln!("{e:?}"),
+                        }
+                    });
+                }
+            }
+            GitMarkResolved { path, repository } => {
                 if let Some(workspace) = self.workspace.as_ref() {
-                    match git_checkout(workspace, &reference) {
-                        Ok(()) => (),
-                        Err(e) => eprintln!("{e:?}"),
+                    let repository = repository.as_deref().unwrap_or(workspace);
+                    if let Err(e) = git_mark_resolved(repository, &path) {
+                        self.core_rpc.show_message(
+                            "Git Mark Resolved failure".to_owned(),
+                            ShowMessageParams {
+                                typ: MessageType::ERROR,
+                                message: e.to_string(),
+                            },
+                        );
                     }
                 }
             }
-            GitDiscardFilesChanges { files } => {
+            GitRebase {
+                onto,
+                todo,
+                repository,
+            } => {
                 if let Some(workspace) = self.workspace.as_ref() {
-                    match git_discard_files_changes(
-                        workspace,
-                        files.iter().map(AsRef::as_ref),
-                    ) {
-                        Ok(()) => (),
-                        Err(e) => eprintln!("{e:?}"),
-                    }
+                    let repository = repository.unwrap_or_else(|| workspace.clone());
+                    let core_rpc = self.core_rpc.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = require_git(&repository)
+                            .map_err(|e| anyhow::anyhow!(e.message))
+                            .and_then(|()| git_rebase(&repository, &onto, todo))
+                        {
+                            core_rpc.show_message(
+                                "Git Rebase failure".to_owned(),
+                                ShowMessageParams {
+                                    typ: MessageType::ERROR,
+                                    message: e.to_string(),
+                                },
+                            );
+                        }
+                    });
                 }
             }
-            GitDiscardWorkspaceChanges {} => {
+            GitRebaseAction { action, repository } => {
                 if let Some(workspace) = self.workspace.as_ref() {
-                    match git_discard_workspace_changes(workspace) {
-                        Ok(()) => (),
-                        Err(e) => eprintln!("{e:?}"),
-                    }
+                    let repository = repository.unwrap_or_else(|| workspace.clone());
+                    let core_rpc = self.core_rpc.clone();
+                    thread::spawn(move || {
+                        if let Err(e) = require_git(&repository)
+                            .map_err(|e| anyhow::anyhow!(e.message))
+                            .and_then(|()| git_rebase_action(&repository, action))
+                        {
+                            core_rpc.show_message(
+                                "Git Rebase failure".to_owned(),
+                                ShowMessageParams {
+                                    typ: MessageType::ERROR,
+                                    message: e.to_string(),
+                                },
+                            );
+                        }
+                    });
                 }
             }
             GitInit {} => {
@@ -404,85 +572,231 @@ fn handle_notification(&mut self, rpc: ProxyNotification) {
     fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
         use ProxyRequest::*;
         match rpc {
-            NewBuffer { buffer_id, path } => {
-                let buffer = Buffer::new(buffer_id, path.clone());
-                let content = buffer.rope.to_string();
-                let read_only = buffer.read_only;
-                self.catalog_rpc.did_open_document(
-                    &path,
-                    buffer.language_id.to_string(),
-                    buffer.rev as i32,
-                    content.clone(),
+            NewBuffer {
+                buffer_id,
+                path,
+                large_file_threshold,
+            } => {
+                let editor_config = self.resolve_editor_config(&path);
+                let mut buffer = Buffer::new(
+                    buffer_id,
+                    path.clone(),
+                    editor_config_encoding(&editor_config),
                 );
+                buffer.large_file = large_file_threshold > 0
+                    && buffer.rope.len() as u64 > large_file_threshold;
+                let large_file = buffer.large_file;
+                let binary = buffer.binary;
+                let read_only = buffer.read_only;
+                let encoding = buffer.encoding.name().to_string();
+                // The content of a large file is sent in chunks instead, and
+                // isn't synced with the plugins
+                let content = if large_file || binary {
+                    String::new()
+                } else {
+                    let content = buffer.rope.to_string();
+                    self.catalog_rpc.did_open_document(
+                        &path,
+                        buffer.language_id.to_string(),
+                        buffer.rev as i32,
+                        content.clone(),
+                    );
+                    content
+                };
                 self.file_watcher.watch(&path, false, OPEN_FILE_EVENT_TOKEN);
                 self.buffers.insert(path, buffer);
                 self.respond_rpc(
                     id,
-                    Ok(ProxyResponse::NewBufferResponse { content, read_only }),
+                    Ok(ProxyResponse::NewBufferResponse {
+                        content,
+                        read_only,
+                        editor_config,
+                        encoding,
+                        large_file,
+                        binary,
+                    }),
                 );
             }
+            BufferChunk { path, offset } => {
+                let result = self
+                    .buffers
+                    .get(&path)
+                    .map(|buffer| {
+                        let (chunk, done) =
+                            rope_chunk(&buffer.rope, offset, BUFFER_CHUNK_SIZE);
+                        ProxyResponse::BufferChunkResponse { chunk, done }
+                    })
+                    .ok_or_else(|| RpcError {
+                        code: 0,
+                        message: "buffer not found".to_string(),
+                    });
+                self.respond_rpc(id, result);
+            }
+            ReloadBuffer { path, rev } => {
+                let result = match self.buffers.get_mut(&path) {
+                    Some(buffer) => match read_path_with_encoding(
+                        &buffer.path,
+                        Some(buffer.encoding),
+                    ) {
+                        Ok((content, _)) => {
+                            buffer.rope = Rope::from(content);
+                            buffer.rev = rev;
+                            buffer.mod_time = get_mod_time(&buffer.path);
+                            Ok(ProxyResponse::ReloadBufferResponse {})
+                        }
+                        Err(err) => Err(RpcError {
+                            code: 0,
+                            message: err.to_string(),
+                        }),
+                    },
+                    None => Err(RpcError {
+                        code: 0,
+                        message: "buffer not found".to_string(),
+                    }),
+                };
+                self.respond_rpc(id, result);
+            }
             BufferHead { path } => {
-                let result = if let Some(workspace) = self.workspace.as_ref() {
-                    let result = file_get_head(workspace, &path);
-                    if let Ok((_blob_id, content)) = result {
-                        Ok(ProxyResponse::BufferHeadResponse {
-                            version: "head".to_string(),
-                            content,
-                        })
+                let workspace = self.workspace.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = if let Some(workspace) = workspace.as_ref() {
+                        let result = vcs::backend(workspace).file_head(&path);
+                        if let Ok((_blob_id, content)) = result {
+                            Ok(ProxyResponse::BufferHeadResponse {
+                                version: "head".to_string(),
+                                content,
+                            })
+                        } else {
+                            Err(RpcError {
+                                code: 0,
+                                message: "can't get file head".to_string(),
+                            })
+                        }
                     } else {
                         Err(RpcError {
                             code: 0,
-                            message: "can't get file head".to_string(),
+                            message: "no workspace set".to_string(),
                         })
-                    }
-                } else {
-                    Err(RpcError {
-                        code: 0,
-                        message: "no workspace set".to_string(),
-                    })
-                };
-                self.respond_rpc(id, result);
+                    };
+                    proxy_rpc.handle_response(id, result);
+                });
             }
             GlobalSearch {
+                search_id,
                 pattern,
                 case_sensitive,
                 whole_word,
                 is_regex,
+                filter,
+                search_editor,
             } => {
-                static WORKER_ID: AtomicU64 = AtomicU64::new(0);
-                let our_id = WORKER_ID.fetch_add(1, Ordering::SeqCst) + 1;
+                let current_id = match search_editor {
+                    Some(params) => self
+                        .search_editor_ids
+                        .entry(params.buffer_id)
+                        .or_default()
+                        .clone(),
+                    None => self.search_panel_id.clone(),
+                };
+                let our_id = current_id.fetch_add(1, Ordering::SeqCst) + 1;
 
                 let workspace = self.workspace.clone();
-                let buffers = self
+                // The open files are searched with their unsaved changes
+                let contents: HashMap<PathBuf, Rope> = self
                     .buffers
                     .iter()
-                    .map(|p| p.0)
-                    .cloned()
-                    .collect::<Vec<PathBuf>>();
+                    .map(|(path, buffer)| (path.clone(), buffer.rope.clone()))
+                    .collect();
                 let proxy_rpc = self.proxy_rpc.clone();
+                let core_rpc = self.core_rpc.clone();
 
-                // Perform the search on another thread to avoid blocking the proxy thread
+                // Perform the search on other threads to avoid blocking the proxy thread
                 thread::spawn(move || {
-                    proxy_rpc.handle_response(
-                        id,
-                        search_in_path(
-                            our_id,
-                            &WORKER_ID,
-                            workspace
-                                .iter()
-                                .flat_map(|w| ignore::Walk::new(w).flatten())
-                                .chain(
-                                    buffers.iter().flat_map(|p| {
-                                        ignore::Walk::new(p).flatten()
-                                    }),
-                                )
-                                .map(|p| p.into_path()),
-                            &pattern,
-                            case_sensitive,
-                            whole_word,
-                            is_regex,
-                        ),
-                    );
+                    let job = SearchJob {
+                        id: our_id,
+                        current_id: &current_id,
+                        search_id,
+                        context_lines: search_editor
+                            .map_or(0, |params| params.context_lines),
+                        core_rpc: &core_rpc,
+                    };
+                    let result = search_matcher(
+                        &pattern,
+                        case_sensitive,
+                        whole_word,
+                        is_regex,
+                    )
+                    .and_then(|matcher| {
+                        let file_filter =
+                            SearchFileFilter::new(workspace.clone(), &filter)?;
+                        let roots = filter
+                            .files
+                            .unwrap_or_else(|| workspace.iter().cloned().collect());
+                        search_in_paths(
+                            &job,
+                            roots,
+                            &contents,
+                            filter.include_ignored,
+                            &matcher,
+                            file_filter,
+                        )
+                    });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GlobalReplace {
+                pattern,
+                case_sensitive,
+                whole_word,
+                is_regex,
+                replacement,
+                paths,
+                only_match,
+            } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                let core_rpc = self.core_rpc.clone();
+                // Rewriting the files may take a while
+                thread::spawn(move || {
+                    let result = SearchPattern::new(
+                        &pattern,
+                        case_sensitive,
+                        whole_word,
+                        is_regex,
+                    )
+                    .map(|pattern| {
+                        // The files which failed keep their matches listed
+                        let mut replaced = Vec::new();
+                        let mut errors = Vec::new();
+                        for path in paths {
+                            match replace_in_file(
+                                &pattern,
+                                &replacement,
+                                &path,
+                                only_match,
+                            ) {
+                                Ok(()) => replaced.push(path),
+                                Err(e) => {
+                                    errors.push(format!("{}: {e}", path.display()))
+                                }
+                            }
+                        }
+                        if !errors.is_empty() {
+                            core_rpc.show_message(
+                                "Replace failure".to_owned(),
+                                ShowMessageParams {
+                                    typ: MessageType::ERROR,
+                                    message: errors.join("\n"),
+                                },
+                            );
+                        }
+                        ProxyResponse::GlobalReplaceResponse { paths: replaced }
+                    })
+                    .map_err(|e| RpcError {
+                        code: 0,
+                        message: e.to_string(),
+                    });
+                    proxy_rpc.handle_response(id, result);
                 });
             }
             CompletionResolve {
@@ -531,9 +845,16 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     },
                 );
             }
-            GitGetRemoteFileUrl { file } => {
+            GitGetRemoteFileUrl {
+                file,
+                lines,
+                permalink,
+                forges,
+            } => {
                 if let Some(workspace) = self.workspace.as_ref() {
-                    match git_get_remote_file_url(workspace, &file) {
+                    match git_get_remote_file_url(
+                        workspace, &file, lines, permalink, &forges,
+                    ) {
                         Ok(s) => self.proxy_rpc.handle_response(
                             id,
                             Ok(ProxyResponse::GitGetRemoteFileUrl { file_url: s }),
@@ -542,6 +863,273 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                     }
                 }
             }
+            GitBlame { path } => {
+                let workspace = match self.workspace.clone() {
+                    Some(workspace) => workspace,
+                    None => {
+                        self.respond_rpc(
+                            id,
+                            Err(RpcError {
+                                code: 0,
+                                message: "no workspace set".to_string(),
+                            }),
+                        );
+                        return;
+                    }
+                };
+                if let Err(err) = require_git(&workspace) {
+                    self.respond_rpc(id, Err(err));
+                    return;
+                }
+                let (rope, rev) = match self.buffers.get(&path) {
+                    Some(buffer) => (buffer.rope.clone(), buffer.rev),
+                    None => (Rope::from(load_file(&path).unwrap_or_default()), 0),
+                };
+                let blame_cache = self.blame_cache.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result =
+                        git_blame(&workspace, &path, rope, rev, &blame_cache)
+                            .map(|hunks| ProxyResponse::GitBlameResponse {
+                                rev,
+                                hunks,
+                            })
+                            .map_err(|e| RpcError {
+                                code: 0,
+                                message: e.to_string(),
+                            });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitLog { path, skip, limit } => {
+                let workspace = self.workspace.clone();
+                let git_log_cache = self.git_log_cache.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = if let Some(workspace) = workspace.as_ref() {
+                        require_git(workspace).and_then(|()| {
+                            git_log(
+                                workspace,
+                                path.as_deref(),
+                                skip,
+                                limit,
+                                &git_log_cache,
+                            )
+                            .map(|commits| ProxyResponse::GitLogResponse { commits })
+                            .map_err(|e| RpcError {
+                                code: 0,
+                                message: e.to_string(),
+                            })
+                        })
+                    } else {
+                        Err(RpcError {
+                            code: 0,
+                            message: "no workspace set".to_string(),
+                        })
+                    };
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitCommitFiles { commit } => {
+                let workspace = self.workspace.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = if let Some(workspace) = workspace.as_ref() {
+                        require_git(workspace).and_then(|()| {
+                            git_commit_files(workspace, &commit)
+                                .map(|diffs| ProxyResponse::GitCommitFilesResponse {
+                                    diffs,
+                                })
+                                .map_err(|e| RpcError {
+                                    code: 0,
+                                    message: e.to_string(),
+                                })
+                        })
+                    } else {
+                        Err(RpcError {
+                            code: 0,
+                            message: "no workspace set".to_string(),
+                        })
+                    };
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitDiffRevision { revision } => {
+                let workspace = self.workspace.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = if let Some(workspace) = workspace.as_ref() {
+                        git_diff_revision(workspace, &revision)
+                            .map(|diffs| ProxyResponse::GitDiffRevisionResponse {
+                                diffs,
+                            })
+                            .map_err(|e| RpcError {
+                                code: 0,
+                                message: e.to_string(),
+                            })
+                    } else {
+                        Err(RpcError {
+                            code: 0,
+                            message: "no workspace set".to_string(),
+                        })
+                    };
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitFileAtRevision { path, revision } => {
+                let workspace = self.workspace.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                let core_rpc = self.core_rpc.clone();
+                thread::spawn(move || {
+                    let result = if let Some(workspace) = workspace.as_ref() {
+                        match vcs::backend(workspace)
+                            .file_at_revision(&path, &revision)
+                        {
+                            Ok(Some(content)) => {
+                                Ok(ProxyResponse::BufferHeadResponse {
+                                    version: revision,
+                                    content,
+                                })
+                            }
+                            Ok(None) => Err(RpcError {
+                                code: RpcError::NOT_FOUND,
+                                message: format!(
+                                    "{} doesn't exist at {revision}",
+                                    path.display()
+                                ),
+                            }),
+                            Err(e) => {
+                                core_rpc.show_message(
+                                    "Read revision failure".to_owned(),
+                                    ShowMessageParams {
+                                        typ: MessageType::ERROR,
+                                        message: e.to_string(),
+                                    },
+                                );
+                                Err(RpcError {
+                                    code: 0,
+                                    message: e.to_string(),
+                                })
+                            }
+                        }
+                    } else {
+                        Err(RpcError {
+                            code: 0,
+                            message: "no workspace set".to_string(),
+                        })
+                    };
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitCommitTemplate {} => {
+                let workspace = self.workspace.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = if let Some(workspace) = workspace.as_ref() {
+                        git_commit_template(workspace)
+                            .map(|template| {
+                                ProxyResponse::GitCommitTemplateResponse { template }
+                            })
+                            .map_err(|e| RpcError {
+                                code: 0,
+                                message: e.to_string(),
+                            })
+                    } else {
+                        Err(RpcError {
+                            code: 0,
+                            message: "no workspace set".to_string(),
+                        })
+                    };
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitLastCommitMessage { repository } => {
+                let workspace = self.workspace.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = if let Some(workspace) = workspace.as_ref() {
+                        git_last_commit_message(
+                            repository.as_deref().unwrap_or(workspace),
+                        )
+                        .map(|message| ProxyResponse::GitLastCommitMessageResponse {
+                            message,
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        })
+                    } else {
+                        Err(RpcError {
+                            code: 0,
+                            message: "no workspace set".to_string(),
+                        })
+                    };
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            GitRebaseTodo { onto, repository } => {
+                let result = if let Some(workspace) = self.workspace.as_ref() {
+                    let repository = repository.as_deref().unwrap_or(workspace);
+                    require_git(repository).and_then(|()| {
+                        git_rebase_todo(repository, &onto)
+                            .map(|path| ProxyResponse::GitRebaseTodoResponse {
+                                path,
+                            })
+                            .map_err(|e| RpcError {
+                                code: 0,
+                                message: e.to_string(),
+                            })
+                    })
+                } else {
+                    Err(RpcError {
+                        code: 0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            }
+            GitWorktrees { repository } => {
+                let result = if let Some(workspace) = self.workspace.as_ref() {
+                    let repository = repository.as_deref().unwrap_or(workspace);
+                    git_worktrees(repository)
+                        .map(|worktrees| ProxyResponse::GitWorktreesResponse {
+                            worktrees,
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        })
+                } else {
+                    Err(RpcError {
+                        code: 0,
+                        message: "no workspace set".to_string(),
+                    })
+                };
+                self.respond_rpc(id, result);
+            }
+            GitCreateWorktree { branch, repository } => {
+                let workspace = self.workspace.clone();
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = if let Some(workspace) = workspace.as_ref() {
+                        let repository = repository.as_deref().unwrap_or(workspace);
+                        git_create_worktree(repository, &branch)
+                            .map(|path| ProxyResponse::GitCreateWorktreeResponse {
+                                path,
+                            })
+                            .map_err(|e| RpcError {
+                                code: 0,
+                                message: e.to_string(),
+                            })
+                    } else {
+                        Err(RpcError {
+                            code: 0,
+                            message: "no workspace set".to_string(),
+                        })
+                    };
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
             GetDefinition {
                 request_id,
                 path,
@@ -820,6 +1408,45 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 let resp = ProxyResponse::GetOpenFilesContentResponse { items };
                 self.proxy_rpc.handle_response(id, Ok(resp));
             }
+            ReadFileBytes { path } => {
+                if self.byte_files.insert(path.clone()) {
+                    self.file_watcher.watch(&path, false, OPEN_FILE_EVENT_TOKEN);
+                }
+                let proxy_rpc = self.proxy_rpc.clone();
+                thread::spawn(move || {
+                    let result = fs::read(&path)
+                        .map(|content| ProxyResponse::ReadFileBytesResponse {
+                            content,
+                        })
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
+            WriteFileBytes { path, content } => {
+                let proxy_rpc = self.proxy_rpc.clone();
+                let core_rpc = self.core_rpc.clone();
+                thread::spawn(move || {
+                    let result = fs::write(&path, content)
+                        .map(|_| ProxyResponse::Success {})
+                        .map_err(|e| RpcError {
+                            code: 0,
+                            message: e.to_string(),
+                        });
+                    if let Err(err) = &result {
+                        core_rpc.show_message(
+                            "Save failure".to_owned(),
+                            ShowMessageParams {
+                                typ: MessageType::ERROR,
+                                message: err.message.clone(),
+                            },
+                        );
+                    }
+                    proxy_rpc.handle_response(id, result);
+                });
+            }
             ReadDir { path } => {
                 let proxy_rpc = self.proxy_rpc.clone();
                 thread::spawn(move || {
@@ -861,8 +1488,10 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 let result = buffer
                     .save(rev, create_parents)
                     .map(|_r| {
-                        self.catalog_rpc
-                            .did_save_text_document(&path, buffer.rope.clone());
+                        if !buffer.large_file {
+                            self.catalog_rpc
+                                .did_save_text_document(&path, buffer.rope.clone());
+                        }
                         ProxyResponse::SaveResponse {}
                     })
                     .map_err(|e| RpcError {
@@ -878,7 +1507,9 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 content,
                 create_parents,
             } => {
-                let mut buffer = Buffer::new(buffer_id, path.clone());
+                let encoding =
+                    editor_config_encoding(&self.resolve_editor_config(&path));
+                let mut buffer = Buffer::new(buffer_id, path.clone(), encoding);
                 buffer.rope = Rope::from(content);
                 buffer.rev = rev;
                 let result = buffer
@@ -891,6 +1522,81 @@ fn handle_request(&mut self, id: RequestId, rpc: ProxyRequest) {
                 self.buffers.insert(path, buffer);
                 self.respond_rpc(id, result);
             }
+            SaveWithEncoding {
+                rev,
+                path,
+                encoding,
+            } => {
+                let result = match (
+                    FileEncoding::from_name(&encoding),
+                    self.buffers.get_mut(&path),
+                ) {
+                    (Some(encoding), Some(buffer)) => {
+                        let previous =
+                            std::mem::replace(&mut buffer.encoding, encoding);
+                        match buffer.save(rev, false) {
+                            Ok(()) => {
+                                if !buffer.large_file {
+                                    self.catalog_rpc.did_save_text_document(
+                                        &path,
+                                        buffer.rope.clone(),
+                                    );
+                                }
+                                Ok(ProxyResponse::SaveResponse {})
+                            }
+                            Err(err) => {
+                                buffer.encoding = previous;
+                                Err(err.to_string())
+                            }
+                        }
+                    }
+                    (None, _) => Err(format!("unknown encoding {encoding}")),
+                    (_, None) => Err("the file isn't open".to_string()),
+                }
+                .map_err(|message| RpcError { code: 0, message });
+                if let Err(err) = &result {
+                    self.core_rpc.show_message(
+                        "Save with encoding failure".to_owned(),
+                        ShowMessageParams {
+                            typ: MessageType::ERROR,
+                            message: err.message.clone(),
+                        },
+                    );
+                }
+                self.respond_rpc(id, result);
+            }
+            ReopenWithEncoding { path, encoding } => {
+                let result = match FileEncoding::from_name(&encoding) {
+                    Some(encoding) => fs::read(&path)
+                        .map_err(|err| err.to_string())
+                        .and_then(|bytes| {
+                            encoding.decode(&bytes).ok_or_else(|| {
+                                format!("the file isn't valid {}", encoding.name())
+                            })
+                        })
+                        .map(|(content, encoding)| {
+                            if let Some(buffer) = self.buffers.get_mut(&path) {
+                                buffer.encoding = encoding;
+                            }
+                            ProxyResponse::ReopenWithEncodingResponse {
+                                content,
+                                encoding: encoding.name().to_string(),
+                            }
+                        }),
+                    None => Err(format!("unknown encoding {encoding}")),
+                }
+                .map_err(|message| RpcError { code: 0, message });
+                if let Err(err) = &result {
+                    self.core_rpc.show_message(
+                        "Reopen with encoding failure".to_owned(),
+                        ShowMessageParams {
+                            typ: MessageType::ERROR,
+                            message: err.message.clone(),
+                        },
+                    );
+                }
+                self.respond_rpc(id, result);
+            }
             CreateFile { path } => {
                 let result = path
                     .parent()
@@ -1221,8 +1927,14 @@ pub fn new(core_rpc: CoreRpcHandler, proxy_rpc: ProxyRpcHandler) -> Self {
             core_rpc,
             catalog_rpc: plugin_rpc,
             buffers: HashMap::new(),
+            editor_config_files: HashSet::new(),
+            byte_files: HashSet::new(),
             terminals: HashMap::new(),
             file_watcher,
+            blame_cache: Arc::new(Mutex::new(HashMap::new())),
+            git_log_cache: Arc::new(Mutex::new(HashMap::new())),
+            search_panel_id: Arc::new(AtomicU64::new(0)),
+            search_editor_ids: HashMap::new(),
             window_id: 1,
             tab_id: 1,
         }
@@ -1232,10 +1944,54 @@ fn respond_rpc(&self, id: RequestId, result: Result<ProxyResponse, RpcError>) {
         self.proxy_rpc.handle_response(id, result);
     }
 
+    /// Resolve the `.editorconfig` properties of `path` and watch the
+    /// `.editorconfig` files they came from.
+    fn resolve_editor_config(&mut self, path: &Path) -> EditorConfigProperties {
+        let (properties, files) = editorconfig::resolve(path);
+        for file in files {
+            if self.editor_config_files.insert(file.clone()) {
+                self.file_watcher.watch(&file, false, OPEN_FILE_EVENT_TOKEN);
+            }
+        }
+        properties
+    }
+
+    /// Re-resolve the properties of the open buffers affected by a change of
+    /// the `.editorconfig` at `path`.
+    fn editor_config_changed(&mut self, path: &Path) {
+        let Some(dir) = path.parent() else {
+            return;
+        };
+        if !path.exists() {
+            self.editor_config_files.remove(path);
+        }
+        let paths: Vec<PathBuf> = self
+            .buffers
+            .keys()
+            .filter(|buffer_path| buffer_path.starts_with(dir))
+            .cloned()
+            .collect();
+        for buffer_path in paths {
+            let properties = self.resolve_editor_config(&buffer_path);
+            let Some(buffer) = self.buffers.get_mut(&buffer_path) else {
+                continue;
+            };
+            // The content stays as it was read, the charset applies when saving
+            if let Some(encoding) = editor_config_encoding(&properties) {
+                buffer.encoding = encoding;
+            }
+            let encoding = buffer.encoding.name().to_string();
+            self.core_rpc
+                .editor_config_changed(buffer_path, properties, encoding);
+        }
+    }
+
     fn get_buffer_or_insert(&mut self, path: PathBuf) -> &mut Buffer {
-        self.buffers
-            .entry(path.clone())
-            .or_insert(Buffer::new(BufferId::next(), path))
+        self.buffers.entry(path.clone()).or_insert(Buffer::new(
+            BufferId::next(),
+            path,
+            None,
+        ))
     }
 }
 
@@ -1271,7 +2027,7 @@ fn new(
             let core_rpc = notifier.core_rpc.clone();
             let last_diff = notifier.last_diff.clone();
             thread::spawn(move || {
-                if let Some(diff) = git_diff_new(&workspace) {
+                if let Some(diff) = vcs::backend(&workspace).diff_info() {
                     core_rpc.diff_info(diff.clone());
                     *last_diff.lock() = diff;
                 }
@@ -1320,6 +2076,20 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
             _ => return,
         };
 
+        // Only the existing `.editorconfig` files are watched, a new one
+        // applies to the open files below it as well
+        if explorer_change {
+            for path in event.paths.iter().filter(|path| {
+                path.file_name()
+                    .is_some_and(|name| name == editorconfig::FILE_NAME)
+            }) {
+                self.proxy_rpc
+                    .notification(ProxyNotification::OpenFileChanged {
+                        path: path.clone(),
+                    });
+            }
+        }
+
         let mut handler = self.workspace_fs_change_handler.lock();
         if let Some(sender) = handler.as_mut() {
             if explorer_change {
@@ -1359,7 +2129,7 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
             if explorer_change {
                 core_rpc.workspace_file_change();
             }
-            if let Some(diff) = git_diff_new(&workspace) {
+            if let Some(diff) = vcs::backend(&workspace).diff_info() {
                 let mut last_diff = last_diff.lock();
                 if diff != *last_diff {
                     core_rpc.diff_info(diff.clone());
@@ -1371,391 +2141,581 @@ fn handle_workspace_fs_event(&self, event: notify::Event) {
     }
 }
 
-#[derive(Clone, Debug)]
-pub struct DiffHunk {
-    pub old_start: u32,
-    pub old_lines: u32,
-    pub new_start: u32,
-    pub new_lines: u32,
-    pub header: String,
+/// Fails for the repositories of other version control systems, which the
+/// features reading the repository with libgit2 don't support
+fn require_git(workspace: &Path) -> Result<(), RpcError> {
+    let vcs = vcs::backend(workspace);
+    if vcs.is_git() {
+        Ok(())
+    } else {
+        Err(RpcError {
+            code: 0,
+            message: format!("{} repositories aren't supported", vcs.name()),
+        })
+    }
 }
 
-fn git_init(workspace_path: &Path) -> Result<()> {
-    if Repository::discover(workspace_path).is_err() {
-        Repository::init(workspace_path)?;
+/// Replace the matches of the pattern in the file, or only the match at the
+/// line and column if given
+fn replace_in_file(
+    pattern: &SearchPattern,
+    replacement: &str,
+    path: &Path,
+    only_match: Option<(usize, usize)>,
+) -> anyhow::Result<()> {
+    // Read and written in the file's encoding, like the buffers are
+    let (content, encoding) = read_path_with_encoding(path, None)?;
+    let new_content = match only_match {
+        Some((line, start)) => {
+            // The lines of the search matches start at 1
+            let line_start: usize = content
+                .split_inclusive('\n')
+                .take(line.saturating_sub(1))
+                .map(str::len)
+                .sum();
+            let Some((range, text)) = pattern
+                .replacements(&content, replacement)
+                .into_iter()
+                .find(|(range, _)| range.start == line_start + start)
+            else {
+                return Ok(());
+            };
+            let mut new_content = content.clone();
+            new_content.replace_range(range, &text);
+            new_content
+        }
+        None => pattern.replace_all(&content, replacement).0,
     };
+    if new_content != content {
+        write_atomically(path, &encoding.encode(&new_content)?)?;
+    }
     Ok(())
 }
 
-fn git_commit(
-    workspace_path: &Path,
-    message: &str,
-    diffs: Vec<FileDiff>,
-) -> Result<()> {
-    let repo = Repository::discover(workspace_path)?;
-    let mut index = repo.index()?;
-    for diff in diffs {
-        match diff {
-            FileDiff::Modified(p) | FileDiff::Added(p) => {
-                index.add_path(p.strip_prefix(workspace_path)?)?;
-            }
-            FileDiff::Renamed(a, d) => {
-                index.add_path(a.strip_prefix(workspace_path)?)?;
-                index.remove_path(d.strip_prefix(workspace_path)?)?;
-            }
-            FileDiff::Deleted(p) => {
-                index.remove_path(p.strip_prefix(workspace_path)?)?;
-            }
-        }
+/// Write the content to a file next to the path which then replaces it, so
+/// that the file is never left partially written
+fn write_atomically(path: &Path, content: &[u8]) -> anyhow::Result<()> {
+    let path = if path.is_symlink() {
+        path.canonicalize()?
+    } else {
+        path.to_path_buf()
+    };
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("{} isn't a file", path.display()))?;
+    let mut tmp_name = OsString::from(".");
+    tmp_name.push(file_name);
+    tmp_name.push(".lapce-replace");
+    let tmp_path = path.with_file_name(tmp_name);
+
+    fs::write(&tmp_path, content)?;
+    if let Ok(metadata) = fs::metadata(&path) {
+        fs::set_permissions(&tmp_path, metadata.permissions())?;
     }
-    index.write()?;
-    let tree = index.write_tree()?;
-    let tree = repo.find_tree(tree)?;
-
-    match repo.signature() {
-        Ok(signature) => {
-            let parents = repo
-                .head()
-                .and_then(|head| Ok(vec![head.peel_to_commit()?]))
-                .unwrap_or(vec![]);
-            let parents_refs = parents.iter().collect::<Vec<_>>();
-
-            repo.commit(
-                Some("HEAD"),
-                &signature,
-                &signature,
-                message,
-                &tree,
-                &parents_refs,
-            )?;
-            Ok(())
-        }
-        Err(e) => match e.code() {
-            NotFound => Err(anyhow!(
-                "No user.name and/or user.email configured for this git repository."
-            )),
-            _ => Err(anyhow!(
-                "Error while creating commit's signature: {}",
-                e.message()
-            )),
-        },
+    if let Err(e) = fs::rename(&tmp_path, &path) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e.into());
     }
+    Ok(())
 }
 
-fn git_checkout(workspace_path: &Path, reference: &str) -> Result<()> {
-    let repo = Repository::discover(workspace_path)?;
-    let (object, reference) = repo.revparse_ext(reference)?;
-    repo.checkout_tree(&object, None)?;
-    repo.set_head(reference.unwrap().name().unwrap())?;
-    Ok(())
+/// The maximum number of matches of a workspace search, after which it stops
+const SEARCH_RESULT_LIMIT: usize = 20_000;
+/// How often the matches found so far are sent to the app
+const SEARCH_BATCH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A workspace search, which is abandoned once a newer one starts
+struct SearchJob<'a> {
+    id: u64,
+    current_id: &'a AtomicU64,
+    /// The id of the search for the app
+    search_id: u64,
+    /// The number of lines around each match to send
+    context_lines: usize,
+    core_rpc: &'a CoreRpcHandler,
 }
 
-fn git_discard_files_changes<'a>(
-    workspace_path: &Path,
-    files: impl Iterator<Item = &'a Path>,
-) -> Result<()> {
-    let repo = Repository::discover(workspace_path)?;
-
-    let mut checkout_b = CheckoutBuilder::new();
-    checkout_b.update_only(false).force();
-
-    let mut had_path = false;
-    for path in files {
-        // Remove the workspace path so it is relative to the folder
-        if let Ok(path) = path.strip_prefix(workspace_path) {
-            had_path = true;
-            checkout_b.path(path);
-        }
+fn search_matcher(
+    pattern: &str,
+    case_sensitive: bool,
+    whole_word: bool,
+    is_regex: bool,
+) -> Result<RegexMatcher, RpcError> {
+    let mut matcher = RegexMatcherBuilder::new();
+    let matcher = matcher.case_insensitive(!case_sensitive).word(whole_word);
+    let matcher = if is_regex {
+        matcher.build(pattern)
+    } else {
+        matcher.build_literals(&[&regex::escape(pattern)])
+    };
+    matcher.map_err(|_| RpcError {
+        code: 0,
+        message: "can't build matcher".to_string(),
+    })
+}
+
+/// The globs of a search filter, which are relative to the workspace
+#[derive(Clone)]
+struct SearchFileFilter {
+    workspace: Option<PathBuf>,
+    include: Option<globset::GlobSet>,
+    exclude: Option<globset::GlobSet>,
+}
+
+impl SearchFileFilter {
+    fn new(
+        workspace: Option<PathBuf>,
+        filter: &SearchFilter,
+    ) -> Result<Self, RpcError> {
+        Ok(Self {
+            workspace,
+            include: search_glob_set(&filter.include)?,
+            exclude: search_glob_set(&filter.exclude)?,
+        })
     }
 
-    if !had_path {
-        // If there we no paths then we do nothing
-        // because the default behavior of checkout builder is to select all files
-        // if it is not given a path
-        return Ok(());
+    fn relative<'a>(&self, path: &'a Path) -> &'a Path {
+        self.workspace
+            .as_ref()
+            .and_then(|workspace| path.strip_prefix(workspace).ok())
+            .unwrap_or(path)
     }
 
-    repo.checkout_index(None, Some(&mut checkout_b))?;
+    /// Whether the file or the directory is excluded, in which case the walk
+    /// doesn't descend into it
+    fn is_excluded(&self, path: &Path) -> bool {
+        self.exclude
+            .as_ref()
+            .is_some_and(|exclude| exclude.is_match(self.relative(path)))
+    }
 
-    Ok(())
+    /// Whether the file is searched
+    fn is_searched(&self, path: &Path) -> bool {
+        self.include
+            .as_ref()
+            .is_none_or(|include| include.is_match(self.relative(path)))
+            && !self.is_excluded(path)
+    }
 }
 
-fn git_discard_workspace_changes(workspace_path: &Path) -> Result<()> {
-    let repo = Repository::discover(workspace_path)?;
-    let mut checkout_b = CheckoutBuilder::new();
-    checkout_b.force();
-
-    repo.checkout_index(None, Some(&mut checkout_b))?;
-
-    Ok(())
+/// The encoding set by the `.editorconfig` `charset`, which a file is read
+/// and saved with.
+fn editor_config_encoding(
+    editor_config: &EditorConfigProperties,
+) -> Option<FileEncoding> {
+    editor_config
+        .charset
+        .as_deref()
+        .and_then(FileEncoding::from_name)
 }
 
-fn git_delta_format(
-    workspace_path: &Path,
-    delta: &git2::DiffDelta,
-) -> Option<(git2::Delta, git2::Oid, PathBuf)> {
-    match delta.status() {
-        git2::Delta::Added | git2::Delta::Untracked => Some((
-            git2::Delta::Added,
-            delta.new_file().id(),
-            delta.new_file().path().map(|p| workspace_path.join(p))?,
-        )),
-        git2::Delta::Deleted => Some((
-            git2::Delta::Deleted,
-            delta.old_file().id(),
-            delta.old_file().path().map(|p| workspace_path.join(p))?,
-        )),
-        git2::Delta::Modified => Some((
-            git2::Delta::Modified,
-            delta.new_file().id(),
-            delta.new_file().path().map(|p| workspace_path.join(p))?,
-        )),
-        _ => None,
+/// The globs of a search filter, which match the files and the directories
+/// anywhere in the workspace unless they contain a `/`
+fn search_glob_set(globs: &[String]) -> Result<Option<globset::GlobSet>, RpcError> {
+    let invalid_glob = |err: globset::Error| RpcError {
+        code: 0,
+        message: format!("invalid glob: {err}"),
+    };
+
+    let mut builder = globset::GlobSetBuilder::new();
+    let mut is_empty = true;
+    for glob in globs {
+        let glob = glob.trim().trim_end_matches('/');
+        // A `./` prefix anchors the glob to the workspace too
+        let anchored = glob.contains('/');
+        let glob = glob.trim_start_matches("./");
+        if glob.is_empty() {
+            continue;
+        }
+        let glob = if anchored {
+            glob.to_string()
+        } else {
+            format!("**/{glob}")
+        };
+        builder
+            .add(globset::Glob::new(&format!("{glob}/**")).map_err(invalid_glob)?);
+        builder.add(globset::Glob::new(&glob).map_err(invalid_glob)?);
+        is_empty = false;
+    }
+    if is_empty {
+        return Ok(None);
     }
+    builder.build().map(Some).map_err(invalid_glob)
 }
 
-fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
-    let repo = Repository::discover(workspace_path).ok()?;
-    let name = match repo.head() {
-        Ok(head) => head.shorthand()?.to_string(),
-        _ => "(No branch)".to_owned(),
+/// Search the files under the roots in parallel, or their contents if they are
+/// open, sending the matches to the app in batches until the limit of matches
+/// is reached
+fn search_in_paths(
+    job: &SearchJob,
+    roots: Vec<PathBuf>,
+    contents: &HashMap<PathBuf, Rope>,
+    include_ignored: bool,
+    matcher: &RegexMatcher,
+    file_filter: SearchFileFilter,
+) -> Result<ProxyResponse, RpcError> {
+    let expired = || RpcError {
+        code: 0,
+        message: "expired search job".to_string(),
+    };
+    let Some((first, rest)) = roots.split_first() else {
+        return Ok(ProxyResponse::GlobalSearchResponse {
+            count: 0,
+            limit_hit: false,
+        });
     };
 
-    let mut branches = Vec::new();
-    for branch in repo.branches(None).ok()? {
-        branches.push(branch.ok()?.0.name().ok()??.to_string());
+    let mut builder = ignore::WalkBuilder::new(first);
+    for root in rest {
+        builder.add(root);
+    }
+    builder.standard_filters(!include_ignored);
+    {
+        let file_filter = file_filter.clone();
+        builder.filter_entry(move |entry| {
+            // The repository itself is never worth searching
+            !(include_ignored && entry.file_name() == ".git")
+                && !file_filter.is_excluded(entry.path())
+        });
     }
+    let walker = builder.build_parallel();
+
+    let (id, current_id, context_lines) =
+        (job.id, job.current_id, job.context_lines);
+    let is_expired = move || current_id.load(Ordering::SeqCst) != id;
+    let stop = AtomicBool::new(false);
+    let (tx, rx) = crossbeam_channel::unbounded();
+
+    thread::scope(|scope| {
+        scope.spawn(|| {
+            let file_filter = &file_filter;
+            let stop = &stop;
+            walker.run(|| {
+                let tx = tx.clone();
+                let mut searcher = SearcherBuilder::new()
+                    .before_context(context_lines)
+                    .after_context(context_lines)
+                    .build();
+                Box::new(move |entry| {
+                    if stop.load(Ordering::Relaxed) || is_expired() {
+                        return ignore::WalkState::Quit;
+                    }
+                    let Ok(entry) = entry else {
+                        return ignore::WalkState::Continue;
+                    };
+                    if !entry.file_type().is_some_and(|t| t.is_file())
+                        || !file_filter.is_searched(entry.path())
+                    {
+                        return ignore::WalkState::Continue;
+                    }
+                    let matches = search_file(
+                        &mut searcher,
+                        matcher,
+                        entry.path(),
+                        contents.get(entry.path()),
+                        is_expired,
+                    );
+                    if !matches.is_empty()
+                        && tx.send((entry.into_path(), matches)).is_err()
+                    {
+                        return ignore::WalkState::Quit;
+                    }
+                    ignore::WalkState::Continue
+                })
+            });
+            drop(tx);
+        });
 
-    let mut tags = Vec::new();
-    if let Ok(git_tags) = repo.tag_names(None) {
-        for tag in git_tags.into_iter().flatten() {
-            tags.push(tag.to_owned());
+        let mut batch = IndexMap::new();
+        // A path is walked once for each root it's under, and its matches are
+        // only counted the first time
+        let mut searched = HashSet::new();
+        let mut count = 0;
+        let mut limit_hit = false;
+        let mut last_sent = Instant::now();
+        loop {
+            match rx.recv_timeout(SEARCH_BATCH_INTERVAL) {
+                Ok((path, _)) if !searched.insert(path.clone()) => {}
+                Ok((path, mut matches)) => {
+                    let remaining = SEARCH_RESULT_LIMIT - count;
+                    if matches.len() >= remaining {
+                        matches.truncate(remaining);
+                        limit_hit = true;
+                    }
+                    count += matches.len();
+                    batch.insert(path, matches);
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => break,
+            }
+            if is_expired() {
+                stop.store(true, Ordering::Relaxed);
+                return Err(expired());
+            }
+            if limit_hit {
+                stop.store(true, Ordering::Relaxed);
+                break;
+            }
+            if !batch.is_empty() && last_sent.elapsed() >= SEARCH_BATCH_INTERVAL {
+                job.core_rpc.global_search_results(
+                    job.search_id,
+                    std::mem::take(&mut batch),
+                    count,
+                );
+                last_sent = Instant::now();
+            }
         }
-    }
 
-    let mut deltas = Vec::new();
-    let mut diff_options = DiffOptions::new();
-    let diff = repo
-        .diff_index_to_workdir(
-            None,
-            Some(
-                diff_options
-                    .include_untracked(true)
-                    .recurse_untracked_dirs(true),
-            ),
-        )
-        .ok()?;
-    for delta in diff.deltas() {
-        if let Some(delta) = git_delta_format(workspace_path, &delta) {
-            deltas.push(delta);
+        if !batch.is_empty() {
+            job.core_rpc
+                .global_search_results(job.search_id, batch, count);
         }
-    }
+        Ok(ProxyResponse::GlobalSearchResponse { count, limit_hit })
+    })
+}
 
-    let oid = match repo.revparse_single("HEAD^{tree}") {
-        Ok(obj) => obj.id(),
-        _ => Oid::zero(),
+/// The matches of the file, or of its content if given, with the long lines
+/// shortened around them
+fn search_file(
+    searcher: &mut Searcher,
+    matcher: &RegexMatcher,
+    path: &Path,
+    content: Option<&Rope>,
+    is_expired: impl Fn() -> bool,
+) -> Vec<SearchMatch> {
+    let mut sink = SearchSink {
+        matcher,
+        is_expired,
+        matches: Vec::new(),
+        context_before: Vec::new(),
     };
-
-    let cached_diff = repo
-        .diff_tree_to_index(repo.find_tree(oid).ok().as_ref(), None, None)
-        .ok();
-
-    if let Some(cached_diff) = cached_diff {
-        for delta in cached_diff.deltas() {
-            if let Some(delta) = git_delta_format(workspace_path, &delta) {
-                deltas.push(delta);
-            }
+    let result = match content {
+        Some(content) => {
+            searcher.search_slice(matcher, content.to_string().as_bytes(), &mut sink)
         }
+        None => searcher.search_path(matcher, path, &mut sink),
+    };
+    if let Err(err) = result {
+        tracing::error!("{:?}", err);
     }
-    let mut renames = Vec::new();
-    let mut renamed_deltas = HashSet::new();
-
-    for (added_index, delta) in deltas.iter().enumerate() {
-        if delta.0 == git2::Delta::Added {
-            for (deleted_index, d) in deltas.iter().enumerate() {
-                if d.0 == git2::Delta::Deleted && d.1 == delta.1 {
-                    renames.push((added_index, deleted_index));
-                    renamed_deltas.insert(added_index);
-                    renamed_deltas.insert(deleted_index);
-                    break;
-                }
-            }
+    sink.matches
+}
+
+/// Collects the matches of a file, with the context lines around them if the
+/// searcher reports them
+struct SearchSink<'a, F> {
+    matcher: &'a RegexMatcher,
+    is_expired: F,
+    matches: Vec<SearchMatch>,
+    /// The context lines before the next match
+    context_before: Vec<String>,
+}
+
+impl<F: Fn() -> bool> Sink for SearchSink<'_, F> {
+    type Error = io::Error;
+
+    fn matched(
+        &mut self,
+        _searcher: &Searcher,
+        mat: &SinkMatch<'_>,
+    ) -> Result<bool, io::Error> {
+        if (self.is_expired)() {
+            return Ok(false);
         }
-    }
 
-    let mut file_diffs = Vec::new();
-    for (added_index, deleted_index) in renames.iter() {
-        file_diffs.push(FileDiff::Renamed(
-            deltas[*added_index].2.clone(),
-            deltas[*deleted_index].2.clone(),
-        ));
+        let line = std::str::from_utf8(mat.bytes())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let Some(mymatch) = self.matcher.find(line.as_bytes())? else {
+            return Ok(true);
+        };
+        let (content_start, line) = if line.len() > 200 {
+            // Shorten the line to avoid sending over absurdly long-lines
+            // (such as in minified javascript)
+            // Note that the start/end are column based, not absolute from the
+            // start of the file.
+            let left_keep = line[..mymatch.start()]
+                .chars()
+                .rev()
+                .take(100)
+                .map(|c| c.len_utf8())
+                .sum::<usize>();
+            let right_keep = line[mymatch.end()..]
+                .chars()
+                .take(100)
+                .map(|c| c.len_utf8())
+                .sum::<usize>();
+            let display_range =
+                mymatch.start() - left_keep..mymatch.end() + right_keep;
+            (display_range.start, line[display_range].to_string())
+        } else {
+            (0, line.to_string())
+        };
+        self.matches.push(SearchMatch {
+            line: mat.line_number().unwrap_or(0) as usize,
+            start: mymatch.start(),
+            end: mymatch.end(),
+            line_content: line,
+            content_start,
+            context_before: std::mem::take(&mut self.context_before),
+            context_after: Vec::new(),
+        });
+        Ok(true)
     }
-    for (i, delta) in deltas.iter().enumerate() {
-        if renamed_deltas.contains(&i) {
-            continue;
+
+    fn context(
+        &mut self,
+        _searcher: &Searcher,
+        context: &SinkContext<'_>,
+    ) -> Result<bool, io::Error> {
+        let line = String::from_utf8_lossy(context.bytes());
+        let line: String = line
+            .trim_end_matches(['\n', '\r'])
+            .chars()
+            .take(200)
+            .collect();
+        match (context.kind(), self.matches.last_mut()) {
+            (SinkContextKind::After, Some(last)) => last.context_after.push(line),
+            _ => self.context_before.push(line),
         }
-        let diff = match delta.0 {
-            git2::Delta::Added => FileDiff::Added(delta.2.clone()),
-            git2::Delta::Deleted => FileDiff::Deleted(delta.2.clone()),
-            git2::Delta::Modified => FileDiff::Modified(delta.2.clone()),
-            _ => continue,
-        };
-        file_diffs.push(diff);
+        Ok(true)
     }
-    file_diffs.sort_by_key(|d| match d {
-        FileDiff::Modified(p)
-        | FileDiff::Added(p)
-        | FileDiff::Renamed(p, _)
-        | FileDiff::Deleted(p) => p.clone(),
-    });
-    Some(DiffInfo {
-        head: name,
-        branches,
-        tags,
-        diffs: file_diffs,
-    })
 }
 
-fn file_get_head(workspace_path: &Path, path: &Path) -> Result<(String, String)> {
-    let repo = Repository::discover(workspace_path)?;
-    let head = repo.head()?;
-    let tree = head.peel_to_tree()?;
-    let tree_entry = tree.get_path(path.strip_prefix(workspace_path)?)?;
-    let blob = repo.find_blob(tree_entry.id())?;
-    let id = blob.id().to_string();
-    let content = std::str::from_utf8(blob.content())
-        .with_context(|| "content bytes to string")?
-        .to_string();
-    Ok((id, content))
-}
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, fs, path::PathBuf, sync::atomic::AtomicU64};
 
-fn git_get_remote_file_url(workspace_path: &Path, file: &Path) -> Result<String> {
-    let repo = Repository::discover(workspace_path)?;
-    let head = repo.head()?;
-    let target_remote = repo.find_remote(
-        repo.branch_upstream_remote(head.name().unwrap())?
-            .as_str()
-            .unwrap(),
-    )?;
-
-    // Grab URL part of remote
-    let remote = target_remote
-        .url()
-        .ok_or(anyhow!("Failed to convert remote to str"))?;
-
-    let remote_url = match Url::parse(remote) {
-        Ok(url) => url,
-        Err(_) => {
-            // Parse URL as ssh
-            Url::parse(&format!("ssh://{}", remote.replacen(':', "/", 1)))?
-        }
+    use lapce_rpc::{
+        core::CoreRpcHandler,
+        proxy::{ProxyResponse, SearchFilter},
     };
 
-    // Get host part
-    let host = remote_url
-        .host_str()
-        .ok_or(anyhow!("Couldn't find remote host"))?;
-    // Get namespace (e.g. organisation/project in case of GitHub, org/team/team/team/../project on GitLab)
-    let namespace = if let Some(stripped) = remote_url.path().strip_suffix(".git") {
-        stripped
-    } else {
-        remote_url.path()
+    use super::{
+        SEARCH_RESULT_LIMIT, SearchFileFilter, SearchJob, search_glob_set,
+        search_in_paths, search_matcher,
     };
 
-    let commit = head.peel_to_commit()?.id();
+    /// The number of matches of `foo` under the roots, and whether the limit
+    /// was hit
+    fn search(roots: Vec<PathBuf>) -> (usize, bool) {
+        let current_id = AtomicU64::new(1);
+        let core_rpc = CoreRpcHandler::new();
+        let job = SearchJob {
+            id: 1,
+            current_id: &current_id,
+            search_id: 1,
+            context_lines: 0,
+            core_rpc: &core_rpc,
+        };
+        let matcher = search_matcher("foo", true, false, false).unwrap();
+        let file_filter =
+            SearchFileFilter::new(None, &SearchFilter::default()).unwrap();
+        match search_in_paths(
+            &job,
+            roots,
+            &HashMap::new(),
+            false,
+            &matcher,
+            file_filter,
+        ) {
+            Ok(ProxyResponse::GlobalSearchResponse { count, limit_hit }) => {
+                (count, limit_hit)
+            }
+            result => panic!("unexpected search result {result:?}"),
+        }
+    }
 
-    let file_path = file
-        .strip_prefix(workspace_path)?
-        .to_str()
-        .ok_or(anyhow!("Couldn't convert file path to str"))?;
+    fn temp_dir() -> tempfile::TempDir {
+        // Hidden directories aren't walked
+        tempfile::Builder::new().prefix("search").tempdir().unwrap()
+    }
 
-    let url = format!("https://{host}{namespace}/blob/{commit}/{file_path}",);
+    #[test]
+    fn test_search_overlapping_roots() {
+        let dir = temp_dir();
+        let sub = dir.path().join("sub");
+        fs::create_dir(&sub).unwrap();
+        fs::write(sub.join("a.txt"), "foo\nbar foo\n").unwrap();
+        fs::write(dir.path().join("b.txt"), "foo\n").unwrap();
 
-    Ok(url)
-}
+        // The file under both roots is only counted once
+        assert_eq!(search(vec![dir.path().to_path_buf(), sub]), (3, false));
+    }
 
-fn search_in_path(
-    id: u64,
-    current_id: &AtomicU64,
-    paths: impl Iterator<Item = PathBuf>,
-    pattern: &str,
-    case_sensitive: bool,
-    whole_word: bool,
-    is_regex: bool,
-) -> Result<ProxyResponse, RpcError> {
-    let mut matches = IndexMap::new();
-    let mut matcher = RegexMatcherBuilder::new();
-    let matcher = matcher.case_insensitive(!case_sensitive).word(whole_word);
-    let matcher = if is_regex {
-        matcher.build(pattern)
-    } else {
-        matcher.build_literals(&[&regex::escape(pattern)])
-    };
-    let matcher = matcher.map_err(|_| RpcError {
-        code: 0,
-        message: "can't build matcher".to_string(),
-    })?;
-    let mut searcher = SearcherBuilder::new().build();
-
-    for path in paths {
-        if current_id.load(Ordering::SeqCst) != id {
-            return Err(RpcError {
-                code: 0,
-                message: "expired search job".to_string(),
-            });
-        }
+    #[test]
+    fn test_search_result_limit() {
+        let dir = temp_dir();
+        fs::write(
+            dir.path().join("a.txt"),
+            "foo\n".repeat(SEARCH_RESULT_LIMIT + 1),
+        )
+        .unwrap();
 
-        if path.is_file() {
-            let mut line_matches = Vec::new();
-            if let Err(err) = searcher.search_path(
-                &matcher,
-                path.clone(),
-                UTF8(|lnum, line| {
-                    if current_id.load(Ordering::SeqCst) != id {
-                        return Ok(false);
-                    }
+        assert_eq!(
+            search(vec![dir.path().to_path_buf()]),
+            (SEARCH_RESULT_LIMIT, true)
+        );
+    }
 
-                    let mymatch = matcher.find(line.as_bytes())?.unwrap();
-                    let line = if line.len() > 200 {
-                        // Shorten the line to avoid sending over absurdly long-lines
-                        // (such as in minified javascript)
-                        // Note that the start/end are column based, not absolute from the
-                        // start of the file.
-                        let left_keep = line[..mymatch.start()]
-                            .chars()
-                            .rev()
-                            .take(100)
-                            .map(|c| c.len_utf8())
-                            .sum::<usize>();
-                        let right_keep = line[mymatch.end()..]
-                            .chars()
-                            .take(100)
-                            .map(|c| c.len_utf8())
-                            .sum::<usize>();
-                        let display_range =
-                            mymatch.start() - left_keep..mymatch.end() + right_keep;
-                        line[display_range].to_string()
-                    } else {
-                        line.to_string()
-                    };
-                    line_matches.push(SearchMatch {
-                        line: lnum as usize,
-                        start: mymatch.start(),
-                        end: mymatch.end(),
-                        line_content: line,
-                    });
-                    Ok(true)
-                }),
-            ) {
-                {
-                    tracing::error!("{:?}", err);
-                }
-            }
-            if !line_matches.is_empty() {
-                matches.insert(path.clone(), line_matches);
-            }
-        }
+    fn file_filter(
+        workspace: &str,
+        include: &[&str],
+        exclude: &[&str],
+    ) -> SearchFileFilter {
+        let globs = |globs: &[&str]| globs.iter().map(|g| g.to_string()).collect();
+        SearchFileFilter::new(
+            Some(PathBuf::from(workspace)),
+            &SearchFilter {
+                include: globs(include),
+                exclude: globs(exclude),
+                ..Default::default()
+            },
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_search_glob_set() {
+        assert!(search_glob_set(&[]).unwrap().is_none());
+        assert!(search_glob_set(&[" ".to_string()]).unwrap().is_none());
+        assert!(search_glob_set(&["[".to_string()]).is_err());
+
+        let globs = search_glob_set(&["*.rs".to_string(), "./docs/".to_string()])
+            .unwrap()
+            .unwrap();
+        // Globs without a `/` match anywhere, including directories whose
+        // content is then matched too
+        assert!(globs.is_match("main.rs"));
+        assert!(globs.is_match("src/main.rs"));
+        assert!(!globs.is_match("src/main.ts"));
+        // `./` anchors the glob to the workspace
+        assert!(globs.is_match("docs"));
+        assert!(globs.is_match("docs/guide/intro.md"));
+        assert!(!globs.is_match("src/docs/intro.md"));
     }
 
-    Ok(ProxyResponse::GlobalSearchResponse { matches })
+    #[test]
+    fn test_search_file_filter() {
+        let filter = file_filter("/ws", &["src", "*.toml"], &["target", "*.lock"]);
+        let path = |path: &str| Path::new("/ws").join(path);
+        assert!(filter.is_searched(&path("src/main.rs")));
+        assert!(filter.is_searched(&path("crates/a/src/lib.rs")));
+        assert!(filter.is_searched(&path("Cargo.toml")));
+        assert!(!filter.is_searched(&path("README.md")));
+        assert!(!filter.is_searched(&path("src/Cargo.lock")));
+        assert!(filter.is_excluded(&path("target")));
+        assert!(filter.is_excluded(&path("src/target/debug/main.rs")));
+        assert!(!filter.is_excluded(&path("src")));
+    }
+
+    #[test]
+    fn test_search_file_filter_glob_characters_in_workspace() {
+        // The workspace is stripped from the path rather than matched as a glob
+        let filter = file_filter("/ws/app[1]", &["./src"], &["*.min.js"]);
+        let path = |path: &str| Path::new("/ws/app[1]").join(path);
+        assert!(filter.is_searched(&path("src/main.js")));
+        assert!(!filter.is_searched(&path("lib/src/main.js")));
+        assert!(!filter.is_searched(&path("src/app.min.js")));
+        assert!(filter.is_excluded(&path("src/app.min.js")));
+    }
 }
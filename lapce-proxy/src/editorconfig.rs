@@ -0,0 +1,225 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use globset::{GlobBuilder, GlobMatcher};
+use lapce_rpc::editorconfig::{EditorConfigProperties, EndOfLine, IndentStyle};
+
+pub const FILE_NAME: &str = ".editorconfig";
+
+struct Section {
+    /// `None` if the section glob couldn't be parsed, in which case the section
+    /// never matches but still owns the properties that follow it.
+    matcher: Option<GlobMatcher>,
+    properties: Vec<(String, String)>,
+}
+
+struct EditorConfigFile {
+    root: bool,
+    sections: Vec<Section>,
+}
+
+impl EditorConfigFile {
+    fn parse(content: &str) -> Self {
+        let mut root = false;
+        let mut sections: Vec<Section> = Vec::new();
+        for line in content.trim_start_matches('\u{feff}').lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if let Some(glob) = line
+                .strip_prefix('[')
+                .and_then(|line| line.strip_suffix(']'))
+            {
+                sections.push(Section {
+                    matcher: section_matcher(glob),
+                    properties: Vec::new(),
+                });
+            } else if let Some((key, value)) = line.split_once('=') {
+                let key = key.trim().to_lowercase();
+                let value = value.trim().to_string();
+                match sections.last_mut() {
+                    Some(section) => section.properties.push((key, value)),
+                    None => {
+                        if key == "root" {
+                            root = value.eq_ignore_ascii_case("true");
+                        }
+                    }
+                }
+            }
+        }
+        Self { root, sections }
+    }
+
+    /// Apply the properties of every section matching `relative`, the path of
+    /// the file relative to the directory of this `.editorconfig`.
+    fn apply(&self, relative: &Path, properties: &mut HashMap<String, String>) {
+        for section in &self.sections {
+            if section
+                .matcher
+                .as_ref()
+                .is_some_and(|matcher| matcher.is_match(relative))
+            {
+                for (key, value) in &section.properties {
+                    properties.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+/// A section glob containing a `/` is relative to the directory of the
+/// `.editorconfig`, otherwise it matches file names at any depth.
+fn section_matcher(glob: &str) -> Option<GlobMatcher> {
+    let glob = if let Some(glob) = glob.strip_prefix('/') {
+        glob.to_string()
+    } else if glob.contains('/') {
+        glob.to_string()
+    } else {
+        format!("**/{glob}")
+    };
+    GlobBuilder::new(&glob)
+        .literal_separator(true)
+        .build()
+        .ok()
+        .map(|glob| glob.compile_matcher())
+}
+
+fn properties_from_raw(raw: &HashMap<String, String>) -> EditorConfigProperties {
+    let get = |key: &str| {
+        raw.get(key)
+            .map(|value| value.to_lowercase())
+            .filter(|value| value != "unset")
+    };
+    let get_bool = |key: &str| {
+        get(key).and_then(|value| match value.as_str() {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        })
+    };
+
+    let indent_style = get("indent_style").and_then(|value| match value.as_str() {
+        "tab" => Some(IndentStyle::Tab),
+        "space" => Some(IndentStyle::Space),
+        _ => None,
+    });
+    let tab_width = get("tab_width").and_then(|value| value.parse().ok());
+    let indent_size = get("indent_size").and_then(|value| {
+        if value == "tab" {
+            tab_width
+        } else {
+            value.parse().ok()
+        }
+    });
+    // As in the specification, `tab_width` defaults to `indent_size`, and
+    // `indent_size` to `tab_width` when indenting with tabs.
+    let tab_width = tab_width.or(indent_size);
+    let indent_size = indent_size.or(if indent_style == Some(IndentStyle::Tab) {
+        tab_width
+    } else {
+        None
+    });
+
+    EditorConfigProperties {
+        indent_style,
+        indent_size,
+        tab_width,
+        end_of_line: get("end_of_line").and_then(|value| match value.as_str() {
+            "lf" => Some(EndOfLine::Lf),
+            "crlf" => Some(EndOfLine::Crlf),
+            "cr" => Some(EndOfLine::Cr),
+            _ => None,
+        }),
+        charset: get("charset"),
+        trim_trailing_whitespace: get_bool("trim_trailing_whitespace"),
+        insert_final_newline: get_bool("insert_final_newline"),
+        max_line_length: get("max_line_length").and_then(|value| value.parse().ok()),
+    }
+}
+
+/// Resolve the `.editorconfig` properties of `path`, reading the
+/// `.editorconfig` files from its directory up to the first one marked with
+/// `root = true`, closer files taking precedence.
+///
+/// Also returns the `.editorconfig` files that were read, so they can be
+/// watched for changes.
+pub fn resolve(path: &Path) -> (EditorConfigProperties, Vec<PathBuf>) {
+    let mut files = Vec::new();
+    for dir in path.ancestors().skip(1) {
+        let file = dir.join(FILE_NAME);
+        let Ok(content) = fs::read_to_string(&file) else {
+            continue;
+        };
+        let config = EditorConfigFile::parse(&content);
+        let root = config.root;
+        files.push((file, dir, config));
+        if root {
+            break;
+        }
+    }
+
+    let mut raw = HashMap::new();
+    for (_, dir, config) in files.iter().rev() {
+        if let Ok(relative) = path.strip_prefix(dir) {
+            config.apply(relative, &mut raw);
+        }
+    }
+
+    (
+        properties_from_raw(&raw),
+        files.into_iter().map(|(file, _, _)| file).collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, path::Path};
+
+    use lapce_rpc::editorconfig::{EndOfLine, IndentStyle};
+
+    use super::{EditorConfigFile, properties_from_raw};
+
+    #[test]
+    fn test_editorconfig() {
+        let parent = EditorConfigFile::parse(
+            "root = true\n\n[*]\nindent_style = space\nindent_size = 4\nend_of_line = lf\n\n[*.{md,txt}]\ntrim_trailing_whitespace = false\n",
+        );
+        let child = EditorConfigFile::parse(
+            "# comment\n[Makefile]\nindent_style = tab\nindent_size = unset\n\n[/src/*.rs]\nmax_line_length = 100\n",
+        );
+        assert!(parent.root);
+        assert!(!child.root);
+
+        let resolve = |relative_to_parent: &str, relative_to_child: &str| {
+            let mut raw = HashMap::new();
+            parent.apply(Path::new(relative_to_parent), &mut raw);
+            child.apply(Path::new(relative_to_child), &mut raw);
+            properties_from_raw(&raw)
+        };
+
+        let properties = resolve("sub/Makefile", "Makefile");
+        assert_eq!(properties.indent_style, Some(IndentStyle::Tab));
+        assert_eq!(properties.indent_size, None);
+        assert_eq!(properties.end_of_line, Some(EndOfLine::Lf));
+
+        let properties = resolve("sub/src/main.rs", "src/main.rs");
+        assert_eq!(properties.indent_style, Some(IndentStyle::Space));
+        assert_eq!(properties.indent_size, Some(4));
+        assert_eq!(properties.tab_width, Some(4));
+        assert_eq!(properties.max_line_length, Some(100));
+
+        let properties = resolve("sub/a/src/main.rs", "a/src/main.rs");
+        assert_eq!(properties.max_line_length, None);
+
+        let mut raw = HashMap::new();
+        parent.apply(Path::new("docs/README.md"), &mut raw);
+        let properties = properties_from_raw(&raw);
+        assert_eq!(properties.trim_trailing_whitespace, Some(false));
+        assert_eq!(properties.indent_size, Some(4));
+    }
+}
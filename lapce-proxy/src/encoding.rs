@@ -0,0 +1,310 @@
+use std::borrow::Cow;
+
+use anyhow::{Result, anyhow};
+use encoding_rs::{Encoding, SHIFT_JIS, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use lapce_rpc::file::UTF8_BOM_ENCODING;
+
+/// How many bytes are looked at to detect UTF-16 without a byte order mark
+const UTF16_SAMPLE_LEN: usize = 4096;
+
+/// The encoding a file is read and saved with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileEncoding {
+    pub encoding: &'static Encoding,
+    /// Whether the file starts with a byte order mark
+    pub bom: bool,
+}
+
+impl Default for FileEncoding {
+    fn default() -> Self {
+        Self::UTF8
+    }
+}
+
+impl FileEncoding {
+    pub const UTF8: Self = Self {
+        encoding: UTF_8,
+        bom: false,
+    };
+
+    /// The name shown to the user, which [`FileEncoding::from_name`] accepts.
+    pub fn name(&self) -> &'static str {
+        if self.encoding == UTF_8 && self.bom {
+            UTF8_BOM_ENCODING
+        } else {
+            self.encoding.name()
+        }
+    }
+
+    /// The encoding of a name from [`lapce_rpc::file::ENCODINGS`], a WHATWG label or an
+    /// `.editorconfig` `charset`. UTF-16 is saved with a byte order mark.
+    pub fn from_name(name: &str) -> Option<Self> {
+        if name.eq_ignore_ascii_case(UTF8_BOM_ENCODING)
+            || name.eq_ignore_ascii_case("utf-8-bom")
+        {
+            return Some(Self {
+                encoding: UTF_8,
+                bom: true,
+            });
+        }
+
+        let encoding = Encoding::for_label(name.trim().as_bytes())?;
+        let utf16 = encoding == UTF_16LE || encoding == UTF_16BE;
+        // Encodings like `replacement` can only be decoded
+        if !utf16 && encoding.output_encoding() != encoding {
+            return None;
+        }
+        Some(Self {
+            encoding,
+            bom: utf16,
+        })
+    }
+
+    /// Decode the content of a file in this encoding, skipping its byte order
+    /// mark, with whether it has one. `None` if the content isn't valid in
+    /// this encoding, as it couldn't be saved back unchanged.
+    pub fn decode(&self, bytes: &[u8]) -> Option<(String, FileEncoding)> {
+        let (bytes, bom) = match Encoding::for_bom(bytes) {
+            Some((encoding, bom_len)) if encoding == self.encoding => {
+                (&bytes[bom_len..], true)
+            }
+            _ => (bytes, false),
+        };
+        let file_encoding = Self {
+            encoding: self.encoding,
+            bom,
+        };
+        file_encoding
+            .decode_without_bom(bytes)
+            .map(|text| (text, file_encoding))
+    }
+
+    fn decode_without_bom(&self, bytes: &[u8]) -> Option<String> {
+        self.encoding
+            .decode_without_bom_handling_and_without_replacement(bytes)
+            .map(Cow::into_owned)
+    }
+
+    /// Decode the content of a file, in the encoding of its byte order mark,
+    /// or in `preferred` if it's valid in it, or else in the encoding detected
+    /// from the content. `None` for binary content.
+    pub fn detect_and_decode(
+        bytes: &[u8],
+        preferred: Option<FileEncoding>,
+    ) -> Option<(String, FileEncoding)> {
+        if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+            // The preferred encoding only decides whether the BOM is kept
+            let bom = preferred
+                .filter(|preferred| preferred.encoding == encoding)
+                .is_none_or(|preferred| preferred.bom);
+            let file_encoding = Self { encoding, bom };
+            return file_encoding
+                .decode_without_bom(&bytes[bom_len..])
+                .map(|text| (text, file_encoding));
+        }
+
+        preferred
+            .and_then(|preferred| {
+                preferred
+                    .decode_without_bom(bytes)
+                    .map(|text| (text, preferred))
+            })
+            .or_else(|| Self::detect(bytes))
+    }
+
+    /// Guess the encoding of content without a byte order mark, and decode it
+    fn detect(bytes: &[u8]) -> Option<(String, Self)> {
+        // The ASCII characters of UTF-16 text have a zero byte, which is the
+        // second one in little endian. Such text is also valid UTF-8, so it's
+        // checked first. Binary content can have zeros in the same places,
+        // so the whole content has to decode to text.
+        let sample = &bytes[..bytes.len().min(UTF16_SAMPLE_LEN)];
+        let units = sample.len() / 2;
+        let zeros = |parity: usize| {
+            sample
+                .iter()
+                .skip(parity)
+                .step_by(2)
+                .filter(|b| **b == 0)
+                .count()
+        };
+        let (even_zeros, odd_zeros) = (zeros(0), zeros(1));
+        let utf16 = if units > 0 && even_zeros * 10 < units && odd_zeros * 2 > units
+        {
+            Some(UTF_16LE)
+        } else if units > 0 && odd_zeros * 10 < units && even_zeros * 2 > units {
+            Some(UTF_16BE)
+        } else {
+            None
+        };
+        if let Some(encoding) = utf16 {
+            let file_encoding = Self {
+                encoding,
+                bom: false,
+            };
+            if let Some(text) = file_encoding
+                .decode_without_bom(bytes)
+                .filter(|text| is_plain_text(text))
+            {
+                return Some((text, file_encoding));
+            }
+        }
+
+        if let Ok(text) = std::str::from_utf8(bytes) {
+            return Some((text.to_string(), Self::UTF8));
+        }
+
+        if sample.contains(&0) {
+            return None;
+        }
+
+        // Japanese text valid in Shift_JIS, which also decodes many Latin-1
+        // byte sequences, so it needs some kana to be chosen
+        if let Some(text) = SHIFT_JIS
+            .decode_without_bom_handling_and_without_replacement(bytes)
+            .filter(|text| {
+                text.chars().any(|c| matches!(c, '\u{3040}'..='\u{30ff}'))
+            })
+        {
+            return Some((
+                text.into_owned(),
+                Self {
+                    encoding: SHIFT_JIS,
+                    bom: false,
+                },
+            ));
+        }
+
+        let windows_1252 = Self {
+            encoding: WINDOWS_1252,
+            bom: false,
+        };
+        windows_1252
+            .decode_without_bom(bytes)
+            .map(|text| (text, windows_1252))
+    }
+
+    /// Encode the content of a file, failing for characters this encoding
+    /// can't represent.
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(text.len() + 3);
+        if self.encoding == UTF_16LE {
+            if self.bom {
+                bytes.extend_from_slice(&[0xFF, 0xFE]);
+            }
+            for unit in text.encode_utf16() {
+                bytes.extend_from_slice(&unit.to_le_bytes());
+            }
+        } else if self.encoding == UTF_16BE {
+            if self.bom {
+                bytes.extend_from_slice(&[0xFE, 0xFF]);
+            }
+            for unit in text.encode_utf16() {
+                bytes.extend_from_slice(&unit.to_be_bytes());
+            }
+        } else {
+            if self.encoding == UTF_8 && self.bom {
+                bytes.extend_from_slice(&[0xEF, 0xBB, 0xBF]);
+            }
+            let (encoded, _, unmappable) = self.encoding.encode(text);
+            if unmappable {
+                return Err(anyhow!(
+                    "the content has characters which can't be encoded in {}",
+                    self.name()
+                ));
+            }
+            bytes.extend_from_slice(&encoded);
+        }
+        Ok(bytes)
+    }
+}
+
+/// Whether decoded text has no control characters but whitespace, which text
+/// in the wrong encoding or binary content usually has
+fn is_plain_text(text: &str) -> bool {
+    !text
+        .chars()
+        .any(|c| c.is_control() && !matches!(c, '\t' | '\n' | '\r' | '\x0c'))
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::{SHIFT_JIS, UTF_8, UTF_16BE, UTF_16LE, WINDOWS_1252};
+
+    use super::FileEncoding;
+
+    fn round_trip(bytes: &[u8]) -> (String, FileEncoding) {
+        let (text, encoding) = FileEncoding::detect_and_decode(bytes, None).unwrap();
+        assert_eq!(encoding.encode(&text).unwrap(), bytes);
+        (text, encoding)
+    }
+
+    #[test]
+    fn test_detect_encoding() {
+        let (text, encoding) = round_trip("héllo\n".as_bytes());
+        assert_eq!(text, "héllo\n");
+        assert_eq!(encoding, FileEncoding::UTF8);
+
+        let (text, encoding) = round_trip(b"\xEF\xBB\xBFhello");
+        assert_eq!(text, "hello");
+        assert_eq!(encoding.name(), "UTF-8 with BOM");
+
+        let (text, encoding) = round_trip(b"caf\xE9 \x80\x81\n");
+        assert_eq!(text, "café €\u{81}\n");
+        assert_eq!(encoding.encoding, WINDOWS_1252);
+
+        let (text, encoding) = round_trip(b"\xFF\xFEh\0i\0");
+        assert_eq!(text, "hi");
+        assert_eq!((encoding.encoding, encoding.bom), (UTF_16LE, true));
+
+        let (text, encoding) = round_trip(b"\0l\0o\0g\0\n");
+        assert_eq!(text, "log\n");
+        assert_eq!((encoding.encoding, encoding.bom), (UTF_16BE, false));
+
+        let japanese = "こんにちは、世界\n";
+        let (bytes, _, _) = SHIFT_JIS.encode(japanese);
+        let (text, encoding) = round_trip(&bytes);
+        assert_eq!(text, japanese);
+        assert_eq!(encoding.encoding, SHIFT_JIS);
+
+        assert!(
+            FileEncoding::detect_and_decode(b"\x89PNG\r\n\x1a\n\0\0\x80", None)
+                .is_none()
+        );
+
+        // Binary content with zeros in the places of UTF-16 text
+        let (_, encoding) =
+            FileEncoding::detect_and_decode(b"\x01\0\x02\0\x03\0\x04\0", None)
+                .unwrap();
+        assert_eq!(encoding, FileEncoding::UTF8);
+    }
+
+    #[test]
+    fn test_preferred_encoding() {
+        let utf8_bom = FileEncoding::from_name("utf-8-bom").unwrap();
+        let (text, encoding) =
+            FileEncoding::detect_and_decode(b"hello", Some(utf8_bom)).unwrap();
+        assert_eq!(encoding.encode(&text).unwrap(), b"\xEF\xBB\xBFhello");
+
+        let (_, encoding) = FileEncoding::detect_and_decode(
+            b"\xEF\xBB\xBFhello",
+            Some(FileEncoding::UTF8),
+        )
+        .unwrap();
+        assert_eq!((encoding.encoding, encoding.bom), (UTF_8, false));
+
+        let latin1 = FileEncoding::from_name("latin1").unwrap();
+        let (text, _) =
+            FileEncoding::detect_and_decode("é".as_bytes(), Some(latin1)).unwrap();
+        assert_eq!(text, "Ã©");
+
+        assert!(latin1.encode("日本").is_err());
+
+        let utf16 = FileEncoding::from_name("utf-16le").unwrap();
+        let (text, encoding) = utf16.decode(b"h\0i\0").unwrap();
+        assert_eq!(text, "hi");
+        assert!(!encoding.bom);
+        assert!(FileEncoding::UTF8.decode(b"\xFF\xFEh\0").is_none());
+        assert!(FileEncoding::from_name("replacement").is_none());
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::HashMap;
+
+/// The kinds of git forges whose web urls of files are known
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Forge {
+    GitHub,
+    GitLab,
+    Bitbucket,
+    /// Gitea and its forks, e.g. Forgejo and Codeberg
+    Gitea,
+}
+
+impl Forge {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "github" => Some(Forge::GitHub),
+            "gitlab" => Some(Forge::GitLab),
+            "bitbucket" => Some(Forge::Bitbucket),
+            "gitea" | "forgejo" | "codeberg" => Some(Forge::Gitea),
+            _ => None,
+        }
+    }
+
+    /// Find the forge of the host, with the overrides from the settings taking
+    /// precedence, so that self-hosted instances can be configured.
+    /// Hosts which can't be detected are assumed to be GitHub like.
+    pub fn detect(host: &str, overrides: &HashMap<String, String>) -> Self {
+        if let Some(forge) =
+            overrides.get(host).and_then(|name| Forge::from_name(name))
+        {
+            return forge;
+        }
+
+        let host = host.to_lowercase();
+        if host.contains("gitlab") {
+            Forge::GitLab
+        } else if host.contains("bitbucket") {
+            Forge::Bitbucket
+        } else if ["gitea", "forgejo", "codeberg"]
+            .iter()
+            .any(|name| host.contains(name))
+        {
+            Forge::Gitea
+        } else {
+            Forge::GitHub
+        }
+    }
+
+    /// The template of the url of a file, with the `{host}`, `{namespace}`,
+    /// `{rev}` and `{path}` placeholders
+    fn file_template(&self, is_commit: bool) -> &'static str {
+        match self {
+            Forge::GitHub => "https://{host}/{namespace}/blob/{rev}/{path}",
+            Forge::GitLab => "https://{host}/{namespace}/-/blob/{rev}/{path}",
+            Forge::Bitbucket => "https://{host}/{namespace}/src/{rev}/{path}",
+            Forge::Gitea if is_commit => {
+                "https://{host}/{namespace}/src/commit/{rev}/{path}"
+            }
+            Forge::Gitea => "https://{host}/{namespace}/src/branch/{rev}/{path}",
+        }
+    }
+
+    /// The templates of the anchor of a single line and of a range of lines,
+    /// with the `{start}` and `{end}` placeholders
+    fn line_templates(&self) -> (&'static str, &'static str) {
+        match self {
+            Forge::GitHub | Forge::Gitea => ("#L{start}", "#L{start}-L{end}"),
+            Forge::GitLab => ("#L{start}", "#L{start}-{end}"),
+            Forge::Bitbucket => ("#lines-{start}", "#lines-{start}:{end}"),
+        }
+    }
+
+    /// Format the url of the file at the revision, which is either a commit or
+    /// a branch. The lines are 1-based and inclusive.
+    pub fn file_url(
+        &self,
+        host: &str,
+        namespace: &str,
+        rev: &str,
+        is_commit: bool,
+        path: &str,
+        lines: Option<(usize, usize)>,
+    ) -> String {
+        let mut url = self
+            .file_template(is_commit)
+            .replace("{host}", host)
+            .replace("{namespace}", namespace.trim_matches('/'))
+            .replace("{rev}", rev)
+            .replace("{path}", path);
+        if let Some((start, end)) = lines {
+            let (line, range) = self.line_templates();
+            let anchor = if start == end { line } else { range };
+            url.push_str(
+                &anchor
+                    .replace("{start}", &start.to_string())
+                    .replace("{end}", &end.to_string()),
+            );
+        }
+        url
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::Forge;
+
+    #[test]
+    fn test_file_url() {
+        let overrides =
+            HashMap::from([("git.example.com".to_string(), "gitlab".to_string())]);
+        assert_eq!(Forge::detect("github.com", &overrides), Forge::GitHub);
+        assert_eq!(Forge::detect("codeberg.org", &overrides), Forge::Gitea);
+        assert_eq!(Forge::detect("git.example.com", &overrides), Forge::GitLab);
+
+        assert_eq!(
+            Forge::GitHub.file_url(
+                "github.com",
+                "/lapce/lapce",
+                "abc",
+                true,
+                "src/main.rs",
+                Some((10, 20))
+            ),
+            "https://github.com/lapce/lapce/blob/abc/src/main.rs#L10-L20"
+        );
+        assert_eq!(
+            Forge::GitLab.file_url(
+                "gitlab.com",
+                "/group/sub/project",
+                "main",
+                false,
+                "README.md",
+                Some((3, 3))
+            ),
+            "https://gitlab.com/group/sub/project/-/blob/main/README.md#L3"
+        );
+        assert_eq!(
+            Forge::Bitbucket.file_url(
+                "bitbucket.org",
+                "/team/repo",
+                "abc",
+                true,
+                "a.rs",
+                Some((1, 2))
+            ),
+            "https://bitbucket.org/team/repo/src/abc/a.rs#lines-1:2"
+        );
+        assert_eq!(
+            Forge::Gitea.file_url(
+                "codeberg.org",
+                "/user/repo",
+                "main",
+                false,
+                "a.rs",
+                None
+            ),
+            "https://codeberg.org/user/repo/src/branch/main/a.rs"
+        );
+    }
+}
@@ -3,12 +3,17 @@
 pub mod buffer;
 pub mod cli;
 pub mod dispatch;
+pub mod editorconfig;
+pub mod encoding;
+pub mod forge;
 pub mod plugin;
 pub mod terminal;
+pub mod vcs;
 pub mod watcher;
 
 use std::{
     io::{BufReader, stdin, stdout},
+    path::PathBuf,
     process::exit,
     sync::Arc,
     thread,
@@ -34,6 +39,10 @@ struct Cli {
     #[clap(short, long, action, hide = true)]
     proxy: bool,
 
+    /// The todo list to replace the one of an interactive rebase with
+    #[clap(long, hide = true)]
+    rebase_todo: Option<PathBuf>,
+
     /// Paths to file(s) and/or folder(s) to open.
     /// When path is a file (that exists or not),
     /// it accepts `path:line:column` syntax
@@ -45,6 +54,9 @@ struct Cli {
 
 pub fn mainloop() {
     let cli = Cli::parse();
+    if let Some(todo) = cli.rebase_todo.as_deref() {
+        cli::edit_rebase_todo(todo, &cli.paths);
+    }
     if !cli.proxy {
         if let Err(e) = cli::try_open_in_existing_process(&cli.paths) {
             error!("failed to open path(s): {e}");
@@ -359,6 +359,7 @@ pub fn find_all_volts(extra_plugin_paths: &[PathBuf]) -> Vec<VoltMetadata> {
 ///         wasm: None,
 ///         color_themes: None,
 ///         icon_themes: None,
+///         languages: None,
 ///         dir: parent_path.canonicalize().ok(),
 ///         activation: None,
 ///         config: None
@@ -397,6 +398,20 @@ pub fn load_volt(path: &Path) -> Result<VoltMetadata> {
             })
             .collect()
     });
+    meta.languages = meta.languages.as_ref().map(|languages| {
+        languages
+            .iter()
+            .filter_map(|language| {
+                Some(
+                    path.join(language)
+                        .canonicalize()
+                        .ok()?
+                        .to_str()?
+                        .to_string(),
+                )
+            })
+            .collect()
+    });
 
     Ok(meta)
 }
@@ -0,0 +1,1261 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, atomic::AtomicU64},
+};
+
+use anyhow::{Context, Result, anyhow};
+use git2::{
+    BlameOptions, BranchType, DiffFindOptions, DiffOptions, ErrorCode::NotFound,
+    Oid, Repository, RepositoryState, Sort, SubmoduleIgnore, SubmoduleStatus,
+    WorktreeAddOptions, build::CheckoutBuilder,
+};
+use lapce_core::{
+    buffer::diff::{DiffLines, rope_diff},
+    merge_conflict::has_conflicts,
+};
+use lapce_rpc::source_control::{
+    BlameCommit, BlameHunk, DiffInfo, FileDiff, GitCommit, RebaseAction,
+};
+use lapce_xi_rope::Rope;
+use lsp_types::Url;
+use parking_lot::Mutex;
+
+use super::{VcsBackend, run_command};
+use crate::forge::Forge;
+
+/// The default backend, which uses libgit2
+pub struct GitBackend {
+    workspace_path: PathBuf,
+}
+
+impl GitBackend {
+    pub fn new(workspace_path: &Path) -> Self {
+        Self {
+            workspace_path: workspace_path.to_path_buf(),
+        }
+    }
+}
+
+impl VcsBackend for GitBackend {
+    fn name(&self) -> &'static str {
+        "Git"
+    }
+
+    fn is_git(&self) -> bool {
+        true
+    }
+
+    fn diff_info(&self) -> Option<DiffInfo> {
+        git_diff_new(&self.workspace_path)
+    }
+
+    fn file_head(&self, path: &Path) -> Result<(String, String)> {
+        file_get_head(&self.workspace_path, path)
+    }
+
+    fn file_at_revision(
+        &self,
+        path: &Path,
+        revision: &str,
+    ) -> Result<Option<String>> {
+        file_get_revision(&self.workspace_path, path, revision)
+    }
+
+    fn commit(
+        &self,
+        message: &str,
+        diffs: Vec<FileDiff>,
+        amend: bool,
+        sign_off: bool,
+    ) -> Result<()> {
+        git_commit(&self.workspace_path, message, diffs, amend, sign_off)
+    }
+
+    fn checkout(&self, reference: &str) -> Result<()> {
+        git_checkout(&self.workspace_path, reference)
+    }
+
+    fn discard_files_changes(&self, files: &[PathBuf]) -> Result<()> {
+        // The files may be in different submodules, each of which is discarded
+        // in its own repository
+        let mut repositories: HashMap<PathBuf, Vec<&Path>> = HashMap::new();
+        for file in files {
+            let workdir = file
+                .parent()
+                .and_then(|dir| Repository::discover(dir).ok())
+                .and_then(|repo| repo.workdir().map(Path::to_path_buf))
+                .unwrap_or_else(|| self.workspace_path.clone());
+            repositories.entry(workdir).or_default().push(file);
+        }
+        for (workdir, files) in repositories {
+            // The workspace may be a sub directory of its repository
+            let root = if self.workspace_path.starts_with(&workdir) {
+                &self.workspace_path
+            } else {
+                &workdir
+            };
+            git_discard_files_changes(root, files.into_iter())?;
+        }
+        Ok(())
+    }
+
+    fn discard_workspace_changes(&self) -> Result<()> {
+        git_discard_workspace_changes(&self.workspace_path)
+    }
+}
+
+/// The blame of a file at HEAD, together with the hunks last computed from it
+/// for a buffer revision
+pub struct BlameCache {
+    head: Oid,
+    head_rope: Rope,
+    commits: Vec<BlameCommit>,
+    /// Index into `commits` for every line of the file at HEAD
+    head_lines: Vec<usize>,
+    rev: u64,
+    hunks: Vec<BlameHunk>,
+}
+
+#[derive(Clone, Debug)]
+pub struct DiffHunk {
+    pub old_start: u32,
+    pub old_lines: u32,
+    pub new_start: u32,
+    pub new_lines: u32,
+    pub header: String,
+}
+
+pub fn git_init(workspace_path: &Path) -> Result<()> {
+    if Repository::discover(workspace_path).is_err() {
+        Repository::init(workspace_path)?;
+    };
+    Ok(())
+}
+
+fn git_commit(
+    workspace_path: &Path,
+    message: &str,
+    diffs: Vec<FileDiff>,
+    amend: bool,
+    sign_off: bool,
+) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let mut index = repo.index()?;
+    for diff in diffs {
+        match diff {
+            FileDiff::Modified(p) | FileDiff::Added(p) => {
+                index.add_path(p.strip_prefix(workspace_path)?)?;
+            }
+            FileDiff::Renamed(a, d) => {
+                index.add_path(a.strip_prefix(workspace_path)?)?;
+                index.remove_path(d.strip_prefix(workspace_path)?)?;
+            }
+            FileDiff::Deleted(p) => {
+                index.remove_path(p.strip_prefix(workspace_path)?)?;
+            }
+        }
+    }
+    index.write()?;
+    let tree = index.write_tree()?;
+    let tree = repo.find_tree(tree)?;
+
+    let signature = match repo.signature() {
+        Ok(signature) => signature,
+        Err(e) => {
+            return match e.code() {
+                NotFound => Err(anyhow!(
+                    "No user.name and/or user.email configured for this git repository."
+                )),
+                _ => Err(anyhow!(
+                    "Error while creating commit's signature: {}",
+                    e.message()
+                )),
+            };
+        }
+    };
+
+    let head = repo.head().and_then(|head| head.peel_to_commit()).ok();
+    let (author, parents) = if amend {
+        let head = head.ok_or(anyhow!("There is no commit to amend"))?;
+        (head.author().to_owned(), head.parents().collect::<Vec<_>>())
+    } else {
+        (signature.clone(), head.into_iter().collect())
+    };
+    let parents_refs = parents.iter().collect::<Vec<_>>();
+
+    // Strip the comments, e.g. of the commit template, like git does
+    let message = git2::message_prettify(message, Some(b'#'))?;
+    let message = if sign_off {
+        super::sign_off_message(
+            &message,
+            signature.name().unwrap_or(""),
+            signature.email().unwrap_or(""),
+        )
+    } else {
+        message
+    };
+
+    let config = repo.config()?;
+    let oid = if config.get_bool("commit.gpgsign").unwrap_or(false) {
+        let buffer = repo.commit_create_buffer(
+            &author,
+            &signature,
+            &message,
+            &tree,
+            &parents_refs,
+        )?;
+        let buffer = std::str::from_utf8(&buffer)
+            .with_context(|| "commit buffer to string")?;
+        let signed = sign_commit_buffer(&config, buffer)?;
+        repo.commit_signed(buffer, &signed, None)?
+    } else {
+        repo.commit(None, &author, &signature, &message, &tree, &parents_refs)?
+    };
+
+    // Move the branch HEAD points to, which may not exist yet for the first
+    // commit of a repository
+    let head_ref = repo.find_reference("HEAD")?;
+    let branch = head_ref
+        .symbolic_target()
+        .map(|target| target.to_string())
+        .unwrap_or_else(|| "HEAD".to_string());
+    let summary = message.lines().next().unwrap_or("");
+    let log_message = if amend {
+        format!("commit (amend): {summary}")
+    } else {
+        format!("commit: {summary}")
+    };
+    repo.reference(&branch, oid, true, &log_message)?;
+
+    Ok(())
+}
+
+/// Sign the commit with the program configured by `gpg.format`, the way git
+/// does when `commit.gpgsign` is set
+fn sign_commit_buffer(config: &git2::Config, buffer: &str) -> Result<String> {
+    let format = config
+        .get_string("gpg.format")
+        .unwrap_or_else(|_| "openpgp".to_string());
+    let mut command = match format.as_str() {
+        "ssh" => {
+            let program = config
+                .get_string("gpg.ssh.program")
+                .unwrap_or_else(|_| "ssh-keygen".to_string());
+            let key = config.get_path("user.signingkey").map_err(|_| {
+                anyhow!("user.signingkey needs to be set to sign commits with ssh")
+            })?;
+            let mut command = std::process::Command::new(program);
+            command.args(["-Y", "sign", "-n", "git", "-f"]).arg(key);
+            command
+        }
+        format => {
+            let (name, default) = if format == "x509" {
+                ("gpg.x509.program", "gpgsm")
+            } else {
+                ("gpg.program", "gpg")
+            };
+            let program = config
+                .get_string(name)
+                .unwrap_or_else(|_| default.to_string());
+            let mut command = std::process::Command::new(program);
+            match config.get_string("user.signingkey") {
+                Ok(key) => command.args(["--status-fd=2", "-bsau", &key]),
+                Err(_) => command.args(["--status-fd=2", "-bsa"]),
+            };
+            command
+        }
+    };
+
+    let mut child = command
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()
+        .with_context(|| "failed to start the signing program")?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(buffer.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Failed to sign the commit: {}",
+            String::from_utf8_lossy(&output.stderr)
+        ));
+    }
+    Ok(String::from_utf8(output.stdout)?)
+}
+
+/// The content of the file configured by `commit.template`, if any
+pub fn git_commit_template(workspace_path: &Path) -> Result<Option<String>> {
+    let repo = Repository::discover(workspace_path)?;
+    let Ok(path) = repo.config()?.get_path("commit.template") else {
+        return Ok(None);
+    };
+    // Relative paths are relative to the work tree, like git does
+    let path = repo.workdir().unwrap_or(workspace_path).join(path);
+    Ok(Some(fs::read_to_string(path)?))
+}
+
+/// The working trees of the repository, the main one first
+pub fn git_worktrees(workspace_path: &Path) -> Result<Vec<PathBuf>> {
+    let repo = Repository::discover(workspace_path)?;
+    // The common directory is the `.git` of the main working tree
+    let mut worktrees: Vec<PathBuf> = repo
+        .commondir()
+        .parent()
+        .map(Path::to_path_buf)
+        .into_iter()
+        .collect();
+    for name in repo.worktrees()?.iter().flatten() {
+        worktrees.push(repo.find_worktree(name)?.path().to_path_buf());
+    }
+    Ok(worktrees)
+}
+
+/// Add a working tree with the local branch checked out, next to the main
+/// working tree and named after both
+pub fn git_create_worktree(workspace_path: &Path, branch: &str) -> Result<PathBuf> {
+    let repo = Repository::discover(workspace_path)?;
+    let main = repo
+        .commondir()
+        .parent()
+        .ok_or(anyhow!("Can't find the main working tree"))?;
+    let repo_name = main
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("worktree");
+    let name = branch.replace(['/', '\\'], "-");
+    let path = main.with_file_name(format!("{repo_name}-{name}"));
+    if path.exists() {
+        return Err(anyhow!("{} already exists", path.display()));
+    }
+
+    let reference = repo
+        .find_branch(branch, BranchType::Local)?
+        .into_reference();
+    let mut options = WorktreeAddOptions::new();
+    options.reference(Some(&reference));
+    repo.worktree(&name, &path, Some(&options))?;
+    Ok(path)
+}
+
+pub fn git_last_commit_message(workspace_path: &Path) -> Result<String> {
+    let repo = Repository::discover(workspace_path)?;
+    let commit = repo.head()?.peel_to_commit()?;
+    Ok(commit.message().unwrap_or("").to_string())
+}
+
+fn git_checkout(workspace_path: &Path, reference: &str) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let (object, reference) = repo.revparse_ext(reference)?;
+    repo.checkout_tree(&object, None)?;
+    repo.set_head(reference.unwrap().name().unwrap())?;
+    Ok(())
+}
+
+fn git_discard_files_changes<'a>(
+    workspace_path: &Path,
+    files: impl Iterator<Item = &'a Path>,
+) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+
+    let mut checkout_b = CheckoutBuilder::new();
+    checkout_b.update_only(false).force();
+
+    let mut had_path = false;
+    for path in files {
+        // Remove the workspace path so it is relative to the folder
+        if let Ok(path) = path.strip_prefix(workspace_path) {
+            had_path = true;
+            checkout_b.path(path);
+        }
+    }
+
+    if !had_path {
+        // If there we no paths then we do nothing
+        // because the default behavior of checkout builder is to select all files
+        // if it is not given a path
+        return Ok(());
+    }
+
+    repo.checkout_index(None, Some(&mut checkout_b))?;
+
+    Ok(())
+}
+
+/// Stage the file to mark its merge conflicts as resolved
+pub fn git_mark_resolved(workspace_path: &Path, path: &Path) -> Result<()> {
+    let content = fs::read_to_string(path)?;
+    if has_conflicts(&Rope::from(content)) {
+        return Err(anyhow!(
+            "{} still contains conflict markers",
+            path.display()
+        ));
+    }
+
+    let repo = Repository::discover(workspace_path)?;
+    let workdir = repo
+        .workdir()
+        .ok_or(anyhow!("Can't stage in a bare repository"))?;
+    let mut index = repo.index()?;
+    index.add_path(path.strip_prefix(workdir)?)?;
+    index.write()?;
+    Ok(())
+}
+
+/// The comment at the end of the todo list written for an interactive rebase
+const REBASE_TODO_HELP: &str = "
+# Commands:
+# p, pick <commit> = use commit
+# r, reword <commit> = use commit, but edit the commit message
+# e, edit <commit> = use commit, but stop for amending
+# s, squash <commit> = use commit, but meld into previous commit
+# f, fixup <commit> = like \"squash\", but discard this commit's log message
+# d, drop <commit> = remove commit
+#
+# These lines can be re-ordered with alt+up and alt+down, and their action
+# changed with alt+. and alt+,; they are executed from top to bottom.
+";
+
+fn is_rebasing(repo: &Repository) -> bool {
+    matches!(
+        repo.state(),
+        RepositoryState::Rebase
+            | RepositoryState::RebaseInteractive
+            | RepositoryState::RebaseMerge
+    )
+}
+
+/// Write the todo list of rebasing the commits since the merge base with
+/// `onto`, the oldest first, to a `git-rebase-todo` file in the git directory
+pub fn git_rebase_todo(workspace_path: &Path, onto: &str) -> Result<PathBuf> {
+    let repo = Repository::discover(workspace_path)?;
+    let onto_id = repo.revparse_single(onto)?.peel_to_commit()?.id();
+    let head = repo.head()?.peel_to_commit()?.id();
+    let base = repo.merge_base(onto_id, head)?;
+
+    let mut revwalk = repo.revwalk()?;
+    revwalk.push(head)?;
+    revwalk.hide(base)?;
+    revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)?;
+    let mut todo = String::new();
+    for oid in revwalk {
+        let commit = repo.find_commit(oid?)?;
+        // Merge commits are dropped by a rebase
+        if commit.parent_count() > 1 {
+            continue;
+        }
+        let id = commit.as_object().short_id()?;
+        todo.push_str(&format!(
+            "pick {} {}\n",
+            id.as_str().unwrap_or_default(),
+            commit.summary().unwrap_or("")
+        ));
+    }
+    if todo.is_empty() {
+        return Err(anyhow!("There are no commits to rebase onto {onto}"));
+    }
+    todo.push_str(REBASE_TODO_HELP);
+
+    let dir = repo.path().join("lapce");
+    fs::create_dir_all(&dir)?;
+    let path = dir.join("git-rebase-todo");
+    fs::write(&path, todo)?;
+    Ok(path)
+}
+
+/// Rebase onto the revision following the todo list, or the one written by
+/// [`git_rebase_todo`] if `None`
+pub fn git_rebase(
+    workspace_path: &Path,
+    onto: &str,
+    todo: Option<String>,
+) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let path = repo.path().join("lapce").join("git-rebase-todo");
+    let todo = match todo {
+        Some(todo) => todo,
+        None => fs::read_to_string(&path)?,
+    };
+    let (todo, reworded) = reword_as_edit(&todo);
+    fs::write(&path, todo)?;
+    fs::write(path.with_file_name("rebase-reword"), reworded.join("\n"))?;
+
+    // git runs the sequence editor with the path of its own todo list, which
+    // is replaced with ours by running this executable again
+    let exe = std::env::current_exe()?;
+    run_rebase(
+        &repo,
+        &[
+            "-c".to_string(),
+            format!(
+                "sequence.editor={} --rebase-todo {}",
+                shell_quote(&exe),
+                shell_quote(&path)
+            ),
+            "rebase".to_string(),
+            "-i".to_string(),
+            onto.to_string(),
+        ],
+    )
+}
+
+/// Quote a path for the shell git runs its editors with, which is `sh` on
+/// Windows too
+fn shell_quote(path: &Path) -> String {
+    let path = path
+        .to_string_lossy()
+        .replace('\\', "/")
+        .replace('\'', "'\\''");
+    format!("'{path}'")
+}
+
+/// Replace the todo list git is editing with the one written for the rebase,
+/// run as the sequence editor of [`git_rebase`]
+pub fn write_rebase_todo(todo: &Path, git_todo: &Path) -> Result<()> {
+    fs::copy(todo, git_todo)?;
+    Ok(())
+}
+
+/// Continue, skip or abort the rebase which stopped
+pub fn git_rebase_action(workspace_path: &Path, action: RebaseAction) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    if !is_rebasing(&repo) {
+        return Err(anyhow!("No rebase in progress"));
+    }
+    let arg = match action {
+        RebaseAction::Continue => "--continue",
+        RebaseAction::Skip => "--skip",
+        RebaseAction::Abort => "--abort",
+    };
+    run_rebase(&repo, &["rebase".to_string(), arg.to_string()])
+}
+
+/// Run a rebase command, which takes the messages of squashed commits as they
+/// are. Stopping at a conflict isn't a failure, the conflicts are listed in the
+/// source control panel.
+fn run_rebase(repo: &Repository, args: &[String]) -> Result<()> {
+    let workdir = repo
+        .workdir()
+        .ok_or(anyhow!("Can't rebase a bare repository"))?;
+    let mut command = vec!["-c".to_string(), "core.editor=true".to_string()];
+    command.extend_from_slice(args);
+    match run_command("git", workdir, &command) {
+        Ok(_) => Ok(()),
+        Err(e) => {
+            let repo = Repository::open(repo.path())?;
+            if is_rebasing(&repo) && repo.index()?.has_conflicts() {
+                Ok(())
+            } else {
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Replace the reword commands of the todo list with edit, returning the
+/// commits to reword. git would stop for the message to be edited in an
+/// editor, which the rebase isn't run with, while an edited commit is amended
+/// from the commit box.
+fn reword_as_edit(todo: &str) -> (String, Vec<String>) {
+    let mut reworded = Vec::new();
+    let mut edited = String::new();
+    for line in todo.lines() {
+        let mut words = line.split_whitespace();
+        match (words.next(), words.next()) {
+            (Some(action @ ("reword" | "r")), Some(commit)) => {
+                reworded.push(commit.to_string());
+                edited.push_str(&line.replacen(action, "edit", 1));
+            }
+            _ => edited.push_str(line),
+        }
+        edited.push('\n');
+    }
+    (edited, reworded)
+}
+
+/// The commit the rebase stopped at, if it was to be reworded
+fn rewording_commit(repo: &Repository) -> Option<String> {
+    if !is_rebasing(repo) {
+        return None;
+    }
+    let stopped =
+        fs::read_to_string(repo.path().join("rebase-merge").join("stopped-sha"))
+            .ok()?;
+    let stopped = stopped.trim();
+    let reworded =
+        fs::read_to_string(repo.path().join("lapce").join("rebase-reword")).ok()?;
+    // Either id may be abbreviated
+    reworded
+        .lines()
+        .any(|id| {
+            !id.is_empty() && (stopped.starts_with(id) || id.starts_with(stopped))
+        })
+        .then(|| stopped.to_string())
+}
+
+fn git_discard_workspace_changes(workspace_path: &Path) -> Result<()> {
+    let repo = Repository::discover(workspace_path)?;
+    let mut checkout_b = CheckoutBuilder::new();
+    checkout_b.force();
+
+    repo.checkout_index(None, Some(&mut checkout_b))?;
+
+    Ok(())
+}
+
+fn git_delta_format(
+    workdir: &Path,
+    delta: &git2::DiffDelta,
+) -> Option<(git2::Delta, git2::Oid, PathBuf)> {
+    match delta.status() {
+        git2::Delta::Added | git2::Delta::Untracked => Some((
+            git2::Delta::Added,
+            delta.new_file().id(),
+            delta.new_file().path().map(|p| workdir.join(p))?,
+        )),
+        git2::Delta::Deleted => Some((
+            git2::Delta::Deleted,
+            delta.old_file().id(),
+            delta.old_file().path().map(|p| workdir.join(p))?,
+        )),
+        git2::Delta::Modified => Some((
+            git2::Delta::Modified,
+            delta.new_file().id(),
+            delta.new_file().path().map(|p| workdir.join(p))?,
+        )),
+        _ => None,
+    }
+}
+
+fn git_diff_new(workspace_path: &Path) -> Option<DiffInfo> {
+    let repo = Repository::discover(workspace_path).ok()?;
+    let name = match repo.head() {
+        Ok(head) => head.shorthand()?.to_string(),
+        _ => "(No branch)".to_owned(),
+    };
+
+    let mut branches = Vec::new();
+    for branch in repo.branches(None).ok()? {
+        branches.push(branch.ok()?.0.name().ok()??.to_string());
+    }
+
+    let mut tags = Vec::new();
+    if let Ok(git_tags) = repo.tag_names(None) {
+        for tag in git_tags.into_iter().flatten() {
+            tags.push(tag.to_owned());
+        }
+    }
+
+    // The paths in the repository are relative to its working directory,
+    // which the workspace may be a subdirectory of
+    let workdir = repo.workdir().unwrap_or(workspace_path);
+
+    let mut conflicts = Vec::new();
+    if let Ok(index) = repo.index() {
+        if let Ok(index_conflicts) = index.conflicts() {
+            for conflict in index_conflicts.flatten() {
+                if let Some(entry) =
+                    conflict.our.or(conflict.their).or(conflict.ancestor)
+                {
+                    let path = String::from_utf8_lossy(&entry.path).to_string();
+                    conflicts.push(workdir.join(path));
+                }
+            }
+        }
+    }
+    conflicts.sort();
+    conflicts.dedup();
+
+    // A submodule whose commit is unchanged shows up as modified when it has
+    // changes of its own, which are listed with the submodule instead
+    let mut submodules = Vec::new();
+    let mut unchanged_submodules = HashSet::new();
+    for submodule in repo.submodules().unwrap_or_default() {
+        let path = workdir.join(submodule.path());
+        if let Some(name) = submodule.name() {
+            if let Ok(status) = repo.submodule_status(name, SubmoduleIgnore::None) {
+                if !status.intersects(
+                    SubmoduleStatus::WD_MODIFIED | SubmoduleStatus::INDEX_MODIFIED,
+                ) {
+                    unchanged_submodules.insert(path.clone());
+                }
+            }
+        }
+        if submodule.open().is_ok() {
+            if let Some(diff) = git_diff_new(&path) {
+                submodules.push(diff);
+            }
+        }
+    }
+
+    let mut deltas = Vec::new();
+    let mut untracked = Vec::new();
+    let mut ignored = Vec::new();
+    let mut diff_options = DiffOptions::new();
+    let diff = repo
+        .diff_index_to_workdir(
+            None,
+            Some(
+                diff_options
+                    .include_untracked(true)
+                    .recurse_untracked_dirs(true)
+                    .include_ignored(true),
+            ),
+        )
+        .ok()?;
+    for delta in diff.deltas() {
+        let path = delta.new_file().path().map(|p| workdir.join(p));
+        match delta.status() {
+            git2::Delta::Untracked => untracked.extend(path),
+            git2::Delta::Ignored => ignored.extend(path),
+            _ => {}
+        }
+        if let Some(delta) = git_delta_format(workdir, &delta) {
+            deltas.push(delta);
+        }
+    }
+
+    let oid = match repo.revparse_single("HEAD^{tree}") {
+        Ok(obj) => obj.id(),
+        _ => Oid::zero(),
+    };
+
+    let cached_diff = repo
+        .diff_tree_to_index(repo.find_tree(oid).ok().as_ref(), None, None)
+        .ok();
+
+    if let Some(cached_diff) = cached_diff {
+        for delta in cached_diff.deltas() {
+            if let Some(delta) = git_delta_format(workdir, &delta) {
+                deltas.push(delta);
+            }
+        }
+    }
+    let mut renames = Vec::new();
+    let mut renamed_deltas = HashSet::new();
+
+    for (added_index, delta) in deltas.iter().enumerate() {
+        if delta.0 == git2::Delta::Added {
+            for (deleted_index, d) in deltas.iter().enumerate() {
+                if d.0 == git2::Delta::Deleted && d.1 == delta.1 {
+                    renames.push((added_index, deleted_index));
+                    renamed_deltas.insert(added_index);
+                    renamed_deltas.insert(deleted_index);
+                    break;
+                }
+            }
+        }
+    }
+
+    let mut file_diffs = Vec::new();
+    for (added_index, deleted_index) in renames.iter() {
+        file_diffs.push(FileDiff::Renamed(
+            deltas[*added_index].2.clone(),
+            deltas[*deleted_index].2.clone(),
+        ));
+    }
+    for (i, delta) in deltas.iter().enumerate() {
+        if renamed_deltas.contains(&i) {
+            continue;
+        }
+        let diff = match delta.0 {
+            git2::Delta::Added => FileDiff::Added(delta.2.clone()),
+            git2::Delta::Deleted => FileDiff::Deleted(delta.2.clone()),
+            git2::Delta::Modified => FileDiff::Modified(delta.2.clone()),
+            _ => continue,
+        };
+        file_diffs.push(diff);
+    }
+    file_diffs.retain(|diff| {
+        !conflicts.contains(diff.path())
+            && !unchanged_submodules.contains(diff.path())
+    });
+    file_diffs.sort_by_key(|d| match d {
+        FileDiff::Modified(p)
+        | FileDiff::Added(p)
+        | FileDiff::Renamed(p, _)
+        | FileDiff::Deleted(p) => p.clone(),
+    });
+    Some(DiffInfo {
+        head: name,
+        branches,
+        tags,
+        diffs: file_diffs,
+        conflicts,
+        untracked,
+        ignored,
+        root: workspace_path.to_path_buf(),
+        submodules,
+        rebasing: is_rebasing(&repo),
+        rewording: rewording_commit(&repo),
+    })
+}
+
+fn file_get_head(workspace_path: &Path, path: &Path) -> Result<(String, String)> {
+    let repo = Repository::discover(workspace_path)?;
+    let head = repo.head()?;
+    let tree = head.peel_to_tree()?;
+    let tree_entry = tree.get_path(path.strip_prefix(workspace_path)?)?;
+    let blob = repo.find_blob(tree_entry.id())?;
+    let id = blob.id().to_string();
+    let content = std::str::from_utf8(blob.content())
+        .with_context(|| "content bytes to string")?
+        .to_string();
+    Ok((id, content))
+}
+
+pub fn git_blame(
+    workspace_path: &Path,
+    path: &Path,
+    rope: Rope,
+    rev: u64,
+    cache: &Mutex<HashMap<PathBuf, BlameCache>>,
+) -> Result<Vec<BlameHunk>> {
+    let repo = Repository::discover(workspace_path)?;
+    let head = repo.head()?.peel_to_commit()?;
+
+    let cached = match cache.lock().get(path) {
+        Some(entry) if entry.head == head.id() => {
+            if entry.rev == rev {
+                return Ok(entry.hunks.clone());
+            }
+            Some((
+                entry.head_rope.clone(),
+                entry.commits.clone(),
+                entry.head_lines.clone(),
+            ))
+        }
+        _ => None,
+    };
+    let (head_rope, commits, head_lines) = match cached {
+        Some(cached) => cached,
+        None => blame_head(&repo, &head, path)?,
+    };
+
+    // Map the blame of HEAD onto the buffer, the same way head changes are
+    // computed, so that unsaved edits show up as uncommitted lines.
+    let changes = rope_diff(
+        head_rope.clone(),
+        rope,
+        rev,
+        Arc::new(AtomicU64::new(rev)),
+        None,
+    )
+    .ok_or_else(|| anyhow!("blame diff was cancelled"))?;
+    let mut lines: Vec<Option<usize>> = Vec::new();
+    for change in changes {
+        match change {
+            DiffLines::Left(_) => {}
+            DiffLines::Both(info) => {
+                lines.extend(
+                    (info.left.start..info.left.start + info.right.len())
+                        .map(|line| head_lines.get(line).copied()),
+                );
+            }
+            DiffLines::Right(range) => {
+                lines.extend(range.map(|_| None));
+            }
+        }
+    }
+
+    let mut hunks: Vec<BlameHunk> = Vec::new();
+    let mut last: Option<Option<usize>> = None;
+    for (line, commit) in lines.into_iter().enumerate() {
+        if last == Some(commit) {
+            if let Some(hunk) = hunks.last_mut() {
+                hunk.lines += 1;
+            }
+            continue;
+        }
+        last = Some(commit);
+        hunks.push(BlameHunk {
+            start_line: line,
+            lines: 1,
+            commit: commit.and_then(|i| commits.get(i).cloned()),
+        });
+    }
+
+    cache.lock().insert(
+        path.to_path_buf(),
+        BlameCache {
+            head: head.id(),
+            head_rope,
+            commits,
+            head_lines,
+            rev,
+            hunks: hunks.clone(),
+        },
+    );
+    Ok(hunks)
+}
+
+fn blame_head(
+    repo: &Repository,
+    head: &git2::Commit,
+    path: &Path,
+) -> Result<(Rope, Vec<BlameCommit>, Vec<usize>)> {
+    let workdir = repo
+        .workdir()
+        .ok_or(anyhow!("Can't blame in a bare repository"))?;
+    let relative_path = path.strip_prefix(workdir)?;
+    let tree_entry = match head.tree()?.get_path(relative_path) {
+        Ok(tree_entry) => tree_entry,
+        // A file added since HEAD has no blame, every line is uncommitted
+        Err(e) if e.code() == git2::ErrorCode::NotFound => {
+            return Ok((Rope::from(""), Vec::new(), Vec::new()));
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let blob = repo.find_blob(tree_entry.id())?;
+    let content = std::str::from_utf8(blob.content())
+        .with_context(|| "content bytes to string")?;
+    let head_rope = Rope::from(content);
+
+    let mut options = BlameOptions::new();
+    options.newest_commit(head.id());
+    let blame = repo.blame_file(relative_path, Some(&mut options))?;
+
+    let mut commits = Vec::new();
+    let mut commit_index: HashMap<Oid, usize> = HashMap::new();
+    let mut head_lines = Vec::new();
+    for hunk in blame.iter() {
+        let id = hunk.final_commit_id();
+        let index = match commit_index.get(&id) {
+            Some(index) => *index,
+            None => {
+                let commit = repo.find_commit(id)?;
+                let author = commit.author();
+                commits.push(BlameCommit {
+                    id: id.to_string(),
+                    author: author.name().unwrap_or_default().to_string(),
+                    email: author.email().unwrap_or_default().to_string(),
+                    time: commit.time().seconds(),
+                    summary: commit.summary().unwrap_or_default().to_string(),
+                    message: commit.message().unwrap_or_default().to_string(),
+                });
+                commit_index.insert(id, commits.len() - 1);
+                commits.len() - 1
+            }
+        };
+        head_lines.extend(std::iter::repeat_n(index, hunk.lines_in_hunk()));
+    }
+
+    Ok((head_rope, commits, head_lines))
+}
+
+fn git_commit_info(commit: &git2::Commit, path: Option<PathBuf>) -> GitCommit {
+    let author = commit.author();
+    GitCommit {
+        id: commit.id().to_string(),
+        parents: commit.parent_ids().map(|id| id.to_string()).collect(),
+        author: author.name().unwrap_or_default().to_string(),
+        email: author.email().unwrap_or_default().to_string(),
+        time: commit.time().seconds(),
+        summary: commit.summary().unwrap_or_default().to_string(),
+        message: commit.message().unwrap_or_default().to_string(),
+        path,
+    }
+}
+
+/// A walk of the history for the commit log, kept so that the next page
+/// continues where the last one stopped instead of walking from HEAD again
+pub struct GitLogWalk {
+    head: Oid,
+    /// The commits reachable from HEAD in the order they're listed
+    ids: Vec<Oid>,
+    /// The index in `ids` of the next commit to look at
+    position: usize,
+    /// The number of commits listed before `position`
+    matched: usize,
+    /// The path of the file at `position`, which changes with renames
+    file_path: Option<PathBuf>,
+}
+
+/// List the commits reachable from HEAD, newest first. If a path is given,
+/// only the commits which changed the file are listed, following renames.
+pub fn git_log(
+    workspace_path: &Path,
+    path: Option<&Path>,
+    skip: usize,
+    limit: usize,
+    cache: &Mutex<HashMap<Option<PathBuf>, GitLogWalk>>,
+) -> Result<Vec<GitCommit>> {
+    let repo = Repository::discover(workspace_path)?;
+    let workdir = repo
+        .workdir()
+        .ok_or(anyhow!("Can't show the log of a bare repository"))?
+        .to_path_buf();
+    let head = repo.head()?.peel_to_commit()?.id();
+
+    let key = path.map(Path::to_path_buf);
+    let mut cache = cache.lock();
+    let walk = match cache.remove(&key) {
+        Some(walk) if walk.head == head && walk.matched == skip => walk,
+        _ => {
+            let mut revwalk = repo.revwalk()?;
+            revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME)?;
+            revwalk.push_head()?;
+            GitLogWalk {
+                head,
+                ids: revwalk.collect::<Result<_, _>>()?,
+                position: 0,
+                matched: 0,
+                file_path: match path {
+                    Some(path) => Some(path.strip_prefix(&workdir)?.to_path_buf()),
+                    None => None,
+                },
+            }
+        }
+    };
+    let walk = cache.entry(key).or_insert(walk);
+
+    let mut commits = Vec::new();
+    while commits.len() < limit && walk.position < walk.ids.len() {
+        let commit = repo.find_commit(walk.ids[walk.position])?;
+        walk.position += 1;
+        let mut commit_path = None;
+        if let Some(current_path) = walk.file_path.as_mut() {
+            let Some(previous_path) = git_file_change(&repo, &commit, current_path)?
+            else {
+                continue;
+            };
+            commit_path = Some(workdir.join(&*current_path));
+            *current_path = previous_path;
+        }
+
+        walk.matched += 1;
+        if walk.matched <= skip {
+            continue;
+        }
+        commits.push(git_commit_info(&commit, commit_path));
+    }
+    Ok(commits)
+}
+
+/// If the commit changed the file, returns the path the file had before it.
+/// A merge only changed the file if it differs from every parent, like the
+/// default history simplification of `git log`.
+fn git_file_change(
+    repo: &Repository,
+    commit: &git2::Commit,
+    path: &Path,
+) -> Result<Option<PathBuf>> {
+    let tree = commit.tree()?;
+    let Ok(entry) = tree.get_path(path) else {
+        return Ok(None);
+    };
+    let mut parent_trees = Vec::new();
+    for parent in commit.parents() {
+        parent_trees.push(parent.tree()?);
+    }
+    let Some(first_parent_tree) = parent_trees.first() else {
+        return Ok(Some(path.to_path_buf()));
+    };
+    if parent_trees.iter().any(|parent_tree| {
+        parent_tree
+            .get_path(path)
+            .is_ok_and(|parent_entry| parent_entry.id() == entry.id())
+    }) {
+        return Ok(None);
+    }
+    if first_parent_tree.get_path(path).is_ok() {
+        return Ok(Some(path.to_path_buf()));
+    }
+
+    // The file was added in this commit, check if it was renamed from another
+    let mut diff =
+        repo.diff_tree_to_tree(Some(first_parent_tree), Some(&tree), None)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    for delta in diff.deltas() {
+        if delta.status() == git2::Delta::Renamed
+            && delta.new_file().path() == Some(path)
+        {
+            if let Some(old_path) = delta.old_file().path() {
+                return Ok(Some(old_path.to_path_buf()));
+            }
+        }
+    }
+    Ok(Some(path.to_path_buf()))
+}
+
+/// The files changed by a commit, compared to its first parent
+pub fn git_commit_files(
+    workspace_path: &Path,
+    commit: &str,
+) -> Result<Vec<FileDiff>> {
+    let repo = Repository::discover(workspace_path)?;
+    let workdir = repo
+        .workdir()
+        .ok_or(anyhow!("Can't show the files of a bare repository"))?
+        .to_path_buf();
+    let commit = repo.find_commit(Oid::from_str(commit)?)?;
+    let parent_tree = match commit.parents().next() {
+        Some(parent) => Some(parent.tree()?),
+        None => None,
+    };
+    let mut diff =
+        repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    Ok(file_diffs(&workdir, &diff))
+}
+
+/// The files of the working tree, including the untracked and the staged ones,
+/// which differ from the revision
+pub fn git_diff_revision(
+    workspace_path: &Path,
+    revision: &str,
+) -> Result<Vec<FileDiff>> {
+    let repo = Repository::discover(workspace_path)?;
+    let workdir = repo
+        .workdir()
+        .ok_or(anyhow!("Can't compare a bare repository"))?
+        .to_path_buf();
+    let tree = repo.revparse_single(revision)?.peel_to_tree()?;
+    let mut diff_options = DiffOptions::new();
+    let mut diff = repo.diff_tree_to_workdir_with_index(
+        Some(&tree),
+        Some(
+            diff_options
+                .include_untracked(true)
+                .recurse_untracked_dirs(true),
+        ),
+    )?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))?;
+    Ok(file_diffs(&workdir, &diff))
+}
+
+fn file_diffs(workdir: &Path, diff: &git2::Diff) -> Vec<FileDiff> {
+    let mut diffs = Vec::new();
+    for delta in diff.deltas() {
+        let old_path = delta.old_file().path().map(|p| workdir.join(p));
+        let new_path = delta.new_file().path().map(|p| workdir.join(p));
+        let diff = match (delta.status(), old_path, new_path) {
+            (git2::Delta::Added | git2::Delta::Untracked, _, Some(path)) => {
+                FileDiff::Added(path)
+            }
+            (git2::Delta::Deleted, Some(path), _) => FileDiff::Deleted(path),
+            (git2::Delta::Modified, _, Some(path)) => FileDiff::Modified(path),
+            (git2::Delta::Renamed, Some(old_path), Some(new_path)) => {
+                FileDiff::Renamed(new_path, old_path)
+            }
+            _ => continue,
+        };
+        diffs.push(diff);
+    }
+    diffs
+}
+
+fn file_get_revision(
+    workspace_path: &Path,
+    path: &Path,
+    revision: &str,
+) -> Result<Option<String>> {
+    let repo = Repository::discover(workspace_path)?;
+    let workdir = repo
+        .workdir()
+        .ok_or(anyhow!("Can't read files of a bare repository"))?;
+    let tree = repo.revparse_single(revision)?.peel_to_tree()?;
+    let tree_entry = match tree.get_path(path.strip_prefix(workdir)?) {
+        Ok(tree_entry) => tree_entry,
+        Err(e) if e.code() == git2::ErrorCode::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let blob = repo.find_blob(tree_entry.id())?;
+    let content = std::str::from_utf8(blob.content())
+        .with_context(|| "content bytes to string")?
+        .to_string();
+    Ok(Some(content))
+}
+
+pub fn git_get_remote_file_url(
+    workspace_path: &Path,
+    file: &Path,
+    lines: Option<(usize, usize)>,
+    permalink: bool,
+    forges: &HashMap<String, String>,
+) -> Result<String> {
+    let repo = Repository::discover(workspace_path)?;
+    let head = repo.head()?;
+    let head_name = head.name().ok_or(anyhow!("Couldn't find head branch"))?;
+    let remote_name = repo.branch_upstream_remote(head_name)?;
+    let remote_name = remote_name
+        .as_str()
+        .ok_or(anyhow!("Failed to convert remote name to str"))?;
+    let target_remote = repo.find_remote(remote_name)?;
+
+    // Grab URL part of remote
+    let remote = target_remote
+        .url()
+        .ok_or(anyhow!("Failed to convert remote to str"))?;
+
+    let remote_url = match Url::parse(remote) {
+        Ok(url) => url,
+        Err(_) => {
+            // Parse URL as ssh
+            Url::parse(&format!("ssh://{}", remote.replacen(':', "/", 1)))?
+        }
+    };
+
+    // Get host part
+    let host = remote_url
+        .host_str()
+        .ok_or(anyhow!("Couldn't find remote host"))?;
+    // Get namespace (e.g. organisation/project in case of GitHub, org/team/team/team/../project on GitLab)
+    let namespace = if let Some(stripped) = remote_url.path().strip_suffix(".git") {
+        stripped
+    } else {
+        remote_url.path()
+    };
+
+    let rev = if permalink {
+        head.peel_to_commit()?.id().to_string()
+    } else {
+        // The name of the upstream branch on the remote, e.g. `main` for
+        // `refs/remotes/origin/main`
+        let upstream = repo.branch_upstream_name(head_name)?;
+        let upstream = upstream
+            .as_str()
+            .ok_or(anyhow!("Failed to convert upstream branch to str"))?;
+        upstream
+            .strip_prefix(&format!("refs/remotes/{remote_name}/"))
+            .unwrap_or(upstream)
+            .to_string()
+    };
+
+    let file_path = file
+        .strip_prefix(workspace_path)?
+        .to_str()
+        .ok_or(anyhow!("Couldn't convert file path to str"))?;
+
+    let forge = Forge::detect(host, forges);
+    Ok(forge.file_url(host, namespace, &rev, permalink, file_path, lines))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::reword_as_edit;
+
+    #[test]
+    fn test_reword_as_edit() {
+        let (todo, reworded) = reword_as_edit(concat!(
+            "pick 1a2b3c Fix typo\n",
+            "reword 4d5e6f Add tests\n",
+            "  r 7a8b9c Rename\n",
+            "# r, reword <commit> = use commit\n",
+        ));
+        assert_eq!(
+            todo,
+            concat!(
+                "pick 1a2b3c Fix typo\n",
+                "edit 4d5e6f Add tests\n",
+                "  edit 7a8b9c Rename\n",
+                "# r, reword <commit> = use commit\n",
+            )
+        );
+        assert_eq!(reworded, vec!["4d5e6f".to_string(), "7a8b9c".to_string()]);
+    }
+}
@@ -0,0 +1,253 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+use lapce_rpc::source_control::{DiffInfo, FileDiff};
+
+use super::{VcsBackend, diff_paths, run_command, sign_off_message, split_user};
+
+/// The backend for Mercurial repositories, which shells out to `hg`
+pub struct MercurialBackend {
+    workspace_path: PathBuf,
+    /// The directory which contains `.hg`
+    root: PathBuf,
+}
+
+impl MercurialBackend {
+    pub fn new(workspace_path: &Path, root: &Path) -> Self {
+        Self {
+            workspace_path: workspace_path.to_path_buf(),
+            root: root.to_path_buf(),
+        }
+    }
+
+    fn hg<I, S>(&self, args: I) -> Result<String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        run_command("hg", &self.root, args)
+    }
+
+    /// The names listed by a `hg branches` like command, one per line
+    fn names(&self, args: &[&str]) -> Vec<String> {
+        self.hg(args)
+            .map(|output| output.lines().map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    /// The pattern which matches exactly the file
+    fn pattern(&self, path: &Path) -> String {
+        let path = path.strip_prefix(&self.root).unwrap_or(path);
+        format!("path:{}", path.to_string_lossy())
+    }
+}
+
+impl VcsBackend for MercurialBackend {
+    fn name(&self) -> &'static str {
+        "Mercurial"
+    }
+
+    fn diff_info(&self) -> Option<DiffInfo> {
+        let head = self.hg(["branch"]).ok()?;
+        let status = self.hg(["status", "--copies"]).ok()?;
+
+        let mut branches = self.names(&["branches", "-T", "{branch}\\n"]);
+        branches.extend(self.names(&["bookmarks", "-T", "{bookmark}\\n"]));
+        branches.sort();
+        branches.dedup();
+        let mut tags = self.names(&["tags", "-T", "{tag}\\n"]);
+        tags.retain(|tag| tag != "tip");
+
+        let conflicts: Vec<PathBuf> = self
+            .hg(["resolve", "--list"])
+            .map(|output| {
+                output
+                    .lines()
+                    .filter_map(|line| line.strip_prefix("U "))
+                    .map(|path| self.root.join(path))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let untracked = status
+            .lines()
+            .filter_map(|line| line.strip_prefix("? "))
+            .map(|path| self.root.join(path))
+            .collect();
+        let mut diffs = parse_status(&self.root, &status);
+        diffs.retain(|diff| !conflicts.contains(diff.path()));
+        diffs.sort_by_key(|diff| diff.path().clone());
+
+        Some(DiffInfo {
+            head: head.trim().to_string(),
+            branches,
+            tags,
+            diffs,
+            conflicts,
+            untracked,
+            // Listing the ignored files isn't limited to the folders
+            ignored: Vec::new(),
+            root: self.root.clone(),
+            submodules: Vec::new(),
+            rebasing: false,
+            rewording: None,
+        })
+    }
+
+    fn file_head(&self, path: &Path) -> Result<(String, String)> {
+        let content = self
+            .file_at_revision(path, ".")?
+            .ok_or_else(|| anyhow!("{} isn't tracked", path.display()))?;
+        Ok((".".to_string(), content))
+    }
+
+    fn file_at_revision(
+        &self,
+        path: &Path,
+        revision: &str,
+    ) -> Result<Option<String>> {
+        match self.hg(["cat", "-r", revision, self.pattern(path).as_str()]) {
+            Ok(content) => Ok(Some(content)),
+            // The file is missing if the revision itself exists
+            Err(err) => match self.hg(["id", "-r", revision]) {
+                Ok(_) => Ok(None),
+                Err(_) => Err(err),
+            },
+        }
+    }
+
+    fn commit(
+        &self,
+        message: &str,
+        diffs: Vec<FileDiff>,
+        amend: bool,
+        sign_off: bool,
+    ) -> Result<()> {
+        let message = if sign_off {
+            let user = self.hg(["config", "ui.username"])?;
+            let (name, email) = split_user(&user);
+            sign_off_message(message, name, email)
+        } else {
+            message.to_string()
+        };
+        let patterns: Vec<String> = diff_paths(&self.root, &diffs)
+            .iter()
+            .map(|path| self.pattern(path))
+            .collect();
+        // Without patterns, hg would commit every change of the working copy
+        if patterns.is_empty() && !amend {
+            return Err(anyhow!("No files selected to commit"));
+        }
+
+        let mut args = vec![
+            "commit".to_string(),
+            "--addremove".to_string(),
+            "-m".to_string(),
+            message,
+        ];
+        if amend {
+            args.push("--amend".to_string());
+            if patterns.is_empty() {
+                // Only change the message, without the changes of the files
+                args.extend(["-X".to_string(), "glob:**".to_string()]);
+            }
+        }
+        args.extend(patterns);
+        self.hg(args)?;
+        Ok(())
+    }
+
+    fn checkout(&self, reference: &str) -> Result<()> {
+        self.hg(["update", reference])?;
+        Ok(())
+    }
+
+    fn discard_files_changes(&self, files: &[PathBuf]) -> Result<()> {
+        if files.is_empty() {
+            return Ok(());
+        }
+        let mut args = vec!["revert".to_string(), "--no-backup".to_string()];
+        args.extend(files.iter().map(|path| self.pattern(path)));
+        self.hg(args)?;
+        Ok(())
+    }
+
+    fn discard_workspace_changes(&self) -> Result<()> {
+        let pattern = self.pattern(&self.workspace_path);
+        self.hg(["revert", "--no-backup", pattern.as_str()])?;
+        Ok(())
+    }
+}
+
+/// Parse the output of `hg status --copies`, where the source of a copied file
+/// is on an indented line after it, and a copy whose source was removed is a
+/// rename. The untracked files are listed on their own.
+fn parse_status(root: &Path, status: &str) -> Vec<FileDiff> {
+    let mut entries: Vec<(char, PathBuf, Option<PathBuf>)> = Vec::new();
+    for line in status.lines() {
+        if let Some(source) = line.strip_prefix("  ") {
+            if let Some((_, _, copy_source)) = entries.last_mut() {
+                *copy_source = Some(root.join(source));
+            }
+            continue;
+        }
+        let Some((status, path)) = line.split_once(' ') else {
+            continue;
+        };
+        let Some(status) = status.chars().next() else {
+            continue;
+        };
+        entries.push((status, root.join(path), None));
+    }
+
+    let removed: Vec<PathBuf> = entries
+        .iter()
+        .filter(|(status, _, _)| matches!(status, 'R' | '!'))
+        .map(|(_, path, _)| path.clone())
+        .collect();
+    let renamed: Vec<PathBuf> = entries
+        .iter()
+        .filter_map(|(_, _, source)| source.clone())
+        .filter(|source| removed.contains(source))
+        .collect();
+
+    entries
+        .into_iter()
+        .filter_map(|(status, path, source)| match status {
+            'M' => Some(FileDiff::Modified(path)),
+            'A' => match source {
+                Some(source) if renamed.contains(&source) => {
+                    Some(FileDiff::Renamed(path, source))
+                }
+                _ => Some(FileDiff::Added(path)),
+            },
+            'R' | '!' if !renamed.contains(&path) => Some(FileDiff::Deleted(path)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use lapce_rpc::source_control::FileDiff;
+
+    use super::parse_status;
+
+    #[test]
+    fn test_parse_status() {
+        let root = Path::new("/repo");
+        let status =
+            "M a.rs\nA new.rs\n  old.rs\nA b.rs\nR old.rs\n! c.rs\n? d.rs\n";
+        assert_eq!(
+            parse_status(root, status),
+            vec![
+                FileDiff::Modified(root.join("a.rs")),
+                FileDiff::Renamed(root.join("new.rs"), root.join("old.rs")),
+                FileDiff::Added(root.join("b.rs")),
+                FileDiff::Deleted(root.join("c.rs")),
+            ]
+        );
+    }
+}
@@ -0,0 +1,303 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+use lapce_rpc::source_control::{DiffInfo, FileDiff};
+
+use super::{VcsBackend, diff_paths, run_command, sign_off_message};
+
+/// The backend for Jujutsu repositories, which shells out to `jj`
+pub struct JujutsuBackend {
+    workspace_path: PathBuf,
+    /// The directory which contains `.jj`
+    root: PathBuf,
+}
+
+impl JujutsuBackend {
+    pub fn new(workspace_path: &Path, root: &Path) -> Self {
+        Self {
+            workspace_path: workspace_path.to_path_buf(),
+            root: root.to_path_buf(),
+        }
+    }
+
+    fn jj<I, S>(&self, args: I) -> Result<String>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<std::ffi::OsStr>,
+    {
+        run_command("jj", &self.root, args)
+    }
+
+    /// The names listed by a `jj bookmark list` like command, one per line
+    fn names(&self, args: &[&str]) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .jj(args)
+            .map(|output| output.lines().map(str::to_string).collect())
+            .unwrap_or_default();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    /// The fileset which matches exactly the file
+    fn fileset(&self, path: &Path) -> String {
+        let path = path.strip_prefix(&self.root).unwrap_or(path);
+        let path = path
+            .to_string_lossy()
+            .replace('\\', "/")
+            .replace('"', "\\\"");
+        format!("root-file:\"{path}\"")
+    }
+}
+
+impl VcsBackend for JujutsuBackend {
+    fn name(&self) -> &'static str {
+        "Jujutsu"
+    }
+
+    fn diff_info(&self) -> Option<DiffInfo> {
+        // Only the first command snapshots the working copy
+        let head = self
+            .jj([
+                "log",
+                "-r",
+                "@",
+                "--no-graph",
+                "-T",
+                "if(bookmarks, bookmarks.join(\" \"), change_id.shortest(8))",
+            ])
+            .ok()?;
+        let summary = self
+            .jj(["diff", "--summary", "--ignore-working-copy"])
+            .ok()?;
+        let branches = self.names(&[
+            "bookmark",
+            "list",
+            "--ignore-working-copy",
+            "-T",
+            "name ++ \"\\n\"",
+        ]);
+        let tags = self.names(&[
+            "tag",
+            "list",
+            "--ignore-working-copy",
+            "-T",
+            "name ++ \"\\n\"",
+        ]);
+
+        // Listing the conflicts fails when there are none
+        let conflicts: Vec<PathBuf> = self
+            .jj(["resolve", "--list", "--ignore-working-copy"])
+            .map(|output| {
+                output
+                    .lines()
+                    .filter_map(|line| line.split("  ").next())
+                    .map(|path| self.root.join(path.trim()))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let mut diffs: Vec<FileDiff> = summary
+            .lines()
+            .filter_map(|line| parse_summary_line(&self.root, line))
+            .filter(|diff| !conflicts.contains(diff.path()))
+            .collect();
+        diffs.sort_by_key(|diff| diff.path().clone());
+
+        Some(DiffInfo {
+            head: head.trim().to_string(),
+            branches,
+            tags,
+            diffs,
+            conflicts,
+            // Every file of the working copy is tracked
+            untracked: Vec::new(),
+            ignored: Vec::new(),
+            root: self.root.clone(),
+            submodules: Vec::new(),
+            rebasing: false,
+            rewording: None,
+        })
+    }
+
+    fn file_head(&self, path: &Path) -> Result<(String, String)> {
+        let content = self
+            .file_at_revision(path, "@-")?
+            .ok_or_else(|| anyhow!("{} isn't in the parent", path.display()))?;
+        Ok(("@-".to_string(), content))
+    }
+
+    fn file_at_revision(
+        &self,
+        path: &Path,
+        revision: &str,
+    ) -> Result<Option<String>> {
+        let result = self.jj([
+            "file".to_string(),
+            "show".to_string(),
+            "-r".to_string(),
+            revision.to_string(),
+            self.fileset(path),
+        ]);
+        match result {
+            Ok(content) => Ok(Some(content)),
+            // The file is missing if the revision itself exists
+            Err(err) => {
+                let exists = self.jj([
+                    "log",
+                    "--ignore-working-copy",
+                    "--no-graph",
+                    "-r",
+                    revision,
+                    "-T",
+                    "\"\"",
+                ]);
+                match exists {
+                    Ok(_) => Ok(None),
+                    Err(_) => Err(err),
+                }
+            }
+        }
+    }
+
+    fn commit(
+        &self,
+        message: &str,
+        diffs: Vec<FileDiff>,
+        amend: bool,
+        sign_off: bool,
+    ) -> Result<()> {
+        let message = if sign_off {
+            let name = self.jj(["config", "get", "user.name"])?;
+            let email = self.jj(["config", "get", "user.email"])?;
+            sign_off_message(message, name.trim(), email.trim())
+        } else {
+            message.to_string()
+        };
+        let filesets: Vec<String> = diff_paths(&self.root, &diffs)
+            .iter()
+            .map(|path| self.fileset(path))
+            .collect();
+
+        // The working copy change is always there, so committing is describing
+        // it and starting a new one, and amending is squashing it into its
+        // parent
+        let mut args = if !amend {
+            vec!["commit".to_string()]
+        } else if filesets.is_empty() {
+            vec!["describe".to_string(), "-r".to_string(), "@-".to_string()]
+        } else {
+            vec!["squash".to_string()]
+        };
+        args.extend(["-m".to_string(), message]);
+        if !(amend && filesets.is_empty()) {
+            args.extend(filesets);
+        }
+        self.jj(args)?;
+        Ok(())
+    }
+
+    fn checkout(&self, reference: &str) -> Result<()> {
+        self.jj(["new", reference])?;
+        Ok(())
+    }
+
+    fn discard_files_changes(&self, files: &[PathBuf]) -> Result<()> {
+        if files.is_empty() {
+            return Ok(());
+        }
+        let mut args = vec!["restore".to_string()];
+        args.extend(files.iter().map(|path| self.fileset(path)));
+        self.jj(args)?;
+        Ok(())
+    }
+
+    fn discard_workspace_changes(&self) -> Result<()> {
+        // Only the files of the workspace, which may be a sub directory of
+        // the repository
+        let path = self
+            .workspace_path
+            .strip_prefix(&self.root)
+            .unwrap_or(Path::new(""));
+        let path = path
+            .to_string_lossy()
+            .replace('\\', "/")
+            .replace('"', "\\\"");
+        self.jj(["restore".to_string(), format!("root:\"{path}\"")])?;
+        Ok(())
+    }
+}
+
+/// Parse a line of `jj diff --summary`, e.g. `M src/main.rs`, or
+/// `R src/{old.rs => new.rs}` for a rename
+fn parse_summary_line(root: &Path, line: &str) -> Option<FileDiff> {
+    let (status, path) = line.split_once(' ')?;
+    let diff = match status {
+        "M" => FileDiff::Modified(root.join(path)),
+        "A" => FileDiff::Added(root.join(path)),
+        "D" => FileDiff::Deleted(root.join(path)),
+        "R" => {
+            let (old, new) = split_rename(path)?;
+            FileDiff::Renamed(root.join(new), root.join(old))
+        }
+        "C" => {
+            let (_, new) = split_rename(path)?;
+            FileDiff::Added(root.join(new))
+        }
+        _ => return None,
+    };
+    Some(diff)
+}
+
+/// Expand `prefix/{old => new}/suffix` into the old and the new path
+fn split_rename(path: &str) -> Option<(String, String)> {
+    let (prefix, rest) = path.split_once('{')?;
+    let (change, suffix) = rest.split_once('}')?;
+    let (old, new) = change.split_once(" => ")?;
+    let join = |middle: &str| {
+        format!("{prefix}{middle}{suffix}")
+            .replace("//", "/")
+            .trim_start_matches('/')
+            .to_string()
+    };
+    Some((join(old), join(new)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use lapce_rpc::source_control::FileDiff;
+
+    use super::parse_summary_line;
+
+    #[test]
+    fn test_parse_summary_line() {
+        let root = Path::new("/repo");
+        assert_eq!(
+            parse_summary_line(root, "M src/main.rs"),
+            Some(FileDiff::Modified(root.join("src/main.rs")))
+        );
+        assert_eq!(
+            parse_summary_line(root, "R src/{old.rs => new.rs}"),
+            Some(FileDiff::Renamed(
+                root.join("src/new.rs"),
+                root.join("src/old.rs")
+            ))
+        );
+        assert_eq!(
+            parse_summary_line(root, "R {a => b/c}/d.rs"),
+            Some(FileDiff::Renamed(
+                root.join("b/c/d.rs"),
+                root.join("a/d.rs")
+            ))
+        );
+        assert_eq!(
+            parse_summary_line(root, "R src/{ => sub}/lib.rs"),
+            Some(FileDiff::Renamed(
+                root.join("src/sub/lib.rs"),
+                root.join("src/lib.rs")
+            ))
+        );
+    }
+}
@@ -0,0 +1,144 @@
+pub mod git;
+pub mod hg;
+pub mod jj;
+
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Result, anyhow};
+use lapce_rpc::source_control::{DiffInfo, FileDiff};
+
+use self::{git::GitBackend, hg::MercurialBackend, jj::JujutsuBackend};
+
+/// The version control operations the source control panel and the editor rely
+/// on. Git is the default, other systems are used when their repository is
+/// found in the workspace.
+pub trait VcsBackend {
+    /// The name of the version control system, used in error messages
+    fn name(&self) -> &'static str;
+
+    /// The current reference, the branches and tags, and the changed files of
+    /// the working copy
+    fn diff_info(&self) -> Option<DiffInfo>;
+
+    /// The content of the file at the last commit, together with an id of
+    /// that version of the file
+    fn file_head(&self, path: &Path) -> Result<(String, String)>;
+
+    /// The content of the file at the revision, `None` if the revision exists
+    /// but the file doesn't
+    fn file_at_revision(
+        &self,
+        path: &Path,
+        revision: &str,
+    ) -> Result<Option<String>>;
+
+    /// Commit the changes of the files, or replace the last commit if `amend`
+    fn commit(
+        &self,
+        message: &str,
+        diffs: Vec<FileDiff>,
+        amend: bool,
+        sign_off: bool,
+    ) -> Result<()>;
+
+    /// Make the working copy match the reference
+    fn checkout(&self, reference: &str) -> Result<()>;
+
+    fn discard_files_changes(&self, files: &[PathBuf]) -> Result<()>;
+
+    fn discard_workspace_changes(&self) -> Result<()>;
+
+    /// Whether the blame, the log and the rebase are available, which read
+    /// the repository with libgit2 and aren't implemented for other systems
+    fn is_git(&self) -> bool {
+        false
+    }
+}
+
+/// Find the version control system of the workspace. Jujutsu is preferred over
+/// git, since it's usually colocated with a git repository whose status doesn't
+/// reflect the working copy.
+pub fn backend(workspace_path: &Path) -> Box<dyn VcsBackend> {
+    for dir in workspace_path.ancestors() {
+        if dir.join(".jj").is_dir() {
+            return Box::new(JujutsuBackend::new(workspace_path, dir));
+        }
+        if dir.join(".hg").is_dir() {
+            return Box::new(MercurialBackend::new(workspace_path, dir));
+        }
+        if dir.join(".git").exists() {
+            break;
+        }
+    }
+    Box::new(GitBackend::new(workspace_path))
+}
+
+/// Run the program in the directory, returning its standard output
+fn run_command<I, S>(program: &str, dir: &Path, args: I) -> Result<String>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<std::ffi::OsStr>,
+{
+    let output = Command::new(program)
+        .current_dir(dir)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow!("Failed to run {program}: {e}"))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "{program} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// The paths of the files whose changes are committed, relative to the root of
+/// the repository
+fn diff_paths(root: &Path, diffs: &[FileDiff]) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for diff in diffs {
+        match diff {
+            FileDiff::Modified(p) | FileDiff::Added(p) | FileDiff::Deleted(p) => {
+                paths.push(p.as_path());
+            }
+            FileDiff::Renamed(a, d) => {
+                paths.push(a.as_path());
+                paths.push(d.as_path());
+            }
+        }
+    }
+    paths
+        .into_iter()
+        .filter_map(|p| p.strip_prefix(root).ok())
+        .map(Path::to_path_buf)
+        .collect()
+}
+
+/// Add a `Signed-off-by` trailer for the author to the message, unless it's
+/// already there
+fn sign_off_message(message: &str, name: &str, email: &str) -> String {
+    let trailer = format!("Signed-off-by: {name} <{email}>");
+    let message = message.trim_end();
+    if message.lines().any(|line| line == trailer) {
+        return format!("{message}\n");
+    }
+
+    // Trailers are a paragraph of their own at the end of the message
+    let has_trailers = message
+        .rsplit_once("\n\n")
+        .is_some_and(|(_, last)| last.lines().all(|line| line.contains(": ")));
+    let separator = if has_trailers { "\n" } else { "\n\n" };
+    format!("{message}{separator}{trailer}\n")
+}
+
+/// Split a `Name <email>` user into its name and email
+fn split_user(user: &str) -> (&str, &str) {
+    match user.trim().split_once('<') {
+        Some((name, email)) => (name.trim(), email.trim_end_matches('>').trim()),
+        None => (user.trim(), ""),
+    }
+}
@@ -8,6 +8,7 @@
 };
 
 use crossbeam_channel::{Receiver, Sender};
+use indexmap::IndexMap;
 use lsp_types::{
     CancelParams, CompletionResponse, LogMessageParams, ProgressParams,
     PublishDiagnosticsParams, ShowMessageParams, SignatureHelp,
@@ -20,9 +21,10 @@
     dap_types::{
         self, DapId, RunDebugConfig, Scope, StackFrame, Stopped, ThreadId, Variable,
     },
+    editorconfig::EditorConfigProperties,
     file::PathObject,
     plugin::{PluginId, VoltInfo, VoltMetadata},
-    proxy::ProxyStatus,
+    proxy::{ProxyStatus, SearchMatch},
     source_control::DiffInfo,
     terminal::TermId,
 };
@@ -37,6 +39,18 @@ pub enum CoreRpc {
 #[serde(rename_all = "snake_case")]
 pub enum FileChanged {
     Change(String),
+    /// A large file grew by `text` on disk, e.g. a log, which follows its
+    /// content of `offset` bytes
+    Append {
+        offset: usize,
+        text: String,
+    },
+    /// A large file changed other than by growing, and has to be loaded again
+    /// in chunks
+    Reload,
+    /// A file shown as bytes, by the hex editor or the image viewer, changed
+    /// and has to be read again
+    Bytes,
     Delete,
 }
 
@@ -51,6 +65,12 @@ pub enum CoreNotification {
         path: PathBuf,
         content: FileChanged,
     },
+    EditorConfigChanged {
+        path: PathBuf,
+        properties: EditorConfigProperties,
+        /// The name of the encoding the file is now saved with
+        encoding: String,
+    },
     CompletionResponse {
         request_id: usize,
         input: String,
@@ -108,6 +128,13 @@ pub enum CoreNotification {
     DiffInfo {
         diff: DiffInfo,
     },
+    /// A batch of the matches of a workspace search, with the number of
+    /// matches found so far
+    GlobalSearchResults {
+        search_id: u64,
+        matches: IndexMap<PathBuf, Vec<SearchMatch>>,
+        count: usize,
+    },
     UpdateTerminal {
         term_id: TermId,
         content: Vec<u8>,
@@ -258,10 +285,36 @@ pub fn diff_info(&self, diff: DiffInfo) {
         self.notification(CoreNotification::DiffInfo { diff });
     }
 
+    pub fn global_search_results(
+        &self,
+        search_id: u64,
+        matches: IndexMap<PathBuf, Vec<SearchMatch>>,
+        count: usize,
+    ) {
+        self.notification(CoreNotification::GlobalSearchResults {
+            search_id,
+            matches,
+            count,
+        });
+    }
+
     pub fn open_file_changed(&self, path: PathBuf, content: FileChanged) {
         self.notification(CoreNotification::OpenFileChanged { path, content });
     }
 
+    pub fn editor_config_changed(
+        &self,
+        path: PathBuf,
+        properties: EditorConfigProperties,
+        encoding: String,
+    ) {
+        self.notification(CoreNotification::EditorConfigChanged {
+            path,
+            properties,
+            encoding,
+        });
+    }
+
     pub fn completion_response(
         &self,
         request_id: usize,
@@ -6,6 +6,31 @@
 
 use serde::{Deserialize, Serialize};
 
+/// The name of UTF-8 with a byte order mark, which isn't a WHATWG label
+pub const UTF8_BOM_ENCODING: &str = "UTF-8 with BOM";
+
+/// The encodings offered to reopen or save a file with, the proxy accepts any
+/// other WHATWG label as well.
+pub const ENCODINGS: &[&str] = &[
+    "UTF-8",
+    UTF8_BOM_ENCODING,
+    "UTF-16LE",
+    "UTF-16BE",
+    "windows-1252",
+    "ISO-8859-2",
+    "ISO-8859-15",
+    "windows-1250",
+    "windows-1251",
+    "KOI8-R",
+    "Shift_JIS",
+    "EUC-JP",
+    "ISO-2022-JP",
+    "EUC-KR",
+    "GBK",
+    "gb18030",
+    "Big5",
+];
+
 /// UTF8 line and column-offset
 #[derive(
     Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
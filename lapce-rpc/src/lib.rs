@@ -4,6 +4,7 @@
 pub mod core;
 pub mod counter;
 pub mod dap_types;
+pub mod editorconfig;
 pub mod file;
 pub mod file_line;
 mod parse;
@@ -31,3 +32,9 @@ pub struct RpcError {
     pub code: i64,
     pub message: String,
 }
+
+impl RpcError {
+    /// The code of the error of a file which doesn't exist at a revision that
+    /// does
+    pub const NOT_FOUND: i64 = 1;
+}
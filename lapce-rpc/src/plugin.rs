@@ -68,6 +68,8 @@ pub struct VoltMetadata {
     pub wasm: Option<String>,
     pub color_themes: Option<Vec<String>>,
     pub icon_themes: Option<Vec<String>>,
+    /// The files of the languages registered by the volt
+    pub languages: Option<Vec<String>>,
     pub dir: Option<PathBuf>,
     pub activation: Option<VoltActivation>,
     pub config: Option<HashMap<String, VoltConfig>>,
@@ -92,6 +94,44 @@ pub fn info(&self) -> VoltInfo {
     }
 }
 
+/// The definition of a language registered at runtime, from the
+/// `languages.toml` in the config directory or from a volt, e.g.
+///
+/// ```toml
+/// [[language]]
+/// name = "My DSL"
+/// extensions = ["mydsl"]
+/// comment = "//"
+/// block-comment = ["/*", "*/"]
+/// indent = "    "
+/// grammar = "grammars/libtree-sitter-mydsl.so"
+/// queries = "queries/mydsl"
+/// ```
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub struct LanguageDefinition {
+    pub name: String,
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    /// The file names of the language, e.g. `Dockerfile`
+    #[serde(default)]
+    pub files: Vec<String>,
+    /// The token of line comments
+    pub comment: Option<String>,
+    /// The start and end tokens of block comments
+    pub block_comment: Option<(String, String)>,
+    /// The indent unit, which is four spaces by default
+    pub indent: Option<String>,
+    /// The path of the tree-sitter grammar library, whose file name is
+    /// `libtree-sitter-<name>` or `tree-sitter-<name>`
+    pub grammar: Option<PathBuf>,
+    /// The name of the grammar function `tree_sitter_<name>`, which is the name
+    /// of the grammar library by default
+    pub grammar_fn: Option<String>,
+    /// The directory with the `highlights.scm` and `injections.scm` queries
+    pub queries: Option<PathBuf>,
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VoltID {
     pub author: String,
@@ -157,6 +197,7 @@ fn test_volt_metadata_id() {
             wasm: None,
             color_themes: None,
             icon_themes: None,
+            languages: None,
             dir: std::env::current_dir().unwrap().canonicalize().ok(),
             activation: None,
             config: None,
@@ -195,6 +236,7 @@ fn test_volt_metadata_info() {
             wasm: None,
             color_themes: None,
             icon_themes: None,
+            languages: None,
             dir: std::env::current_dir().unwrap().canonicalize().ok(),
             activation: None,
             config: None,
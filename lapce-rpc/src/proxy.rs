@@ -1,5 +1,6 @@
 use std::{
     collections::HashMap,
+    ops::Range,
     path::PathBuf,
     sync::{
         Arc,
@@ -8,7 +9,6 @@
 };
 
 use crossbeam_channel::{Receiver, Sender};
-use indexmap::IndexMap;
 use lapce_xi_rope::RopeDelta;
 use lsp_types::{
     CallHierarchyIncomingCall, CallHierarchyItem, CodeAction, CodeActionResponse,
@@ -26,10 +26,11 @@
     RequestId, RpcError, RpcMessage,
     buffer::BufferId,
     dap_types::{self, DapId, RunDebugConfig, SourceBreakpoint, ThreadId},
+    editorconfig::EditorConfigProperties,
     file::{FileNodeItem, PathObject},
     file_line::FileLine,
-    plugin::{PluginId, VoltInfo, VoltMetadata},
-    source_control::FileDiff,
+    plugin::{LanguageDefinition, PluginId, VoltInfo, VoltMetadata},
+    source_control::{BlameHunk, FileDiff, GitCommit, RebaseAction},
     style::SemanticStyles,
     terminal::{TermId, TerminalProfile},
 };
@@ -48,12 +49,55 @@ pub enum ProxyStatus {
     Disconnected,
 }
 
+/// The files a workspace search looks into
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchFilter {
+    /// Globs of the files to search, relative to the workspace. Every file is
+    /// searched when there are none.
+    pub include: Vec<String>,
+    /// Globs of the files not to search, relative to the workspace
+    pub exclude: Vec<String>,
+    /// Also search the hidden files and the files ignored by ignore files
+    pub include_ignored: bool,
+    /// Only search these files, e.g. the ones open in editors, instead of the
+    /// files of the workspace
+    pub files: Option<Vec<PathBuf>>,
+}
+
+/// The search of a search editor. A search only cancels the earlier one of
+/// the same search editor, or of the search panel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SearchEditorParams {
+    /// The buffer of the search editor
+    pub buffer_id: BufferId,
+    /// The number of lines around each match to send
+    pub context_lines: usize,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchMatch {
     pub line: usize,
     pub start: usize,
     pub end: usize,
     pub line_content: String,
+    /// Where `line_content` starts in the line, since long lines are shortened
+    /// around the match while `start` and `end` are columns of the whole line
+    #[serde(default)]
+    pub content_start: usize,
+    /// The lines before the match, when context lines are asked for, unless
+    /// they are after the previous match
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_before: Vec<String>,
+    /// The lines after the match, when context lines are asked for
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub context_after: Vec<String>,
+}
+
+impl SearchMatch {
+    /// The range of the match in `line_content`
+    pub fn content_range(&self) -> Range<usize> {
+        self.start - self.content_start..self.end - self.content_start
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -63,15 +107,49 @@ pub enum ProxyRequest {
     NewBuffer {
         buffer_id: BufferId,
         path: PathBuf,
+        /// The size in bytes above which the file is opened as a large file,
+        /// `0` if there is no limit
+        large_file_threshold: u64,
+    },
+    /// The content of a large file from `offset`, which is loaded in chunks
+    BufferChunk {
+        path: PathBuf,
+        offset: usize,
+    },
+    /// Read a large file again, whose buffer is then at `rev`, before it's
+    /// loaded again in chunks
+    ReloadBuffer {
+        path: PathBuf,
+        rev: u64,
     },
     BufferHead {
         path: PathBuf,
     },
+    /// Search the workspace, streaming the matches with
+    /// `CoreNotification::GlobalSearchResults` of the `search_id`
     GlobalSearch {
+        search_id: u64,
         pattern: String,
         case_sensitive: bool,
         whole_word: bool,
         is_regex: bool,
+        filter: SearchFilter,
+        /// The search editor the search is for, if it isn't for the search
+        /// panel
+        search_editor: Option<SearchEditorParams>,
+    },
+    /// Replace the matches of a workspace search in files which aren't open,
+    /// answered with the files whose matches were replaced
+    GlobalReplace {
+        pattern: String,
+        case_sensitive: bool,
+        whole_word: bool,
+        is_regex: bool,
+        replacement: String,
+        paths: Vec<PathBuf>,
+        /// The line and the start of the only match to replace, when replacing
+        /// in a single file
+        only_match: Option<(usize, usize)>,
     },
     CompletionResolve {
         plugin_id: PluginId,
@@ -96,6 +174,45 @@ pub enum ProxyRequest {
     },
     GitGetRemoteFileUrl {
         file: PathBuf,
+        /// The 1-based, inclusive lines to anchor the url to
+        lines: Option<(usize, usize)>,
+        /// Link to the current commit rather than the upstream branch
+        permalink: bool,
+        /// The forges of self-hosted hosts, e.g. `git.example.com = "gitlab"`
+        forges: HashMap<String, String>,
+    },
+    GitBlame {
+        path: PathBuf,
+    },
+    GitLog {
+        path: Option<PathBuf>,
+        skip: usize,
+        limit: usize,
+    },
+    GitCommitFiles {
+        commit: String,
+    },
+    GitFileAtRevision {
+        path: PathBuf,
+        revision: String,
+    },
+    GitDiffRevision {
+        revision: String,
+    },
+    GitCommitTemplate {},
+    GitLastCommitMessage {
+        repository: Option<PathBuf>,
+    },
+    GitWorktrees {
+        repository: Option<PathBuf>,
+    },
+    GitCreateWorktree {
+        branch: String,
+        repository: Option<PathBuf>,
+    },
+    GitRebaseTodo {
+        onto: String,
+        repository: Option<PathBuf>,
     },
     GetReferences {
         path: PathBuf,
@@ -169,6 +286,15 @@ pub enum ProxyRequest {
         path: PathBuf,
     },
     GetOpenFilesContent {},
+    /// The raw content of a file, for the files which aren't edited as text
+    ReadFileBytes {
+        path: PathBuf,
+    },
+    WriteFileBytes {
+        path: PathBuf,
+        #[serde(with = "base64_bytes")]
+        content: Vec<u8>,
+    },
     GetFiles {
         path: String,
     },
@@ -189,6 +315,18 @@ pub enum ProxyRequest {
         /// Whether to create the parent directories if they do not exist.
         create_parents: bool,
     },
+    /// Save the buffer in another encoding, which it keeps for later saves
+    SaveWithEncoding {
+        rev: u64,
+        path: PathBuf,
+        encoding: String,
+    },
+    /// Read the file again in another encoding, the buffer being updated by
+    /// the editor with the new content
+    ReopenWithEncoding {
+        path: PathBuf,
+        encoding: String,
+    },
     CreateFile {
         path: PathBuf,
     },
@@ -261,6 +399,16 @@ pub enum ProxyNotification {
     UpdatePluginConfigs {
         configs: HashMap<String, HashMap<String, serde_json::Value>>,
     },
+    /// Register the languages of `languages.toml` and the volts, which remote
+    /// proxies don't load themselves
+    RegisterLanguages {
+        languages: Vec<LanguageDefinition>,
+    },
+    /// Register the file associations of the settings, from globs of files to
+    /// the names of their languages
+    RegisterFileAssociations {
+        associations: HashMap<String, String>,
+    },
     NewTerminal {
         term_id: TermId,
         profile: TerminalProfile,
@@ -283,14 +431,44 @@ pub enum ProxyNotification {
     GitCommit {
         message: String,
         diffs: Vec<FileDiff>,
+        /// Replace the last commit instead of creating a new one
+        amend: bool,
+        /// Add a `Signed-off-by` trailer to the message
+        sign_off: bool,
+        /// The root of the repository to commit to, the workspace's if `None`
+        repository: Option<PathBuf>,
     },
     GitCheckout {
         reference: String,
+        /// The root of the repository to check out, the workspace's if `None`
+        repository: Option<PathBuf>,
     },
     GitDiscardFilesChanges {
         files: Vec<PathBuf>,
+        /// The root of the repository of the files, the workspace's if `None`
+        repository: Option<PathBuf>,
+    },
+    GitDiscardWorkspaceChanges {
+        /// The root of the repository to discard the changes of, the
+        /// workspace's if `None`
+        repository: Option<PathBuf>,
+    },
+    GitMarkResolved {
+        path: PathBuf,
+        /// The root of the repository of the file, the workspace's if `None`
+        repository: Option<PathBuf>,
+    },
+    GitRebase {
+        onto: String,
+        /// The edited todo list, the one written for the rebase if `None`
+        todo: Option<String>,
+        /// The root of the repository to rebase, the workspace's if `None`
+        repository: Option<PathBuf>,
+    },
+    GitRebaseAction {
+        action: RebaseAction,
+        repository: Option<PathBuf>,
     },
-    GitDiscardWorkspaceChanges {},
     GitInit {},
     LspCancel {
         id: i32,
@@ -360,9 +538,55 @@ pub enum ProxyResponse {
     GitGetRemoteFileUrl {
         file_url: String,
     },
+    GitBlameResponse {
+        rev: u64,
+        hunks: Vec<BlameHunk>,
+    },
+    GitLogResponse {
+        commits: Vec<GitCommit>,
+    },
+    GitCommitFilesResponse {
+        diffs: Vec<FileDiff>,
+    },
+    GitDiffRevisionResponse {
+        diffs: Vec<FileDiff>,
+    },
+    GitCommitTemplateResponse {
+        template: Option<String>,
+    },
+    GitLastCommitMessageResponse {
+        message: String,
+    },
+    GitWorktreesResponse {
+        worktrees: Vec<PathBuf>,
+    },
+    GitCreateWorktreeResponse {
+        path: PathBuf,
+    },
+    GitRebaseTodoResponse {
+        path: PathBuf,
+    },
     NewBufferResponse {
         content: String,
         read_only: bool,
+        editor_config: EditorConfigProperties,
+        /// The name of the encoding the file was read with
+        encoding: String,
+        /// Whether the file is larger than the threshold, in which case the
+        /// content is empty and is retrieved with `ProxyRequest::BufferChunk`
+        large_file: bool,
+        /// Whether the file isn't text, in which case the content is empty
+        binary: bool,
+    },
+    BufferChunkResponse {
+        chunk: String,
+        /// Whether the chunk is the end of the content
+        done: bool,
+    },
+    ReloadBufferResponse {},
+    ReopenWithEncodingResponse {
+        content: String,
+        encoding: String,
     },
     BufferHeadResponse {
         version: String,
@@ -451,8 +675,18 @@ pub enum ProxyResponse {
     GetOpenFilesContentResponse {
         items: Vec<TextDocumentItem>,
     },
+    ReadFileBytesResponse {
+        #[serde(with = "base64_bytes")]
+        content: Vec<u8>,
+    },
+    /// The search is done, after finding `count` matches or stopping at the
+    /// limit of matches
     GlobalSearchResponse {
-        matches: IndexMap<PathBuf, Vec<SearchMatch>>,
+        count: usize,
+        limit_hit: bool,
+    },
+    GlobalReplaceResponse {
+        paths: Vec<PathBuf>,
     },
     DapVariableResponse {
         varialbes: Vec<dap_types::Variable>,
@@ -601,12 +835,28 @@ pub fn git_init(&self) {
         self.notification(ProxyNotification::GitInit {});
     }
 
-    pub fn git_commit(&self, message: String, diffs: Vec<FileDiff>) {
-        self.notification(ProxyNotification::GitCommit { message, diffs });
+    pub fn git_commit(
+        &self,
+        message: String,
+        diffs: Vec<FileDiff>,
+        amend: bool,
+        sign_off: bool,
+        repository: Option<PathBuf>,
+    ) {
+        self.notification(ProxyNotification::GitCommit {
+            message,
+            diffs,
+            amend,
+            sign_off,
+            repository,
+        });
     }
 
-    pub fn git_checkout(&self, reference: String) {
-        self.notification(ProxyNotification::GitCheckout { reference });
+    pub fn git_checkout(&self, reference: String, repository: Option<PathBuf>) {
+        self.notification(ProxyNotification::GitCheckout {
+            reference,
+            repository,
+        });
     }
 
     pub fn install_volt(&self, volt: VoltInfo) {
@@ -707,9 +957,33 @@ pub fn new_buffer(
         &self,
         buffer_id: BufferId,
         path: PathBuf,
+        large_file_threshold: u64,
         f: impl ProxyCallback + 'static,
     ) {
-        self.request_async(ProxyRequest::NewBuffer { buffer_id, path }, f);
+        self.request_async(
+            ProxyRequest::NewBuffer {
+                buffer_id,
+                path,
+                large_file_threshold,
+            },
+            f,
+        );
+    }
+
+    pub fn get_buffer_chunk(
+        &self,
+        path: PathBuf,
+        offset: usize,
+    ) -> Result<ProxyResponse, RpcError> {
+        self.request(ProxyRequest::BufferChunk { path, offset })
+    }
+
+    pub fn reload_buffer(
+        &self,
+        path: PathBuf,
+        rev: u64,
+    ) -> Result<ProxyResponse, RpcError> {
+        self.request(ProxyRequest::ReloadBuffer { path, rev })
     }
 
     pub fn get_buffer_head(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
@@ -781,20 +1055,53 @@ pub fn save_buffer_as(
         );
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn global_search(
         &self,
+        search_id: u64,
         pattern: String,
         case_sensitive: bool,
         whole_word: bool,
         is_regex: bool,
+        filter: SearchFilter,
+        search_editor: Option<SearchEditorParams>,
         f: impl ProxyCallback + 'static,
     ) {
         self.request_async(
             ProxyRequest::GlobalSearch {
+                search_id,
+                pattern,
+                case_sensitive,
+                whole_word,
+                is_regex,
+                filter,
+                search_editor,
+            },
+            f,
+        );
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn global_replace(
+        &self,
+        pattern: String,
+        case_sensitive: bool,
+        whole_word: bool,
+        is_regex: bool,
+        replacement: String,
+        paths: Vec<PathBuf>,
+        only_match: Option<(usize, usize)>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GlobalReplace {
                 pattern,
                 case_sensitive,
                 whole_word,
                 is_regex,
+                replacement,
+                paths,
+                only_match,
             },
             f,
         );
@@ -817,6 +1124,32 @@ pub fn save(
         );
     }
 
+    pub fn save_with_encoding(
+        &self,
+        rev: u64,
+        path: PathBuf,
+        encoding: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::SaveWithEncoding {
+                rev,
+                path,
+                encoding,
+            },
+            f,
+        );
+    }
+
+    pub fn reopen_with_encoding(
+        &self,
+        path: PathBuf,
+        encoding: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::ReopenWithEncoding { path, encoding }, f);
+    }
+
     pub fn get_files(&self, f: impl ProxyCallback + 'static) {
         self.request_async(
             ProxyRequest::GetFiles {
@@ -826,6 +1159,19 @@ pub fn get_files(&self, f: impl ProxyCallback + 'static) {
         );
     }
 
+    pub fn read_file_bytes(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::ReadFileBytes { path }, f);
+    }
+
+    pub fn write_file_bytes(
+        &self,
+        path: PathBuf,
+        content: Vec<u8>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::WriteFileBytes { path, content }, f);
+    }
+
     pub fn get_open_files_content(&self) -> Result<ProxyResponse, RpcError> {
         self.request(ProxyRequest::GetOpenFilesContent {})
     }
@@ -1047,9 +1393,106 @@ pub fn prepare_rename(
     pub fn git_get_remote_file_url(
         &self,
         file: PathBuf,
+        lines: Option<(usize, usize)>,
+        permalink: bool,
+        forges: HashMap<String, String>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GitGetRemoteFileUrl {
+                file,
+                lines,
+                permalink,
+                forges,
+            },
+            f,
+        );
+    }
+
+    pub fn git_blame(&self, path: PathBuf, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitBlame { path }, f);
+    }
+
+    pub fn git_log(
+        &self,
+        path: Option<PathBuf>,
+        skip: usize,
+        limit: usize,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitLog { path, skip, limit }, f);
+    }
+
+    pub fn git_commit_files(&self, commit: String, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitCommitFiles { commit }, f);
+    }
+
+    /// Get the files of the working tree which differ from a git revision
+    pub fn git_diff_revision(
+        &self,
+        revision: String,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitDiffRevision { revision }, f);
+    }
+
+    /// Get the content of the file at a git revision, responding with a
+    /// `BufferHeadResponse`
+    pub fn git_file_at_revision(
+        &self,
+        path: PathBuf,
+        revision: String,
         f: impl ProxyCallback + 'static,
     ) {
-        self.request_async(ProxyRequest::GitGetRemoteFileUrl { file }, f);
+        self.request_async(ProxyRequest::GitFileAtRevision { path, revision }, f);
+    }
+
+    /// Get the content of the file configured by `commit.template`
+    pub fn git_commit_template(&self, f: impl ProxyCallback + 'static) {
+        self.request_async(ProxyRequest::GitCommitTemplate {}, f);
+    }
+
+    pub fn git_last_commit_message(
+        &self,
+        repository: Option<PathBuf>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitLastCommitMessage { repository }, f);
+    }
+
+    /// List the working trees of the repository, the workspace's if `None`,
+    /// the main one first
+    pub fn git_worktrees(
+        &self,
+        repository: Option<PathBuf>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitWorktrees { repository }, f);
+    }
+
+    /// Add a working tree of the repository, the workspace's if `None`, next
+    /// to its main one with the branch checked out
+    pub fn git_create_worktree(
+        &self,
+        branch: String,
+        repository: Option<PathBuf>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(
+            ProxyRequest::GitCreateWorktree { branch, repository },
+            f,
+        );
+    }
+
+    /// Write the todo list of rebasing the commits since the merge base with
+    /// the revision onto it, which can then be edited before rebasing
+    pub fn git_rebase_todo(
+        &self,
+        onto: String,
+        repository: Option<PathBuf>,
+        f: impl ProxyCallback + 'static,
+    ) {
+        self.request_async(ProxyRequest::GitRebaseTodo { onto, repository }, f);
     }
 
     pub fn rename(
@@ -1101,12 +1544,57 @@ pub fn update_plugin_configs(
         self.notification(ProxyNotification::UpdatePluginConfigs { configs });
     }
 
-    pub fn git_discard_files_changes(&self, files: Vec<PathBuf>) {
-        self.notification(ProxyNotification::GitDiscardFilesChanges { files });
+    pub fn register_languages(&self, languages: Vec<LanguageDefinition>) {
+        self.notification(ProxyNotification::RegisterLanguages { languages });
     }
 
-    pub fn git_discard_workspace_changes(&self) {
-        self.notification(ProxyNotification::GitDiscardWorkspaceChanges {});
+    pub fn register_file_associations(&self, associations: HashMap<String, String>) {
+        self.notification(ProxyNotification::RegisterFileAssociations {
+            associations,
+        });
+    }
+
+    pub fn git_discard_files_changes(
+        &self,
+        files: Vec<PathBuf>,
+        repository: Option<PathBuf>,
+    ) {
+        self.notification(ProxyNotification::GitDiscardFilesChanges {
+            files,
+            repository,
+        });
+    }
+
+    pub fn git_discard_workspace_changes(&self, repository: Option<PathBuf>) {
+        self.notification(ProxyNotification::GitDiscardWorkspaceChanges {
+            repository,
+        });
+    }
+
+    pub fn git_mark_resolved(&self, path: PathBuf, repository: Option<PathBuf>) {
+        self.notification(ProxyNotification::GitMarkResolved { path, repository });
+    }
+
+    /// Rebase onto the revision following the todo list
+    pub fn git_rebase(
+        &self,
+        onto: String,
+        todo: Option<String>,
+        repository: Option<PathBuf>,
+    ) {
+        self.notification(ProxyNotification::GitRebase {
+            onto,
+            todo,
+            repository,
+        });
+    }
+
+    pub fn git_rebase_action(
+        &self,
+        action: RebaseAction,
+        repository: Option<PathBuf>,
+    ) {
+        self.notification(ProxyNotification::GitRebaseAction { action, repository });
     }
 
     pub fn get_selection_range(
@@ -1218,3 +1706,26 @@ fn default() -> Self {
         Self::new()
     }
 }
+
+/// Binary file content travels as a base64 string, as a JSON array of numbers
+/// is several times the size of the file
+mod base64_bytes {
+    use base64::{Engine as _, engine::general_purpose};
+    use serde::{Deserialize, Deserializer, Serializer, de};
+
+    pub fn serialize<S: Serializer>(
+        bytes: &[u8],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&general_purpose::STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(de::Error::custom)
+    }
+}
@@ -8,6 +8,28 @@ pub struct DiffInfo {
     pub branches: Vec<String>,
     pub tags: Vec<String>,
     pub diffs: Vec<FileDiff>,
+    /// The unmerged paths left by a merge, rebase or pull
+    pub conflicts: Vec<PathBuf>,
+    /// The files of `diffs` which aren't tracked yet
+    pub untracked: Vec<PathBuf>,
+    /// The ignored files and folders, whose content isn't listed
+    pub ignored: Vec<PathBuf>,
+    /// The root of the repository
+    pub root: PathBuf,
+    /// The status of the submodules of the repository
+    pub submodules: Vec<DiffInfo>,
+    /// Whether a rebase stopped, waiting to be continued, skipped or aborted
+    pub rebasing: bool,
+    /// The commit a rebase stopped at for its message to be reworded
+    pub rewording: Option<String>,
+}
+
+/// What to do with a rebase which stopped at a conflict or an edited commit
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub enum RebaseAction {
+    Continue,
+    Skip,
+    Abort,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -45,3 +67,37 @@ pub enum FileDiffKind {
     Deleted,
     Renamed,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlameCommit {
+    pub id: String,
+    pub author: String,
+    pub email: String,
+    /// Commit time in seconds since the unix epoch
+    pub time: i64,
+    pub summary: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BlameHunk {
+    /// The first line of the hunk in the current buffer, 0-based
+    pub start_line: usize,
+    pub lines: usize,
+    /// `None` when the lines haven't been committed yet
+    pub commit: Option<BlameCommit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GitCommit {
+    pub id: String,
+    pub parents: Vec<String>,
+    pub author: String,
+    pub email: String,
+    /// Commit time in seconds since the unix epoch
+    pub time: i64,
+    pub summary: String,
+    pub message: String,
+    /// The path of the file at this commit, when the log is filtered to a file
+    pub path: Option<PathBuf>,
+}